@@ -0,0 +1,6 @@
+// sqlx::migrate! embeds the migrations, so changing one has to rebuild the binary
+fn main() {
+    for dir in ["migrations_pg", "migrations_sq", "migrations_mysql"] {
+        println!("cargo:rerun-if-changed={dir}");
+    }
+}
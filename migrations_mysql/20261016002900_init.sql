@@ -0,0 +1,198 @@
+-- MySQL and MariaDB start out with the schema the other backends' migrations add up to.
+-- Timestamps are DATETIME in UTC, the connection's time zone is set to UTC by sqlx
+CREATE TABLE IF NOT EXISTS Websites (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    url VARCHAR(2048) NOT NULL,
+    alias VARCHAR(75) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ip_version VARCHAR(8) NOT NULL DEFAULT 'auto',
+    impact_weight VARCHAR(8) NOT NULL DEFAULT 'major',
+    -- One of degraded, maintenance, major_outage, NULL when the automated state applies
+    status_override VARCHAR(20),
+    status_override_note TEXT,
+    status_override_expires_at DATETIME,
+    -- http, ping or exec, ping monitors keep their hostname or IP address in url
+    monitor_type VARCHAR(10) NOT NULL DEFAULT 'http',
+    client_cert_path TEXT,
+    client_key_path TEXT,
+    client_cert_encrypted TEXT,
+    client_key_encrypted TEXT,
+    client_cert_fingerprint VARCHAR(95),
+    client_cert_expires_at DATETIME,
+    slack_webhook_url TEXT,
+    discord_webhook_url TEXT,
+    invert BOOLEAN NOT NULL DEFAULT FALSE,
+    alert_email_to TEXT,
+    tls_cert_serial TEXT,
+    tls_cert_expires_at DATETIME,
+    tls_expiry_notified_days INT,
+    -- Check options as one versioned JSON document, see MonitorConfig
+    config TEXT NOT NULL DEFAULT ('{"version":1}'),
+    -- Kept for the uptime queries and integrators, derived from the document
+    cron_schedule TEXT
+        GENERATED ALWAYS AS (JSON_VALUE(config, '$.cron_schedule')) VIRTUAL,
+    degraded_threshold_ms INT
+        GENERATED ALWAYS AS (CAST(JSON_VALUE(config, '$.degraded_threshold_ms') AS SIGNED)) VIRTUAL,
+    strict BOOLEAN
+        GENERATED ALWAYS AS (COALESCE(JSON_VALUE(config, '$.strict') = 'true', FALSE)) VIRTUAL,
+    CONSTRAINT websites_alias_unique UNIQUE (alias)
+);
+
+CREATE TABLE IF NOT EXISTS Logs (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    website_id INT NOT NULL,
+    status SMALLINT,
+    created_at DATETIME NOT NULL DEFAULT (DATE_FORMAT(UTC_TIMESTAMP(), '%Y-%m-%d %H:%i:00')),
+    -- Address family ('v4' or 'v6') the check actually connected over
+    ip_family VARCHAR(2),
+    -- Operator override of the impact a failed check inherits from its website
+    impact VARCHAR(8),
+    response_time_ms INT,
+    connection_reused BOOLEAN,
+    degraded BOOLEAN,
+    -- Why a check failed, e.g. which keyword assertion tripped
+    error TEXT,
+    reason_phrase VARCHAR(256),
+    server_header VARCHAR(256),
+    content_hash VARCHAR(64),
+    acknowledged_by TEXT,
+    acknowledged_at DATETIME,
+    ack_escalated_at DATETIME,
+    UNIQUE (website_id, created_at),
+    FOREIGN KEY (website_id) REFERENCES Websites (id)
+);
+
+CREATE TABLE IF NOT EXISTS Users (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    username VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    salt TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS Permissions (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS User_Permissions (
+    id INT PRIMARY KEY,
+    user_id INT NOT NULL,
+    website_id INT NOT NULL,
+    permission_id INT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES Users (id) ON DELETE CASCADE,
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE,
+    FOREIGN KEY (permission_id) REFERENCES Permissions (id),
+    UNIQUE (user_id, website_id, permission_id)
+);
+
+INSERT INTO Permissions (name, description) VALUES
+    ('read', 'Can read the Website'),
+    ('create_modify', 'Can create and modify the Website');
+
+-- Noteworthy things about a website that aren't incidents, like latency anomalies
+CREATE TABLE IF NOT EXISTS Events (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    website_id INT NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE
+);
+
+-- Updates operators post on an incident, which is the failed check it started with
+CREATE TABLE IF NOT EXISTS IncidentUpdates (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    log_id INT NOT NULL,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (log_id) REFERENCES Logs (id) ON DELETE CASCADE
+);
+
+-- Webhooks that get a JSON POST whenever a website goes down or recovers
+CREATE TABLE IF NOT EXISTS Notifications (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    url TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Hosts whose monitors' simultaneous incidents are notified as one
+CREATE TABLE IF NOT EXISTS SharedFateGroups (
+    host VARCHAR(255) PRIMARY KEY,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- What each channel last heard about a website, so restarts keep the throttling
+CREATE TABLE IF NOT EXISTS NotificationStates (
+    website_id INT NOT NULL,
+    channel VARCHAR(64) NOT NULL,
+    status SMALLINT NOT NULL,
+    state_since DATETIME NOT NULL,
+    notified_at DATETIME NOT NULL,
+    PRIMARY KEY (website_id, channel),
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE
+);
+
+-- Every notification sent and whether it was delivered
+CREATE TABLE IF NOT EXISTS NotificationLog (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    website_id INT NOT NULL,
+    channel VARCHAR(64) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    delivered BOOLEAN NOT NULL,
+    error TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE
+);
+
+-- Named channels websites can be routed to instead of the global defaults, config
+-- holds the kind's settings as JSON
+CREATE TABLE IF NOT EXISTS NotificationChannels (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(75) NOT NULL UNIQUE,
+    kind VARCHAR(16) NOT NULL,
+    config TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS WebsiteNotificationChannels (
+    website_id INT NOT NULL,
+    channel_id INT NOT NULL,
+    PRIMARY KEY (website_id, channel_id),
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE,
+    FOREIGN KEY (channel_id) REFERENCES NotificationChannels (id) ON DELETE CASCADE
+);
+
+-- Extra notifications once an incident stays open for after_minutes, repeated every
+-- repeat_minutes if set. Without a channel they go to the website's channels
+CREATE TABLE IF NOT EXISTS EscalationRules (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    website_id INT NOT NULL,
+    after_minutes INT NOT NULL,
+    repeat_minutes INT,
+    channel_id INT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE,
+    FOREIGN KEY (channel_id) REFERENCES NotificationChannels (id) ON DELETE SET NULL
+);
+
+-- How many steps of a rule fired for an incident, identified by its first failed log,
+-- so restarts don't send them again
+CREATE TABLE IF NOT EXISTS IncidentEscalations (
+    log_id INT NOT NULL,
+    rule_id INT NOT NULL,
+    steps INT NOT NULL,
+    fired_at DATETIME NOT NULL,
+    PRIMARY KEY (log_id, rule_id),
+    FOREIGN KEY (log_id) REFERENCES Logs (id) ON DELETE CASCADE,
+    FOREIGN KEY (rule_id) REFERENCES EscalationRules (id) ON DELETE CASCADE
+);
+
+-- Every digest sent, so one isn't sent again for the same period after a restart
+CREATE TABLE IF NOT EXISTS Digests (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    period_start DATETIME NOT NULL,
+    period_end DATETIME NOT NULL UNIQUE,
+    sent_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
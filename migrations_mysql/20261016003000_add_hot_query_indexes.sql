@@ -0,0 +1,8 @@
+-- Logs (website_id, created_at) and Websites (alias) are indexed through their unique
+-- constraints already and InnoDB indexes every foreign key. These cover looking up a
+-- website's failed or successful checks for incidents, pruning checks by age and each
+-- website's events and notification log
+CREATE INDEX logs_website_status_created_at ON Logs (website_id, status, created_at);
+CREATE INDEX logs_created_at ON Logs (created_at);
+CREATE INDEX events_website_created_at ON Events (website_id, created_at);
+CREATE INDEX notification_log_website_created_at ON NotificationLog (website_id, created_at);
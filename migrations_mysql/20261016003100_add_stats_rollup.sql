@@ -0,0 +1,14 @@
+-- Checks older than two days folded into one row per website and hour, so long stats
+-- ranges don't aggregate every raw check and survive the checks being pruned.
+-- degraded_successes counts the successful checks strict websites don't count as up
+CREATE TABLE IF NOT EXISTS StatsRollup (
+    website_id INT NOT NULL,
+    bucket_start DATETIME NOT NULL,
+    checks INT NOT NULL,
+    successes INT NOT NULL,
+    degraded INT NOT NULL,
+    degraded_successes INT NOT NULL,
+    avg_latency DOUBLE,
+    PRIMARY KEY (website_id, bucket_start),
+    FOREIGN KEY (website_id) REFERENCES Websites (id)
+);
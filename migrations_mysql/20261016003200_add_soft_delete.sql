@@ -0,0 +1,4 @@
+-- Deleted websites are hidden until purged after the restore window, NULL for live ones.
+-- The alias stays taken until then
+ALTER TABLE Websites ADD COLUMN deleted_at DATETIME;
+CREATE INDEX websites_deleted_at ON Websites (deleted_at);
@@ -0,0 +1,10 @@
+-- Checks and hourly stats go along with their website, so deleting one is a single
+-- DELETE on Websites like it is for everything else referencing it. The dropped keys
+-- carry the names InnoDB generated for them in the init migration
+ALTER TABLE Logs DROP FOREIGN KEY Logs_ibfk_1;
+ALTER TABLE Logs ADD CONSTRAINT logs_website_id_fkey
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE;
+
+ALTER TABLE StatsRollup DROP FOREIGN KEY StatsRollup_ibfk_1;
+ALTER TABLE StatsRollup ADD CONSTRAINT stats_rollup_website_id_fkey
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS ip_version varchar(8) NOT NULL DEFAULT 'auto';
+
+-- Address family ('v4' or 'v6') the check actually connected over
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS ip_family varchar(2);
@@ -0,0 +1,4 @@
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS impact_weight varchar(8) NOT NULL DEFAULT 'major';
+
+-- Operator override of the impact a failed check inherits from its website
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS impact varchar(8);
@@ -0,0 +1,5 @@
+-- NULL follows the global --fresh-connections setting
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS fresh_connection boolean;
+
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS response_time_ms integer;
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS connection_reused boolean;
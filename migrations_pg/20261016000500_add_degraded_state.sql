@@ -0,0 +1,5 @@
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS degraded_threshold_ms integer;
+-- Degraded checks count as down for the uptime percentage of strict websites
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS strict boolean NOT NULL DEFAULT false;
+
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS degraded boolean;
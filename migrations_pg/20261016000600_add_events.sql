@@ -0,0 +1,8 @@
+-- Noteworthy things about a website that aren't incidents, like latency anomalies
+CREATE TABLE IF NOT EXISTS Events (
+    id serial PRIMARY KEY,
+    website_id int NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    kind varchar(32) NOT NULL,
+    message text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
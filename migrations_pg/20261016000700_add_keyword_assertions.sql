@@ -0,0 +1,5 @@
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS expected_keyword text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS forbidden_keyword text;
+
+-- Why a check failed, e.g. which keyword assertion tripped
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS error text;
@@ -0,0 +1,3 @@
+-- Captured for non-200 responses only, capped at 256 characters by the checker
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS reason_phrase varchar(256);
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS server_header varchar(256);
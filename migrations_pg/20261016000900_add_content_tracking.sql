@@ -0,0 +1,3 @@
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS track_content boolean NOT NULL DEFAULT false;
+-- Hex encoded SHA-256 of the normalized body, only for websites tracking their content
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS content_hash varchar(64);
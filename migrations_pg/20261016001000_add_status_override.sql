@@ -0,0 +1,4 @@
+-- One of degraded, maintenance, major_outage, NULL when the automated state applies
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS status_override varchar(20);
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS status_override_note text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS status_override_expires_at timestamptz;
@@ -0,0 +1,2 @@
+-- http or ping, ping monitors keep their hostname or IP address in url
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS monitor_type varchar(10) NOT NULL DEFAULT 'http';
@@ -0,0 +1,7 @@
+-- Updates operators post on an incident, which is the failed check it started with
+CREATE TABLE IF NOT EXISTS IncidentUpdates (
+    id serial PRIMARY KEY,
+    log_id int NOT NULL REFERENCES Logs(id) ON DELETE CASCADE,
+    message text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
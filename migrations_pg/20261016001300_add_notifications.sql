@@ -0,0 +1,6 @@
+-- Webhooks that get a JSON POST whenever a website goes down or recovers
+CREATE TABLE IF NOT EXISTS Notifications (
+    id serial PRIMARY KEY,
+    url text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
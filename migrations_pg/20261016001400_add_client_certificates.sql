@@ -0,0 +1,8 @@
+-- Client certificate for websites requiring mutual TLS, either as file paths or uploaded
+-- and encrypted with --secrets-key, the PEM is never shown again
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_cert_path text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_key_path text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_cert_encrypted text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_key_encrypted text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_cert_fingerprint varchar(95);
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS client_cert_expires_at timestamptz;
@@ -0,0 +1,2 @@
+-- Overrides --alert-email-to for alert mails about this website
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS alert_email_to text;
@@ -0,0 +1,5 @@
+-- Hosts whose monitors' simultaneous incidents are notified as one
+CREATE TABLE IF NOT EXISTS SharedFateGroups (
+    host text PRIMARY KEY,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
@@ -0,0 +1,23 @@
+-- Remind every this many minutes while the website stays down
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS renotify_minutes integer;
+
+-- What each channel last heard about a website, so restarts keep the throttling
+CREATE TABLE IF NOT EXISTS NotificationStates (
+    website_id int NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel varchar(64) NOT NULL,
+    status smallint NOT NULL,
+    state_since timestamptz NOT NULL,
+    notified_at timestamptz NOT NULL,
+    PRIMARY KEY (website_id, channel)
+);
+
+-- Every notification sent and whether it was delivered
+CREATE TABLE IF NOT EXISTS NotificationLog (
+    id serial PRIMARY KEY,
+    website_id int NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel varchar(64) NOT NULL,
+    kind varchar(16) NOT NULL,
+    delivered boolean NOT NULL,
+    error text,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
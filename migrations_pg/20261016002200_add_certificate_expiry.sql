@@ -0,0 +1,5 @@
+-- Server certificate seen last, a different serial or expiry means it was renewed
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS tls_cert_serial text;
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS tls_cert_expires_at timestamptz;
+-- Tightest of the 30, 14, 7 and 1 day expiry warnings sent for that certificate
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS tls_expiry_notified_days integer;
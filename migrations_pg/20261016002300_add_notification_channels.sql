@@ -0,0 +1,15 @@
+-- Named channels websites can be routed to instead of the global defaults, config
+-- holds the kind's settings as JSON
+CREATE TABLE IF NOT EXISTS NotificationChannels (
+    id serial PRIMARY KEY,
+    name varchar(75) NOT NULL UNIQUE,
+    kind varchar(16) NOT NULL,
+    config text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS WebsiteNotificationChannels (
+    website_id int NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel_id int NOT NULL REFERENCES NotificationChannels(id) ON DELETE CASCADE,
+    PRIMARY KEY (website_id, channel_id)
+);
@@ -0,0 +1,5 @@
+-- Set on the first failed log of an incident once someone took it on
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS acknowledged_by text;
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS acknowledged_at timestamptz;
+-- When the incident was escalated for missing the acknowledgment SLA
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS ack_escalated_at timestamptz;
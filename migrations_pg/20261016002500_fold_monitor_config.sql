@@ -0,0 +1,33 @@
+-- Check options as one versioned JSON document, version 1 is the shape of the columns
+-- folded into it here, the application migrates it to the current one when reading
+ALTER TABLE Websites ADD COLUMN IF NOT EXISTS config text NOT NULL DEFAULT '{"version":1}';
+UPDATE Websites SET config = json_build_object(
+    'version', 1,
+    'fresh_connection', fresh_connection,
+    'cron_schedule', cron_schedule,
+    'degraded_threshold_ms', degraded_threshold_ms,
+    'strict', strict,
+    'expected_keyword', expected_keyword,
+    'forbidden_keyword', forbidden_keyword,
+    'track_content', track_content,
+    'renotify_minutes', renotify_minutes,
+    'exec_args', exec_args
+)::text;
+
+ALTER TABLE Websites DROP COLUMN IF EXISTS fresh_connection;
+ALTER TABLE Websites DROP COLUMN IF EXISTS cron_schedule;
+ALTER TABLE Websites DROP COLUMN IF EXISTS degraded_threshold_ms;
+ALTER TABLE Websites DROP COLUMN IF EXISTS strict;
+ALTER TABLE Websites DROP COLUMN IF EXISTS expected_keyword;
+ALTER TABLE Websites DROP COLUMN IF EXISTS forbidden_keyword;
+ALTER TABLE Websites DROP COLUMN IF EXISTS track_content;
+ALTER TABLE Websites DROP COLUMN IF EXISTS renotify_minutes;
+ALTER TABLE Websites DROP COLUMN IF EXISTS exec_args;
+
+-- Kept for the uptime queries and integrators, derived from the document
+ALTER TABLE Websites ADD COLUMN cron_schedule text
+    GENERATED ALWAYS AS (config::jsonb ->> 'cron_schedule') STORED;
+ALTER TABLE Websites ADD COLUMN degraded_threshold_ms integer
+    GENERATED ALWAYS AS ((config::jsonb ->> 'degraded_threshold_ms')::integer) STORED;
+ALTER TABLE Websites ADD COLUMN strict boolean
+    GENERATED ALWAYS AS (COALESCE((config::jsonb ->> 'strict')::boolean, false)) STORED;
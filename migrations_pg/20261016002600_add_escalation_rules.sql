@@ -0,0 +1,20 @@
+-- Extra notifications once an incident stays open for after_minutes, repeated every
+-- repeat_minutes if set. Without a channel they go to the website's channels
+CREATE TABLE IF NOT EXISTS EscalationRules (
+    id serial PRIMARY KEY,
+    website_id int NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    after_minutes int NOT NULL,
+    repeat_minutes int,
+    channel_id int REFERENCES NotificationChannels(id) ON DELETE SET NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- How many steps of a rule fired for an incident, identified by its first failed log,
+-- so restarts don't send them again
+CREATE TABLE IF NOT EXISTS IncidentEscalations (
+    log_id int NOT NULL REFERENCES Logs(id) ON DELETE CASCADE,
+    rule_id int NOT NULL REFERENCES EscalationRules(id) ON DELETE CASCADE,
+    steps int NOT NULL,
+    fired_at timestamptz NOT NULL,
+    PRIMARY KEY (log_id, rule_id)
+);
@@ -0,0 +1,7 @@
+-- Every digest sent, so one isn't sent again for the same period after a restart
+CREATE TABLE IF NOT EXISTS Digests (
+    id serial PRIMARY KEY,
+    period_start timestamptz NOT NULL,
+    period_end timestamptz NOT NULL UNIQUE,
+    sent_at timestamptz NOT NULL DEFAULT now()
+);
@@ -0,0 +1,27 @@
+-- Databases whose Websites table predates the unique constraint can hold several
+-- websites with one alias. The oldest keeps it, the others get their id appended so
+-- their history stays reachable, with a counter after it should that alias be taken
+-- as well. They're renamed one at a time, so each sees the aliases handed out before it
+DO $$
+DECLARE
+    duplicate RECORD;
+    candidate TEXT;
+    counter INTEGER;
+BEGIN
+    FOR duplicate IN
+        SELECT id, alias FROM Websites
+        WHERE id NOT IN (SELECT MIN(id) FROM Websites GROUP BY alias)
+        ORDER BY id
+    LOOP
+        candidate := duplicate.alias || '-' || duplicate.id;
+        counter := 1;
+        WHILE EXISTS (SELECT 1 FROM Websites WHERE alias = candidate) LOOP
+            counter := counter + 1;
+            candidate := duplicate.alias || '-' || duplicate.id || '-' || counter;
+        END LOOP;
+        UPDATE Websites SET alias = candidate WHERE id = duplicate.id;
+    END LOOP;
+END
+$$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS websites_alias_unique ON Websites (alias);
@@ -0,0 +1,13 @@
+-- Checks older than two days folded into one row per website and hour, so long stats
+-- ranges don't aggregate every raw check and survive the checks being pruned.
+-- degraded_successes counts the successful checks strict websites don't count as up
+CREATE TABLE IF NOT EXISTS StatsRollup (
+    website_id integer NOT NULL REFERENCES Websites (id),
+    bucket_start timestamp without time zone NOT NULL,
+    checks integer NOT NULL,
+    successes integer NOT NULL,
+    degraded integer NOT NULL,
+    degraded_successes integer NOT NULL,
+    avg_latency double precision,
+    PRIMARY KEY (website_id, bucket_start)
+);
@@ -0,0 +1,3 @@
+-- Allow-listed response headers of failed checks as a JSON object, NULL for successful
+-- ones and for failures without a response
+ALTER TABLE Logs ADD COLUMN IF NOT EXISTS response_headers TEXT;
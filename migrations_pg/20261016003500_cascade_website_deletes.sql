@@ -0,0 +1,9 @@
+-- Checks and hourly stats go along with their website, so deleting one is a single
+-- DELETE on Websites like it is for everything else referencing it
+ALTER TABLE Logs DROP CONSTRAINT IF EXISTS logs_website_id_fkey;
+ALTER TABLE Logs ADD CONSTRAINT logs_website_id_fkey
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE;
+
+ALTER TABLE StatsRollup DROP CONSTRAINT IF EXISTS statsrollup_website_id_fkey;
+ALTER TABLE StatsRollup ADD CONSTRAINT statsrollup_website_id_fkey
+    FOREIGN KEY (website_id) REFERENCES Websites (id) ON DELETE CASCADE;
@@ -0,0 +1,6 @@
+-- The first tables stored UTC without a time zone, which doesn't decode as the
+-- DateTime<Utc> everything reads these columns as
+ALTER TABLE Websites ALTER COLUMN created_at TYPE timestamptz USING created_at AT TIME ZONE 'UTC';
+ALTER TABLE Logs ALTER COLUMN created_at TYPE timestamptz USING created_at AT TIME ZONE 'UTC';
+ALTER TABLE Users ALTER COLUMN created_at TYPE timestamptz USING created_at AT TIME ZONE 'UTC';
+ALTER TABLE StatsRollup ALTER COLUMN bucket_start TYPE timestamptz USING bucket_start AT TIME ZONE 'UTC';
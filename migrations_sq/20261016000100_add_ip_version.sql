@@ -0,0 +1,4 @@
+ALTER TABLE Websites ADD COLUMN ip_version TEXT NOT NULL DEFAULT 'auto';
+
+-- Address family ('v4' or 'v6') the check actually connected over
+ALTER TABLE Logs ADD COLUMN ip_family TEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE Websites ADD COLUMN impact_weight TEXT NOT NULL DEFAULT 'major';
+
+-- Operator override of the impact a failed check inherits from its website
+ALTER TABLE Logs ADD COLUMN impact TEXT;
@@ -0,0 +1,5 @@
+-- NULL follows the global --fresh-connections setting
+ALTER TABLE Websites ADD COLUMN fresh_connection BOOLEAN;
+
+ALTER TABLE Logs ADD COLUMN response_time_ms INTEGER;
+ALTER TABLE Logs ADD COLUMN connection_reused BOOLEAN;
@@ -0,0 +1,2 @@
+-- Checks follow this cron expression instead of the fixed interval when set
+ALTER TABLE Websites ADD COLUMN cron_schedule TEXT;
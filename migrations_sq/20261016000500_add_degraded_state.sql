@@ -0,0 +1,5 @@
+ALTER TABLE Websites ADD COLUMN degraded_threshold_ms INTEGER;
+-- Degraded checks count as down for the uptime percentage of strict websites
+ALTER TABLE Websites ADD COLUMN strict BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE Logs ADD COLUMN degraded BOOLEAN;
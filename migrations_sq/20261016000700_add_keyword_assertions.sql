@@ -0,0 +1,5 @@
+ALTER TABLE Websites ADD COLUMN expected_keyword TEXT;
+ALTER TABLE Websites ADD COLUMN forbidden_keyword TEXT;
+
+-- Why a check failed, e.g. which keyword assertion tripped
+ALTER TABLE Logs ADD COLUMN error TEXT;
@@ -0,0 +1,3 @@
+-- Captured for non-200 responses only, capped at 256 characters by the checker
+ALTER TABLE Logs ADD COLUMN reason_phrase TEXT;
+ALTER TABLE Logs ADD COLUMN server_header TEXT;
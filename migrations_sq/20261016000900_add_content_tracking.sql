@@ -0,0 +1,3 @@
+ALTER TABLE Websites ADD COLUMN track_content BOOLEAN NOT NULL DEFAULT FALSE;
+-- Hex encoded SHA-256 of the normalized body, only for websites tracking their content
+ALTER TABLE Logs ADD COLUMN content_hash TEXT;
@@ -0,0 +1,4 @@
+-- One of degraded, maintenance, major_outage, NULL when the automated state applies
+ALTER TABLE Websites ADD COLUMN status_override TEXT;
+ALTER TABLE Websites ADD COLUMN status_override_note TEXT;
+ALTER TABLE Websites ADD COLUMN status_override_expires_at TIMESTAMP;
@@ -0,0 +1,2 @@
+-- http or ping, ping monitors keep their hostname or IP address in url
+ALTER TABLE Websites ADD COLUMN monitor_type TEXT NOT NULL DEFAULT 'http';
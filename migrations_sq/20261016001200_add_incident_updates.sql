@@ -0,0 +1,7 @@
+-- Updates operators post on an incident, which is the failed check it started with
+CREATE TABLE IF NOT EXISTS IncidentUpdates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    log_id INTEGER NOT NULL REFERENCES Logs(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
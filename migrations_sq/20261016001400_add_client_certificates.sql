@@ -0,0 +1,8 @@
+-- Client certificate for websites requiring mutual TLS, either as file paths or uploaded
+-- and encrypted with --secrets-key, the PEM is never shown again
+ALTER TABLE Websites ADD COLUMN client_cert_path TEXT;
+ALTER TABLE Websites ADD COLUMN client_key_path TEXT;
+ALTER TABLE Websites ADD COLUMN client_cert_encrypted TEXT;
+ALTER TABLE Websites ADD COLUMN client_key_encrypted TEXT;
+ALTER TABLE Websites ADD COLUMN client_cert_fingerprint TEXT;
+ALTER TABLE Websites ADD COLUMN client_cert_expires_at TIMESTAMP;
@@ -0,0 +1,2 @@
+-- Overrides --slack-webhook-url for alerts about this website
+ALTER TABLE Websites ADD COLUMN slack_webhook_url TEXT;
@@ -0,0 +1,2 @@
+-- Overrides --discord-webhook-url for alerts about this website
+ALTER TABLE Websites ADD COLUMN discord_webhook_url TEXT;
@@ -0,0 +1,2 @@
+-- Websites expected to be offline, reachable checks count as down
+ALTER TABLE Websites ADD COLUMN invert BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Overrides --alert-email-to for alert mails about this website
+ALTER TABLE Websites ADD COLUMN alert_email_to TEXT;
@@ -0,0 +1,5 @@
+-- Hosts whose monitors' simultaneous incidents are notified as one
+CREATE TABLE IF NOT EXISTS SharedFateGroups (
+    host TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
@@ -0,0 +1,23 @@
+-- Remind every this many minutes while the website stays down
+ALTER TABLE Websites ADD COLUMN renotify_minutes INTEGER;
+
+-- What each channel last heard about a website, so restarts keep the throttling
+CREATE TABLE IF NOT EXISTS NotificationStates (
+    website_id INTEGER NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    state_since TIMESTAMP NOT NULL,
+    notified_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (website_id, channel)
+);
+
+-- Every notification sent and whether it was delivered
+CREATE TABLE IF NOT EXISTS NotificationLog (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    website_id INTEGER NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    delivered BOOLEAN NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
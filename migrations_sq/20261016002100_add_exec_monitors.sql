@@ -0,0 +1,2 @@
+-- Arguments of exec monitors' program, one per line, passed without a shell
+ALTER TABLE Websites ADD COLUMN exec_args TEXT;
@@ -0,0 +1,15 @@
+-- Named channels websites can be routed to instead of the global defaults, config
+-- holds the kind's settings as JSON
+CREATE TABLE IF NOT EXISTS NotificationChannels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    config TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS WebsiteNotificationChannels (
+    website_id INTEGER NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    channel_id INTEGER NOT NULL REFERENCES NotificationChannels(id) ON DELETE CASCADE,
+    PRIMARY KEY (website_id, channel_id)
+);
@@ -0,0 +1,5 @@
+-- Set on the first failed log of an incident once someone took it on
+ALTER TABLE Logs ADD COLUMN acknowledged_by TEXT;
+ALTER TABLE Logs ADD COLUMN acknowledged_at TIMESTAMP;
+-- When the incident was escalated for missing the acknowledgment SLA
+ALTER TABLE Logs ADD COLUMN ack_escalated_at TIMESTAMP;
@@ -0,0 +1,33 @@
+-- Check options as one versioned JSON document, version 1 is the shape of the columns
+-- folded into it here, the application migrates it to the current one when reading
+ALTER TABLE Websites ADD COLUMN config TEXT NOT NULL DEFAULT '{"version":1}';
+UPDATE Websites SET config = json_object(
+    'version', 1,
+    'fresh_connection', CASE fresh_connection WHEN 1 THEN json('true') WHEN 0 THEN json('false') END,
+    'cron_schedule', cron_schedule,
+    'degraded_threshold_ms', degraded_threshold_ms,
+    'strict', CASE WHEN strict THEN json('true') ELSE json('false') END,
+    'expected_keyword', expected_keyword,
+    'forbidden_keyword', forbidden_keyword,
+    'track_content', CASE WHEN track_content THEN json('true') ELSE json('false') END,
+    'renotify_minutes', renotify_minutes,
+    'exec_args', exec_args
+);
+
+ALTER TABLE Websites DROP COLUMN fresh_connection;
+ALTER TABLE Websites DROP COLUMN cron_schedule;
+ALTER TABLE Websites DROP COLUMN degraded_threshold_ms;
+ALTER TABLE Websites DROP COLUMN strict;
+ALTER TABLE Websites DROP COLUMN expected_keyword;
+ALTER TABLE Websites DROP COLUMN forbidden_keyword;
+ALTER TABLE Websites DROP COLUMN track_content;
+ALTER TABLE Websites DROP COLUMN renotify_minutes;
+ALTER TABLE Websites DROP COLUMN exec_args;
+
+-- Kept for the uptime queries and integrators, derived from the document
+ALTER TABLE Websites ADD COLUMN cron_schedule TEXT
+    GENERATED ALWAYS AS (json_extract(config, '$.cron_schedule')) VIRTUAL;
+ALTER TABLE Websites ADD COLUMN degraded_threshold_ms INTEGER
+    GENERATED ALWAYS AS (json_extract(config, '$.degraded_threshold_ms')) VIRTUAL;
+ALTER TABLE Websites ADD COLUMN strict BOOLEAN
+    GENERATED ALWAYS AS (COALESCE(json_extract(config, '$.strict'), 0)) VIRTUAL;
@@ -0,0 +1,20 @@
+-- Extra notifications once an incident stays open for after_minutes, repeated every
+-- repeat_minutes if set. Without a channel they go to the website's channels
+CREATE TABLE IF NOT EXISTS EscalationRules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    website_id INTEGER NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    after_minutes INTEGER NOT NULL,
+    repeat_minutes INTEGER,
+    channel_id INTEGER REFERENCES NotificationChannels(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
+
+-- How many steps of a rule fired for an incident, identified by its first failed log,
+-- so restarts don't send them again
+CREATE TABLE IF NOT EXISTS IncidentEscalations (
+    log_id INTEGER NOT NULL REFERENCES Logs(id) ON DELETE CASCADE,
+    rule_id INTEGER NOT NULL REFERENCES EscalationRules(id) ON DELETE CASCADE,
+    steps INTEGER NOT NULL,
+    fired_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (log_id, rule_id)
+);
@@ -0,0 +1,7 @@
+-- Every digest sent, so one isn't sent again for the same period after a restart
+CREATE TABLE IF NOT EXISTS Digests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    period_start TIMESTAMP NOT NULL,
+    period_end TIMESTAMP NOT NULL UNIQUE,
+    sent_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now'))
+);
@@ -0,0 +1,40 @@
+-- Databases whose Websites table predates the unique constraint can hold several
+-- websites with one alias. The oldest keeps it, the others get their id appended so
+-- their history stays reachable, with a counter after it should that alias be taken
+-- as well. The trigger renames them one at a time, so each sees the aliases handed
+-- out before it, and with one counter more than there are websites a free alias is
+-- always among them
+CREATE TEMP TABLE alias_counters (n INTEGER PRIMARY KEY);
+WITH RECURSIVE counter(n) AS (
+    SELECT 1
+    UNION ALL
+    SELECT n + 1 FROM counter WHERE n <= (SELECT COUNT(*) FROM Websites)
+)
+INSERT INTO alias_counters (n) SELECT n FROM counter;
+
+CREATE TEMP TABLE alias_renames (id INTEGER PRIMARY KEY, alias TEXT NOT NULL);
+CREATE TEMP TRIGGER rename_duplicate_alias AFTER INSERT ON alias_renames
+BEGIN
+    UPDATE Websites SET alias = (
+        SELECT candidate FROM (
+            SELECT n, NEW.alias || '-' || NEW.id || CASE WHEN n = 1 THEN '' ELSE '-' || n END
+                AS candidate
+            FROM alias_counters
+        )
+        WHERE NOT EXISTS (SELECT 1 FROM Websites WHERE Websites.alias = candidate)
+        ORDER BY n
+        LIMIT 1
+    )
+    WHERE id = NEW.id;
+END;
+
+INSERT INTO alias_renames (id, alias)
+SELECT id, alias FROM Websites
+WHERE id NOT IN (SELECT MIN(id) FROM Websites GROUP BY alias)
+ORDER BY id;
+
+DROP TRIGGER rename_duplicate_alias;
+DROP TABLE alias_renames;
+DROP TABLE alias_counters;
+
+CREATE UNIQUE INDEX IF NOT EXISTS websites_alias_unique ON Websites (alias);
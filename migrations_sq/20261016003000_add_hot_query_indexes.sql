@@ -0,0 +1,9 @@
+-- Logs (website_id, created_at) and Websites (alias) are indexed through their unique
+-- constraints already. These cover looking up a website's failed or successful checks
+-- for incidents, pruning checks by age, the cascade from deleted checks to their
+-- incident updates and each website's events and notification log
+CREATE INDEX IF NOT EXISTS logs_website_status_created_at ON Logs (website_id, status, created_at);
+CREATE INDEX IF NOT EXISTS logs_created_at ON Logs (created_at);
+CREATE INDEX IF NOT EXISTS incident_updates_log_id ON IncidentUpdates (log_id);
+CREATE INDEX IF NOT EXISTS events_website_created_at ON Events (website_id, created_at);
+CREATE INDEX IF NOT EXISTS notification_log_website_created_at ON NotificationLog (website_id, created_at);
@@ -0,0 +1,13 @@
+-- Checks older than two days folded into one row per website and hour, so long stats
+-- ranges don't aggregate every raw check and survive the checks being pruned.
+-- degraded_successes counts the successful checks strict websites don't count as up
+CREATE TABLE IF NOT EXISTS StatsRollup (
+    website_id INTEGER NOT NULL REFERENCES Websites (id),
+    bucket_start TIMESTAMP NOT NULL,
+    checks INTEGER NOT NULL,
+    successes INTEGER NOT NULL,
+    degraded INTEGER NOT NULL,
+    degraded_successes INTEGER NOT NULL,
+    avg_latency REAL,
+    PRIMARY KEY (website_id, bucket_start)
+);
@@ -0,0 +1,2 @@
+-- Days the website's checks are kept for, NULL goes by --retention-days
+ALTER TABLE Websites ADD COLUMN retention_days INTEGER;
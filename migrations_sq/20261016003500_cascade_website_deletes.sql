@@ -0,0 +1,53 @@
+-- Checks and hourly stats go along with their website, so deleting one is a single
+-- DELETE on Websites like it is for everything else referencing it.
+-- Sqlite can't change a foreign key in place, so both tables are rebuilt. migrate_sqlite
+-- turns foreign keys off for the migrations, dropping Logs would otherwise cascade to the
+-- incident updates and escalations of its checks
+
+CREATE TABLE Logs_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    website_id INTEGER NOT NULL REFERENCES Websites(id) ON DELETE CASCADE,
+    status INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:00', 'now')),
+    ip_family TEXT,
+    impact TEXT,
+    response_time_ms INTEGER,
+    connection_reused BOOLEAN,
+    degraded BOOLEAN,
+    error TEXT,
+    reason_phrase TEXT,
+    server_header TEXT,
+    content_hash TEXT,
+    acknowledged_by TEXT,
+    acknowledged_at TIMESTAMP,
+    ack_escalated_at TIMESTAMP,
+    response_headers TEXT,
+    UNIQUE (website_id, created_at)
+);
+INSERT INTO Logs_new (id, website_id, status, created_at, ip_family, impact,
+    response_time_ms, connection_reused, degraded, error, reason_phrase, server_header,
+    content_hash, acknowledged_by, acknowledged_at, ack_escalated_at, response_headers)
+SELECT id, website_id, status, created_at, ip_family, impact,
+    response_time_ms, connection_reused, degraded, error, reason_phrase, server_header,
+    content_hash, acknowledged_by, acknowledged_at, ack_escalated_at, response_headers
+FROM Logs;
+DROP TABLE Logs;
+ALTER TABLE Logs_new RENAME TO Logs;
+CREATE INDEX IF NOT EXISTS logs_website_status_created_at ON Logs (website_id, status, created_at);
+CREATE INDEX IF NOT EXISTS logs_created_at ON Logs (created_at);
+
+CREATE TABLE StatsRollup_new (
+    website_id INTEGER NOT NULL REFERENCES Websites (id) ON DELETE CASCADE,
+    bucket_start TIMESTAMP NOT NULL,
+    checks INTEGER NOT NULL,
+    successes INTEGER NOT NULL,
+    degraded INTEGER NOT NULL,
+    degraded_successes INTEGER NOT NULL,
+    avg_latency REAL,
+    PRIMARY KEY (website_id, bucket_start)
+);
+INSERT INTO StatsRollup_new
+SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes, avg_latency
+FROM StatsRollup;
+DROP TABLE StatsRollup;
+ALTER TABLE StatsRollup_new RENAME TO StatsRollup;
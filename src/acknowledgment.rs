@@ -0,0 +1,185 @@
+use crate::{
+    Website,
+    notifications::{Transition, Warning},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+
+/// The first failed check of an outage that is still going on, incidents are
+/// acknowledged on it
+#[derive(Clone, Serialize, sqlx::FromRow)]
+pub struct OpenIncident {
+    pub alias: String,
+    pub id: i32,
+    pub started_at: DateTime<Utc>,
+    /// What the first failed check got
+    pub status: i16,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// When the escalation for missing the acknowledgment SLA went out
+    pub ack_escalated_at: Option<DateTime<Utc>>,
+}
+
+impl OpenIncident {
+    /// Badge text for incidents nobody acknowledged yet, e.g. "unacked for 14m"
+    pub fn aging(&self, now: DateTime<Utc>) -> Option<String> {
+        if self.acknowledged_at.is_some() {
+            return None;
+        }
+        Some(format!(
+            "unacked for {}",
+            duration_text((now - self.started_at).num_seconds())
+        ))
+    }
+
+    /// Unacknowledged for longer than the SLA and not escalated yet
+    pub fn is_overdue(&self, sla: TimeDelta, now: DateTime<Utc>) -> bool {
+        self.acknowledged_at.is_none()
+            && self.ack_escalated_at.is_none()
+            && now - self.started_at >= sla
+    }
+
+    /// Goes to the website's channels like a transition, the website is still down
+    pub fn escalation(&self, website: &Website, now: DateTime<Utc>) -> Transition {
+        Transition {
+            alias: website.alias.clone(),
+            url: website.url.clone(),
+            old_status: self.status,
+            new_status: self.status,
+            timestamp: now,
+            since: self.started_at,
+            duration: (now - self.started_at).num_seconds(),
+            inverted: website.invert,
+            shared_host: None,
+            affected: Vec::new(),
+            reminder: false,
+            warning: Some(Warning::Unacknowledged {
+                incident_id: self.id,
+            }),
+            message: None,
+        }
+    }
+}
+
+/// Time to acknowledge over a website's acknowledged incidents
+#[derive(Clone, Serialize)]
+pub struct TtaStats {
+    pub incidents: usize,
+    pub median_secs: i64,
+    pub worst_secs: i64,
+}
+
+impl TtaStats {
+    /// `None` when nothing was acknowledged, takes when each incident started and when
+    /// it was acknowledged
+    pub fn new(acknowledged: &[(DateTime<Utc>, DateTime<Utc>)]) -> Option<Self> {
+        let mut tta: Vec<i64> = acknowledged
+            .iter()
+            .map(|(started_at, acknowledged_at)| (*acknowledged_at - *started_at).num_seconds())
+            .collect();
+        tta.sort_unstable();
+        let worst_secs = *tta.last()?;
+        let middle = tta.len() / 2;
+        let median_secs = if tta.len().is_multiple_of(2) {
+            (tta[middle - 1] + tta[middle]) / 2
+        } else {
+            tta[middle]
+        };
+
+        Some(Self {
+            incidents: tta.len(),
+            median_secs,
+            worst_secs,
+        })
+    }
+
+    pub fn median_text(&self) -> String {
+        duration_text(self.median_secs)
+    }
+
+    pub fn worst_text(&self) -> String {
+        duration_text(self.worst_secs)
+    }
+}
+
+pub fn duration_text(secs: i64) -> String {
+    match secs {
+        ..60 => format!("{}s", secs.max(0)),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        "2026-10-16T08:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minute)
+    }
+
+    fn incident(acknowledged_at: Option<i64>, ack_escalated_at: Option<i64>) -> OpenIncident {
+        OpenIncident {
+            alias: "api".to_owned(),
+            id: 1,
+            started_at: at(0),
+            status: 599,
+            acknowledged_by: acknowledged_at.map(|_| "ops".to_owned()),
+            acknowledged_at: acknowledged_at.map(at),
+            ack_escalated_at: ack_escalated_at.map(at),
+        }
+    }
+
+    #[test]
+    fn unacknowledged_incidents_age() {
+        assert_eq!(
+            incident(None, None).aging(at(14)).as_deref(),
+            Some("unacked for 14m")
+        );
+        assert_eq!(
+            incident(None, None)
+                .aging(at(0) + TimeDelta::seconds(42))
+                .as_deref(),
+            Some("unacked for 42s")
+        );
+        assert_eq!(
+            incident(None, Some(10)).aging(at(125)).as_deref(),
+            Some("unacked for 2h 5m")
+        );
+        assert_eq!(incident(Some(3), None).aging(at(14)), None);
+    }
+
+    #[test]
+    fn incidents_are_overdue_once_past_the_sla_until_escalated_or_acknowledged() {
+        let sla = TimeDelta::minutes(10);
+        assert!(!incident(None, None).is_overdue(sla, at(9)));
+        assert!(incident(None, None).is_overdue(sla, at(10)));
+        assert!(!incident(None, Some(10)).is_overdue(sla, at(30)));
+        assert!(!incident(Some(12), None).is_overdue(sla, at(30)));
+    }
+
+    #[test]
+    fn time_to_acknowledge_has_a_median_and_worst() {
+        assert!(TtaStats::new(&[]).is_none());
+
+        let odd = TtaStats::new(&[(at(0), at(9)), (at(60), at(62)), (at(120), at(125))]).unwrap();
+        assert_eq!(
+            (odd.incidents, odd.median_secs, odd.worst_secs),
+            (3, 5 * 60, 9 * 60)
+        );
+        assert_eq!(
+            (odd.median_text(), odd.worst_text()),
+            ("5m".into(), "9m".into())
+        );
+
+        let even = TtaStats::new(&[
+            (at(0), at(1)),
+            (at(10), at(12)),
+            (at(20), at(24)),
+            (at(30), at(100)),
+        ])
+        .unwrap();
+        assert_eq!(even.median_secs, 3 * 60);
+        assert_eq!(even.worst_text(), "1h 10m");
+    }
+}
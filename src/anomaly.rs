@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Events table kind for latency regressions
+pub const LATENCY_ANOMALY_EVENT: &str = "latency_anomaly";
+
+/// Hourly buckets needed as a baseline before a website is judged at all
+pub const MIN_BASELINE_BUCKETS: usize = 24;
+
+/// Scales the median absolute deviation to be comparable to a standard deviation
+const MAD_SCALE: f64 = 1.4826;
+
+/// Fraction of the baseline median a bucket always may deviate, so perfectly flat
+/// baselines (MAD of zero) don't flag every millisecond of jitter
+const MIN_SPREAD_FRACTION: f64 = 0.1;
+
+#[derive(Debug, PartialEq)]
+pub struct LatencyAnomaly {
+    pub latency_ms: f64,
+    pub baseline_median_ms: f64,
+    pub threshold_ms: f64,
+}
+
+impl LatencyAnomaly {
+    pub fn message(&self) -> String {
+        format!(
+            "Latency of {:.0}ms over the last hour exceeds {:.0}ms (7 day median {:.0}ms)",
+            self.latency_ms, self.threshold_ms, self.baseline_median_ms
+        )
+    }
+}
+
+/// Judges the newest bucket of an hourly latency series (oldest first) against the
+/// rest of it: anomalous when it exceeds median + k·MAD of the baseline
+pub fn detect_latency_anomaly(series: &[f64], sensitivity: f64) -> Option<LatencyAnomaly> {
+    let (latency_ms, baseline) = series.split_last()?;
+    if baseline.len() < MIN_BASELINE_BUCKETS {
+        return None;
+    }
+
+    let baseline_median_ms = median(baseline.to_vec())?;
+    let deviations = baseline
+        .iter()
+        .map(|latency| (latency - baseline_median_ms).abs())
+        .collect();
+    let spread = (median(deviations)? * MAD_SCALE).max(baseline_median_ms * MIN_SPREAD_FRACTION);
+    let threshold_ms = baseline_median_ms + sensitivity * spread;
+
+    (*latency_ms > threshold_ms).then_some(LatencyAnomaly {
+        latency_ms: *latency_ms,
+        baseline_median_ms,
+        threshold_ms,
+    })
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[middle - 1] + values[middle]) / 2.0)
+    } else {
+        Some(values[middle])
+    }
+}
+
+/// Remembers which websites were flagged recently, so an anomalous hour is recorded once
+/// and not on every check within it
+pub struct AnomalyWatch {
+    sensitivity: f64,
+    flagged_at: HashMap<String, DateTime<Utc>>,
+}
+
+impl AnomalyWatch {
+    pub fn new(sensitivity: f64) -> Self {
+        Self {
+            sensitivity,
+            flagged_at: HashMap::new(),
+        }
+    }
+
+    pub fn judge(
+        &mut self,
+        alias: &str,
+        series: &[f64],
+        now: DateTime<Utc>,
+    ) -> Option<LatencyAnomaly> {
+        if self
+            .flagged_at
+            .get(alias)
+            .is_some_and(|flagged_at| now - *flagged_at < chrono::Duration::hours(1))
+        {
+            return None;
+        }
+
+        let anomaly = detect_latency_anomaly(series, self.sensitivity)?;
+        self.flagged_at.insert(alias.to_owned(), now);
+        Some(anomaly)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENSITIVITY: f64 = 3.0;
+    /// A week of hourly buckets followed by the last hour
+    const WEEK: usize = 7 * 24;
+
+    /// Deterministic normally distributed noise, xorshift and the sum of twelve uniforms
+    struct Noise(u64);
+
+    impl Noise {
+        fn uniform(&mut self) -> f64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn normal(&mut self, mean: f64, deviation: f64) -> f64 {
+            let sum: f64 = (0..12).map(|_| self.uniform()).sum();
+            mean + (sum - 6.0) * deviation
+        }
+    }
+
+    fn noisy_week(noise: &mut Noise) -> Vec<f64> {
+        (0..WEEK).map(|_| noise.normal(200.0, 20.0)).collect()
+    }
+
+    #[test]
+    fn a_step_change_is_flagged() {
+        let mut series = noisy_week(&mut Noise(7));
+        series.push(400.0);
+
+        let anomaly = detect_latency_anomaly(&series, SENSITIVITY).unwrap();
+
+        assert_eq!(anomaly.latency_ms, 400.0);
+        assert!((190.0..210.0).contains(&anomaly.baseline_median_ms));
+        assert!(anomaly.threshold_ms < 400.0);
+    }
+
+    #[test]
+    fn the_hour_after_a_step_change_into_the_baseline_is_still_flagged() {
+        let mut series = noisy_week(&mut Noise(11));
+        series.extend([400.0, 410.0]);
+
+        assert!(detect_latency_anomaly(&series, SENSITIVITY).is_some());
+    }
+
+    #[test]
+    fn a_gradual_drift_is_not_flagged() {
+        let series: Vec<f64> = (0..=WEEK).map(|hour| 200.0 + hour as f64 * 0.3).collect();
+
+        assert_eq!(detect_latency_anomaly(&series, SENSITIVITY), None);
+    }
+
+    #[test]
+    fn a_jump_at_the_end_of_a_drift_is_flagged() {
+        let mut series: Vec<f64> = (0..WEEK).map(|hour| 200.0 + hour as f64 * 0.3).collect();
+        series.push(600.0);
+
+        assert!(detect_latency_anomaly(&series, SENSITIVITY).is_some());
+    }
+
+    #[test]
+    fn noisy_but_normal_latency_is_rarely_flagged() {
+        let mut noise = Noise(42);
+        let flagged = (0..1000)
+            .filter(|_| {
+                let mut series = noisy_week(&mut noise);
+                series.push(noise.normal(200.0, 20.0));
+                detect_latency_anomaly(&series, SENSITIVITY).is_some()
+            })
+            .count();
+
+        assert!(flagged <= 10, "{flagged} of 1000 normal hours were flagged");
+    }
+
+    #[test]
+    fn jitter_on_a_flat_baseline_is_not_flagged() {
+        let mut series = vec![100.0; WEEK];
+        series.push(125.0);
+
+        assert_eq!(detect_latency_anomaly(&series, SENSITIVITY), None);
+    }
+
+    #[test]
+    fn a_larger_k_flags_less() {
+        let mut series = noisy_week(&mut Noise(3));
+        series.push(290.0);
+
+        assert!(detect_latency_anomaly(&series, 3.0).is_some());
+        assert_eq!(detect_latency_anomaly(&series, 6.0), None);
+    }
+
+    #[test]
+    fn websites_without_enough_history_are_not_judged() {
+        let mut series = vec![100.0; MIN_BASELINE_BUCKETS - 1];
+        series.push(10_000.0);
+
+        assert_eq!(detect_latency_anomaly(&series, SENSITIVITY), None);
+        assert_eq!(detect_latency_anomaly(&[], SENSITIVITY), None);
+    }
+
+    #[test]
+    fn an_anomalous_hour_is_recorded_once() {
+        let mut series = noisy_week(&mut Noise(5));
+        series.push(500.0);
+        let mut watch = AnomalyWatch::new(SENSITIVITY);
+        let now = Utc::now();
+
+        assert!(watch.judge("slow", &series, now).is_some());
+        assert_eq!(
+            watch.judge("slow", &series, now + chrono::Duration::minutes(59)),
+            None
+        );
+        assert!(watch.judge("other", &series, now).is_some());
+        assert!(
+            watch
+                .judge("slow", &series, now + chrono::Duration::hours(1))
+                .is_some()
+        );
+    }
+}
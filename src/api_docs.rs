@@ -0,0 +1,205 @@
+use crate::{
+    ChannelSummary, ClearedHistory, CurrentStatus, ErrorBody, Incident, RenameRequest,
+    RenamedWebsite, StatusOverrideRequest, Website, WebsiteStats, WebsiteSummary,
+    backup::{RestoreAction, RestoreItem, RestoreReport},
+    escalation::{EscalationRule, NewEscalationRule},
+    field_errors::FieldError,
+    heatmap::{Heatmap, HeatmapCell, HeatmapRow},
+    latency::{LatencyPercentiles, LatencyStats},
+    migrations::{Migration, MigrationStatus},
+    monitor_config::MonitorConfig,
+    public_status::{OverallState, PublicStatus, WebsiteStatus},
+    sla::{SlaReport, SlaSummary, WebsiteSla},
+    stats_range::{StatsSummary, UptimeStats},
+    status_codes::StatusCount,
+    trends::{Delta, MonthSummary, Trends},
+    website_import::{ExportedWebsite, ImportReport, RowReport, RowStatus},
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// The JSON API as generated from the handlers' `#[utoipa::path]` annotations, so it
+/// changes together with them
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::get_websites_json,
+        crate::create_website,
+        crate::update_website,
+        crate::rename_website,
+        crate::delete_website,
+        crate::get_website_by_id,
+        crate::delete_website_by_id,
+        crate::delete_website_form,
+        crate::restore_deleted_website,
+        crate::purge_website,
+        crate::clear_website_history,
+        crate::import_websites,
+        crate::export_websites,
+        crate::get_backup,
+        crate::restore_backup,
+        crate::get_heatmap_by_alias,
+        crate::get_current_status_by_alias,
+        crate::get_stats_by_alias,
+        crate::get_latency_by_alias,
+        crate::get_status_codes_by_alias,
+        crate::get_sla_by_alias,
+        crate::get_sla,
+        crate::get_incidents_by_alias,
+        crate::get_trends_by_alias,
+        crate::put_status_override,
+        crate::get_website_channels,
+        crate::put_website_channel,
+        crate::delete_website_channel,
+        crate::get_escalation_rules,
+        crate::create_escalation_rule,
+        crate::delete_escalation_rule,
+        crate::get_status_json,
+        crate::get_db_status,
+        crate::get_sqlite_backup,
+    ),
+    components(schemas(
+        ChannelSummary,
+        ClearedHistory,
+        CurrentStatus,
+        Delta,
+        ErrorBody,
+        EscalationRule,
+        ExportedWebsite,
+        FieldError,
+        Heatmap,
+        HeatmapCell,
+        HeatmapRow,
+        ImportReport,
+        Incident,
+        LatencyPercentiles,
+        LatencyStats,
+        Migration,
+        MigrationStatus,
+        MonitorConfig,
+        MonthSummary,
+        NewEscalationRule,
+        OverallState,
+        PublicStatus,
+        RenameRequest,
+        RenamedWebsite,
+        RestoreAction,
+        RestoreItem,
+        RestoreReport,
+        RowReport,
+        RowStatus,
+        SlaReport,
+        SlaSummary,
+        StatsSummary,
+        StatusCount,
+        StatusOverrideRequest,
+        Trends,
+        UptimeStats,
+        Website,
+        WebsiteSla,
+        WebsiteStats,
+        WebsiteStatus,
+        WebsiteSummary,
+    )),
+    tags(
+        (name = "websites", description = "Monitored websites and their statistics"),
+        (name = "backup", description = "The whole configuration, to move it elsewhere"),
+        (name = "status", description = "Public status for widgets and external tooling"),
+        (name = "admin", description = "Operating the server, needs the admin token"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Swagger UI at `/api/docs`, reading the spec served at `/api/openapi.json`
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn spec() -> Value {
+        serde_json::from_str(&ApiDoc::openapi().to_json().unwrap()).unwrap()
+    }
+
+    fn operation<'a>(spec: &'a Value, path: &str, method: &str) -> &'a Value {
+        let operation = &spec["paths"][path][method];
+        assert!(operation.is_object(), "{method} {path} isn't documented");
+        operation
+    }
+
+    fn parameter<'a>(operation: &'a Value, name: &str) -> &'a Value {
+        operation["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|parameter| parameter["name"] == name)
+            .unwrap_or_else(|| panic!("{name} isn't documented"))
+    }
+
+    #[test]
+    fn the_routes_are_documented() {
+        let spec = spec();
+
+        for (method, path) in [
+            ("post", "/websites"),
+            ("put", "/websites/{alias}"),
+            ("delete", "/websites/{alias}"),
+            ("post", "/websites/{alias}/rename"),
+            ("delete", "/websites/{alias}/logs"),
+            ("get", "/api/websites"),
+            ("get", "/api/websites/by-id/{id}"),
+            ("delete", "/api/websites/by-id/{id}"),
+            ("post", "/api/websites/import"),
+            ("get", "/api/websites/export"),
+            ("get", "/api/websites/{alias}/stats"),
+            ("get", "/api/websites/{alias}/status"),
+            ("get", "/api/websites/{alias}/incidents"),
+            ("get", "/api/websites/{alias}/latency"),
+            ("get", "/api/websites/{alias}/sla"),
+            ("get", "/api/websites/{alias}/trends"),
+            ("get", "/api/websites/{alias}/heatmap"),
+            ("put", "/api/websites/{alias}/status-override"),
+            ("get", "/api/websites/{alias}/notification-channels"),
+            ("get", "/api/websites/{alias}/escalation-rules"),
+            ("get", "/api/sla"),
+            ("get", "/api/backup"),
+            ("post", "/api/restore"),
+            ("get", "/status.json"),
+            ("get", "/api/admin/db"),
+        ] {
+            operation(&spec, path, method);
+        }
+    }
+
+    #[test]
+    fn path_and_query_parameters_are_documented() {
+        let spec = spec();
+        let stats = operation(&spec, "/api/websites/{alias}/stats", "get");
+
+        assert_eq!(parameter(stats, "alias")["in"], "path");
+        assert_eq!(parameter(stats, "alias")["required"], true);
+        assert_eq!(parameter(stats, "range")["in"], "query");
+    }
+
+    #[test]
+    fn error_responses_are_documented() {
+        let spec = spec();
+        let create = operation(&spec, "/websites", "post");
+        let stats = operation(&spec, "/api/websites/{alias}/stats", "get");
+
+        for status in ["400", "409", "422"] {
+            assert!(create["responses"][status].is_object(), "{status}");
+        }
+        for status in ["400", "404"] {
+            assert!(stats["responses"][status].is_object(), "{status}");
+        }
+        assert_eq!(
+            create["responses"]["409"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ErrorBody"
+        );
+        assert!(spec["components"]["schemas"]["Website"].is_object());
+    }
+}
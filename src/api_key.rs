@@ -0,0 +1,268 @@
+use crate::ApiError;
+use askama::Template;
+use axum::{
+    Form,
+    extract::{Request, State},
+    http::{
+        HeaderMap, Method, StatusCode,
+        header::{ACCEPT, AUTHORIZATION, COOKIE, SET_COOKIE},
+    },
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+use chacha20poly1305::aead::{OsRng, rand_core::RngCore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Set by `/login` to a random session token, so browsers can post the dashboard's forms
+const LOGIN_COOKIE: &str = "uptime_ferris_login";
+
+/// How long a login lasts, the cookie's Max-Age and the session's expiry alike
+const SESSION_LIFETIME: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Key every request that changes something has to carry, nothing is required when unset.
+/// Only the key's digest is kept, so comparisons take as long whatever is presented
+#[derive(Clone)]
+pub struct ApiKey {
+    digest: Option<[u8; 32]>,
+    /// Digests of the session tokens handed out by `/login` with their expiry. They live
+    /// in memory only, so a restart logs everyone out
+    sessions: Arc<Mutex<HashMap<[u8; 32], Instant>>>,
+}
+
+impl ApiKey {
+    pub fn new(api_key: Option<&str>) -> Self {
+        Self {
+            digest: api_key
+                .filter(|api_key| !api_key.is_empty())
+                .map(|api_key| Sha256::digest(api_key).into()),
+            sessions: Arc::default(),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    fn accepts(&self, headers: &HeaderMap) -> bool {
+        let Some(digest) = &self.digest else {
+            return true;
+        };
+
+        presented_keys(headers).any(|presented| {
+            let presented: [u8; 32] = Sha256::digest(presented).into();
+            equal_in_constant_time(digest, &presented)
+        })
+    }
+
+    /// The key as a header, or the login cookie of a session that hasn't expired
+    fn accepts_login(&self, headers: &HeaderMap) -> bool {
+        if self.digest.is_none() {
+            return true;
+        }
+
+        self.accepts(headers) || {
+            let sessions = self.sessions.lock().unwrap();
+            let now = Instant::now();
+            login_cookies(headers).any(|cookie| {
+                let presented: [u8; 32] = Sha256::digest(cookie).into();
+                sessions
+                    .get(&presented)
+                    .is_some_and(|expires_at| *expires_at > now)
+            })
+        }
+    }
+
+    /// A new random session token, expired sessions are dropped on the way
+    fn start_session(&self) -> String {
+        let mut bytes = [0; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex(&bytes);
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, expires_at| *expires_at > now);
+        sessions.insert(Sha256::digest(&token).into(), now + SESSION_LIFETIME);
+        token
+    }
+
+    fn end_sessions(&self, headers: &HeaderMap) {
+        let mut sessions = self.sessions.lock().unwrap();
+        for cookie in login_cookies(headers) {
+            let presented: [u8; 32] = Sha256::digest(cookie).into();
+            sessions.remove(&presented);
+        }
+    }
+}
+
+/// Lengths aren't secret, the digests always have the same one
+pub(crate) fn equal_in_constant_time(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `Authorization: Bearer <key>` and `X-Api-Key: <key>`, either may hold the key. Routes
+/// that take the admin token as a bearer token get the key as X-Api-Key next to it
+fn presented_keys(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(|authorization| authorization.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, key)| key.trim());
+    let api_key = headers
+        .get(API_KEY_HEADER)
+        .and_then(|api_key| api_key.to_str().ok());
+
+    bearer.into_iter().chain(api_key)
+}
+
+/// Every value of the login cookie, browsers may send the cookie header more than once
+fn login_cookies(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|cookies| cookies.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .filter(|(name, _)| *name == LOGIN_COOKIE)
+        .map(|(_, value)| value)
+}
+
+/// Lets reads through and refuses everything else without the key, routes added later
+/// are covered without having to opt in
+pub async fn require_api_key(
+    State(api_key): State<ApiKey>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let read_only = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    if read_only || api_key.accepts(request.headers()) {
+        return next.run(request).await;
+    }
+
+    ApiError::Unauthorized.into_response()
+}
+
+/// Refuses even reads without the key, for routes handing out everything configured
+pub async fn require_api_key_for_reads(
+    State(api_key): State<ApiKey>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if api_key.accepts(request.headers()) {
+        return next.run(request).await;
+    }
+
+    ApiError::Unauthorized.into_response()
+}
+
+/// `require_api_key` for the dashboard, where the login cookie stands in for the key.
+/// Browsers are sent to `/login` instead of getting a bare 401
+pub async fn require_login(
+    State(api_key): State<ApiKey>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let read_only = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    if read_only || api_key.accepts_login(request.headers()) {
+        return next.run(request).await;
+    }
+
+    let browser = request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    if browser {
+        return Redirect::to("/login").into_response();
+    }
+    ApiError::Unauthorized.into_response()
+}
+
+#[derive(Template)]
+#[template(path = "login.html")]
+pub struct LoginPage {
+    failed: bool,
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    api_key: String,
+}
+
+pub async fn login_page() -> LoginPage {
+    LoginPage { failed: false }
+}
+
+/// Whether the browser reached us over https, directly or through a proxy terminating TLS.
+/// A client claiming so over plain http only keeps its own cookie from coming back
+fn is_https(headers: &HeaderMap) -> bool {
+    headers
+        .get("X-Forwarded-Proto")
+        .and_then(|proto| proto.to_str().ok())
+        .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"))
+}
+
+/// The login cookie with `value`, HttpOnly keeps it from scripts and SameSite=Strict from
+/// forms on other sites
+fn login_cookie(value: &str, max_age: Duration, headers: &HeaderMap) -> String {
+    let secure = if is_https(headers) { "; Secure" } else { "" };
+    format!(
+        "{LOGIN_COOKIE}={value}; Max-Age={}; HttpOnly; SameSite=Strict; Path=/{secure}",
+        max_age.as_secs()
+    )
+}
+
+/// Trades the key for a cookie holding a session token, which is worth nothing once the
+/// session expires, the user logs out or the server restarts
+pub async fn login(
+    State(api_key): State<ApiKey>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    let Some(digest) = &api_key.digest else {
+        return Redirect::to("/").into_response();
+    };
+    let presented: [u8; 32] = Sha256::digest(&form.api_key).into();
+    if !equal_in_constant_time(digest, &presented) {
+        return (StatusCode::UNAUTHORIZED, LoginPage { failed: true }).into_response();
+    }
+
+    let token = api_key.start_session();
+    (
+        [(SET_COOKIE, login_cookie(&token, SESSION_LIFETIME, &headers))],
+        Redirect::to("/"),
+    )
+        .into_response()
+}
+
+/// Ends the session of the login cookie and has the browser drop it
+pub async fn logout(State(api_key): State<ApiKey>, headers: HeaderMap) -> Response {
+    api_key.end_sessions(&headers);
+
+    (
+        [(SET_COOKIE, login_cookie("", Duration::ZERO, &headers))],
+        Redirect::to("/login"),
+    )
+        .into_response()
+}
@@ -0,0 +1,377 @@
+use crate::{
+    ApiError, Website,
+    field_errors::FieldError,
+    manual_status::StatusOverride,
+    notifications::{routing::ChannelConfig, template::MessageTemplate},
+    website_import::{self, ExportedWebsite},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+/// Format written by this build, older documents are migrated when restored
+pub const CURRENT_VERSION: u32 = 1;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BackupParams {
+    /// Whether webhook URLs, channel settings and client certificate paths are included,
+    /// false when left out. Takes the admin token as a bearer token
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RestoreParams {
+    /// Reports what the restore would do and keeps none of it, false when left out
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Everything configured, without any logs. Restores match websites by alias and
+/// channels by name, so a backup can be restored into a fresh database or over the one
+/// it came from
+#[derive(Deserialize, Serialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Restores without the secrets keep the current ones of existing websites
+    pub includes_secrets: bool,
+    pub websites: Vec<BackupWebsite>,
+    pub notification_channels: Vec<BackupChannel>,
+    /// URLs of the webhooks every website notifies, only with the secrets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BackupWebsite {
+    /// The website's inputs as the export writes them, with the secrets also its own
+    /// webhooks and client certificate paths
+    #[serde(flatten)]
+    pub settings: Map<String, Value>,
+    /// Names of the channels it notifies instead of the defaults
+    #[serde(default)]
+    pub channels: Vec<String>,
+    #[serde(default)]
+    pub escalation_rules: Vec<BackupEscalationRule>,
+    /// An operator's status like maintenance, if one is in effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_override: Option<BackupStatusOverride>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BackupChannel {
+    pub name: String,
+    pub kind: String,
+    /// Where the channel delivers to and its message template, only with the secrets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BackupEscalationRule {
+    pub after_minutes: i32,
+    pub repeat_minutes: Option<i32>,
+    /// Name of the channel, the website's own channels when `None`
+    pub channel: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BackupStatusOverride {
+    pub status: String,
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// What a backup is made of, as read from the database
+pub struct BackupTables {
+    pub websites: Vec<Website>,
+    pub status_overrides: Vec<StatusOverride>,
+    /// Name, kind and stored config of every channel
+    pub channels: Vec<(String, String, String)>,
+    /// Alias and channel name of every assignment
+    pub assignments: Vec<(String, String)>,
+    /// Alias, after and repeat minutes and channel name of every rule
+    pub escalation_rules: Vec<(String, i32, Option<i32>, Option<String>)>,
+    pub webhooks: Vec<String>,
+}
+
+impl Backup {
+    pub fn new(tables: BackupTables, include_secrets: bool, now: DateTime<Utc>) -> Self {
+        let BackupTables {
+            websites,
+            status_overrides,
+            channels,
+            assignments,
+            escalation_rules,
+            webhooks,
+        } = tables;
+
+        Self {
+            version: CURRENT_VERSION,
+            created_at: now,
+            includes_secrets: include_secrets,
+            websites: websites
+                .into_iter()
+                .map(|website| {
+                    let alias = website.alias.clone();
+                    BackupWebsite {
+                        settings: website_settings(website, include_secrets),
+                        channels: assignments
+                            .iter()
+                            .filter(|(assigned, _)| *assigned == alias)
+                            .map(|(_, channel)| channel.clone())
+                            .collect(),
+                        escalation_rules: escalation_rules
+                            .iter()
+                            .filter(|(ruled, ..)| *ruled == alias)
+                            .map(|(_, after_minutes, repeat_minutes, channel)| {
+                                BackupEscalationRule {
+                                    after_minutes: *after_minutes,
+                                    repeat_minutes: *repeat_minutes,
+                                    channel: channel.clone(),
+                                }
+                            })
+                            .collect(),
+                        status_override: status_overrides
+                            .iter()
+                            .find(|status_override| status_override.alias == alias)
+                            .map(|status_override| BackupStatusOverride {
+                                status: status_override.status.clone(),
+                                note: status_override.note.clone(),
+                                expires_at: status_override.expires_at,
+                            }),
+                    }
+                })
+                .collect(),
+            notification_channels: channels
+                .into_iter()
+                .map(|(name, kind, config)| BackupChannel {
+                    name,
+                    kind,
+                    config: include_secrets
+                        .then(|| serde_json::from_str(&config).ok())
+                        .flatten(),
+                })
+                .collect(),
+            webhooks: if include_secrets {
+                webhooks
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Migrates older versions. Versions newer than this build are refused rather than
+    /// restored partly
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+
+        let Versioned { version } =
+            serde_json::from_str(json).map_err(|e| format!("Not a backup: {e}"))?;
+        match version {
+            CURRENT_VERSION => {
+                serde_json::from_str(json).map_err(|e| format!("Invalid backup: {e}"))
+            }
+            newer if newer > CURRENT_VERSION => Err(format!(
+                "Backup version {newer} is newer than the supported {CURRENT_VERSION}"
+            )),
+            unknown => Err(format!("Unknown backup version {unknown}")),
+        }
+    }
+
+    /// What restoring the backup takes, channels first as websites and rules refer to
+    /// them. Websites are read like submitted ones and checked with `prepare`, channel
+    /// settings like those of created channels
+    pub fn into_steps(
+        self,
+        prepare: impl Fn(Website) -> Result<Website, ApiError>,
+    ) -> Vec<RestoreStep> {
+        let channels = self
+            .notification_channels
+            .into_iter()
+            .map(|channel| RestoreStep::Channel {
+                settings: channel.config.map(|config| -> Result<_, String> {
+                    let template = MessageTemplate::from_config(&config)?;
+                    let config = ChannelConfig::from_value(&channel.kind, config)?;
+                    Ok((config.kind(), config.config_json(template.as_ref())))
+                }),
+                name: channel.name,
+            });
+        let webhooks = self.webhooks.into_iter().map(RestoreStep::Webhook);
+        let websites = self.websites.into_iter().map(|website| {
+            RestoreStep::Website(Box::new(RestoredWebsite {
+                alias: website
+                    .settings
+                    .get("alias")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                website: website_import::website_from_json(website.settings)
+                    .map_err(ApiError::BadRequest)
+                    .and_then(&prepare),
+                channels: website.channels,
+                escalation_rules: website.escalation_rules,
+                status_override: website.status_override,
+            }))
+        });
+
+        channels.chain(webhooks).chain(websites).collect()
+    }
+}
+
+/// The export's fields, with the secrets added when asked for
+fn website_settings(website: Website, include_secrets: bool) -> Map<String, Value> {
+    let secrets = [
+        ("slack_webhook_url", website.slack_webhook_url.clone()),
+        ("discord_webhook_url", website.discord_webhook_url.clone()),
+        ("client_cert_path", website.client_cert_path.clone()),
+        ("client_key_path", website.client_key_path.clone()),
+    ];
+    let Ok(Value::Object(mut settings)) = serde_json::to_value(ExportedWebsite::from(website))
+    else {
+        return Map::new();
+    };
+    if include_secrets {
+        for (field, secret) in secrets {
+            if let Some(secret) = secret {
+                settings.insert(field.to_owned(), Value::String(secret));
+            }
+        }
+    }
+    settings
+}
+
+/// One thing a restore writes, reported on its own
+pub enum RestoreStep {
+    Channel {
+        name: String,
+        /// Kind and config as they're stored, `None` when the backup has no settings
+        settings: Option<Result<(&'static str, String), String>>,
+    },
+    Webhook(String),
+    Website(Box<RestoredWebsite>),
+}
+
+impl RestoreStep {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Channel { .. } => "notification_channel",
+            Self::Webhook(_) => "webhook",
+            Self::Website(_) => "website",
+        }
+    }
+
+    /// How the report names it
+    pub fn name(&self) -> String {
+        match self {
+            Self::Channel { name, .. } => name.clone(),
+            Self::Webhook(url) => url.clone(),
+            Self::Website(restored) => restored.alias.clone(),
+        }
+    }
+}
+
+pub struct RestoredWebsite {
+    pub alias: String,
+    pub website: Result<Website, ApiError>,
+    pub channels: Vec<String>,
+    pub escalation_rules: Vec<BackupEscalationRule>,
+    pub status_override: Option<BackupStatusOverride>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreAction {
+    Created,
+    Updated,
+    /// Already there as in the backup
+    Unchanged,
+    Invalid,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RestoreItem {
+    /// website, notification_channel or webhook
+    pub kind: &'static str,
+    /// Alias, channel name or webhook URL
+    pub name: String,
+    pub action: RestoreAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// What happened to everything in the backup, channels first, then webhooks and websites
+#[derive(Serialize, ToSchema)]
+pub struct RestoreReport {
+    pub dry_run: bool,
+    /// Never for dry runs, and only when nothing was invalid
+    pub committed: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub invalid: usize,
+    pub items: Vec<RestoreItem>,
+}
+
+impl RestoreReport {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            committed: false,
+            created: 0,
+            updated: 0,
+            invalid: 0,
+            items: Vec::new(),
+        }
+    }
+
+    /// Items the backup got wrong are reported, anything else fails the whole restore
+    pub fn record(
+        &mut self,
+        kind: &'static str,
+        name: String,
+        outcome: Result<RestoreAction, ApiError>,
+    ) -> Result<(), ApiError> {
+        let (action, reason, fields) = match outcome {
+            Ok(action) => (action, None, Vec::new()),
+            Err(ApiError::Conflict(reason) | ApiError::BadRequest(reason)) => {
+                (RestoreAction::Invalid, Some(reason), Vec::new())
+            }
+            Err(ApiError::Invalid(fields)) => (RestoreAction::Invalid, None, fields),
+            Err(e) => return Err(e),
+        };
+        match action {
+            RestoreAction::Created => self.created += 1,
+            RestoreAction::Updated => self.updated += 1,
+            RestoreAction::Unchanged => {}
+            RestoreAction::Invalid => self.invalid += 1,
+        }
+        self.items.push(RestoreItem {
+            kind,
+            name,
+            action,
+            reason,
+            fields,
+        });
+
+        Ok(())
+    }
+
+    /// Whether what was written is kept
+    pub fn should_commit(&self) -> bool {
+        !self.dry_run && self.invalid == 0
+    }
+
+    pub fn finish(mut self, committed: bool) -> Self {
+        self.committed = committed;
+        self
+    }
+}
@@ -0,0 +1,126 @@
+use crate::manual_status::ManualStatus;
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    http::{HeaderValue, header},
+    response::Response,
+};
+use serde::Deserialize;
+
+const GREEN: &str = "#4c1";
+const RED: &str = "#e05d44";
+const GREY: &str = "#9f9f9f";
+const YELLOW: &str = "#dfb317";
+const BLUE: &str = "#007ec6";
+
+/// How long proxies like GitHub's camo may serve a badge before fetching it again
+const CACHE_CONTROL: &str = "max-age=60, s-maxage=60";
+
+/// Websites below this 24h uptime get a red uptime badge
+const UPTIME_GREEN_FROM: f64 = 99.0;
+
+/// Width of a character in the badge's 11px Verdana, close enough for the usual aliases
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+/// `?style=uptime` shows the 24h uptime instead of the latest check
+#[derive(Deserialize)]
+pub struct BadgeParams {
+    style: Option<String>,
+}
+
+impl BadgeParams {
+    pub fn uptime(&self) -> bool {
+        self.style.as_deref() == Some("uptime")
+    }
+}
+
+/// A shields.io style badge, the alias on the left and its state on the right
+#[derive(Template)]
+#[template(path = "badge.svg", escape = "html")]
+pub struct Badge {
+    pub label: String,
+    pub value: String,
+    pub color: &'static str,
+}
+
+impl Badge {
+    /// From the status of the latest check, `None` before the first one
+    pub fn status(alias: &str, status: Option<i16>) -> Self {
+        let (value, color) = match status {
+            Some(200) => ("up", GREEN),
+            Some(_) => ("down", RED),
+            None => ("unknown", GREY),
+        };
+        Self::new(alias, value.to_owned(), color)
+    }
+
+    /// An operator's status in place of the latest check, marked so it isn't taken for
+    /// a measured one
+    pub fn manual(alias: &str, manual_status: ManualStatus) -> Self {
+        let (value, color) = match manual_status {
+            ManualStatus::Degraded => ("degraded", YELLOW),
+            ManualStatus::Maintenance => ("maintenance", BLUE),
+            ManualStatus::MajorOutage => ("major outage", RED),
+        };
+        Self::new(alias, format!("{value} (manual)"), color)
+    }
+
+    /// From the uptime in percent, `None` without checks in the last 24h
+    pub fn uptime(alias: &str, uptime_pct: Option<f64>) -> Self {
+        match uptime_pct {
+            Some(uptime_pct) if uptime_pct >= UPTIME_GREEN_FROM => {
+                Self::new(alias, format!("{uptime_pct:.2}%"), GREEN)
+            }
+            Some(uptime_pct) => Self::new(alias, format!("{uptime_pct:.2}%"), RED),
+            None => Self::unknown(alias),
+        }
+    }
+
+    /// For aliases without a website, so embedded images don't break
+    pub fn unknown(alias: &str) -> Self {
+        Self::new(alias, "unknown".to_owned(), GREY)
+    }
+
+    fn new(alias: &str, value: String, color: &'static str) -> Self {
+        Self {
+            label: alias.to_owned(),
+            value,
+            color,
+        }
+    }
+
+    pub fn label_width(&self) -> usize {
+        self.label.chars().count() * CHAR_WIDTH + PADDING
+    }
+
+    pub fn value_width(&self) -> usize {
+        self.value.chars().count() * CHAR_WIDTH + PADDING
+    }
+
+    pub fn width(&self) -> usize {
+        self.label_width() + self.value_width()
+    }
+
+    pub fn label_x(&self) -> usize {
+        self.label_width() / 2
+    }
+
+    pub fn value_x(&self) -> usize {
+        self.label_width() + self.value_width() / 2
+    }
+
+    pub fn into_svg_response(self) -> Response {
+        let mut response = self.into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("image/svg+xml; charset=utf-8"),
+        );
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL),
+        );
+        response
+    }
+}
@@ -0,0 +1,98 @@
+use reqwest::header::{HeaderMap, HeaderName};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Headers kept from every non-200 response, the ones CDNs and proxies identify
+/// themselves and their cache with. `--capture-headers` adds to them
+pub const DEFAULT_CAPTURED_HEADERS: [&str; 7] = [
+    "server",
+    "via",
+    "x-cache",
+    "retry-after",
+    "content-type",
+    "cf-ray",
+    "x-served-by",
+];
+
+/// Most bytes of names and values kept per check, headers past it are left out
+const MAX_CAPTURED_BYTES: usize = 2048;
+
+/// Headers failed checks keep
+#[derive(Clone)]
+pub struct CapturedHeaders(Vec<HeaderName>);
+
+impl CapturedHeaders {
+    /// Refuses extra names that aren't valid header names, duplicates are dropped
+    pub fn new(extra: &[String]) -> Result<Self, String> {
+        let mut names: Vec<HeaderName> = DEFAULT_CAPTURED_HEADERS
+            .into_iter()
+            .map(HeaderName::from_static)
+            .collect();
+        for name in extra
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            let name = HeaderName::try_from(name)
+                .map_err(|e| format!("'{name}' is no header name: {e}"))?;
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        Ok(Self(names))
+    }
+
+    /// The listed headers the response carried as a JSON object, `None` when it carried
+    /// none of them. Repeated headers are joined with ", "
+    pub fn capture(&self, headers: &HeaderMap) -> Option<String> {
+        let mut captured = BTreeMap::new();
+        let mut bytes = 0;
+        for name in &self.0 {
+            let values: Vec<_> = headers
+                .get_all(name)
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+            let value = values.join(", ");
+            if bytes + name.as_str().len() + value.len() > MAX_CAPTURED_BYTES {
+                continue;
+            }
+            bytes += name.as_str().len() + value.len();
+            captured.insert(name.as_str(), value);
+        }
+
+        (!captured.is_empty())
+            .then(|| serde_json::to_string(&captured).expect("A map of strings serializes"))
+    }
+}
+
+/// Headers a failed check captured, read back from their JSON column
+#[derive(Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct ResponseHeaders(BTreeMap<String, String>);
+
+impl ResponseHeaders {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+}
+
+impl TryFrom<Option<String>> for ResponseHeaders {
+    type Error = String;
+
+    fn try_from(json: Option<String>) -> Result<Self, Self::Error> {
+        match json {
+            Some(json) => serde_json::from_str(&json)
+                .map(Self)
+                .map_err(|e| format!("invalid captured response headers: {e}")),
+            None => Ok(Self::default()),
+        }
+    }
+}
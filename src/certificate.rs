@@ -0,0 +1,116 @@
+use crate::{
+    Website,
+    notifications::{Transition, Warning},
+};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use std::collections::HashMap;
+
+/// Days before expiry a warning goes out, each once per certificate
+const WARNING_DAYS: [i64; 4] = [30, 14, 7, 1];
+
+/// The certificate a website's server presented
+pub struct PeerCertificate {
+    /// Colon separated hex, tells a renewed certificate from the old one
+    pub serial: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// `None` for anything x509-parser can't make sense of
+pub fn parse_peer_certificate(der: &[u8]) -> Option<PeerCertificate> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(der).ok()?;
+    Some(PeerCertificate {
+        serial: certificate.raw_serial_as_string(),
+        expires_at: DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)?,
+    })
+}
+
+/// Evaluates each website's certificate at most once a day, the checker keeps one for
+/// its whole lifetime
+#[derive(Default)]
+pub struct CertificateWatch {
+    evaluated: HashMap<String, NaiveDate>,
+}
+
+impl CertificateWatch {
+    /// Whether the certificate should be looked at now, websites expected to be offline
+    /// and plain http ones never are
+    pub fn is_due(&mut self, website: &Website, now: DateTime<Utc>) -> bool {
+        if website.invert || !website.url.starts_with("https://") {
+            return false;
+        }
+        let today = now.date_naive();
+        if self.evaluated.get(&website.alias) == Some(&today) {
+            return false;
+        }
+        self.evaluated.insert(website.alias.clone(), today);
+        true
+    }
+
+    /// Forgets websites that were deleted in the meantime
+    pub fn retain(&mut self, websites: &[Website]) {
+        self.evaluated
+            .retain(|alias, _| websites.iter().any(|website| &website.alias == alias));
+    }
+}
+
+/// What to store for a website's certificate and whether to warn about it
+pub struct CertificateUpdate {
+    /// Tightest threshold already warned about for the current certificate
+    pub notified_days: Option<i32>,
+    /// Threshold just crossed
+    pub warning: Option<i32>,
+}
+
+/// `None` when the stored state is still accurate, a different serial or expiry means
+/// the certificate was renewed and every threshold applies again
+pub fn evaluate(
+    website: &Website,
+    certificate: &PeerCertificate,
+    now: DateTime<Utc>,
+) -> Option<CertificateUpdate> {
+    let renewed = website.tls_cert_serial.as_deref() != Some(certificate.serial.as_str())
+        || website.tls_cert_expires_at != Some(certificate.expires_at);
+    let notified_days = website.tls_expiry_notified_days.filter(|_| !renewed);
+    let remaining = certificate.expires_at - now;
+    let warning = WARNING_DAYS
+        .into_iter()
+        .filter(|days| remaining <= TimeDelta::days(*days))
+        .min()
+        .map(|days| days as i32)
+        .filter(|days| notified_days.is_none_or(|notified| *days < notified));
+    if !renewed && warning.is_none() {
+        return None;
+    }
+
+    Some(CertificateUpdate {
+        notified_days: warning.or(notified_days),
+        warning,
+    })
+}
+
+/// Sent through the website's channels like a transition, the status stays what the
+/// check found
+pub fn expiry_warning(
+    website: &Website,
+    certificate: &PeerCertificate,
+    status: i16,
+    now: DateTime<Utc>,
+) -> Transition {
+    Transition {
+        alias: website.alias.clone(),
+        url: website.url.clone(),
+        old_status: status,
+        new_status: status,
+        timestamp: now,
+        since: now,
+        duration: 0,
+        inverted: false,
+        shared_host: None,
+        affected: Vec::new(),
+        reminder: false,
+        warning: Some(Warning::CertificateExpiry {
+            certificate_expires_at: certificate.expires_at,
+        }),
+        message: None,
+    }
+}
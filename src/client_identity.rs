@@ -0,0 +1,137 @@
+use crate::Website;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+
+/// Nonce length of ChaCha20-Poly1305, stored in front of the ciphertext
+const NONCE_LEN: usize = 12;
+
+/// Encrypts uploaded client keys and certificates at rest, the cipher key is derived
+/// from `--secrets-key`
+#[derive(Clone)]
+pub struct Secrets {
+    cipher: Option<ChaCha20Poly1305>,
+}
+
+impl Secrets {
+    pub fn new(secrets_key: Option<&str>) -> Self {
+        Self {
+            cipher: secrets_key
+                .filter(|secrets_key| !secrets_key.is_empty())
+                .map(|secrets_key| ChaCha20Poly1305::new(&Sha256::digest(secrets_key))),
+        }
+    }
+
+    fn cipher(&self) -> Result<&ChaCha20Poly1305, String> {
+        self.cipher
+            .as_ref()
+            .ok_or_else(|| "no --secrets-key is configured".to_owned())
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
+        let cipher = self.cipher()?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| format!("encryption failed: {e}"))?;
+
+        Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, String> {
+        let cipher = self.cipher()?;
+        let bytes = STANDARD
+            .decode(encrypted)
+            .map_err(|e| format!("stored secret isn't valid base64: {e}"))?;
+        if bytes.len() < NONCE_LEN {
+            return Err("stored secret is truncated".to_owned());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "stored secret can't be decrypted with this --secrets-key".to_owned())?;
+
+        String::from_utf8(plaintext).map_err(|e| e.to_string())
+    }
+}
+
+/// What the UI shows of a client certificate, the PEM itself is never rendered back
+pub struct CertificateSummary {
+    /// SHA-256 over the DER encoded certificate, colon separated hex
+    pub fingerprint: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub fn summarize_certificate(cert_pem: &str) -> Result<CertificateSummary, String> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(cert_pem.as_bytes())
+        .map_err(|e| format!("not a PEM certificate: {e}"))?;
+    let certificate = pem
+        .parse_x509()
+        .map_err(|e| format!("not an X.509 certificate: {e}"))?;
+    let expires_at = DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)
+        .ok_or_else(|| "certificate expiry is out of range".to_owned())?;
+
+    Ok(CertificateSummary {
+        fingerprint: Sha256::digest(&pem.contents)
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+        expires_at,
+    })
+}
+
+/// Client certificate and key a website is checked with, read from the configured
+/// files or decrypted from the uploaded copies
+pub fn load_identity(
+    website: &Website,
+    secrets: &Secrets,
+) -> Result<Option<reqwest::Identity>, String> {
+    let (cert_pem, key_pem) = match (
+        &website.client_cert_path,
+        &website.client_key_path,
+        &website.client_cert_encrypted,
+        &website.client_key_encrypted,
+    ) {
+        (Some(cert_path), Some(key_path), _, _) => (read_pem(cert_path)?, read_pem(key_path)?),
+        (_, _, Some(cert_encrypted), Some(key_encrypted)) => (
+            secrets.decrypt(cert_encrypted)?,
+            secrets.decrypt(key_encrypted)?,
+        ),
+        _ => return Ok(None),
+    };
+
+    reqwest::Identity::from_pkcs8_pem(cert_pem.as_bytes(), key_pem.as_bytes())
+        .map(Some)
+        .map_err(|e| format!("invalid client certificate or key: {e}"))
+}
+
+pub fn read_pem(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("reading {path} failed: {e}"))
+}
+
+/// Tells handshakes that failed over the client certificate apart from other failures
+pub fn classify_tls_failure(error: &reqwest::Error) -> Option<&'static str> {
+    let mut source: Option<&dyn Error> = Some(error);
+    while let Some(error) = source {
+        let message = error.to_string().to_lowercase();
+        if message.contains("certificate required") {
+            return Some("server requires a client certificate");
+        }
+        if message.contains("bad certificate")
+            || message.contains("unknown ca")
+            || message.contains("certificate unknown")
+            || message.contains("access denied")
+        {
+            return Some("client certificate was rejected");
+        }
+        source = error.source();
+    }
+
+    None
+}
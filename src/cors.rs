@@ -0,0 +1,51 @@
+use axum::http::{
+    HeaderName, HeaderValue, Method,
+    header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
+
+/// How long browsers may reuse a preflight answer
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// CORS for the JSON API, so widgets on other domains can call it. `None` without any
+/// allowed origin, the API then answers without CORS headers like before.
+///
+/// The API key headers are always allowed, browsers would otherwise refuse to send them
+/// in the first place. No credentials are allowed, the key is no cookie
+pub fn layer(allowed_origins: &[String]) -> Option<CorsLayer> {
+    let origins = allowed_origins
+        .iter()
+        .map(|origin| origin.trim().trim_end_matches('/'))
+        .filter(|origin| !origin.is_empty())
+        .collect::<Vec<_>>();
+    if origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if origins.contains(&"*") {
+        warn!(
+            "--cors-allowed-origins allows any website to call the API, list the origins instead"
+        );
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.into_iter().map(|origin| {
+            HeaderValue::from_str(origin)
+                .unwrap_or_else(|_| panic!("--cors-allowed-origins has an invalid origin {origin}"))
+        }))
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+            .allow_headers([
+                AUTHORIZATION,
+                CONTENT_TYPE,
+                IF_NONE_MATCH,
+                HeaderName::from_static("x-api-key"),
+            ])
+            .expose_headers([ETAG, HeaderName::from_static("x-total-count")])
+            .max_age(PREFLIGHT_MAX_AGE),
+    )
+}
@@ -0,0 +1,74 @@
+use crate::store::{CheckRow, Store};
+use axum::body::Body;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::{Stream, StreamExt, stream};
+use std::{fmt::Write, sync::Arc};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Rows are sent on once this many bytes of them are together
+const CHUNK_BYTES: usize = 16 * 1024;
+
+/// Chunks the export gets ahead of the client at most
+const CHUNK_BACKLOG: usize = 4;
+
+const HEADER: &str = "timestamp,status,latency_ms\n";
+
+type Chunk = Result<String, sqlx::Error>;
+
+/// Every check of the website from `from` until before `to` as CSV, read from the
+/// database while the client downloads it instead of all at once
+pub(crate) fn logs_csv(
+    store: Arc<dyn Store>,
+    alias: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Body {
+    let (sender, receiver) = mpsc::channel(CHUNK_BACKLOG);
+    tokio::spawn(async move {
+        if sender.send(Ok(HEADER.to_owned())).await.is_err() {
+            return;
+        }
+        send_rows(store.window_logs(&alias, from, to), &sender).await;
+    });
+
+    Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    }))
+}
+
+/// Stops early when the client went away, a failing query ends the download with an
+/// error instead of a truncated file that looks complete
+async fn send_rows(
+    mut rows: impl Stream<Item = Result<CheckRow, sqlx::Error>> + Unpin,
+    sender: &mpsc::Sender<Chunk>,
+) {
+    let mut chunk = String::new();
+    while let Some(row) = rows.next().await {
+        match row {
+            Ok((checked_at, status, latency_ms)) => {
+                let _ = writeln!(
+                    chunk,
+                    "{},{status},{}",
+                    checked_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    latency_ms
+                        .map(|latency_ms| latency_ms.to_string())
+                        .unwrap_or_default()
+                );
+                if chunk.len() >= CHUNK_BYTES
+                    && sender.send(Ok(std::mem::take(&mut chunk))).await.is_err()
+                {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!("Exporting logs failed: {e}");
+                let _ = sender.send(Err(e)).await;
+                return;
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        let _ = sender.send(Ok(chunk)).await;
+    }
+}
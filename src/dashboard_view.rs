@@ -0,0 +1,195 @@
+use crate::public_status::StatusSummary;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    /// Websites with an open incident first, then by alias
+    #[default]
+    DownFirst,
+    Alias,
+    /// Lowest uptime over the last 24 hours first
+    Uptime,
+    /// Most recent failed check first
+    LastIncident,
+}
+
+impl SortBy {
+    const ALL: [SortBy; 4] = [
+        SortBy::DownFirst,
+        SortBy::Alias,
+        SortBy::Uptime,
+        SortBy::LastIncident,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortBy::DownFirst => "down_first",
+            SortBy::Alias => "alias",
+            SortBy::Uptime => "uptime",
+            SortBy::LastIncident => "last_incident",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SortBy::DownFirst => "Down first",
+            SortBy::Alias => "Alias",
+            SortBy::Uptime => "Uptime",
+            SortBy::LastIncident => "Last incident",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusFilter {
+    #[default]
+    All,
+    /// Only websites with an open incident
+    Down,
+}
+
+/// `?sort=&status=` of the dashboard
+#[derive(Default, Deserialize)]
+pub struct DashboardParams {
+    /// down_first, alias, uptime or last_incident, down_first when left out
+    sort: Option<String>,
+    /// down for only the websites that are down, all of them when left out
+    status: Option<String>,
+}
+
+impl DashboardParams {
+    pub fn view(&self) -> Result<DashboardView, String> {
+        let sort = match self.sort.as_deref() {
+            None => SortBy::default(),
+            Some(sort) => SortBy::ALL
+                .into_iter()
+                .find(|sort_by| sort_by.as_str() == sort)
+                .ok_or_else(|| {
+                    format!(
+                        "Unsupported sort '{sort}', use down_first, alias, uptime or last_incident"
+                    )
+                })?,
+        };
+        let status = match self.status.as_deref() {
+            None | Some("all") => StatusFilter::All,
+            Some("down") => StatusFilter::Down,
+            Some(other) => return Err(format!("Unsupported status '{other}', use all or down")),
+        };
+
+        Ok(DashboardView { sort, status })
+    }
+}
+
+/// One of the dashboard's sort options, linking to the first page sorted by it
+#[derive(Clone, Serialize)]
+pub struct SortLink {
+    pub label: &'static str,
+    pub href: String,
+    pub selected: bool,
+}
+
+/// How the websites on the dashboard are ordered and which of them are shown
+#[derive(Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DashboardView {
+    pub sort: SortBy,
+    pub status: StatusFilter,
+}
+
+impl DashboardView {
+    /// Sorting by uptime or last incident takes the aggregated status of every website
+    pub fn needs_summaries(&self) -> bool {
+        matches!(self.sort, SortBy::Uptime | SortBy::LastIncident)
+    }
+
+    pub fn only_down(&self) -> bool {
+        self.status == StatusFilter::Down
+    }
+
+    /// The parameters that differ from the defaults, each starting with `&` so they can
+    /// go behind the page parameters
+    pub fn query(&self) -> String {
+        let mut query = String::new();
+        if self.sort != SortBy::default() {
+            query.push_str("&sort=");
+            query.push_str(self.sort.as_str());
+        }
+        if self.only_down() {
+            query.push_str("&status=down");
+        }
+        query
+    }
+
+    /// Takes `per_page` by reference like the templates hand their fields to methods
+    pub fn sort_links(&self, per_page: &u32) -> Vec<SortLink> {
+        SortBy::ALL
+            .into_iter()
+            .map(|sort| SortLink {
+                label: sort.label(),
+                href: DashboardView { sort, ..*self }.href(*per_page),
+                selected: sort == self.sort,
+            })
+            .collect()
+    }
+
+    /// Where the down only filter is toggled, keeping the sort
+    pub fn status_toggle_href(&self, per_page: &u32) -> String {
+        let status = match self.status {
+            StatusFilter::All => StatusFilter::Down,
+            StatusFilter::Down => StatusFilter::All,
+        };
+        DashboardView { status, ..*self }.href(*per_page)
+    }
+
+    fn href(&self, per_page: u32) -> String {
+        format!("/?page=1&per_page={per_page}{}", self.query())
+    }
+
+    /// Drops the websites the status filter hides and sorts the rest, ties and websites
+    /// without data always go by alias
+    pub fn arrange<T>(
+        &self,
+        mut websites: Vec<T>,
+        alias: impl Fn(&T) -> &str,
+        is_down: impl Fn(&str) -> bool,
+        summaries: &[StatusSummary],
+    ) -> Vec<T> {
+        if self.only_down() {
+            websites.retain(|website| is_down(alias(website)));
+        }
+        let summary = |alias: &str| summaries.iter().find(|summary| summary.alias == alias);
+        match self.sort {
+            SortBy::DownFirst => websites.sort_by(|a, b| {
+                let (a, b) = (alias(a), alias(b));
+                (!is_down(a), a).cmp(&(!is_down(b), b))
+            }),
+            SortBy::Alias => websites.sort_by(|a, b| alias(a).cmp(alias(b))),
+            SortBy::Uptime => websites.sort_by(|a, b| {
+                let (a, b) = (alias(a), alias(b));
+                let uptime = |alias| summary(alias).and_then(|summary| summary.uptime_24h);
+                match (uptime(a), uptime(b)) {
+                    (Some(uptime_a), Some(uptime_b)) => {
+                        uptime_a.total_cmp(&uptime_b).then_with(|| a.cmp(b))
+                    }
+                    (uptime_a, uptime_b) => uptime_b
+                        .is_some()
+                        .cmp(&uptime_a.is_some())
+                        .then_with(|| a.cmp(b)),
+                }
+            }),
+            SortBy::LastIncident => websites.sort_by(|a, b| {
+                let (a, b) = (alias(a), alias(b));
+                let last_incident =
+                    |alias| summary(alias).and_then(|summary| summary.last_incident);
+                (last_incident(a).is_none(), Reverse(last_incident(a)), a).cmp(&(
+                    last_incident(b).is_none(),
+                    Reverse(last_incident(b)),
+                    b,
+                ))
+            }),
+        }
+        websites
+    }
+}
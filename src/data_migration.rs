@@ -0,0 +1,322 @@
+use crate::{AppState, argument_parsing::MigrateDataArgs, postgres_queries, sqlite_queries};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, SqlitePool, sqlite::SqliteConnectOptions};
+use std::{collections::HashMap, fmt, str::FromStr};
+use tracing::{error, info, warn};
+
+/// Rows read and written per transaction
+const BATCH_SIZE: i64 = 1000;
+
+#[derive(sqlx::FromRow)]
+struct CopiedWebsite {
+    id: i64,
+    url: String,
+    alias: String,
+    created_at: Option<DateTime<Utc>>,
+    ip_version: String,
+    impact_weight: String,
+    config: String,
+    monitor_type: String,
+    client_cert_path: Option<String>,
+    client_key_path: Option<String>,
+    client_cert_encrypted: Option<String>,
+    client_key_encrypted: Option<String>,
+    client_cert_fingerprint: Option<String>,
+    client_cert_expires_at: Option<DateTime<Utc>>,
+    slack_webhook_url: Option<String>,
+    discord_webhook_url: Option<String>,
+    invert: bool,
+    alert_email_to: Option<String>,
+    tls_cert_serial: Option<String>,
+    tls_cert_expires_at: Option<DateTime<Utc>>,
+    tls_expiry_notified_days: Option<i32>,
+    status_override: Option<String>,
+    status_override_note: Option<String>,
+    status_override_expires_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+    retention_days: Option<i32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CopiedLog {
+    id: i64,
+    website_id: i64,
+    status: Option<i16>,
+    created_at: DateTime<Utc>,
+    ip_family: Option<String>,
+    impact: Option<String>,
+    response_time_ms: Option<i32>,
+    connection_reused: Option<bool>,
+    degraded: Option<bool>,
+    error: Option<String>,
+    reason_phrase: Option<String>,
+    server_header: Option<String>,
+    response_headers: Option<String>,
+    content_hash: Option<String>,
+    acknowledged_by: Option<String>,
+    acknowledged_at: Option<DateTime<Utc>>,
+    ack_escalated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CopiedBucket {
+    id: i64,
+    website_id: i64,
+    bucket_start: DateTime<Utc>,
+    checks: i32,
+    successes: i32,
+    degraded: i32,
+    degraded_successes: i32,
+    avg_latency: Option<f64>,
+}
+
+/// Rows of one table, those the destination had already are skipped rather than copied
+#[derive(Default)]
+struct TableCount {
+    copied: u64,
+    skipped: u64,
+}
+
+impl TableCount {
+    fn add(&mut self, rows_affected: u64) {
+        match rows_affected {
+            0 => self.skipped += 1,
+            _ => self.copied += 1,
+        }
+    }
+}
+
+impl fmt::Display for TableCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} copied, {} already there", self.copied, self.skipped)
+    }
+}
+
+/// `migrate-data`, exiting with 1 when a database can't be opened or the copy fails.
+/// Running it again after a failure picks up where it stopped, everything copied before
+/// is skipped
+pub async fn run(args: MigrateDataArgs) {
+    if let Err(e) = copy(args).await {
+        error!("{e}");
+        std::process::exit(1);
+    }
+}
+
+async fn copy(args: MigrateDataArgs) -> Result<(), String> {
+    let options = if args.from.starts_with("sqlite:") {
+        SqliteConnectOptions::from_str(&args.from)
+            .map_err(|e| format!("--from is no valid connection string: {e}"))?
+    } else {
+        SqliteConnectOptions::new().filename(&args.from)
+    };
+    let source = SqlitePool::connect_with(options)
+        .await
+        .map_err(|e| format!("Opening the Sqlite database {} failed: {e}", args.from))?;
+    let destination = PgPool::connect(&args.to)
+        .await
+        .map_err(|e| format!("Connecting to Postgres failed: {e}"))?;
+
+    info!("Bringing both databases to the current schema");
+    AppState::Sqlite(source.clone()).migrate_db(None).await?;
+    AppState::Postgres(destination.clone())
+        .migrate_db(None)
+        .await?;
+
+    let has_websites: bool = sqlx::query_scalar(postgres_queries::SELECT_ANY_WEBSITE)
+        .fetch_one(&destination)
+        .await
+        .map_err(|e| format!("Looking for websites in Postgres failed: {e}"))?;
+    if has_websites && !args.force {
+        return Err(
+            "Postgres has websites already, --force copies into it anyway and skips the \
+            websites whose alias it has for another website"
+                .to_owned(),
+        );
+    }
+
+    let (websites, ids) = copy_websites(&source, &destination)
+        .await
+        .map_err(|e| format!("Copying the websites failed: {e}"))?;
+    let logs = copy_logs(&source, &destination, &ids)
+        .await
+        .map_err(|e| format!("Copying the checks failed: {e}"))?;
+    let buckets = copy_stats_rollup(&source, &destination, &ids)
+        .await
+        .map_err(|e| format!("Copying the hourly stats failed: {e}"))?;
+
+    println!("Websites: {websites}");
+    println!("Logs: {logs}");
+    println!("StatsRollup: {buckets}");
+    Ok(())
+}
+
+/// Returns the Postgres id of every Sqlite website this run or an earlier one copied. A
+/// website whose alias Postgres has for another one is left out, so its checks and
+/// stats are skipped instead of landing on the other website
+async fn copy_websites(
+    source: &SqlitePool,
+    destination: &PgPool,
+) -> Result<(TableCount, HashMap<i64, i32>), sqlx::Error> {
+    let websites = sqlx::query_as::<_, CopiedWebsite>(sqlite_queries::SELECT_WEBSITES_TO_COPY)
+        .fetch_all(source)
+        .await?;
+
+    let mut count = TableCount::default();
+    let mut ids = HashMap::new();
+    let mut tx = destination.begin().await?;
+    for website in &websites {
+        let inserted: Option<i32> = sqlx::query_scalar(postgres_queries::INSERT_COPIED_WEBSITE)
+            .bind(&website.url)
+            .bind(&website.alias)
+            .bind(website.created_at)
+            .bind(&website.ip_version)
+            .bind(&website.impact_weight)
+            .bind(&website.config)
+            .bind(&website.monitor_type)
+            .bind(&website.client_cert_path)
+            .bind(&website.client_key_path)
+            .bind(&website.client_cert_encrypted)
+            .bind(&website.client_key_encrypted)
+            .bind(&website.client_cert_fingerprint)
+            .bind(website.client_cert_expires_at)
+            .bind(&website.slack_webhook_url)
+            .bind(&website.discord_webhook_url)
+            .bind(website.invert)
+            .bind(&website.alert_email_to)
+            .bind(&website.tls_cert_serial)
+            .bind(website.tls_cert_expires_at)
+            .bind(website.tls_expiry_notified_days)
+            .bind(&website.status_override)
+            .bind(&website.status_override_note)
+            .bind(website.status_override_expires_at)
+            .bind(website.deleted_at)
+            .bind(website.retention_days)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let id = match inserted {
+            Some(id) => {
+                count.copied += 1;
+                Some(id)
+            }
+            None => {
+                count.skipped += 1;
+                sqlx::query_scalar(postgres_queries::SELECT_COPIED_WEBSITE_ID)
+                    .bind(&website.alias)
+                    .bind(&website.url)
+                    .bind(website.created_at)
+                    .fetch_optional(&mut *tx)
+                    .await?
+            }
+        };
+        match id {
+            Some(id) => {
+                ids.insert(website.id, id);
+            }
+            None => warn!(
+                "Postgres has another website called {}, its checks and stats are skipped",
+                website.alias
+            ),
+        }
+    }
+    tx.commit().await?;
+    info!("Websites: {count}");
+
+    Ok((count, ids))
+}
+
+/// Batch after batch in id order, checks of websites that weren't copied are skipped
+async fn copy_logs(
+    source: &SqlitePool,
+    destination: &PgPool,
+    ids: &HashMap<i64, i32>,
+) -> Result<TableCount, sqlx::Error> {
+    let mut count = TableCount::default();
+    let mut after = 0;
+    loop {
+        let logs = sqlx::query_as::<_, CopiedLog>(sqlite_queries::SELECT_LOGS_TO_COPY)
+            .bind(after)
+            .bind(BATCH_SIZE)
+            .fetch_all(source)
+            .await?;
+        let Some(last) = logs.last() else {
+            break;
+        };
+        after = last.id;
+
+        let mut tx = destination.begin().await?;
+        for log in &logs {
+            let Some(website_id) = ids.get(&log.website_id) else {
+                count.skipped += 1;
+                continue;
+            };
+            let inserted = sqlx::query(postgres_queries::INSERT_COPIED_LOG)
+                .bind(website_id)
+                .bind(log.status)
+                .bind(log.created_at)
+                .bind(&log.ip_family)
+                .bind(&log.impact)
+                .bind(log.response_time_ms)
+                .bind(log.connection_reused)
+                .bind(log.degraded)
+                .bind(&log.error)
+                .bind(&log.reason_phrase)
+                .bind(&log.server_header)
+                .bind(&log.response_headers)
+                .bind(&log.content_hash)
+                .bind(&log.acknowledged_by)
+                .bind(log.acknowledged_at)
+                .bind(log.ack_escalated_at)
+                .execute(&mut *tx)
+                .await?;
+            count.add(inserted.rows_affected());
+        }
+        tx.commit().await?;
+        info!("Logs: {count} up to id {after}");
+    }
+
+    Ok(count)
+}
+
+async fn copy_stats_rollup(
+    source: &SqlitePool,
+    destination: &PgPool,
+    ids: &HashMap<i64, i32>,
+) -> Result<TableCount, sqlx::Error> {
+    let mut count = TableCount::default();
+    let mut after = 0;
+    loop {
+        let buckets =
+            sqlx::query_as::<_, CopiedBucket>(sqlite_queries::SELECT_STATS_ROLLUP_TO_COPY)
+                .bind(after)
+                .bind(BATCH_SIZE)
+                .fetch_all(source)
+                .await?;
+        let Some(last) = buckets.last() else {
+            break;
+        };
+        after = last.id;
+
+        let mut tx = destination.begin().await?;
+        for bucket in &buckets {
+            let Some(website_id) = ids.get(&bucket.website_id) else {
+                count.skipped += 1;
+                continue;
+            };
+            let inserted = sqlx::query(postgres_queries::INSERT_COPIED_STATS_ROLLUP)
+                .bind(website_id)
+                .bind(bucket.bucket_start)
+                .bind(bucket.checks)
+                .bind(bucket.successes)
+                .bind(bucket.degraded)
+                .bind(bucket.degraded_successes)
+                .bind(bucket.avg_latency)
+                .execute(&mut *tx)
+                .await?;
+            count.add(inserted.rows_affected());
+        }
+        tx.commit().await?;
+    }
+    info!("StatsRollup: {count}");
+
+    Ok(count)
+}
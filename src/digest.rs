@@ -0,0 +1,238 @@
+use crate::{
+    AppState, Website,
+    notifications::{NotificationSettings, Outbox, Transition, Warning, routing},
+    on_db,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use cron::Schedule;
+use serde::Serialize;
+use tokio::time;
+use tracing::{info, warn};
+
+/// One website's numbers over a digest's period
+#[derive(Clone, Debug, Serialize)]
+pub struct DigestStats {
+    /// `None` when the website wasn't checked during the period
+    pub uptime_pct: Option<f64>,
+    pub incidents: i64,
+    pub longest_outage_secs: i64,
+    pub average_latency_ms: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct WindowStats {
+    uptime_pct: Option<f64>,
+    average_latency_ms: Option<f64>,
+}
+
+/// Sends a digest of every website through its channels whenever `schedule` fires,
+/// until the process ends
+pub async fn send_digests(
+    app_state: AppState,
+    schedule: Schedule,
+    notification_settings: NotificationSettings,
+) {
+    let mut outbox = Outbox::new(notification_settings);
+
+    // One missed while the process wasn't running is caught up on, but only once a
+    // digest was sent before, so the very first one waits for the schedule
+    match last_sent(&app_state).await {
+        Ok(Some(last_sent)) => {
+            if let Some(missed) = schedule.after(&Utc::now()).next_back()
+                && missed > last_sent
+            {
+                send_digest(&app_state, &mut outbox, &schedule, missed).await;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Loading when the last digest was sent failed: {e}"),
+    }
+
+    loop {
+        let Some(next) = schedule.after(&Utc::now()).next() else {
+            warn!("The digest schedule has no further runs, no more digests are sent");
+            return;
+        };
+        time::sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+        send_digest(&app_state, &mut outbox, &schedule, next).await;
+    }
+}
+
+/// The period ends at the scheduled run and started at the one before it
+async fn send_digest(
+    app_state: &AppState,
+    outbox: &mut Outbox,
+    schedule: &Schedule,
+    period_end: DateTime<Utc>,
+) {
+    let period_start = schedule
+        .after(&period_end)
+        .next_back()
+        .unwrap_or(period_end - TimeDelta::days(1));
+    match claim_period(app_state, period_start, period_end).await {
+        Ok(true) => {}
+        Ok(false) => {
+            info!("The digest until {period_end} was sent already");
+            return;
+        }
+        Err(e) => {
+            warn!("Recording the digest until {period_end} failed, not sending it: {e}");
+            return;
+        }
+    }
+
+    if let Err(e) = load_channels(app_state, outbox).await {
+        warn!("Loading the notification channels for the digest failed: {e}");
+    }
+    let websites = match websites(app_state).await {
+        Ok(websites) => websites,
+        Err(e) => {
+            warn!("Loading the websites for the digest failed: {e}");
+            return;
+        }
+    };
+    info!(
+        "Sending the digest of {} websites from {period_start} until {period_end}",
+        websites.len()
+    );
+    for website in &websites {
+        match stats(app_state, &website.alias, period_start, period_end).await {
+            Ok(stats) => outbox.warn(
+                website,
+                transition(website, stats, period_start, period_end),
+            ),
+            Err(e) => warn!("Digest stats of {} failed: {e}", website.alias),
+        }
+    }
+    outbox.flush();
+}
+
+/// Goes to the website's channels like a transition, which the digest isn't
+fn transition(
+    website: &Website,
+    stats: DigestStats,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Transition {
+    Transition {
+        alias: website.alias.clone(),
+        url: website.url.clone(),
+        old_status: 200,
+        new_status: 200,
+        timestamp: period_end,
+        since: period_start,
+        duration: (period_end - period_start).num_seconds(),
+        inverted: website.invert,
+        shared_host: None,
+        affected: Vec::new(),
+        reminder: false,
+        warning: Some(Warning::Digest(stats)),
+        message: None,
+    }
+}
+
+/// Incidents that started within the checks, given oldest first, and how long the
+/// longest outage lasted until the next successful check or `period_end`. A period
+/// starting with failed checks counts as an incident starting then
+fn outages(checks: &[(DateTime<Utc>, i16)], period_end: DateTime<Utc>) -> (i64, i64) {
+    let mut incidents = 0;
+    let mut longest = TimeDelta::zero();
+    let mut down_since: Option<DateTime<Utc>> = None;
+    for (checked_at, status) in checks {
+        match (down_since, *status == 200) {
+            (None, false) => {
+                incidents += 1;
+                down_since = Some(*checked_at);
+            }
+            (Some(started_at), true) => {
+                longest = longest.max(*checked_at - started_at);
+                down_since = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(started_at) = down_since {
+        longest = longest.max(period_end - started_at);
+    }
+
+    (incidents, longest.num_seconds())
+}
+
+async fn stats(
+    app_state: &AppState,
+    alias: &str,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<DigestStats, sqlx::Error> {
+    let (window, checks) = on_db!(app_state, |db| (
+        sqlx::query_as::<_, WindowStats>(SELECT_WINDOW_STATS)
+            .bind(alias)
+            .bind(period_start)
+            .bind(period_end)
+            .fetch_one(db)
+            .await?,
+        sqlx::query_as::<_, (DateTime<Utc>, i16)>(SELECT_WINDOW_STATUSES)
+            .bind(alias)
+            .bind(period_start)
+            .bind(period_end)
+            .fetch_all(db)
+            .await?,
+    ));
+    let (incidents, longest_outage_secs) = outages(&checks, period_end);
+
+    Ok(DigestStats {
+        uptime_pct: window.uptime_pct,
+        incidents,
+        longest_outage_secs,
+        average_latency_ms: window.average_latency_ms,
+    })
+}
+
+async fn last_sent(app_state: &AppState) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    on_db!(app_state, |db| {
+        sqlx::query_scalar(SELECT_LAST_DIGEST_QUERY)
+            .fetch_one(db)
+            .await
+    })
+}
+
+/// Records the period as sent before anything goes out, false when it was already.
+/// A crash while sending skips the digest rather than sending it twice
+async fn claim_period(
+    app_state: &AppState,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let claimed = on_db!(app_state, |db| sqlx::query(INSERT_DIGEST_QUERY)
+        .bind(period_start)
+        .bind(period_end)
+        .execute(db)
+        .await?
+        .rows_affected());
+
+    Ok(claimed > 0)
+}
+
+async fn websites(app_state: &AppState) -> Result<Vec<Website>, sqlx::Error> {
+    on_db!(app_state, |db| {
+        sqlx::query_as::<_, Website>(SELECT_MONITORED_WEBSITES_QUERY)
+            .fetch_all(db)
+            .await
+    })
+}
+
+/// The same channels the checker notifies
+async fn load_channels(app_state: &AppState, outbox: &mut Outbox) -> Result<(), sqlx::Error> {
+    let (webhooks, routes) = on_db!(app_state, |db| (
+        sqlx::query_as::<_, (i32, String)>(SELECT_NOTIFICATIONS_QUERY)
+            .fetch_all(db)
+            .await?,
+        sqlx::query_as::<_, (String, i32, String, String)>(SELECT_CHANNEL_ROUTES_QUERY)
+            .fetch_all(db)
+            .await?,
+    ));
+    outbox.set_webhooks(webhooks);
+    outbox.set_routes(routing::routes(routes));
+
+    Ok(())
+}
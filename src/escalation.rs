@@ -0,0 +1,103 @@
+use crate::{
+    Website,
+    acknowledgment::OpenIncident,
+    notifications::{
+        Transition, Warning,
+        routing::{ChannelConfig, Route},
+    },
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Sends an extra notification once an incident of the website has been open for
+/// `after_minutes`, again every `repeat_minutes` if set
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct EscalationRule {
+    pub id: i32,
+    pub alias: String,
+    pub after_minutes: i32,
+    pub repeat_minutes: Option<i32>,
+    /// The website's own channels when `None`
+    pub channel_id: Option<i32>,
+    #[serde(skip)]
+    pub channel_kind: Option<String>,
+    #[serde(skip)]
+    pub channel_config: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct NewEscalationRule {
+    #[validate(range(min = 1))]
+    pub after_minutes: i32,
+    #[validate(range(min = 1))]
+    pub repeat_minutes: Option<i32>,
+    pub channel_id: Option<i32>,
+}
+
+impl EscalationRule {
+    /// How many steps should have fired for an incident that started at `started_at`,
+    /// the first one at `after_minutes` and one more per `repeat_minutes` after that
+    pub fn due_steps(&self, started_at: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+        let open_for = now - started_at;
+        let after = TimeDelta::minutes(self.after_minutes.into());
+        if open_for < after {
+            return 0;
+        }
+
+        match self.repeat_minutes {
+            Some(repeat_minutes) if repeat_minutes > 0 => {
+                let repeats = (open_for - after).num_minutes() / i64::from(repeat_minutes);
+                i32::try_from(repeats).map_or(i32::MAX, |repeats| repeats.saturating_add(1))
+            }
+            _ => 1,
+        }
+    }
+
+    /// The channel the rule sends to, `None` for the website's own channels. Channels
+    /// whose config no longer parses fall back to those too
+    pub fn route(&self) -> Option<Route> {
+        let id = self.channel_id?;
+        let (kind, config) = (self.channel_kind.as_ref()?, self.channel_config.as_ref()?);
+        match ChannelConfig::parse(kind, config) {
+            Ok((config, template)) => Some((id, config, template)),
+            Err(e) => {
+                warn!(
+                    "Escalation rule {} of {} can't use channel {id}: {e}",
+                    self.id, self.alias
+                );
+                None
+            }
+        }
+    }
+}
+
+/// The notification for the `step`th escalation of an incident, the website is still
+/// down
+pub fn transition(
+    incident: &OpenIncident,
+    website: &Website,
+    step: i32,
+    now: DateTime<Utc>,
+) -> Transition {
+    Transition {
+        alias: website.alias.clone(),
+        url: website.url.clone(),
+        old_status: incident.status,
+        new_status: incident.status,
+        timestamp: now,
+        since: incident.started_at,
+        duration: (now - incident.started_at).num_seconds(),
+        inverted: website.invert,
+        shared_host: None,
+        affected: Vec::new(),
+        reminder: false,
+        warning: Some(Warning::Escalated {
+            incident_id: incident.id,
+            step,
+        }),
+        message: None,
+    }
+}
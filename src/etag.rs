@@ -0,0 +1,68 @@
+use axum::{
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{ETAG, IF_NONE_MATCH},
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// What the dashboard and the status APIs are built from, cheap enough to check on every
+/// poll. A new check result always bumps the last log id
+#[derive(sqlx::FromRow)]
+pub struct ContentVersion {
+    pub last_log_id: i64,
+    pub websites: i64,
+    pub last_website_id: i64,
+}
+
+impl ContentVersion {
+    /// Weak, since the same version renders differently as time goes by. The minute is
+    /// part of it so relative times and changes to existing rows, like acknowledgments or
+    /// status overrides, show within a minute even when no check lands. A new release
+    /// changes it too, in case the templates did. Pages that also answer with JSON tag
+    /// it apart from the HTML
+    pub fn etag(&self, json: bool, now: DateTime<Utc>) -> HeaderValue {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(if json { "json" } else { "html" });
+        for part in [
+            self.last_log_id,
+            self.websites,
+            self.last_website_id,
+            now.timestamp() / 60,
+        ] {
+            hasher.update(part.to_be_bytes());
+        }
+        let hash: String = hasher.finalize()[..12]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        HeaderValue::from_str(&format!("W/\"{hash}\"")).expect("hex is a valid header value")
+    }
+}
+
+/// Whether the client already has the response tagged `etag`, compared weakly as
+/// `If-None-Match` asks for
+pub fn is_fresh(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(etag) = etag.to_str().ok().map(opaque_tag) else {
+        return false;
+    };
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || opaque_tag(tag) == etag)
+}
+
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// The 304 that replaces the whole response, still carrying its tag
+pub fn not_modified(etag: HeaderValue) -> Response {
+    (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response()
+}
@@ -0,0 +1,394 @@
+use crate::checker::{CheckResult, TIMEOUT_STATUS, truncate_chars};
+use serde::Deserialize;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+use tokio::{
+    io::AsyncReadExt,
+    process::Command,
+    sync::Semaphore,
+    time::{self, Duration, Instant},
+};
+use tracing::warn;
+
+/// Status recorded when a check command exited with a non-zero code
+pub const EXEC_FAILED_STATUS: i16 = 595;
+
+/// Anything a command prints beyond this is discarded
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+/// Commands don't inherit our environment, which may hold database and webhook secrets
+const SCRUBBED_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// Runs the commands of exec monitors, only programs directly inside the allow-listed
+/// directory are started and never through a shell
+#[derive(Clone)]
+pub struct ExecRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    permits: Arc<Semaphore>,
+}
+
+/// What a command may print as its first line instead of plain text
+#[derive(Deserialize)]
+struct Report {
+    status: Option<i16>,
+    latency_ms: Option<i32>,
+    message: Option<String>,
+}
+
+impl ExecRunner {
+    /// Fails unless `dir` is an existing directory
+    pub fn new(dir: &Path, timeout: Duration, max_concurrency: usize) -> Result<Self, String> {
+        let dir = dir
+            .canonicalize()
+            .ok()
+            .filter(|dir| dir.is_dir())
+            .ok_or_else(|| {
+                format!(
+                    "--exec-monitor-dir {} has to be an existing directory",
+                    dir.display()
+                )
+            })?;
+        Ok(Self {
+            dir,
+            timeout,
+            permits: Arc::new(Semaphore::new(max_concurrency.max(1))),
+        })
+    }
+
+    /// The program's path, as long as it is a file in the allow-listed directory after
+    /// following symlinks
+    pub fn resolve(&self, program: &str) -> Result<PathBuf, String> {
+        let path = self
+            .dir
+            .join(program)
+            .canonicalize()
+            .map_err(|e| format!("'{program}' not found in the exec monitor directory: {e}"))?;
+        if path.parent() != Some(self.dir.as_path()) || !path.is_file() {
+            return Err(format!("'{program}' is not in the exec monitor directory"));
+        }
+
+        Ok(path)
+    }
+
+    /// Runs `program` with one argument per line of `args`, exit code 0 means up
+    pub async fn run(&self, alias: &str, program: &str, args: &[String]) -> CheckResult {
+        let path = match self.resolve(program) {
+            Ok(path) => path,
+            Err(e) => {
+                return CheckResult {
+                    error: Some(e),
+                    ..CheckResult::failed()
+                };
+            }
+        };
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the exec semaphore is never closed");
+
+        let mut command = Command::new(&path);
+        command
+            .args(args)
+            .env_clear()
+            .env("PATH", SCRUBBED_PATH)
+            .env("UPTIME_FERRIS_ALIAS", alias)
+            .current_dir(&self.dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let started = Instant::now();
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Starting the check command of {alias} failed: {e}");
+                return CheckResult {
+                    error: Some(format!("could not start '{program}': {e}")),
+                    ..CheckResult::failed()
+                };
+            }
+        };
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let outcome = time::timeout(self.timeout, async {
+            let mut output = Vec::new();
+            stdout
+                .take(MAX_OUTPUT_BYTES)
+                .read_to_end(&mut output)
+                .await?;
+            // the pipe is closed by now, so a command still writing gets SIGPIPE
+            let status = child.wait().await?;
+            Ok::<_, io::Error>((status, output))
+        })
+        .await;
+        let elapsed_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+        match outcome {
+            Ok(Ok((status, output))) => judge(status.success(), status.code(), &output, elapsed_ms),
+            Ok(Err(e)) => CheckResult {
+                error: Some(format!("reading the output of '{program}' failed: {e}")),
+                ..CheckResult::failed()
+            },
+            Err(_) => {
+                let _ = child.start_kill();
+                CheckResult {
+                    status: TIMEOUT_STATUS,
+                    error: Some(format!(
+                        "'{program}' didn't finish within {}s",
+                        self.timeout.as_secs()
+                    )),
+                    ..CheckResult::failed()
+                }
+            }
+        }
+    }
+}
+
+/// One argument per line, passed as is without any shell expansion
+pub fn arguments(args: &str) -> impl Iterator<Item = &str> {
+    args.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+}
+
+/// A first line holding a JSON object can set the status, latency and message, anything
+/// else is the message itself
+fn judge(success: bool, code: Option<i32>, output: &[u8], elapsed_ms: i32) -> CheckResult {
+    let output = String::from_utf8_lossy(output);
+    let first_line = output.lines().next().unwrap_or_default().trim();
+    let report = first_line
+        .starts_with('{')
+        .then(|| serde_json::from_str::<Report>(first_line).ok())
+        .flatten();
+    let (reported_status, latency_ms, message) = match report {
+        Some(report) => (report.status, report.latency_ms, report.message),
+        None => (None, None, Some(first_line.to_owned())),
+    };
+    let reported_status = reported_status.filter(|status| (100..=599).contains(status));
+    let status = match (success, reported_status) {
+        (true, status) => status.unwrap_or(200),
+        (false, Some(status)) if status != 200 => status,
+        (false, _) => EXEC_FAILED_STATUS,
+    };
+    let message = message
+        .filter(|message| !message.is_empty())
+        .map(|message| truncate_chars(message.as_bytes()));
+
+    CheckResult {
+        status,
+        response_time_ms: Some(latency_ms.unwrap_or(elapsed_ms)),
+        error: match (status, message) {
+            (200, _) => None,
+            (_, Some(message)) => Some(message),
+            (_, None) => Some(match code {
+                Some(code) => format!("exited with code {code}"),
+                None => "killed by a signal".to_owned(),
+            }),
+        },
+        ..CheckResult::failed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A fresh allow-listed directory holding the given shell scripts
+    fn scripts(test: &str, scripts: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("uptime_ferris_exec_{test}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        for (name, body) in scripts {
+            let path = dir.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        dir
+    }
+
+    fn judged(success: bool, code: i32, output: &str) -> CheckResult {
+        judge(success, Some(code), output.as_bytes(), 7)
+    }
+
+    #[test]
+    fn the_exit_code_decides_without_a_report() {
+        let up = judged(true, 0, "all good\n");
+        assert_eq!(
+            (up.status, up.response_time_ms, up.error),
+            (200, Some(7), None)
+        );
+        let down = judged(false, 2, "disk full\nmore detail\n");
+        assert_eq!(down.status, EXEC_FAILED_STATUS);
+        assert_eq!(down.error.as_deref(), Some("disk full"));
+        let silent = judged(false, 3, "");
+        assert_eq!(silent.error.as_deref(), Some("exited with code 3"));
+        let killed = judge(false, None, b"", 7);
+        assert_eq!(killed.error.as_deref(), Some("killed by a signal"));
+    }
+
+    #[test]
+    fn a_json_first_line_sets_status_latency_and_message() {
+        let up = judged(true, 0, "{\"latency_ms\": 42}\n");
+        assert_eq!(
+            (up.status, up.response_time_ms, up.error),
+            (200, Some(42), None)
+        );
+        let down = judged(
+            false,
+            1,
+            r#"{"status": 503, "latency_ms": 900, "message": "busy"}"#,
+        );
+        assert_eq!(down.status, 503);
+        assert_eq!(down.response_time_ms, Some(900));
+        assert_eq!(down.error.as_deref(), Some("busy"));
+        // a command reporting a failure status is down even when it exits with 0
+        let reported = judged(true, 0, r#"{"status": 502}"#);
+        assert_eq!(reported.status, 502);
+        assert_eq!(reported.error.as_deref(), Some("exited with code 0"));
+        // a non-zero exit can't be overruled into up
+        let contradicting = judged(false, 1, r#"{"status": 200, "message": "fine"}"#);
+        assert_eq!(contradicting.status, EXEC_FAILED_STATUS);
+        assert_eq!(contradicting.error.as_deref(), Some("fine"));
+    }
+
+    #[test]
+    fn malformed_or_out_of_range_reports_are_ignored() {
+        let malformed = judged(false, 1, "{not json\n");
+        assert_eq!(malformed.status, EXEC_FAILED_STATUS);
+        assert_eq!(malformed.error.as_deref(), Some("{not json"));
+        assert_eq!(malformed.response_time_ms, Some(7));
+        let out_of_range = judged(false, 1, r#"{"status": 42}"#);
+        assert_eq!(out_of_range.status, EXEC_FAILED_STATUS);
+        let wrong_type = judged(true, 0, r#"{"latency_ms": "fast"}"#);
+        assert_eq!(
+            (wrong_type.status, wrong_type.response_time_ms),
+            (200, Some(7))
+        );
+    }
+
+    #[test]
+    fn arguments_are_one_per_line() {
+        let args: Vec<_> = arguments("-c\r\n1\n\n$(reboot)\n").collect();
+        assert_eq!(args, ["-c", "1", "$(reboot)"]);
+    }
+
+    #[test]
+    fn the_directory_has_to_exist() {
+        let dir = scripts("missing_dir", &[("check", "true")]);
+
+        let missing = ExecRunner::new(&dir.join("missing"), Duration::from_secs(1), 4);
+        let file = ExecRunner::new(&dir.join("check"), Duration::from_secs(1), 4);
+
+        assert!(missing.is_err_and(|e| e.contains("has to be an existing directory")));
+        assert!(file.is_err());
+        assert!(ExecRunner::new(&dir, Duration::from_secs(1), 4).is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_hanging_command_is_killed_at_the_timeout() {
+        let dir = scripts("timeout", &[("hang", "sleep 2\ntouch finished")]);
+        let runner = ExecRunner::new(&dir, Duration::from_secs(1), 4).unwrap();
+        let started = Instant::now();
+        let result = runner.run("hanging", "hang", &[]).await;
+        assert!(started.elapsed() < Duration::from_millis(1500));
+        assert_eq!(result.status, TIMEOUT_STATUS);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("'hang' didn't finish within 1s")
+        );
+        time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.join("finished").exists(), "the command kept running");
+    }
+
+    #[tokio::test]
+    async fn large_output_is_truncated() {
+        let dir = scripts(
+            "output",
+            &[
+                (
+                    "long_line",
+                    "head -c 1000000 /dev/zero | tr '\\0' x\nexit 1",
+                ),
+                ("endless", "yes\nexit 1"),
+            ],
+        );
+        let runner = ExecRunner::new(&dir, Duration::from_secs(10), 4).unwrap();
+        let long_line = runner.run("long", "long_line", &[]).await;
+        assert_eq!(long_line.status, EXEC_FAILED_STATUS);
+        assert_eq!(long_line.error, Some("x".repeat(256)));
+
+        // only the first 64 KiB are read, a command that never stops printing
+        // doesn't run into the timeout
+        let started = Instant::now();
+        let endless = runner.run("endless", "endless", &[]).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(endless.status, EXEC_FAILED_STATUS);
+        assert_eq!(endless.error.as_deref(), Some("y"));
+    }
+
+    #[tokio::test]
+    async fn commands_get_a_scrubbed_environment_and_literal_arguments() {
+        let dir = scripts(
+            "environment",
+            &[(
+                "show",
+                "echo \"$(env | grep -v '^PWD=\\|^SHLVL=\\|^_=' | sort | tr '\\n' ' ')$1\"\nexit 1",
+            )],
+        );
+        let runner = ExecRunner::new(&dir, Duration::from_secs(10), 4).unwrap();
+        let result = runner
+            .run("probe", "show", &["$(touch pwned)".to_owned()])
+            .await;
+        assert_eq!(
+            result.error.as_deref(),
+            Some("PATH=/usr/local/bin:/usr/bin:/bin UPTIME_FERRIS_ALIAS=probe $(touch pwned)")
+        );
+        assert!(!dir.join("pwned").exists());
+    }
+
+    #[tokio::test]
+    async fn only_programs_inside_the_directory_run() {
+        let dir = scripts("resolve", &[("inside", "exit 0")]);
+        let outside = scripts("resolve_outside", &[("outside", "exit 0")]);
+        std::os::unix::fs::symlink(outside.join("outside"), dir.join("link")).unwrap();
+        std::fs::create_dir(dir.join("nested")).unwrap();
+        let runner = ExecRunner::new(&dir, Duration::from_secs(10), 4).unwrap();
+
+        assert_eq!(runner.run("inside", "inside", &[]).await.status, 200);
+        let parent = format!(
+            "../{}/outside",
+            outside.file_name().unwrap().to_str().unwrap()
+        );
+        let absolute = outside.join("outside");
+        for program in [
+            &parent,
+            absolute.to_str().unwrap(),
+            "link",
+            "nested",
+            "missing",
+        ] {
+            let result = runner.run("escape", program, &[]).await;
+            assert_eq!(result.status, 599, "{program}");
+            assert!(result.error.unwrap().contains("exec monitor directory"));
+        }
+    }
+
+    #[tokio::test]
+    async fn commands_beyond_the_concurrency_limit_wait() {
+        let dir = scripts("concurrency", &[("slow", "sleep 0.3")]);
+        let runner = ExecRunner::new(&dir, Duration::from_secs(10), 1).unwrap();
+        let started = Instant::now();
+        let (first, second) =
+            tokio::join!(runner.run("a", "slow", &[]), runner.run("b", "slow", &[]));
+        assert_eq!((first.status, second.status), (200, 200));
+        assert!(started.elapsed() >= Duration::from_millis(600));
+    }
+}
@@ -0,0 +1,48 @@
+use askama::Template;
+use askama_axum::IntoResponse;
+use axum::{
+    http::{HeaderValue, header},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+
+/// An Atom feed, entries are expected newest first
+#[derive(Template)]
+#[template(path = "feed.xml")]
+pub struct AtomFeed {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+pub struct FeedEntry {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub time: DateTime<Utc>,
+}
+
+impl FeedEntry {
+    pub fn updated(&self) -> String {
+        self.time.to_rfc3339()
+    }
+}
+
+impl AtomFeed {
+    /// When the newest entry was written, feeds without entries count as updated now
+    pub fn updated(&self) -> String {
+        self.entries
+            .first()
+            .map_or_else(|| Utc::now().to_rfc3339(), FeedEntry::updated)
+    }
+
+    pub fn into_atom_response(self) -> Response {
+        let mut response = self.into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/atom+xml; charset=utf-8"),
+        );
+        response
+    }
+}
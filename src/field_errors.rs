@@ -0,0 +1,75 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// Key validator files schema level errors under, those name their field in the code
+const SCHEMA_ERRORS: &str = "__all__";
+
+/// One rejected input of a submitted form, `field` is the name of the form's input
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Every failure in `errors` by the input it belongs to, sorted by field. Nested
+/// structs are flattened into the form, so their fields keep their own names
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors = Vec::new();
+    collect(errors, &mut field_errors);
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+    field_errors
+}
+
+fn collect(errors: &ValidationErrors, field_errors: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    let field = if field == SCHEMA_ERRORS {
+                        &error.code
+                    } else {
+                        field
+                    };
+                    field_errors.push(FieldError::new(field, message(error)));
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect(errors, field_errors),
+            ValidationErrorsKind::List(errors) => {
+                for errors in errors.values() {
+                    collect(errors, field_errors);
+                }
+            }
+        }
+    }
+}
+
+/// The validator's own message when it has one, a description of the check otherwise
+fn message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let (min, max) = (error.params.get("min"), error.params.get("max"));
+    match (error.code.as_ref(), min, max) {
+        ("length", Some(min), Some(max)) => format!("must be {min} to {max} characters long"),
+        ("length", Some(min), None) => format!("must be at least {min} characters long"),
+        ("length", None, Some(max)) => format!("must be at most {max} characters long"),
+        ("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        ("range", Some(min), None) => format!("must be at least {min}"),
+        ("range", None, Some(max)) => format!("must be at most {max}"),
+        ("url", _, _) => "must be a valid URL".to_owned(),
+        ("email", _, _) => "must be a valid email address".to_owned(),
+        _ => "is invalid".to_owned(),
+    }
+}
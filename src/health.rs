@@ -0,0 +1,82 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::sync::{
+    Arc,
+    atomic::{AtomicI64, Ordering},
+};
+
+/// How long the checker may go without a beat before it counts as stuck. It beats every
+/// tick and after every website, so only a single check running this long trips it
+const CHECKER_STALE_AFTER: TimeDelta = TimeDelta::minutes(5);
+
+/// When the checker last showed signs of life, shared with `/readyz`
+#[derive(Clone, Default)]
+pub struct Heartbeat {
+    /// Milliseconds since the epoch, 0 before the first beat
+    last_beat_ms: Arc<AtomicI64>,
+}
+
+impl Heartbeat {
+    /// Always the wall clock, whatever clock the checker runs on
+    pub fn beat(&self) {
+        self.last_beat_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn last_beat(&self) -> Option<DateTime<Utc>> {
+        match self.last_beat_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last_beat_ms => DateTime::from_timestamp_millis(last_beat_ms),
+        }
+    }
+
+    fn check(&self, now: DateTime<Utc>) -> Result<(), String> {
+        match self.last_beat() {
+            None => Err("The checker hasn't ticked yet".to_owned()),
+            Some(last_beat) if now - last_beat > CHECKER_STALE_AFTER => Err(format!(
+                "The checker last ticked {}s ago",
+                (now - last_beat).num_seconds()
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Component {
+    pub healthy: bool,
+    /// What's wrong, only there when unhealthy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+}
+
+impl From<Result<(), String>> for Component {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            healthy: result.is_ok(),
+            problem: result.err(),
+        }
+    }
+}
+
+/// What `/readyz` answers, 503 unless every component is healthy
+#[derive(Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: Component,
+    pub checker: Component,
+    pub checker_last_tick: Option<DateTime<Utc>>,
+}
+
+impl Readiness {
+    pub fn new(database: Result<(), String>, heartbeat: &Heartbeat, now: DateTime<Utc>) -> Self {
+        let database = Component::from(database);
+        let checker = Component::from(heartbeat.check(now));
+        Self {
+            ready: database.healthy && checker.healthy,
+            database,
+            checker,
+            checker_last_tick: heartbeat.last_beat(),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// How many weeks the heatmap covers unless asked otherwise
+pub const DEFAULT_WEEKS: u32 = 8;
+pub const MAX_WEEKS: u32 = 52;
+
+/// Monday first, matching the normalized weekday numbers of the heatmap queries
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapParams {
+    /// How many weeks back, 8 when left out and at most 52
+    weeks: Option<u32>,
+}
+
+impl HeatmapParams {
+    pub fn weeks(&self) -> u32 {
+        self.weeks.unwrap_or(DEFAULT_WEEKS).clamp(1, MAX_WEEKS)
+    }
+}
+
+/// One weekday/hour combination as aggregated by the backend queries, both return the
+/// weekday as 0 = Monday to 6 = Sunday
+#[derive(sqlx::FromRow)]
+pub struct HeatmapSample {
+    weekday: i32,
+    hour: i32,
+    uptime_pct: i16,
+    samples: i64,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct Heatmap {
+    pub weeks: u32,
+    pub rows: Vec<HeatmapRow>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct HeatmapRow {
+    #[schema(value_type = String)]
+    pub weekday: &'static str,
+    pub cells: Vec<HeatmapCell>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct HeatmapCell {
+    pub hour: u32,
+    /// `None` when there wasn't a single check in this slot
+    pub uptime_pct: Option<i16>,
+    pub samples: i64,
+}
+
+impl Heatmap {
+    pub fn from_samples(weeks: u32, samples: Vec<HeatmapSample>) -> Self {
+        let mut rows: Vec<HeatmapRow> = WEEKDAYS
+            .iter()
+            .map(|&weekday| HeatmapRow {
+                weekday,
+                cells: (0..24)
+                    .map(|hour| HeatmapCell {
+                        hour,
+                        uptime_pct: None,
+                        samples: 0,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        for sample in samples {
+            let cell = usize::try_from(sample.weekday)
+                .ok()
+                .zip(usize::try_from(sample.hour).ok())
+                .and_then(|(weekday, hour)| rows.get_mut(weekday)?.cells.get_mut(hour));
+            if let Some(cell) = cell {
+                cell.uptime_pct = Some(sample.uptime_pct);
+                cell.samples = sample.samples;
+            }
+        }
+
+        Self { weeks, rows }
+    }
+}
+
+impl HeatmapCell {
+    pub fn label(&self, weekday: &str) -> String {
+        match self.uptime_pct {
+            Some(uptime_pct) => format!(
+                "{weekday} {:02}:00-{:02}:59: {uptime_pct}% uptime over {} checks",
+                self.hour, self.hour, self.samples
+            ),
+            None => format!("{weekday} {:02}:00-{:02}:59: no data", self.hour, self.hour),
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self.uptime_pct {
+            None => "heat-none",
+            Some(100) => "heat-full",
+            Some(99) => "heat-high",
+            Some(95..=98) => "heat-mid",
+            Some(_) => "heat-low",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::CONNECTION_FAILED_STATUS;
+    use crate::tests::{check, on_every_backend, website};
+    use crate::{AppState, on_db};
+    use chrono::{Datelike, DurationRound, TimeDelta, Utc};
+
+    fn sample(weekday: i32, hour: i32, uptime_pct: i16) -> HeatmapSample {
+        HeatmapSample {
+            weekday,
+            hour,
+            uptime_pct,
+            samples: 4,
+        }
+    }
+
+    #[test]
+    fn slots_without_checks_have_no_data_instead_of_full_uptime() {
+        let heatmap = Heatmap::from_samples(8, vec![sample(1, 2, 100)]);
+        let tuesday = &heatmap.rows[1];
+        let empty = &tuesday.cells[3];
+
+        assert_eq!(heatmap.rows.len(), 7);
+        assert!(heatmap.rows.iter().all(|row| row.cells.len() == 24));
+        assert_eq!(tuesday.cells[2].uptime_pct, Some(100));
+        assert_eq!(empty.uptime_pct, None);
+        assert_eq!(empty.css_class(), "heat-none");
+        assert_eq!(empty.label(tuesday.weekday), "Tuesday 03:00-03:59: no data");
+        assert_eq!(
+            tuesday.cells[2].label(tuesday.weekday),
+            "Tuesday 02:00-02:59: 100% uptime over 4 checks"
+        );
+    }
+
+    #[test]
+    fn monday_comes_first_and_sunday_last() {
+        let heatmap = Heatmap::from_samples(8, vec![sample(0, 0, 50), sample(6, 23, 0)]);
+
+        assert_eq!(heatmap.rows[0].weekday, "Monday");
+        assert_eq!(heatmap.rows[0].cells[0].uptime_pct, Some(50));
+        assert_eq!(heatmap.rows[6].weekday, "Sunday");
+        assert_eq!(heatmap.rows[6].cells[23].uptime_pct, Some(0));
+    }
+
+    #[test]
+    fn samples_outside_the_grid_are_dropped() {
+        let heatmap =
+            Heatmap::from_samples(8, vec![sample(7, 0, 0), sample(-1, 0, 0), sample(0, 24, 0)]);
+
+        assert!(
+            heatmap
+                .rows
+                .iter()
+                .flat_map(|row| &row.cells)
+                .all(|cell| cell.uptime_pct.is_none())
+        );
+    }
+
+    #[test]
+    fn weeks_default_to_eight_and_stay_within_a_year() {
+        let weeks = |weeks| HeatmapParams { weeks }.weeks();
+
+        assert_eq!(weeks(None), DEFAULT_WEEKS);
+        assert_eq!(weeks(Some(0)), 1);
+        assert_eq!(weeks(Some(520)), MAX_WEEKS);
+    }
+
+    /// A failed check late on a Sunday, a good one early the Monday after and one on
+    /// the Saturday before, all last week
+    async fn weekdays_are_numbered_from_monday(db: AppState) {
+        db.store()
+            .insert_website(&website("weekly", serde_json::json!({})))
+            .await
+            .unwrap();
+        let today = Utc::now().duration_trunc(TimeDelta::days(1)).unwrap();
+        let monday = today
+            - TimeDelta::days(today.weekday().num_days_from_monday().into())
+            - TimeDelta::weeks(1);
+        db.store()
+            .write_logs(&[
+                check(
+                    "weekly",
+                    CONNECTION_FAILED_STATUS,
+                    monday - TimeDelta::minutes(30),
+                ),
+                check("weekly", 200, monday + TimeDelta::minutes(30)),
+                check(
+                    "weekly",
+                    200,
+                    monday - TimeDelta::days(2) + TimeDelta::hours(2),
+                ),
+            ])
+            .await
+            .unwrap();
+
+        let samples = on_db!(&db, |db| {
+            sqlx::query_as::<_, HeatmapSample>(SELECT_UPTIME_HEATMAP)
+                .bind("weekly")
+                .bind(DEFAULT_WEEKS as i32)
+                .fetch_all(db)
+                .await
+                .unwrap()
+        });
+        let heatmap = Heatmap::from_samples(DEFAULT_WEEKS, samples);
+
+        let slots: Vec<_> = heatmap
+            .rows
+            .iter()
+            .flat_map(|row| {
+                row.cells
+                    .iter()
+                    .filter(|cell| cell.samples > 0)
+                    .map(|cell| (row.weekday, cell.hour, cell.uptime_pct))
+            })
+            .collect();
+        assert_eq!(
+            slots,
+            [
+                ("Monday", 0, Some(100)),
+                ("Saturday", 2, Some(100)),
+                ("Sunday", 23, Some(0)),
+            ]
+        );
+    }
+
+    on_every_backend!(weekdays_are_numbered_from_monday, "heatmap");
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::str::FromStr;
+
+/// How badly an outage hurts, websites carry a default weight which incidents inherit
+/// unless an operator overrides it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    Minor,
+    #[default]
+    Major,
+    Critical,
+}
+
+impl Impact {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Minor => "minor",
+            Self::Major => "major",
+            Self::Critical => "critical",
+        }
+    }
+
+    pub fn banner_text(&self) -> &'static str {
+        match self {
+            Self::Minor => "Minor service disruption",
+            Self::Major => "Partial outage",
+            Self::Critical => "Major outage",
+        }
+    }
+}
+
+impl FromStr for Impact {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minor" => Ok(Self::Minor),
+            "major" => Ok(Self::Major),
+            "critical" => Ok(Self::Critical),
+            other => Err(format!(
+                "unknown impact '{other}', expected one of minor, major, critical"
+            )),
+        }
+    }
+}
+
+/// The overall indicator is driven by the worst active impact, not by how many sites are down
+pub fn overall_impact<'a>(active_impacts: impl IntoIterator<Item = &'a str>) -> Option<Impact> {
+    active_impacts
+        .into_iter()
+        .map(|impact| impact.parse().unwrap_or_default())
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_worst_active_impact_wins_over_the_count() {
+        let impacts = ["minor", "minor", "minor", "critical", "major"];
+
+        assert_eq!(overall_impact(impacts), Some(Impact::Critical));
+        assert_eq!(overall_impact(["minor"; 40]), Some(Impact::Minor));
+    }
+
+    #[test]
+    fn nothing_active_is_no_impact() {
+        assert_eq!(overall_impact([]), None);
+    }
+
+    #[test]
+    fn unknown_stored_impacts_count_as_major() {
+        assert_eq!(overall_impact(["minor", "severe"]), Some(Impact::Major));
+    }
+
+    #[test]
+    fn impacts_parse_from_their_names_only() {
+        for impact in [Impact::Minor, Impact::Major, Impact::Critical] {
+            assert_eq!(impact.as_str().parse(), Ok(impact));
+        }
+        assert!("Critical".parse::<Impact>().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::checker::TIMEOUT_STATUS;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+/// How many incidents are listed unless asked otherwise
+const DEFAULT_LIMIT: i64 = 100;
+
+/// The most incidents one request lists
+const MAX_LIMIT: i64 = 1000;
+
+/// Narrows a website's incidents down, all of them up to the limit when left out
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncidentParams {
+    /// RFC 3339, incidents from then on
+    from: Option<String>,
+    /// RFC 3339, incidents before then
+    to: Option<String>,
+    /// A status code, or timeout for checks that got no answer in time
+    status: Option<String>,
+    /// 100 when left out, at most 1000
+    limit: Option<i64>,
+}
+
+impl IncidentParams {
+    pub fn filter(&self) -> Result<IncidentFilter, String> {
+        let from = self.from.as_deref().map(parse_time).transpose()?;
+        let to = self.to.as_deref().map(parse_time).transpose()?;
+        if let (Some(from), Some(to)) = (from, to)
+            && from >= to
+        {
+            return Err("from has to be before to".to_owned());
+        }
+        let status = match self.status.as_deref() {
+            None => None,
+            Some("timeout") => Some(TIMEOUT_STATUS),
+            Some(status) => Some(status.parse().map_err(|_| {
+                format!("Unsupported status '{status}', use a status code or timeout")
+            })?),
+        };
+
+        Ok(IncidentFilter {
+            from,
+            to,
+            status,
+            limit: self.limit,
+        })
+    }
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.to_utc())
+        .map_err(|e| format!("'{time}' is no RFC 3339 timestamp: {e}"))
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct IncidentFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub status: Option<i16>,
+    limit: Option<i64>,
+}
+
+/// A filter the incidents are listed with, and where to go without it
+#[derive(Clone, Serialize)]
+pub struct ActiveFilter {
+    pub label: String,
+    pub clear_href: String,
+}
+
+impl IncidentFilter {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none() && self.status.is_none() && self.limit.is_none()
+    }
+
+    /// Every filter in use with a link to `path` that keeps the other ones and `keep`,
+    /// parameters of the page that aren't about incidents
+    pub fn active(&self, path: &str, keep: &[(&str, String)]) -> Vec<ActiveFilter> {
+        let filters = self.params();
+        filters
+            .iter()
+            .map(|(name, _, label)| {
+                let others = filters
+                    .iter()
+                    .filter(|(other, ..)| other != name)
+                    .map(|(other, value, _)| (*other, value));
+                ActiveFilter {
+                    label: label.clone(),
+                    clear_href: with_query(path, keep, others),
+                }
+            })
+            .collect()
+    }
+
+    /// `path` with every filter in use and `keep`
+    pub fn href(&self, path: &str, keep: &[(&str, String)]) -> String {
+        let filters = self.params();
+        with_query(
+            path,
+            keep,
+            filters.iter().map(|(name, value, _)| (*name, value)),
+        )
+    }
+
+    /// Name, value and label of every filter in use
+    fn params(&self) -> Vec<(&'static str, String, String)> {
+        let time = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut filters = Vec::new();
+        if let Some(from) = self.from {
+            filters.push(("from", time(from), format!("from {from}")));
+        }
+        if let Some(to) = self.to {
+            filters.push(("to", time(to), format!("before {to}")));
+        }
+        match self.status {
+            Some(TIMEOUT_STATUS) => {
+                filters.push(("status", "timeout".to_owned(), "timeouts only".to_owned()))
+            }
+            Some(status) => {
+                filters.push(("status", status.to_string(), format!("status {status}")))
+            }
+            None => {}
+        }
+        if self.limit.is_some() {
+            let limit = self.limit();
+            filters.push(("limit", limit.to_string(), format!("latest {limit}")));
+        }
+        filters
+    }
+}
+
+fn with_query<'a>(
+    path: &str,
+    keep: &'a [(&str, String)],
+    filters: impl Iterator<Item = (&'a str, &'a String)>,
+) -> String {
+    let query = keep
+        .iter()
+        .map(|(name, value)| (*name, value))
+        .chain(filters)
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+    if query.is_empty() {
+        path.to_owned()
+    } else {
+        format!("{path}?{query}")
+    }
+}
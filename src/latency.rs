@@ -0,0 +1,113 @@
+use crate::stats_range::StatsRange;
+use chrono::{DateTime, DurationRound, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Response time percentiles of one bucket, all of them `None` when no check of the
+/// bucket got a response
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct LatencyPercentiles {
+    pub time: DateTime<Utc>,
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// Checks with a response time, failed connections and timeouts have none
+    pub samples: i64,
+}
+
+/// Response time percentiles of a website bucketed like its uptime stats, oldest bucket
+/// first
+#[derive(Serialize, ToSchema)]
+pub struct LatencyStats {
+    pub range: String,
+    pub buckets: Vec<LatencyPercentiles>,
+}
+
+/// One check's response time, SQLite has no percentile function so they're aggregated here
+#[derive(sqlx::FromRow)]
+pub struct LatencySample {
+    pub created_at: DateTime<Utc>,
+    pub response_time_ms: i32,
+}
+
+impl LatencyPercentiles {
+    fn empty(time: DateTime<Utc>) -> Self {
+        Self {
+            time,
+            p50_ms: None,
+            p90_ms: None,
+            p95_ms: None,
+            p99_ms: None,
+            max_ms: None,
+            samples: 0,
+        }
+    }
+
+    /// `sorted` ascending and not empty
+    fn from_sorted(time: DateTime<Utc>, sorted: &[i32]) -> Self {
+        Self {
+            time,
+            p50_ms: Some(percentile(sorted, 0.5)),
+            p90_ms: Some(percentile(sorted, 0.9)),
+            p95_ms: Some(percentile(sorted, 0.95)),
+            p99_ms: Some(percentile(sorted, 0.99)),
+            max_ms: sorted.last().copied().map(f64::from),
+            samples: sorted.len() as i64,
+        }
+    }
+}
+
+/// Interpolates between the two closest ranks like Postgres' `percentile_cont`, so both
+/// backends report the same figures
+fn percentile(sorted: &[i32], fraction: f64) -> f64 {
+    let position = fraction * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let below = f64::from(sorted[lower]);
+
+    below + (f64::from(sorted[upper]) - below) * (position - lower as f64)
+}
+
+/// Buckets the samples of a range and computes their percentiles
+pub fn from_samples(samples: Vec<LatencySample>, range: StatsRange) -> Vec<LatencyPercentiles> {
+    let bucket = range.split_by().duration();
+    let mut grouped: Vec<(DateTime<Utc>, Vec<i32>)> = Vec::new();
+    for sample in samples {
+        let time = sample
+            .created_at
+            .duration_trunc(bucket)
+            .unwrap_or(sample.created_at);
+        match grouped.iter_mut().find(|(bucket, _)| *bucket == time) {
+            Some((_, response_times)) => response_times.push(sample.response_time_ms),
+            None => grouped.push((time, vec![sample.response_time_ms])),
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|(time, mut response_times)| {
+            response_times.sort_unstable();
+            LatencyPercentiles::from_sorted(time, &response_times)
+        })
+        .collect()
+}
+
+/// One entry per bucket of the range like the uptime stats, empty where nothing responded
+pub fn fill_gaps(
+    mut buckets: Vec<LatencyPercentiles>,
+    range: StatsRange,
+    first_bucket: DateTime<Utc>,
+) -> Vec<LatencyPercentiles> {
+    let bucket = range.split_by().duration();
+    for i in 0..range.splits() {
+        let time = first_bucket + bucket * i;
+        if !buckets.iter().any(|x| x.time == time) {
+            buckets.push(LatencyPercentiles::empty(time));
+        }
+    }
+    buckets.sort_by_key(|bucket| bucket.time);
+
+    buckets
+}
@@ -1,560 +1,6376 @@
-use crate::shared_queries::*;
-use argument_parsing::Args;
+use acknowledgment::{OpenIncident, TtaStats};
+use api_key::ApiKey;
+use argument_parsing::{Args, Command, DbCommand};
 use askama::Template;
 use askama_axum::IntoResponse as AskamaIntoResponse;
 use axum::{
-    Form, Router,
-    extract::{Path, State},
+    Form, Json, Router,
+    extract::{FromRef, Path, Query, State},
+    handler::Handler,
+    http::{
+        HeaderMap, HeaderValue,
+        header::{
+            ACCEPT, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, ETAG, HOST, LOCATION, RETRY_AFTER,
+            VARY, WWW_AUTHENTICATE,
+        },
+    },
+    middleware,
     response::{IntoResponse as AxumIntoResponse, Redirect, Response},
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
-use chrono::{DateTime, Timelike, Utc};
+use backup::{Backup, BackupParams, BackupStatusOverride, RestoreParams, RestoreReport};
+use badge::{Badge, BadgeParams};
+use captured_headers::{CapturedHeaders, ResponseHeaders};
+use checker::Wakeup;
+use chrono::{DateTime, TimeDelta, Utc};
 use clap::Parser;
-use futures_util::StreamExt;
+use client_identity::Secrets;
+use dashboard_view::{DashboardParams, DashboardView};
+use escalation::{EscalationRule, NewEscalationRule};
+use etag::ContentVersion;
+use exec::ExecRunner;
+use feed::{AtomFeed, FeedEntry};
+use field_errors::FieldError;
+use health::{Heartbeat, Readiness};
+use heatmap::{Heatmap, HeatmapParams};
+use impact::Impact;
+use incident_filter::{ActiveFilter, IncidentFilter, IncidentParams};
+use latency::{LatencyPercentiles, LatencyStats};
+use manual_status::{ManualStatus, StatusOverride};
+use migrations::{MigrationStatus, MigrationsDir};
+use monitor_config::MonitorConfig;
+use notifications::{
+    DeliveryLog, NotificationSettings, Notifier, Transition, Warning,
+    discord::DiscordNotifier,
+    email::{Mailer, SmtpSettings},
+    routing::ChannelConfig,
+    telegram::TelegramChat,
+    template::MessageTemplate,
+    webhook::WebhookNotifier,
+};
+use pagination::{Page, PageParams};
+use public_status::{PublicStatus, StatusSummary};
+use query_log::{QueryLog, SlowQueriesPage, SlowQuery};
+use rate_limit::RateLimiter;
 use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, SqlitePool, migrate::Migrator};
+use retention::Retention;
+use robots::Indexing;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use shared_fate::HostGroup;
+use sla::{SlaCounts, SlaParams, SlaPeriod, SlaReport};
+use snapshot::SnapshotCache;
+use sqlx::{MySqlPool, PgPool, SqlitePool, pool::PoolOptions};
+use stats_range::{DEFAULT_RANGE, StatsParams, StatsRange, StatsSummary, UptimeStats};
+use status_codes::{StatusCodes, StatusCount};
+use std::{fmt::Display, net::SocketAddr, str::FromStr, sync::Arc};
+use store::Store;
 use tokio::{
     signal,
+    sync::watch,
     time::{self, Duration},
 };
-use tower_http::trace::TraceLayer;
-use tracing::info;
+use tower_http::{
+    compression::{
+        CompressionLayer, DefaultPredicate,
+        predicate::{NotForContentType, Predicate},
+    },
+    cors::CorsLayer,
+    trace::TraceLayer,
+};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use validator::Validate;
+use trends::Trends;
+use uptime_bars::UptimeBar;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidateUrl, ValidationError};
+use website_import::{ExportFormat, ExportParams, ExportedWebsite, ImportParams, ImportReport};
 
+mod acknowledgment;
+mod anomaly;
+mod api_docs;
+mod api_key;
 mod argument_parsing;
+mod backup;
+mod badge;
+mod captured_headers;
+mod certificate;
+mod checker;
+mod client_identity;
+mod cors;
+mod csv_export;
+mod dashboard_view;
+mod data_migration;
+mod digest;
+mod escalation;
+mod etag;
+mod exec;
+mod feed;
+mod field_errors;
+mod health;
+mod heatmap;
+mod impact;
+mod incident_filter;
+mod latency;
+mod manual_status;
+mod metrics;
+mod migrations;
+mod monitor_config;
+mod mysql_queries;
+mod notifications;
+mod pagination;
+mod ping;
 mod postgres_queries;
+mod public_status;
+mod query_log;
+mod rate_limit;
+mod retention;
+mod robots;
+mod rollup;
+mod same_origin;
+mod schedule;
+mod schema;
+mod shared_fate;
 mod shared_queries;
+mod sla;
+mod snapshot;
 mod sqlite;
 mod sqlite_queries;
+mod stats_range;
+mod status_codes;
+mod store;
+mod trends;
+mod uptime_bars;
+mod website_import;
+
+/// How long shutdown waits for the checker to finish its current website
+const CHECKER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long `/readyz` waits for the database, probes give up after a few seconds anyway
+const READINESS_DB_TIMEOUT: Duration = Duration::from_secs(2);
 
-#[derive(Deserialize, sqlx::FromRow, Validate)]
+#[derive(Clone, Deserialize, sqlx::FromRow, ToSchema, Validate)]
+#[validate(schema(function = "validate_target"))]
 struct Website {
-    #[validate(url)]
+    /// Assigned on creation and kept through renames, unlike the alias
+    #[serde(skip_deserializing)]
+    #[sqlx(default)]
+    id: i32,
+    /// A hostname or IP address for ping monitors, the program's file name for exec monitors
+    #[validate(length(max = 2048))]
     url: String,
+    /// Part of the website's paths, so limited to characters that need no escaping there
+    #[validate(length(min = 1, max = 64), custom(function = "validate_alias"))]
     alias: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[validate(custom(function = "validate_monitor_type"))]
+    monitor_type: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[validate(custom(function = "validate_ip_version"))]
+    ip_version: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[validate(custom(function = "validate_impact_weight"))]
+    impact_weight: String,
+    /// Check options, stored as one versioned JSON document
+    #[serde(flatten)]
+    #[sqlx(default, try_from = "String")]
+    #[validate(nested)]
+    config: MonitorConfig,
+    /// Expected to be offline, any answer counts as down and unreachability as up
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    #[sqlx(default)]
+    invert: bool,
+    /// Client certificate and key files for websites requiring mutual TLS
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(default)]
+    client_cert_path: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(default)]
+    client_key_path: Option<String>,
+    /// Uploaded PEM, only ever stored encrypted
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(skip)]
+    client_cert_pem: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(skip)]
+    client_key_pem: Option<String>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    client_cert_encrypted: Option<String>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    client_key_encrypted: Option<String>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    client_cert_fingerprint: Option<String>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    client_cert_expires_at: Option<DateTime<Utc>>,
+    /// Slack incoming webhook used instead of `--slack-webhook-url` for this website
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(default)]
+    #[validate(url)]
+    slack_webhook_url: Option<String>,
+    /// Discord webhook used instead of `--discord-webhook-url` for this website
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(default)]
+    #[validate(url)]
+    discord_webhook_url: Option<String>,
+    /// Alert mails for this website go here instead of `--alert-email-to`
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[sqlx(default)]
+    #[validate(email)]
+    alert_email_to: Option<String>,
+    /// Days this website's checks are kept for instead of `--retention-days`
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    #[sqlx(default)]
+    #[validate(range(min = 1))]
+    retention_days: Option<i32>,
+    /// The server certificate seen last and the tightest expiry warning sent for it
+    #[serde(skip)]
+    #[sqlx(default)]
+    tls_cert_serial: Option<String>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    tls_cert_expires_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    #[sqlx(default)]
+    tls_expiry_notified_days: Option<i32>,
+    /// Channel the new website notifies instead of the defaults, only read on creation
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    #[sqlx(skip)]
+    channel_id: Option<i32>,
+}
+
+/// Empty form inputs mean "not set"
+fn deserialize_optional_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty()))
+}
+
+fn deserialize_optional_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    deserialize_optional_text(deserializer)?
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Checked checkboxes are sent as "on", unchecked ones aren't sent at all
+fn deserialize_checkbox<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(matches!(value.as_deref(), Some("on" | "true" | "1")))
+}
+
+/// The form offers "default", "reuse" and "fresh" for the connection handling
+fn deserialize_connection_mode<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "fresh" => Ok(Some(true)),
+        "reuse" => Ok(Some(false)),
+        _ => Ok(None),
+    }
+}
+
+fn validate_monitor_type(monitor_type: &str) -> Result<(), ValidationError> {
+    monitor_type
+        .parse::<checker::MonitorType>()
+        .map(|_| ())
+        .map_err(|_| {
+            ValidationError::new("monitor_type").with_message("must be http, ping or exec".into())
+        })
+}
+
+fn validate_alias(alias: &str) -> Result<(), ValidationError> {
+    // `/api/websites/by-id/{id}` would shadow the API routes of a website called that
+    if alias == "by-id" {
+        return Err(ValidationError::new("alias").with_message("'by-id' is reserved".into()));
+    }
+    // "." and ".." are dot-segments, clients and proxies resolve them out of the path
+    if !alias.is_empty() && alias.chars().all(|c| c == '.') {
+        return Err(ValidationError::new("alias").with_message("may not be only dots".into()));
+    }
+    if alias
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new("alias")
+            .with_message("may only contain letters, digits, '-', '_', '.' and '~'".into()))
+    }
+}
+
+/// HTTP monitors need an http or https url, ping monitors a bare hostname or IP address
+/// and exec monitors a bare file name
+fn validate_target(website: &Website) -> Result<(), ValidationError> {
+    let (valid, message) = match website.monitor_type.parse() {
+        Ok(checker::MonitorType::Ping) => (
+            !website.url.is_empty()
+                && !website
+                    .url
+                    .contains(|c: char| c.is_whitespace() || c == '/'),
+            "must be a hostname or IP address for ping monitors",
+        ),
+        Ok(checker::MonitorType::Exec) => (
+            !website.url.is_empty()
+                && !website.url.starts_with('.')
+                && !website.url.contains(['/', '\\']),
+            "must be the file name of a program for exec monitors",
+        ),
+        _ => (
+            website.url.validate_url()
+                && reqwest::Url::parse(&website.url)
+                    .is_ok_and(|url| matches!(url.scheme(), "http" | "https")),
+            "must be an http or https URL",
+        ),
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("url").with_message(message.into()))
+    }
+}
+
+fn validate_ip_version(ip_version: &str) -> Result<(), ValidationError> {
+    ip_version
+        .parse::<checker::IpVersion>()
+        .map(|_| ())
+        .map_err(|_| {
+            ValidationError::new("ip_version").with_message("must be auto, v4only or v6only".into())
+        })
+}
+
+fn validate_impact_weight(impact_weight: &str) -> Result<(), ValidationError> {
+    if impact_weight.is_empty() {
+        return Ok(());
+    }
+    impact_weight.parse::<Impact>().map(|_| ()).map_err(|_| {
+        ValidationError::new("impact_weight")
+            .with_message("must be minor, major or critical".into())
+    })
 }
 
-#[derive(Serialize, Validate)]
+#[derive(Clone, Serialize, Validate)]
 struct WebsiteInfo {
+    id: i32,
     #[validate(url)]
     url: String,
     alias: String,
     data: Vec<WebsiteStats>,
+    /// Manual status set by an operator, if one is active
+    status_override: Option<StatusOverride>,
+    /// Up means unreachable for websites expected to be offline
+    inverted: bool,
+    /// First failed check of the ongoing incident, if the website is down
+    open_incident_id: Option<i32>,
+    /// Aging badge while nobody acknowledged the ongoing incident
+    unacked_for: Option<String>,
+}
+
+impl WebsiteInfo {
+    fn bars(&self) -> Vec<UptimeBar> {
+        uptime_bars::chart(&self.data, &[])
+    }
 }
 
-#[derive(sqlx::FromRow, Serialize)]
+#[derive(Clone, sqlx::FromRow, Serialize, ToSchema)]
 pub struct WebsiteStats {
     time: DateTime<Utc>,
     uptime_pct: Option<i16>,
+    /// Share of checks slower than the website's degraded threshold
+    #[sqlx(default)]
+    degraded_pct: Option<i16>,
+    #[sqlx(default)]
+    checks: i64,
 }
 
-#[derive(Serialize, sqlx::FromRow, Template)]
+#[derive(Clone, Serialize, sqlx::FromRow, Template)]
 #[template(path = "index.html")]
 struct WebsiteLogs {
     logs: Vec<WebsiteInfo>,
+    /// Worst impact among the websites that are currently down
+    overall_impact: Option<Impact>,
+    /// The overall impact comes from a manual status override
+    overall_is_manual: bool,
+    /// Set when serving a snapshot because the database was too slow
+    stale_as_of: Option<String>,
+    /// Hosts several monitors point at
+    host_groups: Vec<HostGroup>,
+    /// The website the form was just submitted with, when it got rejected
+    rejected: Option<RejectedWebsite>,
+    /// Which of the websites `logs` are
+    page: Page,
+    /// How the websites are sorted and filtered
+    view: DashboardView,
+    /// Deleted websites that can still be restored
+    recently_deleted: Vec<DeletedWebsite>,
+}
+
+/// The status banner and websites of the dashboard, which it polls for
+#[derive(Template)]
+#[template(path = "websites_fragment.html")]
+struct WebsitesFragment {
+    logs: Vec<WebsiteInfo>,
+    overall_impact: Option<Impact>,
+    overall_is_manual: bool,
+    stale_as_of: Option<String>,
+    page: Page,
+    view: DashboardView,
+}
+
+impl From<WebsiteLogs> for WebsitesFragment {
+    fn from(website_logs: WebsiteLogs) -> Self {
+        Self {
+            logs: website_logs.logs,
+            overall_impact: website_logs.overall_impact,
+            overall_is_manual: website_logs.overall_is_manual,
+            stale_as_of: website_logs.stale_as_of,
+            page: website_logs.page,
+            view: website_logs.view,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct RejectedWebsite {
+    url: String,
+    alias: String,
+    errors: Vec<FieldError>,
 }
 
-#[derive(Serialize, sqlx::FromRow, Template)]
+#[derive(Clone, Serialize, sqlx::FromRow, Template)]
 #[template(path = "single_website.html")]
 struct SingleWebsiteLog {
     log: WebsiteInfo,
+    /// Newest first, narrowed down by `incident_filters`
     incidents: Vec<Incident>,
-    monthly_data: Vec<WebsiteStats>,
+    incident_filters: Vec<ActiveFilter>,
+    /// Share of checks that went over an already open connection
+    connection_reuse_pct: Option<i64>,
+    cron_schedule: Option<String>,
+    next_check_at: Option<DateTime<Utc>>,
+    events: Vec<Event>,
+    notification_log: Vec<NotificationLogEntry>,
+    /// Channels notifications go to instead of the global defaults
+    channels: Vec<ChannelSummary>,
+    /// Every channel, to pick further ones from
+    all_channels: Vec<ChannelSummary>,
+    /// What went wrong with the most recent failed check
+    last_failure: Option<String>,
+    /// What the chart of `log.data` covers, e.g. "Last 7 days"
+    stats_label: String,
+    /// Response times of the chart's buckets, in the same order
+    latency: Vec<LatencyPercentiles>,
+    heatmap: Heatmap,
+    /// How the checks of the chart's range ended
+    status_codes: StatusCodes,
+    /// This calendar month against the last one
+    trends: Trends,
+    /// SHA-256 fingerprint of the client certificate for mutual TLS
+    client_cert_fingerprint: Option<String>,
+    client_cert_expires_at: Option<DateTime<Utc>>,
+    /// What the edit form starts from
+    settings: WebsiteSettings,
+    /// Days the checks are kept for, the website's own retention or `--retention-days`.
+    /// `None` keeps them forever
+    retention_days: Option<u32>,
+    /// Set when serving a snapshot because the database was too slow
+    stale_as_of: Option<String>,
+    /// Where the uptime chart and incidents are polled from
+    #[serde(skip)]
+    live_href: String,
+    /// The url and alias the edit form was just submitted with, when they got rejected
+    #[serde(skip)]
+    rejected: Option<RejectedWebsite>,
 }
 
-#[derive(Serialize, sqlx::FromRow)]
-struct Incident {
-    time: DateTime<Utc>,
-    status: i16,
+impl SingleWebsiteLog {
+    fn bars(&self) -> Vec<UptimeBar> {
+        uptime_bars::chart(&self.log.data, &self.latency)
+    }
 }
 
-#[derive(Clone, Debug)]
-enum AppState {
-    Postgres(PgPool),
-    Sqlite(SqlitePool),
+/// The uptime chart and incidents of the detail page, which it polls for
+#[derive(Template)]
+#[template(path = "website_fragment.html")]
+struct WebsiteFragment {
+    log: WebsiteInfo,
+    incidents: Vec<Incident>,
+    incident_filters: Vec<ActiveFilter>,
+    stats_label: String,
+    latency: Vec<LatencyPercentiles>,
+    live_href: String,
 }
 
-impl AppState {
-    fn new(postgres: Option<PgPool>, sqlite: Option<SqlitePool>) -> Self {
-        match (postgres, sqlite) {
-            (Some(p), _) => AppState::Postgres(p),
-            (_, Some(s)) => AppState::Sqlite(s),
-            _ => panic!("You need to configure either Postgres or Sqlite!"),
-        }
-    }
-
-    async fn migrate_db(&self) {
-        match self {
-            Self::Postgres(p) => Self::migrate_postgres(p).await,
-            Self::Sqlite(s) => sqlite::migrate_sqlite(s).await,
-        }
+impl WebsiteFragment {
+    fn bars(&self) -> Vec<UptimeBar> {
+        uptime_bars::chart(&self.log.data, &self.latency)
     }
+}
 
-    async fn migrate_postgres(pool: &PgPool) {
-        let migrator = Migrator::new(std::path::Path::new("./migrations_pg"))
-            .await
-            .expect("Migration folder couldn't be found");
-        migrator
-            .run(pool)
-            .await
-            .expect("Postgres migrations failed");
-    }
+/// The options of a website as the edit form shows them, secrets aren't sent back
+#[derive(Clone, Serialize)]
+struct WebsiteSettings {
+    monitor_type: String,
+    ip_version: String,
+    impact_weight: String,
+    /// "default", "reuse" or "fresh" like the form's choices
+    connection_mode: &'static str,
+    degraded_threshold_ms: Option<i32>,
+    strict: bool,
+    expected_keyword: Option<String>,
+    forbidden_keyword: Option<String>,
+    track_content: bool,
+    renotify_minutes: Option<i32>,
+    /// One per line
+    exec_args: String,
+    alert_email_to: Option<String>,
+    retention_days: Option<i32>,
+    cron_schedule: Option<String>,
+    invert: bool,
+}
 
-    async fn from(item: argument_parsing::Args) -> Self {
-        if let Some(pg_string) = item.pg {
-            if pg_string.is_empty() {
-                AppState::new(
-                    None,
-                    Some(SqlitePool::connect(SQLITE_CONNECTION_STRING).await.unwrap()),
-                )
-            } else {
-                AppState::new(Some(PgPool::connect(&pg_string).await.unwrap()), None)
-            }
-        } else {
-            AppState::new(
-                None,
-                Some(SqlitePool::connect(SQLITE_CONNECTION_STRING).await.unwrap()),
-            )
+impl From<&Website> for WebsiteSettings {
+    fn from(website: &Website) -> Self {
+        Self {
+            monitor_type: website.monitor_type.clone(),
+            ip_version: website.ip_version.clone(),
+            impact_weight: website.impact_weight.clone(),
+            connection_mode: match website.config.fresh_connection {
+                Some(true) => "fresh",
+                Some(false) => "reuse",
+                None => "default",
+            },
+            degraded_threshold_ms: website.config.degraded_threshold_ms,
+            strict: website.config.strict,
+            expected_keyword: website.config.expected_keyword.clone(),
+            forbidden_keyword: website.config.forbidden_keyword.clone(),
+            track_content: website.config.track_content,
+            renotify_minutes: website.config.renotify_minutes,
+            exec_args: website.config.exec_args.join("\n"),
+            alert_email_to: website.alert_email_to.clone(),
+            retention_days: website.retention_days,
+            cron_schedule: website.config.cron_schedule.clone(),
+            invert: website.invert,
         }
     }
 }
 
-enum ApiError {
-    SQL(sqlx::Error),
+/// The last 30 days of the detail page, loaded separately as they're the expensive part
+#[derive(Template)]
+#[template(path = "monthly_fragment.html")]
+struct MonthlyFragment {
+    alias: String,
+    monthly_data: Vec<WebsiteStats>,
+    /// Time to acknowledge over the last 30 days, `None` without acknowledged incidents
+    tta: Option<TtaStats>,
 }
 
-impl From<sqlx::Error> for ApiError {
-    fn from(e: sqlx::Error) -> Self {
-        Self::SQL(e)
+impl MonthlyFragment {
+    fn bars(&self) -> Vec<UptimeBar> {
+        uptime_bars::chart(&self.monthly_data, &[])
     }
 }
 
-impl AxumIntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        match self {
-            Self::SQL(e) => AxumIntoResponse::into_response((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("SQL Error: {e}"),
-            )),
-        }
-    }
+/// The monthly fragment as a standalone page for browsers without JavaScript
+#[derive(Template)]
+#[template(path = "monthly_page.html")]
+struct MonthlyPage {
+    alias: String,
+    monthly_data: Vec<WebsiteStats>,
+    /// Time to acknowledge over the last 30 days, `None` without acknowledged incidents
+    tta: Option<TtaStats>,
 }
 
-const SQLITE_CONNECTION_STRING: &'static str = "sqlite://uptime_ferris.db?mode=rwc";
-
-#[tokio::main]
-async fn main() {
-    //Init tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                // axum logs rejections from built-in extractors with the `axum::rejection`
-                // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
-                format!(
-                    "{}=debug,tower_http=debug,axum::rejection=trace",
-                    env!("CARGO_CRATE_NAME")
-                )
-                .into()
-            }),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    let args = Args::parse();
-    let app_state = AppState::from(args).await;
-    // carry out migrations
-    info!("Starting db migration");
-    let _ = &app_state.migrate_db().await;
-    info!("Finished db migration");
-    let cloned_state = app_state.clone();
-    //Check the website status
-    info!("Starting background task for checking website status");
-    tokio::spawn(async move {
-        check_websites_general(cloned_state).await;
-    });
+impl MonthlyPage {
+    fn bars(&self) -> Vec<UptimeBar> {
+        uptime_bars::chart(&self.monthly_data, &[])
+    }
+}
 
-    // build our application with a route
-    let app = Router::new()
-        .route("/", get(get_websites))
-        .route("/websites", post(create_website))
-        .route(
-            "/websites/:alias",
-            get(get_website_by_alias).delete(delete_website),
-        )
-        .route("/styles.css", get(styles))
-        .layer(TraceLayer::new_for_http())
-        .with_state(app_state);
+/// What browsers get for anything that doesn't exist
+#[derive(Template)]
+#[template(path = "not_found.html")]
+struct NotFoundPage;
 
-    // run it
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .unwrap();
-    info!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+/// A deleted website that can still be restored
+#[derive(Clone, Serialize, sqlx::FromRow)]
+struct DeletedWebsite {
+    alias: String,
+    url: String,
+    deleted_at: DateTime<Utc>,
 }
 
-async fn styles() -> impl AxumIntoResponse {
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "text/css")
-        .body(include_str!("../templates/styles.css").to_owned())
-        .unwrap()
+/// A website as listed by the JSON API
+#[derive(Serialize, sqlx::FromRow, ToSchema)]
+struct WebsiteSummary {
+    /// Stays the same when the website is renamed, see `/api/websites/by-id/{id}`
+    id: i32,
+    alias: String,
+    url: String,
+    created_at: Option<DateTime<Utc>>,
+    /// Status of the newest check, `None` before the first one
+    current_status: Option<i16>,
+    last_checked: Option<DateTime<Utc>>,
 }
 
-async fn create_website(
-    State(state): State<AppState>,
-    Form(new_website): Form<Website>,
-) -> Result<impl AxumIntoResponse, impl AxumIntoResponse> {
-    if new_website.validate().is_err() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Validation Error: is your website a reachable URL?",
-        ));
-    }
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+struct Incident {
+    id: i32,
+    time: DateTime<Utc>,
+    status: i16,
+    ip_family: Option<String>,
+    /// Operator override if set, the website's impact weight otherwise
+    impact: String,
+    error: Option<String>,
+    reason_phrase: Option<String>,
+    server_header: Option<String>,
+    /// The allow-listed headers of the response, empty for checks without one
+    #[sqlx(default, try_from = "Option<String>")]
+    #[schema(value_type = Object)]
+    response_headers: ResponseHeaders,
+    /// Only ever set on the first failed check of an incident
+    acknowledged_by: Option<String>,
+}
 
-    match state {
-        AppState::Postgres(p) => {
-            let _ = sqlx::query(INSERT_INTO_WEBSITES_QUERY)
-                .bind(new_website.url)
-                .bind(new_website.alias)
-                .execute(&p)
-                .await
-                .unwrap();
-        }
-        AppState::Sqlite(s) => {
-            let _ = sqlx::query(INSERT_INTO_WEBSITES_QUERY)
-                .bind(new_website.url)
-                .bind(new_website.alias)
-                .execute(&s)
-                .await
-                .unwrap();
-        }
+impl Incident {
+    /// Someone took the incident on and the website is still down
+    fn is_acknowledged_while_open(&self, open_incident_id: &Option<i32>) -> bool {
+        self.acknowledged_by.is_some() && *open_incident_id == Some(self.id)
     }
-
-    Ok(Redirect::to("/"))
 }
 
-#[axum::debug_handler]
-async fn get_websites(State(state): State<AppState>) -> Result<impl AskamaIntoResponse, ApiError> {
-    let websites = match state {
-        AppState::Postgres(ref p) => {
-            sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_QUERY)
-                .fetch_all(p)
-                .await?
-        }
-        AppState::Sqlite(ref s) => {
-            sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_QUERY)
-                .fetch_all(s)
-                .await?
-        }
-    };
-    let mut logs = Vec::new();
-
-    for website in websites {
-        let data = get_daily_stats(&website.alias, &state).await?;
+#[derive(sqlx::FromRow)]
+struct IncidentDetail {
+    #[sqlx(flatten)]
+    incident: Incident,
+    acknowledged_at: Option<DateTime<Utc>>,
+    alias: String,
+    url: String,
+}
 
-        logs.push(WebsiteInfo {
-            url: website.url,
-            alias: website.alias,
-            data,
+impl IncidentDetail {
+    /// How long it took until someone acknowledged the incident
+    fn time_to_acknowledge(&self) -> Option<String> {
+        self.acknowledged_at.map(|acknowledged_at| {
+            acknowledgment::duration_text((acknowledged_at - self.incident.time).num_seconds())
         })
     }
-
-    Ok(WebsiteLogs { logs })
 }
 
-enum SplitBy {
-    Hour,
-    Day,
+/// An update an operator posted on an incident
+#[derive(sqlx::FromRow)]
+struct IncidentUpdate {
+    id: i32,
+    time: DateTime<Utc>,
+    message: String,
 }
 
-async fn get_daily_stats(alias: &str, app_state: &AppState) -> Result<Vec<WebsiteStats>, ApiError> {
-    let data = match app_state {
-        AppState::Postgres(p) => {
-            sqlx::query_as::<_, WebsiteStats>(postgres_queries::SELECT_DAILY_STATS)
-                .bind(alias)
-                .fetch_all(p)
-                .await?
-        }
-        AppState::Sqlite(s) => {
-            sqlx::query_as::<_, WebsiteStats>(sqlite_queries::SELECT_DAILY_STATS)
-                .bind(alias)
-                .fetch_all(s)
-                .await?
-        }
-    };
-
-    let number_of_splits = 24;
-    let number_of_seconds = 3600;
-
-    let data = fill_data_gaps(data, number_of_splits, SplitBy::Hour, number_of_seconds);
-
-    Ok(data)
+#[derive(Template)]
+#[template(path = "incident.html")]
+struct IncidentPage {
+    detail: IncidentDetail,
+    /// When the website answered with a 200 again after the incident
+    resolved_at: Option<DateTime<Utc>>,
+    updates: Vec<IncidentUpdate>,
 }
 
-async fn get_monthly_stats(
-    alias: &str,
-    app_state: &AppState,
-) -> Result<Vec<WebsiteStats>, ApiError> {
-    let data = match app_state {
-        AppState::Postgres(p) => {
-            sqlx::query_as::<_, WebsiteStats>(postgres_queries::SELECT_MONTHLY_STATS)
-                .bind(alias)
-                .fetch_all(p)
-                .await?
-        }
-        AppState::Sqlite(s) => {
-            sqlx::query_as::<_, WebsiteStats>(sqlite_queries::SELECT_MONTHLY_STATS)
-                .bind(alias)
-                .fetch_all(s)
-                .await?
-        }
-    };
+#[derive(Deserialize)]
+struct IncidentUpdateForm {
+    message: String,
+    token: String,
+}
 
-    let number_of_splits = 30;
-    let number_of_seconds = 86400;
+#[derive(Deserialize)]
+struct AcknowledgeForm {
+    by: String,
+    token: String,
+}
 
-    let data = fill_data_gaps(data, number_of_splits, SplitBy::Day, number_of_seconds);
-    Ok(data)
+/// A webhook transitions are POSTed to
+#[derive(Serialize, sqlx::FromRow)]
+struct NotificationTarget {
+    id: i32,
+    url: String,
 }
 
-fn fill_data_gaps(
-    mut data: Vec<WebsiteStats>,
-    splits: i32,
-    format: SplitBy,
-    number_of_seconds: i32,
-) -> Vec<WebsiteStats> {
-    // If the length of data is not as long as the number of required splits (24)
-    // then we fill in the gaps
-    if (data.len() as i32) < splits {
-        for i in 1..24 {
-            let time = Utc::now() - chrono::Duration::seconds((number_of_seconds * i).into());
-            let time = time
-                .with_minute(0)
-                .unwrap()
-                .with_second(0)
-                .unwrap()
-                .with_nanosecond(0)
-                .unwrap();
+/// A webhook as the API lists it, the url is a bearer secret for Slack, Discord and the
+/// like, so only its host is shown
+#[derive(Serialize)]
+struct NotificationSummary {
+    id: i32,
+    host: Option<String>,
+}
 
-            let time = if matches!(format, SplitBy::Day) {
-                time.with_hour(0).unwrap()
-            } else {
-                time
-            };
-
-            // if timestamp doesn't exist, push a timestamp with None
-            if !data.iter().any(|x| x.time == time) {
-                data.push(WebsiteStats {
-                    time,
-                    uptime_pct: None,
-                });
-            }
+impl From<NotificationTarget> for NotificationSummary {
+    fn from(target: NotificationTarget) -> Self {
+        Self {
+            id: target.id,
+            host: reqwest::Url::parse(&target.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_owned)),
         }
-        // finally, sort the data
-        data.sort_by(|a, b| b.time.cmp(&a.time));
     }
-
-    data
 }
 
-#[axum::debug_handler]
-async fn get_website_by_alias(
-    State(state): State<AppState>,
-    Path(alias): Path<String>,
-) -> Result<impl AskamaIntoResponse, ApiError> {
-    info!("retrieving website entry for alias");
-    let website = match state {
-        AppState::Postgres(ref p) => {
-            sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_TOP_ONE_WHERE_ALIAS_QUERY)
-                .bind(&alias)
-                .fetch_one(p)
-                .await?
-        }
-        AppState::Sqlite(ref s) => {
-            sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_TOP_ONE_WHERE_ALIAS_QUERY)
-                .bind(&alias)
-                .fetch_one(s)
-                .await?
-        }
-    };
-
-    info!("Getting stats for last 24h");
-    let last_24_hours_data = get_daily_stats(&website.alias, &state).await?;
-    info!("Getting monthly data");
-    let monthly_data = get_monthly_stats(&website.alias, &state).await?;
+#[derive(Deserialize, Validate)]
+struct NewNotification {
+    #[validate(url)]
+    url: String,
+}
 
-    info!("Getting incidents");
-    let incidents = match state {
-        AppState::Postgres(p) => {
-            sqlx::query_as::<_, Incident>(SELECT_INCIDENTS_BY_WEBSITE_ALIAS_QUERY)
-                .bind(&alias)
-                .fetch_all(&p)
-                .await?
-        }
-        AppState::Sqlite(s) => {
-            sqlx::query_as::<_, Incident>(SELECT_INCIDENTS_BY_WEBSITE_ALIAS_QUERY)
-                .bind(&alias)
-                .fetch_all(&s)
-                .await?
-        }
-    };
+/// A row of NotificationChannels without its config, which may hold secrets
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+struct ChannelSummary {
+    id: i32,
+    name: String,
+    kind: String,
+}
 
-    let log = WebsiteInfo {
-        url: website.url,
-        alias,
-        data: last_24_hours_data,
-    };
+#[derive(Deserialize)]
+struct NewChannel {
+    name: String,
+    kind: String,
+    /// The kind's settings, e.g. `{"webhook_url": "..."}` for Slack, and optionally the
+    /// message `template`
+    config: serde_json::Value,
+}
 
-    Ok(SingleWebsiteLog {
-        log,
-        incidents,
-        monthly_data,
-    })
+/// The management page's form, `target` is the url, address or bot token depending on
+/// the kind
+#[derive(Deserialize)]
+struct ChannelForm {
+    name: String,
+    kind: String,
+    target: String,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    chat_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    topic: Option<String>,
+    /// ntfy access token or Gotify app token
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    token: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    insecure_tls: bool,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    template: Option<String>,
 }
 
-async fn delete_website(
-    State(state): State<AppState>,
-    Path(alias): Path<String>,
-) -> Result<impl AxumIntoResponse, ApiError> {
-    match state {
-        AppState::Postgres(p) => delete_website_postgres(&alias, p).await?,
-        AppState::Sqlite(s) => delete_website_sqlite(&alias, s).await?,
-    };
+/// Preview of a message template, without one the built in headline is shown
+#[derive(Deserialize)]
+struct TemplatePreviewRequest {
+    template: Option<String>,
+}
 
-    Ok(StatusCode::OK)
+#[derive(Serialize)]
+struct TemplatePreview {
+    message: String,
 }
 
-async fn delete_website_postgres(alias: &str, db: PgPool) -> Result<(), ApiError> {
-    let mut tx = db.begin().await?;
-    if let Err(e) = sqlx::query(DELETE_LOGS_BY_WEBSITE_ALIAS_QUERY)
-        .bind(alias)
-        .execute(&mut *tx)
-        .await
-    {
-        tx.rollback().await?;
-        return Err(ApiError::SQL(e));
-    };
+#[derive(Deserialize)]
+struct AssignChannelForm {
+    channel_id: i32,
+}
 
-    if let Err(e) = sqlx::query(DELETE_WEBSITE_BY_ALIAS_QUERY)
-        .bind(alias)
-        .execute(&mut *tx)
-        .await
-    {
-        tx.rollback().await?;
-        return Err(ApiError::SQL(e));
-    }
+#[derive(Template)]
+#[template(path = "notification_channels.html")]
+struct NotificationChannelsPage {
+    channels: Vec<ChannelSummary>,
+}
 
-    tx.commit().await?;
+/// A notification sent about a website
+#[derive(Clone, Serialize, sqlx::FromRow)]
+struct NotificationLogEntry {
+    time: DateTime<Utc>,
+    channel: String,
+    kind: String,
+    delivered: bool,
+    error: Option<String>,
+}
 
-    Ok(())
+/// Something noteworthy that isn't an incident, like a latency anomaly
+#[derive(Clone, Serialize, sqlx::FromRow)]
+struct Event {
+    time: DateTime<Utc>,
+    kind: String,
+    message: String,
 }
 
-async fn delete_website_sqlite(alias: &str, db: SqlitePool) -> Result<(), ApiError> {
-    let mut tx = db.begin().await?;
-    if let Err(e) = sqlx::query(DELETE_LOGS_BY_WEBSITE_ALIAS_QUERY)
-        .bind(alias)
-        .execute(&mut *tx)
-        .await
-    {
-        tx.rollback().await?;
-        return Err(ApiError::SQL(e));
-    };
+#[derive(sqlx::FromRow)]
+struct ConnectionReuse {
+    reused: i64,
+    total: i64,
+}
 
-    if let Err(e) = sqlx::query(DELETE_WEBSITE_BY_ALIAS_QUERY)
-        .bind(alias)
-        .execute(&mut *tx)
-        .await
-    {
-        tx.rollback().await?;
-        return Err(ApiError::SQL(e));
+impl ConnectionReuse {
+    fn percentage(&self) -> Option<i64> {
+        (self.total > 0).then(|| self.reused * 100 / self.total)
     }
+}
 
-    tx.commit().await?;
+#[derive(Deserialize)]
+struct ImpactOverride {
+    impact: String,
+}
 
-    Ok(())
+#[derive(Deserialize)]
+struct StatusOverrideForm {
+    status: String,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    note: Option<String>,
+    /// Empty keeps the override until it's cleared by hand
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    expires_in_minutes: Option<i64>,
 }
-async fn check_websites_general(app_state: AppState) {
-    match app_state {
-        AppState::Postgres(p) => check_websites_postgres(p).await,
-        AppState::Sqlite(s) => check_websites_sqlite(s).await,
-    };
+
+#[derive(Deserialize)]
+struct SharedFateForm {
+    host: String,
+    enabled: bool,
 }
 
-async fn check_websites_postgres(db: PgPool) {
-    let mut interval = time::interval(Duration::from_secs(60));
-    loop {
-        interval.tick().await;
+#[derive(Deserialize, ToSchema)]
+struct StatusOverrideRequest {
+    status: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
 
-        let client = reqwest::Client::new();
+#[derive(Clone, FromRef)]
+struct ServerState {
+    db: AppState,
+    store: Arc<dyn Store>,
+    snapshots: SnapshotCache,
+    admin_token: AdminToken,
+    api_key: ApiKey,
+    secrets: Secrets,
+    notification_settings: NotificationSettings,
+    query_log: QueryLog,
+    exec: Option<ExecRunner>,
+    heartbeat: Heartbeat,
+    wakeup: Wakeup,
+    retention: Retention,
+    migrations_dir: MigrationsDir,
+    indexing: Indexing,
+}
 
-        let mut res = sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_QUERY).fetch(&db);
+/// Admin actions are refused unless a token is configured and the request carries it. Like
+/// the API key, only the token's digest is kept and compared
+#[derive(Clone)]
+struct AdminToken {
+    digest: Option<[u8; 32]>,
+}
 
-        while let Some(website) = res.next().await {
-            let website = website.unwrap();
+impl AdminToken {
+    fn new(admin_token: Option<&str>) -> Self {
+        Self {
+            digest: admin_token
+                .filter(|admin_token| !admin_token.is_empty())
+                .map(|admin_token| Sha256::digest(admin_token).into()),
+        }
+    }
 
-            let response = client.get(website.url).send().await.unwrap();
+    fn is_configured(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    fn authorize(&self, token: &str) -> Result<(), ApiError> {
+        let presented: [u8; 32] = Sha256::digest(token).into();
+        match &self.digest {
+            Some(digest) if api_key::equal_in_constant_time(digest, &presented) => Ok(()),
+            _ => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// The token as `Authorization: Bearer <token>`, for reads that aren't form posts
+    fn authorize_bearer(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim())
+            .unwrap_or_default();
+        self.authorize(token)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum AppState {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+    MySql(MySqlPool),
+}
+
+impl AppState {
+    /// The backend for handlers that go through the `Store` trait
+    fn store(&self) -> Arc<dyn Store> {
+        match self {
+            Self::Postgres(p) => Arc::new(p.clone()),
+            Self::Sqlite(s) => Arc::new(s.clone()),
+            Self::MySql(m) => Arc::new(m.clone()),
+        }
+    }
+
+    /// The migrations built in for the backend, or those in `dir` instead
+    async fn migrate_db(&self, dir: Option<&std::path::Path>) -> Result<(), String> {
+        match self {
+            Self::Postgres(p) => Self::migrate_postgres(p, dir).await,
+            Self::Sqlite(s) => sqlite::migrate_sqlite(s, dir).await,
+            Self::MySql(m) => Self::migrate_mysql(m, dir).await,
+        }
+    }
+
+    /// What the database applied compared to the built in migrations, or those in `dir`
+    async fn migration_status(
+        &self,
+        dir: Option<&std::path::Path>,
+    ) -> Result<MigrationStatus, String> {
+        match self {
+            Self::Postgres(p) => {
+                migrations::status(sqlx::migrate!("./migrations_pg"), dir, "postgres", p).await
+            }
+            Self::Sqlite(s) => {
+                migrations::status(sqlx::migrate!("./migrations_sq"), dir, "sqlite", s).await
+            }
+            Self::MySql(m) => {
+                migrations::status(sqlx::migrate!("./migrations_mysql"), dir, "mysql", m).await
+            }
+        }
+    }
+
+    /// The database file for `backup`, Postgres and MySQL are backed up with their own tools
+    fn sqlite_for_backup(&self) -> Result<&SqlitePool, String> {
+        match self {
+            Self::Sqlite(s) => Ok(s),
+            Self::Postgres(_) => {
+                Err("Backups of Postgres are taken with pg_dump, not uptime-ferris".to_owned())
+            }
+            Self::MySql(_) => {
+                Err("Backups of MySQL are taken with mysqldump, not uptime-ferris".to_owned())
+            }
+        }
+    }
+
+    async fn migrate_postgres(pool: &PgPool, dir: Option<&std::path::Path>) -> Result<(), String> {
+        migrations::run(sqlx::migrate!("./migrations_pg"), dir, pool).await
+    }
+
+    async fn migrate_mysql(pool: &MySqlPool, dir: Option<&std::path::Path>) -> Result<(), String> {
+        migrations::run(sqlx::migrate!("./migrations_mysql"), dir, pool).await
+    }
+
+    /// Postgres with --pg, MySQL or MariaDB with --mysql or Sqlite with --sqlite, which
+    /// is also used with its default path when none is given. Empty values count as not
+    /// given, more than one is refused rather than picking one
+    async fn from(item: argument_parsing::Args) -> Result<Self, String> {
+        let pool = PoolSettings::from(&item);
+        let pg = item.pg.filter(|pg| !pg.is_empty());
+        let mysql = item.mysql.filter(|mysql| !mysql.is_empty());
+        let sqlite_path = item.sqlite.filter(|path| !path.is_empty());
+        match (pg, mysql, sqlite_path) {
+            (Some(pg_string), None, None) => pool
+                .options()
+                .connect(&pg_string)
+                .await
+                .map(AppState::Postgres)
+                .map_err(|e| format!("Connecting to Postgres failed: {e}")),
+            (None, Some(mysql_string), None) => pool
+                .options()
+                .connect(&mysql_string)
+                .await
+                .map(AppState::MySql)
+                .map_err(|e| format!("Connecting to MySQL failed: {e}")),
+            (None, None, sqlite_path) => {
+                let path = sqlite_path
+                    .as_deref()
+                    .unwrap_or(sqlite::DEFAULT_SQLITE_PATH);
+                let pragmas = sqlite::Pragmas {
+                    busy_timeout: Duration::from_millis(item.sqlite_busy_timeout_ms),
+                    journal_mode: item.sqlite_journal_mode,
+                    synchronous: item.sqlite_synchronous,
+                };
+                sqlite::connect_sqlite(path, pool.options(), pragmas)
+                    .await
+                    .map(AppState::Sqlite)
+            }
+            _ => Err(
+                "Only one of --pg, --mysql and --sqlite can be given, each selects the database"
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+/// Runs `body` on the backend's pool, bound to `db`, with the backend's queries and
+/// `bind!` in scope. For queries that are the same on every backend but their SQL
+macro_rules! on_db {
+    ($state:expr, |$db:ident| $body:expr) => {
+        match $state {
+            AppState::Postgres($db) => {
+                #[allow(unused_imports)]
+                use crate::postgres_queries::*;
+                $body
+            }
+            AppState::Sqlite($db) => {
+                #[allow(unused_imports)]
+                use crate::sqlite_queries::*;
+                $body
+            }
+            AppState::MySql($db) => {
+                #[allow(unused_imports)]
+                use crate::mysql_queries::*;
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use on_db;
+
+/// Sizes and timeouts of the connection pool, the same for every backend
+#[derive(Debug)]
+struct PoolSettings {
+    max_connections: u32,
+    acquire_timeout: Duration,
+    /// `None` keeps idle connections open
+    idle_timeout: Option<Duration>,
+}
+
+impl PoolSettings {
+    fn from(args: &argument_parsing::Args) -> Self {
+        let settings = Self {
+            max_connections: args.db_max_connections,
+            acquire_timeout: Duration::from_secs(args.db_acquire_timeout_secs),
+            idle_timeout: Some(args.db_idle_timeout_secs)
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+        };
+        info!("Database pool: {settings:?}");
+        settings
+    }
+
+    fn options<DB: sqlx::Database>(&self) -> PoolOptions<DB> {
+        PoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+enum ApiError {
+    SQL(sqlx::Error),
+    BadRequest(String),
+    NotFound,
+    /// Changes were attempted without the configured API key
+    Unauthorized,
+    Forbidden,
+    /// Would clash with something that exists already, like an alias
+    Conflict(String),
+    /// Submitted form inputs that didn't pass validation
+    Invalid(Vec<FieldError>),
+    Timeout,
+    /// The client has to wait this long before writing again
+    TooManyRequests(Duration),
+    /// A notification couldn't be delivered
+    Delivery(String),
+    /// The applied migrations couldn't be read
+    Migrations(String),
+    /// The database couldn't be backed up
+    Backup(String),
+    /// The error as `{"error": ...}` for JSON APIs, with the same status code
+    Json(Box<ApiError>),
+}
+
+impl ApiError {
+    /// For handlers of JSON APIs, `.map_err(ApiError::json)?`
+    fn json(e: impl Into<ApiError>) -> Self {
+        match e.into() {
+            json @ Self::Json(_) => json,
+            e => Self::Json(Box::new(e)),
+        }
+    }
+
+    /// For handlers answering with either a page or JSON, see [`Negotiated`]
+    fn negotiated(self, json: bool) -> Self {
+        if json { Self::json(self) } else { self }
+    }
+
+    fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::SQL(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("SQL Error: {e}")),
+            Self::BadRequest(e) => (StatusCode::BAD_REQUEST, e.clone()),
+            Self::NotFound => (StatusCode::NOT_FOUND, "Not found".to_owned()),
+            Self::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong API key".to_owned(),
+            ),
+            Self::Forbidden => (StatusCode::FORBIDDEN, "Not allowed".to_owned()),
+            Self::Conflict(e) => (StatusCode::CONFLICT, e.clone()),
+            Self::Invalid(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Invalid input: {}",
+                    errors
+                        .iter()
+                        .map(|error| format!("{} {}", error.field, error.message))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+            Self::Timeout => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Database didn't answer in time, try again later".to_owned(),
+            ),
+            Self::TooManyRequests(retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Too many requests, try again in {}s",
+                    retry_after_secs(*retry_after)
+                ),
+            ),
+            Self::Delivery(e) => (StatusCode::BAD_GATEWAY, format!("Delivery failed: {e}")),
+            Self::Migrations(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.clone()),
+            Self::Backup(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.clone()),
+            Self::Json(e) => e.status_and_message(),
+        }
+    }
+}
+
+/// How JSON APIs answer with an error
+#[derive(Serialize, ToSchema)]
+struct ErrorBody {
+    error: String,
+    /// The rejected inputs when validation failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldError>,
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::SQL(e)
+    }
+}
+
+/// Whole seconds as `Retry-After` wants them, rounded up so retrying on time succeeds
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+impl AxumIntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.status_and_message();
+        match self {
+            Self::Json(e) => {
+                let fields = match *e {
+                    Self::Invalid(errors) => errors,
+                    _ => Vec::new(),
+                };
+                AxumIntoResponse::into_response((
+                    status,
+                    Json(ErrorBody {
+                        error: message,
+                        fields,
+                    }),
+                ))
+            }
+            Self::NotFound => AxumIntoResponse::into_response((status, NotFoundPage)),
+            Self::TooManyRequests(retry_after) => AxumIntoResponse::into_response((
+                status,
+                [(RETRY_AFTER, retry_after_secs(retry_after).to_string())],
+                message,
+            )),
+            Self::Unauthorized => {
+                AxumIntoResponse::into_response((status, [(WWW_AUTHENTICATE, "Bearer")], message))
+            }
+            _ => AxumIntoResponse::into_response((status, message)),
+        }
+    }
+}
+
+/// `db status` and `db migrate`, exiting with 1 when the database can't be opened or a
+/// migration fails
+async fn run_db_command(args: Args, command: DbCommand) {
+    let migrations_dir = args.migrations_dir.clone();
+    let app_state = match AppState::from(args).await {
+        Ok(app_state) => app_state,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if let DbCommand::Migrate = command {
+        if let Err(e) = app_state.migrate_db(migrations_dir.as_deref()).await {
+            error!("{e}");
+            std::process::exit(1);
+        }
+        info!("Finished db migration");
+    }
+    match app_state.migration_status(migrations_dir.as_deref()).await {
+        Ok(status) => print!("{status}"),
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `backup`, exiting with 1 when the copy fails or fails its integrity check
+async fn run_backup(args: Args, out: std::path::PathBuf) {
+    let app_state = match AppState::from(args).await {
+        Ok(app_state) => app_state,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let backed_up = match app_state.sqlite_for_backup() {
+        Ok(pool) => sqlite::backup_sqlite(pool, &out).await,
+        Err(e) => Err(e),
+    };
+    match backed_up {
+        Ok(()) => println!("Backed up to {}", out.display()),
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    //Init tracing
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+                // axum logs rejections from built-in extractors with the `axum::rejection`
+                // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
+                format!(
+                    "{}=debug,tower_http=debug,axum::rejection=trace",
+                    env!("CARGO_CRATE_NAME")
+                )
+                .into()
+            }),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let mut args = Args::parse();
+    match args.command.take() {
+        Some(Command::Db { command }) => {
+            run_db_command(args, command).await;
+            return;
+        }
+        Some(Command::Backup { out }) => {
+            run_backup(args, out).await;
+            return;
+        }
+        Some(Command::MigrateData(migrate_data)) => {
+            data_migration::run(migrate_data).await;
+            return;
+        }
+        Some(Command::Serve) | None => {}
+    }
+    let snapshots = SnapshotCache::new(Duration::from_millis(args.db_time_budget_ms));
+    let admin_token = AdminToken::new(args.admin_token.as_deref());
+    let api_key = ApiKey::new(args.api_key.as_deref());
+    let rate_limiter = RateLimiter::new(args.write_rate_limit_per_minute, args.trust_proxy);
+    let secrets = Secrets::new(args.secrets_key.as_deref());
+    let query_log = QueryLog::new(
+        Duration::from_millis(args.slow_query_threshold_ms),
+        args.slow_query_log_size,
+    );
+    if !admin_token.is_configured() {
+        info!("No admin token configured, posting incident updates is disabled");
+    }
+    if !api_key.is_configured() {
+        info!("No API key configured, anyone who can reach the server can change it");
+    }
+    let exec = args
+        .exec_monitor_dir
+        .as_deref()
+        .filter(|_| args.allow_exec_monitors)
+        .map(|dir| {
+            ExecRunner::new(
+                dir,
+                Duration::from_secs(args.exec_timeout_secs),
+                args.exec_max_concurrency,
+            )
+        })
+        .transpose();
+    let exec = match exec {
+        Ok(exec) => exec,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let proxy = match checker::build_proxy(args.proxy_url.as_deref(), args.no_proxy.as_deref()) {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let captured_headers = match CapturedHeaders::new(&args.capture_headers) {
+        Ok(captured_headers) => captured_headers,
+        Err(e) => {
+            error!("--capture-headers is invalid: {e}");
+            std::process::exit(1);
+        }
+    };
+    let checker_settings = checker::CheckerSettings {
+        proxy,
+        fresh_connections: args.fresh_connections,
+        timeout: Duration::from_secs(args.check_timeout_secs),
+        connect_timeout: Duration::from_secs(args.check_connect_timeout_secs),
+        anomaly_sensitivity: args.anomaly_sensitivity,
+        secrets: secrets.clone(),
+        exec: exec.clone(),
+        captured_headers,
+    };
+    let mailer = match (args.smtp_host.as_deref(), args.smtp_from.as_deref()) {
+        (Some(host), Some(from)) => match Mailer::new(SmtpSettings {
+            host,
+            port: args.smtp_port,
+            tls: args.smtp_tls,
+            username: args.smtp_username.as_deref(),
+            password: args.smtp_password.as_deref(),
+            from,
+            default_to: args.alert_email_to.as_deref(),
+        }) {
+            Ok(mailer) => Some(mailer),
+            Err(e) => {
+                error!("{e}");
+                std::process::exit(1);
+            }
+        },
+        (Some(_), None) => {
+            error!("--smtp-from is required with --smtp-host");
+            std::process::exit(1);
+        }
+        (None, _) => None,
+    };
+    let (deliveries, delivered) = DeliveryLog::new();
+    let notification_settings = NotificationSettings {
+        client: reqwest::Client::new(),
+        insecure_client: reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .expect("building the notification client failed"),
+        slack_webhook_url: args.slack_webhook_url.clone(),
+        discord_webhook_url: args.discord_webhook_url.clone(),
+        telegram: args
+            .telegram_bot_token
+            .clone()
+            .zip(args.telegram_chat_id.clone())
+            .map(|(bot_token, chat_id)| TelegramChat { bot_token, chat_id }),
+        shared_fate_window: TimeDelta::seconds(args.shared_fate_window_secs as i64),
+        cooldown: TimeDelta::seconds(args.notification_cooldown_secs as i64),
+        ack_sla: args
+            .ack_sla_minutes
+            .map(|minutes| TimeDelta::minutes(minutes as i64)),
+        deliveries,
+        mailer,
+        public_url: args.public_url.clone(),
+    };
+    let indexing = Indexing(args.allow_indexing);
+    let compress = !args.no_compression;
+    let cors = cors::layer(&args.cors_allowed_origins);
+    let digest_schedule = match args
+        .digest_schedule
+        .as_deref()
+        .map(schedule::parse_cron)
+        .transpose()
+    {
+        Ok(digest_schedule) => digest_schedule,
+        Err(e) => {
+            error!("--digest-schedule is no valid cron expression: {e}");
+            std::process::exit(1);
+        }
+    };
+    if checker_settings.proxy.is_none() {
+        info!("Checks are sent without a proxy");
+    }
+    let readonly_role = args
+        .create_readonly_role
+        .clone()
+        .zip(args.readonly_role_password.clone());
+    let migrations_dir = args.migrations_dir.clone();
+    let (retention, retention_vacuum) = (Retention(args.retention_days), args.retention_vacuum);
+    let app_state = match AppState::from(args).await {
+        Ok(app_state) => app_state,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    // carry out migrations
+    info!("Starting db migration");
+    if let Err(e) = app_state.migrate_db(migrations_dir.as_deref()).await {
+        error!("{e}");
+        std::process::exit(1);
+    }
+    info!("Finished db migration");
+    if let Some((role, password)) = readonly_role {
+        let AppState::Postgres(pool) = &app_state else {
+            error!("--create-readonly-role needs a Postgres database");
+            std::process::exit(1);
+        };
+        if let Err(e) = schema::create_readonly_role(pool, &role, &password).await {
+            error!("Creating the read-only role failed: {e}");
+            std::process::exit(1);
+        }
+        info!("Read-only role {role} can now read the stable tables");
+        return;
+    }
+    let cloned_state = app_state.clone();
+    tokio::spawn(notifications::record_deliveries(
+        app_state.clone(),
+        delivered,
+    ));
+    if let Some(digest_schedule) = digest_schedule {
+        info!("Starting background task for sending digests");
+        tokio::spawn(digest::send_digests(
+            app_state.clone(),
+            digest_schedule,
+            notification_settings.clone(),
+        ));
+    }
+    tokio::spawn(rate_limit::evict_stale_buckets(rate_limiter.clone()));
+    info!("Starting background task for purging deleted websites");
+    tokio::spawn(retention::purge_deleted_websites(app_state.store()));
+    info!("Starting background task for folding old checks into hourly stats");
+    tokio::spawn(rollup::fold_stats(app_state.clone()));
+    match retention.0 {
+        Some(days) => info!("Starting background task for pruning checks older than {days} days"),
+        None => info!("Starting background task for pruning checks of websites with a retention"),
+    }
+    tokio::spawn(retention::prune_logs(
+        app_state.clone(),
+        retention,
+        retention_vacuum,
+    ));
+    //Check the website status
+    info!("Starting background task for checking website status");
+    let (stop_checker, checker_shutdown) = watch::channel(false);
+    let checker_notification_settings = notification_settings.clone();
+    let heartbeat = Heartbeat::default();
+    let checker_heartbeat = heartbeat.clone();
+    let wakeup = Wakeup::default();
+    let checker_wakeup = wakeup.clone();
+    let checker = tokio::spawn(async move {
+        checker::check_websites_general(
+            cloned_state.store(),
+            checker_settings,
+            checker_notification_settings,
+            checker_heartbeat,
+            checker_wakeup,
+            checker_shutdown,
+        )
+        .await;
+    });
+
+    let app = router(
+        ServerState {
+            store: app_state.store(),
+            db: app_state,
+            snapshots,
+            admin_token,
+            api_key,
+            secrets,
+            notification_settings,
+            query_log,
+            exec,
+            heartbeat,
+            wakeup,
+            retention,
+            migrations_dir: MigrationsDir(migrations_dir),
+            indexing,
+        },
+        rate_limiter,
+        cors,
+        compress,
+    );
+
+    // run it
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
+        .await
+        .unwrap();
+    info!("listening on {}", listener.local_addr().unwrap());
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+
+    info!("Waiting for the checker to finish the website it's checking");
+    let _ = stop_checker.send(true);
+    match time::timeout(CHECKER_SHUTDOWN_TIMEOUT, checker).await {
+        Ok(Ok(())) => info!("checker stopped cleanly"),
+        Ok(Err(e)) => warn!("checker task failed: {e}"),
+        Err(_) => warn!(
+            "checker didn't stop within {}s, exiting anyway",
+            CHECKER_SHUTDOWN_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+/// Every route with its layers, `compress` puts gzip and friends in front of all of them
+fn router(
+    state: ServerState,
+    rate_limiter: RateLimiter,
+    cors: Option<CorsLayer>,
+    compress: bool,
+) -> Router {
+    let api_key = state.api_key.clone();
+    let indexing = state.indexing;
+    // The JSON API has its own router, so CORS covers nothing else and even refusals for
+    // a missing API key carry the CORS headers a browser needs to read them
+    let api = Router::new()
+        .route("/api/websites", get(get_websites_json))
+        .route(
+            "/api/websites/import",
+            post(import_websites.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route("/api/websites/export", get(export_websites))
+        .route(
+            "/api/websites/by-id/:id",
+            get(get_website_by_id).delete(delete_website_by_id.layer(
+                middleware::from_fn_with_state(rate_limiter.clone(), rate_limit::limit),
+            )),
+        )
+        .route(
+            "/api/backup",
+            get(get_backup.layer(middleware::from_fn_with_state(
+                api_key.clone(),
+                api_key::require_api_key_for_reads,
+            ))),
+        )
+        .route(
+            "/api/restore",
+            post(restore_backup.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route(
+            "/api/websites/:alias/status-override",
+            put(put_status_override),
+        )
+        .route("/api/websites/:alias/heatmap", get(get_heatmap_by_alias))
+        .route("/api/websites/:alias/trends", get(get_trends_by_alias))
+        .route(
+            "/api/websites/:alias/status",
+            get(get_current_status_by_alias),
+        )
+        .route("/api/websites/:alias/stats", get(get_stats_by_alias))
+        .route("/api/websites/:alias/latency", get(get_latency_by_alias))
+        .route(
+            "/api/websites/:alias/status-codes",
+            get(get_status_codes_by_alias),
+        )
+        .route("/api/websites/:alias/sla", get(get_sla_by_alias))
+        .route("/api/sla", get(get_sla))
+        .route(
+            "/api/websites/:alias/incidents",
+            get(get_incidents_by_alias),
+        )
+        .route(
+            "/api/notifications",
+            get(get_notifications).post(create_notification),
+        )
+        .route("/api/notifications/:id", delete(delete_notification))
+        .route("/api/notifications/:id/test", post(test_notification))
+        .route("/api/discord/test", post(test_discord))
+        .route(
+            "/api/notification-channels",
+            get(get_notification_channels).post(create_notification_channel),
+        )
+        .route(
+            "/api/notification-channels/:id",
+            delete(delete_notification_channel),
+        )
+        .route(
+            "/api/notification-channels/:id/test",
+            post(test_notification_channel),
+        )
+        .route(
+            "/api/notification-templates/preview",
+            post(preview_message_template),
+        )
+        .route(
+            "/api/websites/:alias/notification-channels",
+            get(get_website_channels),
+        )
+        .route(
+            "/api/websites/:alias/notification-channels/:id",
+            put(put_website_channel).delete(delete_website_channel),
+        )
+        .route(
+            "/api/websites/:alias/escalation-rules",
+            get(get_escalation_rules).post(create_escalation_rule),
+        )
+        .route(
+            "/api/websites/:alias/escalation-rules/:id",
+            delete(delete_escalation_rule),
+        )
+        .route("/api/slow-queries", get(get_slow_queries_json))
+        .route("/api/schema", get(get_schema))
+        .route("/api/admin/db", get(get_db_status))
+        .route("/api/admin/backup", get(get_sqlite_backup))
+        .route("/status.json", get(get_status_json))
+        .merge(api_docs::swagger_ui())
+        .layer(middleware::from_fn_with_state(
+            api_key.clone(),
+            api_key::require_api_key,
+        ));
+    let api = match cors {
+        Some(cors) => api.layer(cors),
+        None => api,
+    };
+    let app = Router::new()
+        .route(
+            "/",
+            get(get_websites.layer(middleware::map_response(vary_on_accept))),
+        )
+        .route("/fragments/websites", get(get_websites_fragment))
+        .route(
+            "/websites",
+            post(create_website.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route(
+            "/websites/:alias",
+            get(get_website_by_alias.layer(middleware::map_response(vary_on_accept)))
+                .put(update_website)
+                .delete(delete_website.layer(middleware::from_fn_with_state(
+                    rate_limiter.clone(),
+                    rate_limit::limit,
+                ))),
+        )
+        .route(
+            "/websites/:alias/logs",
+            delete(clear_website_history.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route("/websites/:alias/edit", post(update_website_form))
+        .route("/websites/:alias/rename", post(rename_website))
+        .route(
+            "/websites/:alias/delete",
+            post(delete_website_form.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route("/websites/:alias/restore", post(restore_deleted_website))
+        .route(
+            "/websites/:alias/purge",
+            post(purge_website.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route("/websites/:alias/logs.csv", get(get_logs_csv))
+        .route(
+            "/websites/:alias/fragments/monthly",
+            get(get_monthly_fragment),
+        )
+        .route("/websites/:alias/fragments/live", get(get_website_fragment))
+        .route(
+            "/websites/:alias/incidents/:id/impact",
+            post(set_incident_impact),
+        )
+        .route(
+            "/websites/:alias/status-override",
+            post(set_status_override),
+        )
+        .route("/incidents/:id", get(get_incident))
+        .route("/incidents/:id/updates", post(post_incident_update))
+        .route("/incidents/:id/ack", post(acknowledge_incident))
+        .route("/incidents/:id/acknowledge", post(acknowledge_incident))
+        .route("/incidents/:id/feed.atom", get(get_incident_feed))
+        .route(
+            "/notification-channels",
+            get(get_notification_channels_page).post(post_notification_channel_form),
+        )
+        .route(
+            "/websites/:alias/notification-channels",
+            post(post_website_channel_form),
+        )
+        .route("/shared-fate", post(set_shared_fate))
+        .route("/slow-queries", get(get_slow_queries))
+        .route("/styles.css", get(styles))
+        .route("/robots.txt", get(get_robots_txt))
+        .route("/sitemap.xml", get(get_sitemap))
+        .route("/metrics", get(get_metrics))
+        .route("/badge/:badge", get(get_badge))
+        .fallback(not_found)
+        .layer(middleware::from_fn_with_state(
+            api_key,
+            api_key::require_login,
+        ))
+        // After the login check, which would otherwise send browsers here to log in first
+        .route(
+            "/login",
+            get(api_key::login_page).post(api_key::login.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                rate_limit::limit,
+            ))),
+        )
+        .route("/logout", post(api_key::logout))
+        // The forms and the login, the API is meant to be called from other origins
+        .layer(middleware::from_fn(same_origin::require_same_origin))
+        .merge(api)
+        .layer(middleware::from_fn_with_state(
+            indexing,
+            robots::noindex_html,
+        ))
+        .layer(TraceLayer::new_for_http())
+        // Added after the layers, probes come every few seconds and would drown the
+        // request traces
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .with_state(state);
+    // Outermost, so everything including the probes is compressed when it's worth it.
+    // Badges are a few hundred bytes and get embedded through image proxies, which
+    // don't all pass encoded SVGs on
+    if compress {
+        app.layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new().and(NotForContentType::const_new("image/svg+xml")),
+        ))
+    } else {
+        app
+    }
+}
+
+async fn styles() -> impl AxumIntoResponse {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/css")
+        .body(include_str!("../templates/styles.css").to_owned())
+        .unwrap()
+}
+
+/// Where the dashboard is reachable from outside, the request's host without
+/// `--public-url`
+fn base_url(notification_settings: &NotificationSettings, headers: &HeaderMap) -> Option<String> {
+    match &notification_settings.public_url {
+        Some(public_url) => Some(public_url.trim_end_matches('/').to_owned()),
+        None => headers
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(|host| format!("http://{host}")),
+    }
+}
+
+async fn not_found() -> ApiError {
+    ApiError::NotFound
+}
+
+async fn get_robots_txt(
+    State(notification_settings): State<NotificationSettings>,
+    State(indexing): State<Indexing>,
+    headers: HeaderMap,
+) -> impl AxumIntoResponse {
+    let sitemap_url = base_url(&notification_settings, &headers)
+        .map(|base_url| format!("{base_url}/sitemap.xml"));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(robots::robots_txt(indexing, sitemap_url.as_deref()))
+        .unwrap()
+}
+
+/// Public pages for search engines, only served while indexing is allowed
+#[axum::debug_handler(state = ServerState)]
+async fn get_sitemap(
+    State(store): State<Arc<dyn Store>>,
+    State(notification_settings): State<NotificationSettings>,
+    State(Indexing(allow)): State<Indexing>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if !allow {
+        return Err(ApiError::NotFound);
+    }
+    let base_url = base_url(&notification_settings, &headers).ok_or(ApiError::NotFound)?;
+    let websites = store.websites().await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/xml")
+        .body(
+            robots::sitemap_xml(
+                &base_url,
+                websites.iter().map(|website| website.alias.as_str()),
+            )
+            .into(),
+        )
+        .unwrap())
+}
+
+/// Liveness, answers as long as the server serves requests
+async fn get_healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness, the database answers a trivial query and the checker is still ticking.
+/// 503 with what's unhealthy otherwise
+#[axum::debug_handler(state = ServerState)]
+async fn get_readyz(
+    State(store): State<Arc<dyn Store>>,
+    State(heartbeat): State<Heartbeat>,
+) -> Response {
+    let database = time::timeout(READINESS_DB_TIMEOUT, store.ping()).await;
+    let database = match database {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!(
+            "The database didn't answer within {}s",
+            READINESS_DB_TIMEOUT.as_secs()
+        )),
+    };
+
+    let readiness = Readiness::new(database, &heartbeat, Utc::now());
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+/// Every website's latest check for Prometheus, read from the logs without probing
+#[axum::debug_handler(state = ServerState)]
+async fn get_metrics(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+) -> Result<Response, ApiError> {
+    let websites = query_log
+        .time("metrics", None, store.website_metrics())
+        .await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics::prometheus_text(&websites).into())
+        .unwrap())
+}
+
+/// `/badge/{alias}.svg`, the router can't split a parameter from a suffix in the same
+/// segment. Unknown aliases get a grey badge instead of a 404 so READMEs keep an image,
+/// an active manual status shows in place of the latest check
+#[axum::debug_handler(state = ServerState)]
+async fn get_badge(
+    State(store): State<Arc<dyn Store>>,
+    Path(badge): Path<String>,
+    Query(params): Query<BadgeParams>,
+) -> Result<Response, ApiError> {
+    let alias = badge.strip_suffix(".svg").ok_or(ApiError::NotFound)?;
+    let website = store.website_summary(alias).await?;
+    let Some(website) = website else {
+        return Ok(Badge::unknown(alias).into_svg_response());
+    };
+    if !params.uptime() {
+        let manual_status = store
+            .active_status_overrides(Utc::now())
+            .await?
+            .iter()
+            .find(|status_override| status_override.alias == alias)
+            .and_then(StatusOverride::manual_status);
+        let badge = match manual_status {
+            Some(manual_status) => Badge::manual(alias, manual_status),
+            None => Badge::status(alias, website.current_status),
+        };
+        return Ok(badge.into_svg_response());
+    }
+
+    let until = Utc::now();
+    let from = until - TimeDelta::hours(24);
+    let (uptime_pct, _) = store.window_stats(alias, from, until).await?;
+
+    Ok(Badge::uptime(alias, uptime_pct).into_svg_response())
+}
+
+/// Browsers get the dashboard back with the errors next to the form when the website
+/// is rejected
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/websites",
+    tag = "websites",
+    request_body(content = Website, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 201, description = "Created, for callers accepting application/json", body = WebsiteSummary),
+        (status = 303, description = "Created, browsers are sent to the website's page"),
+        (status = 400, description = "The notification channel doesn't exist", body = ErrorBody),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 409, description = "The alias is taken", body = ErrorBody),
+        (status = 422, description = "Inputs failed validation", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn create_website(
+    State(store): State<Arc<dyn Store>>,
+    State(secrets): State<Secrets>,
+    State(exec): State<Option<ExecRunner>>,
+    State(query_log): State<QueryLog>,
+    State(wakeup): State<Wakeup>,
+    headers: HeaderMap,
+    Form(new_website): Form<Website>,
+) -> Result<Response, ApiError> {
+    let (url, alias) = (new_website.url.clone(), new_website.alias.clone());
+    let created = add_website(
+        store.as_ref(),
+        &secrets,
+        exec.as_ref(),
+        &wakeup,
+        new_website,
+    )
+    .await;
+
+    if accepts_json(&headers) {
+        let website = created.map_err(ApiError::json)?;
+        return Ok((StatusCode::CREATED, Json(website)).into_response());
+    }
+    let (status, errors) = match created {
+        Ok(website) => {
+            return Ok(Redirect::to(&format!("/websites/{}", website.alias)).into_response());
+        }
+        Err(ApiError::Invalid(errors)) => (StatusCode::UNPROCESSABLE_ENTITY, errors),
+        Err(ApiError::Conflict(_)) => (
+            StatusCode::CONFLICT,
+            vec![FieldError::new("alias", "is taken by another website")],
+        ),
+        Err(e) => return Err(e),
+    };
+    let mut website_logs = load_website_logs(
+        store.as_ref(),
+        &query_log,
+        PageParams::default(),
+        DashboardView::default(),
+    )
+    .await?;
+    website_logs.rejected = Some(RejectedWebsite { url, alias, errors });
+
+    Ok((status, website_logs).into_response())
+}
+
+/// API callers ask for JSON, browsers submitting the form don't
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"))
+}
+
+/// Set on every response of a route that negotiates, errors included, so caches keep
+/// the representations apart
+async fn vary_on_accept(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// A page's data as JSON for clients asking for it with `Accept`, the rendered page
+/// otherwise. Routes serving it are layered with [`vary_on_accept`]
+enum Negotiated<T> {
+    Html(T),
+    Json(T),
+}
+
+impl<T> Negotiated<T> {
+    fn new(json: bool, data: T) -> Self {
+        if json {
+            Self::Json(data)
+        } else {
+            Self::Html(data)
+        }
+    }
+}
+
+impl<T: AskamaIntoResponse + Serialize> AxumIntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Html(page) => AskamaIntoResponse::into_response(page),
+            Self::Json(data) => Json(data).into_response(),
+        }
+    }
+}
+
+/// Many websites at once from a JSON array or a CSV with a header row, every row checked
+/// like a created website. `?mode=all_or_nothing` keeps none of them unless all are good
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/api/websites/import",
+    tag = "websites",
+    params(ImportParams),
+    request_body(
+        content = [ExportedWebsite],
+        description = "A JSON array of websites, or as text/csv a header row naming the same inputs and a website per row"
+    ),
+    responses(
+        (status = 200, description = "What happened to every row", body = ImportReport),
+        (status = 400, description = "Unknown mode or a body that isn't a JSON array or CSV", body = ErrorBody),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn import_websites(
+    State(store): State<Arc<dyn Store>>,
+    State(secrets): State<Secrets>,
+    State(exec): State<Option<ExecRunner>>,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportReport>, ApiError> {
+    let mode = params
+        .mode()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok());
+    let rows = website_import::parse_rows(content_type, &body)
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?
+        .into_iter()
+        .map(|row| {
+            let website = row
+                .website
+                .map_err(ApiError::BadRequest)
+                .and_then(|website| prepare_website(&secrets, exec.as_ref(), website));
+            (row.alias, website)
+        })
+        .collect();
+
+    let report = store
+        .import_websites(rows, mode)
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok(Json(report))
+}
+
+/// Every website in the shape the import takes, `?format=csv` for a CSV download
+#[utoipa::path(
+    get,
+    path = "/api/websites/export",
+    tag = "websites",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "Every website as the import takes it, a CSV download with format=csv", body = [ExportedWebsite]),
+        (status = 400, description = "Unknown format", body = ErrorBody),
+    )
+)]
+async fn export_websites(
+    State(store): State<Arc<dyn Store>>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, ApiError> {
+    let format = params
+        .format()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let websites = store.export_websites().await.map_err(ApiError::json)?;
+    let websites: Vec<ExportedWebsite> = websites.into_iter().map(ExportedWebsite::from).collect();
+
+    Ok(match format {
+        ExportFormat::Json => Json(websites).into_response(),
+        ExportFormat::Csv => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/csv; charset=utf-8")
+            .header(
+                "Content-Disposition",
+                "attachment; filename=\"websites.csv\"",
+            )
+            .body(website_import::websites_csv(&websites).into())
+            .unwrap(),
+    })
+}
+
+/// Validates the website and stores it together with its channel, either both are
+/// there afterwards or neither is. The checker is woken once it's committed, so the
+/// first check doesn't wait for the next tick
+async fn add_website(
+    store: &dyn Store,
+    secrets: &Secrets,
+    exec: Option<&ExecRunner>,
+    wakeup: &Wakeup,
+    new_website: Website,
+) -> Result<WebsiteSummary, ApiError> {
+    let new_website = prepare_website(secrets, exec, new_website)?;
+
+    let created = store.insert_website(&new_website).await?;
+    wakeup.wake();
+    Ok(created)
+}
+
+/// Checks a submitted website and brings it into the shape it's stored in, the same
+/// for creating and editing one
+fn prepare_website(
+    secrets: &Secrets,
+    exec: Option<&ExecRunner>,
+    mut new_website: Website,
+) -> Result<Website, ApiError> {
+    if let Err(errors) = new_website.validate() {
+        return Err(ApiError::Invalid(field_errors::field_errors(&errors)));
+    }
+
+    if let Some(cron_schedule) = &new_website.config.cron_schedule
+        && let Err(e) = schedule::parse_cron(cron_schedule)
+    {
+        return Err(ApiError::Invalid(vec![FieldError::new(
+            "cron_schedule",
+            format!("isn't a valid cron schedule: {e}"),
+        )]));
+    }
+
+    if new_website.monitor_type.parse() == Ok(checker::MonitorType::Exec) {
+        let Some(exec) = exec else {
+            return Err(ApiError::Invalid(vec![FieldError::new(
+                "monitor_type",
+                "can't be exec, start with --allow-exec-monitors to enable exec monitors",
+            )]));
+        };
+        if let Err(e) = exec.resolve(&new_website.url) {
+            return Err(ApiError::Invalid(vec![FieldError::new(
+                "url",
+                format!("isn't a usable command: {e}"),
+            )]));
+        }
+    }
+
+    if let Err(e) = prepare_client_identity(&mut new_website, secrets) {
+        return Err(ApiError::Invalid(vec![FieldError::new(
+            "client_cert_path",
+            format!("isn't a usable client certificate: {e}"),
+        )]));
+    }
+
+    new_website.monitor_type = new_website
+        .monitor_type
+        .parse::<checker::MonitorType>()
+        .unwrap_or_default()
+        .as_str()
+        .to_owned();
+    new_website.ip_version = new_website
+        .ip_version
+        .parse::<checker::IpVersion>()
+        .unwrap_or_default()
+        .as_str()
+        .to_owned();
+    new_website.impact_weight = new_website
+        .impact_weight
+        .parse::<Impact>()
+        .unwrap_or_default()
+        .as_str()
+        .to_owned();
+
+    Ok(new_website)
+}
+
+/// A duplicate alias or a channel that doesn't exist is the caller's mistake
+fn website_write_error(e: sqlx::Error, alias: &str) -> ApiError {
+    match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => ApiError::Conflict(format!(
+            "A website with the alias '{alias}' exists already, or was deleted and can be \
+                restored, or purged with POST /websites/{alias}/purge to free the alias"
+        )),
+        Some(db_error) if db_error.is_foreign_key_violation() => {
+            ApiError::BadRequest("No such notification channel".to_owned())
+        }
+        _ => ApiError::SQL(e),
+    }
+}
+
+/// Everything configured as one versioned document `/api/restore` takes back, no logs.
+/// Needs the API key even though it's a read. `?include_secrets=true` adds webhook URLs,
+/// channel settings and client certificate paths, for the admin token as a bearer token
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/backup",
+    tag = "backup",
+    params(BackupParams),
+    responses(
+        (status = 200, description = "Websites with their channels, escalation rules and status overrides, the channels and, with the secrets, the webhooks", body = Object),
+        (status = 401, description = "An API key is configured and the request doesn't carry it as X-Api-Key"),
+        (status = 403, description = "Secrets were asked for and no admin token is configured or the request doesn't carry it"),
+    )
+)]
+async fn get_backup(
+    State(store): State<Arc<dyn Store>>,
+    State(admin_token): State<AdminToken>,
+    headers: HeaderMap,
+    Query(params): Query<BackupParams>,
+) -> Result<Json<Backup>, ApiError> {
+    if params.include_secrets {
+        admin_token
+            .authorize_bearer(&headers)
+            .map_err(ApiError::json)?;
+    }
+    let now = Utc::now();
+    let tables = store.backup_tables(now).await.map_err(ApiError::json)?;
+
+    Ok(Json(Backup::new(tables, params.include_secrets, now)))
+}
+
+/// Creates or updates everything in a backup, websites by alias and channels by name.
+/// All of it is kept or none, `?dry_run=true` only reports what would happen
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/api/restore",
+    tag = "backup",
+    params(RestoreParams),
+    request_body(content = Object, description = "A document written by /api/backup"),
+    responses(
+        (status = 200, description = "What happened to everything in the backup", body = RestoreReport),
+        (status = 400, description = "Not a backup, or one of a version this build doesn't know", body = ErrorBody),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn restore_backup(
+    State(store): State<Arc<dyn Store>>,
+    State(secrets): State<Secrets>,
+    State(exec): State<Option<ExecRunner>>,
+    Query(params): Query<RestoreParams>,
+    body: String,
+) -> Result<Json<RestoreReport>, ApiError> {
+    let steps = Backup::from_json(&body)
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?
+        .into_steps(|website| prepare_website(&secrets, exec.as_ref(), website));
+
+    let report = store
+        .restore_backup(steps, params.dry_run)
+        .await
+        .map_err(ApiError::json)?;
+    if report.committed {
+        info!(
+            "Restored a backup, {} created and {} updated",
+            report.created, report.updated
+        );
+    }
+
+    Ok(Json(report))
+}
+
+/// The rule as if created through the API, checked the same way
+fn escalation_rule(
+    after_minutes: i32,
+    repeat_minutes: Option<i32>,
+    channel_id: Option<i32>,
+) -> Result<NewEscalationRule, ApiError> {
+    let rule = NewEscalationRule {
+        after_minutes,
+        repeat_minutes,
+        channel_id,
+    };
+    if let Err(e) = rule.validate() {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid escalation rule: {e}"
+        )));
+    }
+    Ok(rule)
+}
+
+/// A manual status with its note and expiry, the way the websites table holds it
+type StoredStatusOverride = (ManualStatus, Option<String>, Option<DateTime<Utc>>);
+
+/// The status as stored with its note and expiry, `None` clears any override
+fn restored_status_override(
+    status_override: Option<BackupStatusOverride>,
+) -> Result<Option<StoredStatusOverride>, ApiError> {
+    status_override
+        .map(|status_override| {
+            let status = status_override
+                .status
+                .parse::<ManualStatus>()
+                .map_err(ApiError::BadRequest)?;
+            Ok((status, status_override.note, status_override.expires_at))
+        })
+        .transpose()
+}
+
+/// Serves both the API's PUT and the edit form, which can only POST. Renaming keeps the
+/// history as the logs reference the website by id
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    put,
+    path = "/websites/{alias}",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    request_body(content = Website, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Updated, for callers accepting application/json", body = WebsiteSummary),
+        (status = 303, description = "Updated, browsers are sent to the website's page"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+        (status = 409, description = "The new alias is taken", body = ErrorBody),
+        (status = 422, description = "Inputs failed validation", body = ErrorBody),
+    )
+)]
+async fn update_website(
+    State(store): State<Arc<dyn Store>>,
+    State(secrets): State<Secrets>,
+    State(exec): State<Option<ExecRunner>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+    Form(website): Form<Website>,
+) -> Result<Response, ApiError> {
+    let updated = edit_website(store.as_ref(), &secrets, exec.as_ref(), &alias, website).await;
+    if updated.is_ok() {
+        snapshots.forget_website(&alias);
+    }
+
+    if accepts_json(&headers) {
+        Ok(Json(updated.map_err(ApiError::json)?).into_response())
+    } else {
+        let website = updated?;
+        Ok(Redirect::to(&format!("/websites/{}", website.alias)).into_response())
+    }
+}
+
+/// The edit form of the detail page. Rejected values come back on the page with the
+/// errors, saved ones lead to the website's page under its possibly new alias
+#[axum::debug_handler(state = ServerState)]
+#[allow(clippy::too_many_arguments)]
+async fn update_website_form(
+    State(store): State<Arc<dyn Store>>,
+    State(secrets): State<Secrets>,
+    State(exec): State<Option<ExecRunner>>,
+    State(snapshots): State<SnapshotCache>,
+    State(query_log): State<QueryLog>,
+    State(retention): State<Retention>,
+    Path(alias): Path<String>,
+    Form(website): Form<Website>,
+) -> Result<Response, ApiError> {
+    let submitted = website.clone();
+    let updated = edit_website(store.as_ref(), &secrets, exec.as_ref(), &alias, website).await;
+    let (status, errors) = match updated {
+        Ok(website) => {
+            snapshots.forget_website(&alias);
+            return Ok(Redirect::to(&format!("/websites/{}#saved", website.alias)).into_response());
+        }
+        Err(ApiError::Invalid(errors)) => (StatusCode::UNPROCESSABLE_ENTITY, errors),
+        Err(ApiError::Conflict(_)) => (
+            StatusCode::CONFLICT,
+            vec![FieldError::new("alias", "is taken by another website")],
+        ),
+        Err(e) => return Err(e),
+    };
+    let mut single_website_log = load_single_website_log(
+        store.as_ref(),
+        &query_log,
+        retention,
+        &alias,
+        DEFAULT_RANGE,
+        IncidentFilter::default(),
+    )
+    .await?;
+    single_website_log.settings = WebsiteSettings::from(&submitted);
+    single_website_log.rejected = Some(RejectedWebsite {
+        url: submitted.url,
+        alias: submitted.alias,
+        errors,
+    });
+
+    Ok((status, single_website_log).into_response())
+}
+
+async fn edit_website(
+    store: &dyn Store,
+    secrets: &Secrets,
+    exec: Option<&ExecRunner>,
+    alias: &str,
+    website: Website,
+) -> Result<WebsiteSummary, ApiError> {
+    let website = prepare_website(secrets, exec, website)?;
+    let updated = store
+        .update_website(alias, &website)
+        .await
+        .map_err(|e| website_write_error(e, &website.alias))?;
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    let updated = store
+        .website_summary(&website.alias)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    Ok(updated)
+}
+
+/// `POST /websites/{alias}/rename`, the new alias is checked like the alias of a created
+/// website
+#[derive(Deserialize, ToSchema, Validate)]
+struct RenameRequest {
+    #[validate(length(min = 1, max = 64), custom(function = "validate_alias"))]
+    alias: String,
+}
+
+/// Where a renamed website is found from now on
+#[derive(Serialize, ToSchema)]
+struct RenamedWebsite {
+    alias: String,
+    /// Path of the website's page, the old alias isn't found anymore
+    url: String,
+}
+
+/// Changes only the alias, the logs and everything else refer to the website by id and
+/// keep their history
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/websites/{alias}/rename",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Current alias of the website")),
+    request_body(content = RenameRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Renamed, for callers accepting application/json", body = RenamedWebsite),
+        (status = 303, description = "Renamed, browsers are sent to the website's new page"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+        (status = 409, description = "The new alias is taken", body = ErrorBody),
+        (status = 422, description = "The new alias failed validation", body = ErrorBody),
+    )
+)]
+async fn rename_website(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+    Form(rename): Form<RenameRequest>,
+) -> Result<Response, ApiError> {
+    let json = accepts_json(&headers);
+    let renamed = rename_website_by_alias(store.as_ref(), &alias, rename)
+        .await
+        .map_err(|e| e.negotiated(json))?;
+    snapshots.forget_website(&alias);
+    info!("Renamed {alias} to {}", renamed.alias);
+
+    if json {
+        Ok(([(LOCATION, renamed.url.clone())], Json(renamed)).into_response())
+    } else {
+        Ok(Redirect::to(&renamed.url).into_response())
+    }
+}
+
+async fn rename_website_by_alias(
+    store: &dyn Store,
+    alias: &str,
+    rename: RenameRequest,
+) -> Result<RenamedWebsite, ApiError> {
+    if let Err(errors) = rename.validate() {
+        return Err(ApiError::Invalid(field_errors::field_errors(&errors)));
+    }
+
+    let renamed = store
+        .rename_website(alias, &rename.alias)
+        .await
+        .map_err(|e| website_write_error(e, &rename.alias))?;
+    if renamed == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(RenamedWebsite {
+        url: format!("/websites/{}", rename.alias),
+        alias: rename.alias,
+    })
+}
+
+/// Encrypts an uploaded client certificate and key and records the certificate's
+/// fingerprint and expiry, file paths are only checked for being readable
+fn prepare_client_identity(website: &mut Website, secrets: &Secrets) -> Result<(), String> {
+    let cert_pem = match (
+        website.client_cert_path.as_deref(),
+        website.client_key_path.as_deref(),
+        website.client_cert_pem.take(),
+        website.client_key_pem.take(),
+    ) {
+        (None, None, None, None) => return Ok(()),
+        (Some(cert_path), Some(key_path), None, None) => {
+            client_identity::read_pem(key_path)?;
+            client_identity::read_pem(cert_path)?
+        }
+        (None, None, Some(cert_pem), Some(key_pem)) => {
+            website.client_cert_encrypted = Some(secrets.encrypt(&cert_pem)?);
+            website.client_key_encrypted = Some(secrets.encrypt(&key_pem)?);
+            cert_pem
+        }
+        _ => {
+            return Err("give either both a certificate and a key file or upload both".to_owned());
+        }
+    };
+
+    let summary = client_identity::summarize_certificate(&cert_pem)?;
+    website.client_cert_fingerprint = Some(summary.fingerprint);
+    website.client_cert_expires_at = Some(summary.expires_at);
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = ServerState)]
+async fn get_websites(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    State(query_log): State<QueryLog>,
+    headers: HeaderMap,
+    Query(pages): Query<PageParams>,
+    Query(params): Query<DashboardParams>,
+) -> Result<Response, ApiError> {
+    dashboard(
+        store.as_ref(),
+        &snapshots,
+        &query_log,
+        &headers,
+        pages,
+        params,
+        |website_logs, json| Negotiated::new(json, website_logs).into_response(),
+    )
+    .await
+}
+
+/// The status banner and websites of the dashboard, which it polls for with htmx
+#[axum::debug_handler(state = ServerState)]
+async fn get_websites_fragment(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    State(query_log): State<QueryLog>,
+    headers: HeaderMap,
+    Query(pages): Query<PageParams>,
+    Query(params): Query<DashboardParams>,
+) -> Result<Response, ApiError> {
+    dashboard(
+        store.as_ref(),
+        &snapshots,
+        &query_log,
+        &headers,
+        pages,
+        params,
+        |website_logs, _| WebsitesFragment::from(website_logs).into_response(),
+    )
+    .await
+}
+
+/// The dashboard as `render` makes it, from the last snapshot when the database is over
+/// the time budget
+async fn dashboard(
+    store: &dyn Store,
+    snapshots: &SnapshotCache,
+    query_log: &QueryLog,
+    headers: &HeaderMap,
+    pages: PageParams,
+    params: DashboardParams,
+    render: fn(WebsiteLogs, bool) -> Response,
+) -> Result<Response, ApiError> {
+    let json = accepts_json(headers);
+    let view = params
+        .view()
+        .map_err(|e| ApiError::BadRequest(e).negotiated(json))?;
+    let load = async {
+        let etag = get_content_version(store, query_log)
+            .await?
+            .etag(json, Utc::now());
+        if etag::is_fresh(headers, &etag) {
+            return Ok(([(VARY, ACCEPT.as_str())], etag::not_modified(etag)).into_response());
+        }
+        let website_logs = load_website_logs(store, query_log, pages, view).await?;
+        snapshots.store_index(&website_logs);
+        Ok::<_, ApiError>(([(ETAG, etag)], render(website_logs, json)).into_response())
+    };
+    match time::timeout(snapshots.budget(), load).await {
+        Ok(response) => response.map_err(|e| e.negotiated(json)),
+        Err(_) => {
+            info!("Database exceeded the time budget, serving the last dashboard snapshot");
+            snapshots
+                .stale_index(pages, view)
+                .map(|website_logs| render(website_logs, json))
+                .ok_or(ApiError::Timeout.negotiated(json))
+        }
+    }
+}
+
+/// Read before the response is built, so a check landing meanwhile only makes the next
+/// poll load everything again
+async fn get_content_version(
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<ContentVersion, ApiError> {
+    let version = query_log
+        .time("content_version", None, store.content_version())
+        .await?;
+
+    Ok(version)
+}
+
+/// The ongoing incident of every website that is down
+async fn load_open_incidents(
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<OpenIncident>, ApiError> {
+    let open_incidents = query_log
+        .time("open_incidents", None, store.open_incidents())
+        .await?;
+
+    Ok(open_incidents)
+}
+
+/// Only the websites on the page get their stats loaded, the shared host groups and the
+/// overall status still cover all of them. Websites are sorted and filtered before
+/// paginating, so the pages follow the view
+async fn load_website_logs(
+    store: &dyn Store,
+    query_log: &QueryLog,
+    pages: PageParams,
+    view: DashboardView,
+) -> Result<WebsiteLogs, ApiError> {
+    let websites = query_log.time("websites", None, store.websites()).await?;
+    let status_overrides = get_active_status_overrides(store, query_log).await?;
+    let shared_fate_hosts = query_log
+        .time("shared_fate_hosts", None, store.shared_fate_hosts())
+        .await?;
+    let host_groups = shared_fate::host_groups(
+        websites
+            .iter()
+            .map(|website| (website.url.as_str(), website.alias.as_str())),
+        &shared_fate_hosts,
+    );
+    let open_incidents = load_open_incidents(store, query_log).await?;
+    let summaries = if view.needs_summaries() {
+        get_status_summaries(store, query_log).await?
+    } else {
+        Vec::new()
+    };
+    let websites = view.arrange(
+        websites,
+        |website| website.alias.as_str(),
+        |alias| {
+            open_incidents
+                .iter()
+                .any(|incident| incident.alias == alias)
+        },
+        &summaries,
+    );
+    let now = Utc::now();
+    let mut logs = Vec::new();
+    let page = pages.with_total(websites.len() as i64);
+    let on_page = pages.range(websites.len());
+
+    for website in websites.into_iter().take(on_page.end).skip(on_page.start) {
+        let data = get_stats(
+            website.id,
+            &website.alias,
+            StatsRange::DAY,
+            store,
+            query_log,
+        )
+        .await?;
+        let open_incident = open_incidents
+            .iter()
+            .find(|incident| incident.alias == website.alias);
+
+        logs.push(WebsiteInfo {
+            id: website.id,
+            open_incident_id: open_incident.map(|incident| incident.id),
+            unacked_for: open_incident.and_then(|incident| incident.aging(now)),
+            status_override: status_overrides
+                .iter()
+                .find(|status_override| status_override.alias == website.alias)
+                .cloned(),
+            url: website.url,
+            alias: website.alias,
+            data,
+            inverted: website.invert,
+        })
+    }
+
+    let active_incidents = query_log
+        .time(
+            "active_incident_impacts",
+            None,
+            store.active_incident_impacts(),
+        )
+        .await?;
+    let (overall_impact, overall_is_manual) =
+        manual_status::overall_status(&active_incidents, &status_overrides);
+    let recently_deleted = query_log
+        .time(
+            "deleted_websites",
+            None,
+            store.deleted_websites(now - retention::RESTORE_WINDOW),
+        )
+        .await?;
+
+    Ok(WebsiteLogs {
+        logs,
+        overall_impact,
+        overall_is_manual,
+        stale_as_of: None,
+        host_groups,
+        rejected: None,
+        page,
+        view,
+        recently_deleted,
+    })
+}
+
+/// Public summary of every website for widgets and external tooling, polled often so
+/// it's aggregated over all websites at once instead of loaded per website
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/status.json",
+    tag = "status",
+    responses(
+        (status = 200, description = "Overall state and every website's status, cacheable for 15 seconds", body = PublicStatus,
+            headers(("ETag" = String, description = "Changes with every check, send it back in If-None-Match"))),
+        (status = 304, description = "Nothing changed since the ETag in If-None-Match"),
+    )
+)]
+async fn get_status_json(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let etag = get_content_version(store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?
+        .etag(true, Utc::now());
+    if etag::is_fresh(&headers, &etag) {
+        return Ok((
+            [(CACHE_CONTROL, public_status::CACHE_CONTROL)],
+            etag::not_modified(etag),
+        )
+            .into_response());
+    }
+    let summaries = get_status_summaries(store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?;
+    let status_overrides = get_active_status_overrides(store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?;
+    let active_incidents = query_log
+        .time(
+            "active_incident_impacts",
+            None,
+            store.active_incident_impacts(),
+        )
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok((
+        [
+            (
+                CACHE_CONTROL,
+                HeaderValue::from_static(public_status::CACHE_CONTROL),
+            ),
+            (ETAG, etag),
+        ],
+        Json(PublicStatus::new(
+            summaries,
+            &active_incidents,
+            &status_overrides,
+        )),
+    )
+        .into_response())
+}
+
+/// Uptime and last incident of every website, aggregated in one query
+async fn get_status_summaries(
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<StatusSummary>, ApiError> {
+    let now = Utc::now();
+    let day_ago = now - TimeDelta::hours(24);
+    let month_ago = now - TimeDelta::days(30);
+    let summaries = query_log
+        .time(
+            "status_summaries",
+            None,
+            store.status_summaries(day_ago, month_ago),
+        )
+        .await?;
+
+    Ok(summaries)
+}
+
+/// One page of the websites by alias, how many there are in all is in `X-Total-Count`
+#[utoipa::path(
+    get,
+    path = "/api/websites",
+    tag = "websites",
+    params(PageParams),
+    responses(
+        (status = 200, description = "One page of the websites by alias", body = [WebsiteSummary],
+            headers(
+                ("X-Total-Count" = i64, description = "How many websites there are in all"),
+                ("ETag" = String, description = "Changes with every check, send it back in If-None-Match"),
+            )),
+        (status = 304, description = "Nothing changed since the ETag in If-None-Match"),
+    )
+)]
+async fn get_websites_json(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    headers: HeaderMap,
+    Query(pages): Query<PageParams>,
+) -> Result<Response, ApiError> {
+    let etag = get_content_version(store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?
+        .etag(true, Utc::now());
+    if etag::is_fresh(&headers, &etag) {
+        return Ok(etag::not_modified(etag));
+    }
+    let websites = store
+        .website_summaries(pages.limit(), pages.offset())
+        .await
+        .map_err(ApiError::json)?;
+    let total = store.website_count().await.map_err(ApiError::json)?;
+
+    Ok((
+        [
+            ("X-Total-Count", HeaderValue::from(total)),
+            (ETAG.as_str(), etag),
+        ],
+        Json(websites),
+    )
+        .into_response())
+}
+
+async fn get_active_status_overrides(
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<StatusOverride>, ApiError> {
+    let status_overrides = query_log
+        .time(
+            "active_status_overrides",
+            None,
+            store.active_status_overrides(Utc::now()),
+        )
+        .await?;
+
+    Ok(status_overrides)
+}
+
+/// Goes by the website's id, `alias` only labels the query in the slow query log
+async fn get_stats(
+    website_id: i32,
+    alias: &str,
+    range: StatsRange,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<WebsiteStats>, ApiError> {
+    let first_bucket = range.first_bucket(Utc::now());
+    let data = query_log
+        .time(
+            range.query_name(),
+            Some(alias),
+            store.stats(website_id, range.split_by(), first_bucket),
+        )
+        .await?;
+
+    Ok(fill_data_gaps(data, range, first_bucket))
+}
+
+async fn get_heatmap(
+    alias: &str,
+    weeks: u32,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Heatmap, ApiError> {
+    let samples = query_log
+        .time(
+            "uptime_heatmap",
+            Some(alias),
+            store.uptime_heatmap(alias, weeks as i32),
+        )
+        .await?;
+
+    Ok(Heatmap::from_samples(weeks, samples))
+}
+
+async fn get_latency(
+    alias: &str,
+    range: StatsRange,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<LatencyPercentiles>, ApiError> {
+    let first_bucket = range.first_bucket(Utc::now());
+    let buckets = query_log
+        .time(
+            "latency_percentiles",
+            Some(alias),
+            store.latency(alias, range, first_bucket),
+        )
+        .await?;
+
+    Ok(latency::fill_gaps(buckets, range, first_bucket))
+}
+
+async fn get_status_codes(
+    alias: &str,
+    from: DateTime<Utc>,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<StatusCount>, ApiError> {
+    Ok(query_log
+        .time(
+            "status_codes",
+            Some(alias),
+            store.status_code_counts(alias, from),
+        )
+        .await?)
+}
+
+async fn get_trends(
+    alias: &str,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Trends, ApiError> {
+    let now = Utc::now();
+    let (last_month, _, next_month) = trends::month_windows(now);
+    let samples = query_log
+        .time(
+            "month_trends",
+            Some(alias),
+            store.month_trends(alias, last_month, next_month),
+        )
+        .await?;
+
+    Ok(Trends::from_samples(samples, now))
+}
+
+/// Failed checks of the website, newest first. `alias` only labels the query
+async fn get_incidents(
+    website_id: i32,
+    alias: &str,
+    filter: IncidentFilter,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<Incident>, ApiError> {
+    let incidents = query_log
+        .time(
+            "incidents",
+            Some(alias),
+            store.incidents(website_id, filter),
+        )
+        .await?;
+
+    Ok(incidents)
+}
+
+/// Resolves the alias once per request, the website's queries go by id from there
+async fn website_id_by_alias(store: &dyn Store, alias: &str) -> Result<i32, ApiError> {
+    store.website_id(alias).await?.ok_or(ApiError::NotFound)
+}
+
+/// The alias the website with this id goes by now, for the routes taking ids
+async fn alias_by_id(store: &dyn Store, id: i32) -> Result<String, ApiError> {
+    store.website_alias(id).await?.ok_or(ApiError::NotFound)
+}
+
+/// Adds empty buckets for the ones without checks, so there are `range.splits()` from
+/// `first_bucket` on, oldest first
+fn fill_data_gaps(
+    mut data: Vec<WebsiteStats>,
+    range: StatsRange,
+    first_bucket: DateTime<Utc>,
+) -> Vec<WebsiteStats> {
+    let bucket = range.split_by().duration();
+    for i in 0..range.splits() {
+        let time = first_bucket + bucket * i;
+        // if timestamp doesn't exist, push a timestamp with None
+        if !data.iter().any(|x| x.time == time) {
+            data.push(WebsiteStats {
+                time,
+                uptime_pct: None,
+                degraded_pct: None,
+                checks: 0,
+            });
+        }
+    }
+    data.sort_by_key(|stats| stats.time);
+
+    data
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[allow(clippy::too_many_arguments)]
+async fn get_website_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    State(query_log): State<QueryLog>,
+    State(retention): State<Retention>,
+    headers: HeaderMap,
+    Path(alias): Path<String>,
+    Query(params): Query<StatsParams>,
+    Query(incident_params): Query<IncidentParams>,
+) -> Result<Negotiated<SingleWebsiteLog>, ApiError> {
+    let json = accepts_json(&headers);
+    let range = params
+        .range()
+        .map_err(|e| ApiError::BadRequest(e).negotiated(json))?;
+    let incident_filter = incident_params
+        .filter()
+        .map_err(|e| ApiError::BadRequest(e).negotiated(json))?;
+    let single_website_log = match time::timeout(
+        snapshots.budget(),
+        load_single_website_log(
+            store.as_ref(),
+            &query_log,
+            retention,
+            &alias,
+            range,
+            incident_filter,
+        ),
+    )
+    .await
+    {
+        Ok(Ok(single_website_log)) => {
+            // Snapshots stand in for the page as usually asked for, they keep their label
+            if range == DEFAULT_RANGE && incident_filter.is_empty() {
+                snapshots.store_website(&alias, &single_website_log);
+            }
+            single_website_log
+        }
+        Ok(Err(e)) => return Err(e.negotiated(json)),
+        Err(_) => {
+            info!("Database exceeded the time budget, serving the last snapshot for {alias}");
+            snapshots
+                .stale_website(&alias)
+                .ok_or(ApiError::Timeout.negotiated(json))?
+        }
+    };
+
+    Ok(Negotiated::new(json, single_website_log))
+}
+
+/// The uptime chart and incidents of the detail page, which it polls for with htmx
+#[axum::debug_handler(state = ServerState)]
+async fn get_website_fragment(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<StatsParams>,
+    Query(incident_params): Query<IncidentParams>,
+) -> Result<Response, ApiError> {
+    let range = params.range().map_err(ApiError::BadRequest)?;
+    let incident_filter = incident_params.filter().map_err(ApiError::BadRequest)?;
+    let website = load_website(store.as_ref(), &query_log, &alias).await?;
+    let stats = get_stats(
+        website.id,
+        &website.alias,
+        range,
+        store.as_ref(),
+        &query_log,
+    )
+    .await?;
+    let latency = get_latency(&website.alias, range, store.as_ref(), &query_log).await?;
+    let incidents = get_incidents(
+        website.id,
+        &alias,
+        incident_filter,
+        store.as_ref(),
+        &query_log,
+    )
+    .await?;
+    let kept_params = kept_params(range);
+
+    Ok(WebsiteFragment {
+        log: load_website_info(store.as_ref(), &query_log, &website, stats).await?,
+        incidents,
+        incident_filters: incident_filter.active(&format!("/websites/{alias}"), &kept_params),
+        stats_label: range.label(),
+        latency,
+        live_href: incident_filter.href(&format!("/websites/{alias}/fragments/live"), &kept_params),
+    }
+    .into_response())
+}
+
+/// `GET /websites/{alias}` as JSON, by the id that stays the same through renames
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/by-id/{id}",
+    tag = "websites",
+    params(("id" = i32, Path, description = "Id of the website"), StatsParams, IncidentParams),
+    responses(
+        (status = 200, description = "The website with its stats, incidents and settings"),
+        (status = 400, description = "Unsupported range or invalid incident filter", body = ErrorBody),
+        (status = 404, description = "No website has this id", body = ErrorBody),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+async fn get_website_by_id(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    State(query_log): State<QueryLog>,
+    State(retention): State<Retention>,
+    Path(id): Path<i32>,
+    Query(params): Query<StatsParams>,
+    Query(incident_params): Query<IncidentParams>,
+) -> Result<Negotiated<SingleWebsiteLog>, ApiError> {
+    let alias = alias_by_id(store.as_ref(), id)
+        .await
+        .map_err(ApiError::json)?;
+    // Always JSON, the page is under the alias
+    let headers = HeaderMap::from_iter([(ACCEPT, HeaderValue::from_static("application/json"))]);
+    get_website_by_alias(
+        State(store),
+        State(snapshots),
+        State(query_log),
+        State(retention),
+        headers,
+        Path(alias),
+        Query(params),
+        Query(incident_params),
+    )
+    .await
+}
+
+async fn load_single_website_log(
+    store: &dyn Store,
+    query_log: &QueryLog,
+    retention: Retention,
+    alias: &str,
+    range: StatsRange,
+    incident_filter: IncidentFilter,
+) -> Result<SingleWebsiteLog, ApiError> {
+    info!("retrieving website entry for alias");
+    let website = load_website(store, query_log, alias).await?;
+
+    info!("Getting stats for the last {range}");
+    let stats = get_stats(website.id, &website.alias, range, store, query_log).await?;
+
+    info!("Getting latency percentiles for the last {range}");
+    let latency = get_latency(&website.alias, range, store, query_log).await?;
+
+    info!("Getting status codes");
+    let status_codes = get_status_codes(
+        &website.alias,
+        range.first_bucket(Utc::now()),
+        store,
+        query_log,
+    )
+    .await?;
+
+    info!("Getting uptime heatmap");
+    let heatmap = get_heatmap(&website.alias, heatmap::DEFAULT_WEEKS, store, query_log).await?;
+
+    info!("Getting month over month trends");
+    let trends = get_trends(&website.alias, store, query_log).await?;
+
+    info!("Getting incidents");
+    let incidents = get_incidents(website.id, alias, incident_filter, store, query_log).await?;
+    let kept_params = kept_params(range);
+    let incident_filters = incident_filter.active(&format!("/websites/{alias}"), &kept_params);
+    let live_href =
+        incident_filter.href(&format!("/websites/{alias}/fragments/live"), &kept_params);
+
+    let connection_reuse = query_log
+        .time(
+            "connection_reuse",
+            Some(alias),
+            store.connection_reuse(alias),
+        )
+        .await?;
+
+    let last_checked_at = query_log
+        .time("last_check", Some(alias), store.last_check(alias))
+        .await?;
+    let next_check_at = match website.config.cron_schedule.as_deref() {
+        Some(cron_schedule) => Some(schedule::next_run(Some(cron_schedule), Utc::now())),
+        None => last_checked_at.map(|last_checked_at| schedule::next_run(None, last_checked_at)),
+    };
+
+    let events = query_log
+        .time("events", Some(alias), store.events(alias))
+        .await?;
+
+    let notification_log = query_log
+        .time(
+            "notification_log",
+            Some(alias),
+            store.notification_log(alias),
+        )
+        .await?;
+
+    let channels = query_log
+        .time("channels", Some(alias), store.website_channels(alias))
+        .await?;
+
+    let all_channels = query_log
+        .time("all_channels", None, store.notification_channels())
+        .await?;
+
+    let last_failure = query_log
+        .time("last_failure", Some(alias), store.last_failure(alias))
+        .await?;
+
+    let settings = WebsiteSettings::from(&website);
+    let log = load_website_info(store, query_log, &website, stats).await?;
+
+    Ok(SingleWebsiteLog {
+        log,
+        incidents,
+        incident_filters,
+        connection_reuse_pct: connection_reuse.percentage(),
+        cron_schedule: website.config.cron_schedule,
+        next_check_at,
+        events,
+        notification_log,
+        channels,
+        all_channels,
+        last_failure,
+        stats_label: range.label(),
+        latency,
+        heatmap,
+        status_codes: StatusCodes::new(status_codes),
+        trends,
+        client_cert_fingerprint: website.client_cert_fingerprint,
+        client_cert_expires_at: website.client_cert_expires_at,
+        settings,
+        retention_days: retention.effective(website.retention_days),
+        stale_as_of: None,
+        live_href,
+        rejected: None,
+    })
+}
+
+async fn load_website(
+    store: &dyn Store,
+    query_log: &QueryLog,
+    alias: &str,
+) -> Result<Website, ApiError> {
+    let website = query_log
+        .time("website_by_alias", Some(alias), store.website(alias))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(website)
+}
+
+/// `website` with its uptime bars `data`, manual status override and open incident
+async fn load_website_info(
+    store: &dyn Store,
+    query_log: &QueryLog,
+    website: &Website,
+    data: Vec<WebsiteStats>,
+) -> Result<WebsiteInfo, ApiError> {
+    let status_override = get_active_status_overrides(store, query_log)
+        .await?
+        .into_iter()
+        .find(|status_override| status_override.alias == website.alias);
+    let open_incident = load_open_incidents(store, query_log)
+        .await?
+        .into_iter()
+        .find(|incident| incident.alias == website.alias);
+
+    Ok(WebsiteInfo {
+        id: website.id,
+        url: website.url.clone(),
+        alias: website.alias.clone(),
+        data,
+        status_override,
+        inverted: website.invert,
+        open_incident_id: open_incident.as_ref().map(|incident| incident.id),
+        unacked_for: open_incident.and_then(|incident| incident.aging(Utc::now())),
+    })
+}
+
+/// The parameters of the detail page that aren't about incidents, for links that keep them
+fn kept_params(range: StatsRange) -> Vec<(&'static str, String)> {
+    if range == DEFAULT_RANGE {
+        Vec::new()
+    } else {
+        vec![("range", range.to_string())]
+    }
+}
+
+/// `from` and `to` in RFC 3339, the last 7 days until now when left out
+#[derive(Deserialize)]
+struct LogsCsvParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+#[axum::debug_handler(state = ServerState)]
+async fn get_logs_csv(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+    Query(params): Query<LogsCsvParams>,
+) -> Result<Response, ApiError> {
+    let to = params.to.unwrap_or_else(Utc::now);
+    let from = params.from.unwrap_or(to - TimeDelta::days(7));
+    if from >= to {
+        return Err(ApiError::BadRequest("from has to be before to".to_owned()));
+    }
+    if store.website(&alias).await?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    // Aliases from before they were validated can hold anything, which a header can't
+    let file_alias = alias.replace(
+        |c: char| !c.is_ascii_alphanumeric() && !"-_.~".contains(c),
+        "_",
+    );
+    let file_name = format!(
+        "{file_alias}-{}-{}.csv",
+        from.format("%Y%m%dT%H%MZ"),
+        to.format("%Y%m%dT%H%MZ")
+    );
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/csv; charset=utf-8")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{file_name}\""),
+        )
+        .body(csv_export::logs_csv(store, alias, from, to))
+        .unwrap())
+}
+
+#[axum::debug_handler(state = ServerState)]
+async fn get_monthly_fragment(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    info!("Getting monthly data");
+    let website_id = website_id_by_alias(store.as_ref(), &alias).await?;
+    let monthly_data = get_stats(
+        website_id,
+        &alias,
+        StatsRange::MONTH,
+        store.as_ref(),
+        &query_log,
+    )
+    .await?;
+    let since = Utc::now() - TimeDelta::days(30);
+    let acknowledged = query_log
+        .time(
+            "acknowledged_incidents",
+            Some(&alias),
+            store.acknowledged_incidents(&alias, since),
+        )
+        .await?;
+    let tta = TtaStats::new(&acknowledged);
+
+    if headers.contains_key("HX-Request") {
+        Ok(MonthlyFragment {
+            alias,
+            monthly_data,
+            tta,
+        }
+        .into_response())
+    } else {
+        Ok(MonthlyPage {
+            alias,
+            monthly_data,
+            tta,
+        }
+        .into_response())
+    }
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/heatmap",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), HeatmapParams),
+    responses(
+        (status = 200, description = "Uptime per weekday and hour", body = Heatmap),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_heatmap_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<HeatmapParams>,
+) -> Result<Json<Heatmap>, ApiError> {
+    let website = store.website(&alias).await?;
+    if website.is_none() {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(Json(
+        get_heatmap(&alias, params.weeks(), store.as_ref(), &query_log).await?,
+    ))
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/latency",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), StatsParams),
+    responses(
+        (status = 200, description = "Response time percentiles bucketed over the range", body = LatencyStats),
+        (status = 400, description = "Unsupported range or longer than a year", body = ErrorBody),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_latency_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<LatencyStats>, ApiError> {
+    let range = params
+        .range()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let website = store.website(&alias).await.map_err(ApiError::json)?;
+    if website.is_none() {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(Json(LatencyStats {
+        range: range.to_string(),
+        buckets: get_latency(&alias, range, store.as_ref(), &query_log)
+            .await
+            .map_err(ApiError::json)?,
+    }))
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/status-codes",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), StatsParams),
+    responses(
+        (status = 200, description = "Checks per status over the range, most frequent first", body = [StatusCount]),
+        (status = 400, description = "Unsupported range or longer than a year", body = ErrorBody),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_status_codes_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<Vec<StatusCount>>, ApiError> {
+    let range = params
+        .range()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let website = store.website(&alias).await.map_err(ApiError::json)?;
+    if website.is_none() {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    let from = range.first_bucket(Utc::now());
+    Ok(Json(
+        get_status_codes(&alias, from, store.as_ref(), &query_log)
+            .await
+            .map_err(ApiError::json)?,
+    ))
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/incidents",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), IncidentParams),
+    responses(
+        (status = 200, description = "Failed checks, newest first", body = [Incident]),
+        (status = 400, description = "Invalid timestamp, status or range", body = ErrorBody),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_incidents_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<IncidentParams>,
+) -> Result<Json<Vec<Incident>>, ApiError> {
+    let filter = params
+        .filter()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let website_id = website_id_by_alias(store.as_ref(), &alias)
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok(Json(
+        get_incidents(website_id, &alias, filter, store.as_ref(), &query_log)
+            .await
+            .map_err(ApiError::json)?,
+    ))
+}
+
+/// Uptime, downtime and incidents of one website over a window or calendar month
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/sla",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), SlaParams),
+    responses(
+        (status = 200, description = "The website's numbers over the period, nulls without a single check", body = SlaReport),
+        (status = 400, description = "Unsupported window or month", body = ErrorBody),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_sla_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<SlaParams>,
+) -> Result<Json<SlaReport>, ApiError> {
+    let period = params
+        .period(Utc::now())
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let counts = get_sla_counts(Some(&alias), &period, store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?;
+    if counts.is_empty() {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(Json(SlaReport::new(period, &counts, false)))
+}
+
+/// Uptime, downtime and incidents of all websites together and of each of them
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/sla",
+    tag = "websites",
+    params(SlaParams),
+    responses(
+        (status = 200, description = "All websites' numbers over the period and each website's, nulls without a single check", body = SlaReport),
+        (status = 400, description = "Unsupported window or month", body = ErrorBody),
+    )
+)]
+async fn get_sla(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Query(params): Query<SlaParams>,
+) -> Result<Json<SlaReport>, ApiError> {
+    let period = params
+        .period(Utc::now())
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let counts = get_sla_counts(None, &period, store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok(Json(SlaReport::new(period, &counts, true)))
+}
+
+/// Every website's counts, or only those of `alias`
+async fn get_sla_counts(
+    alias: Option<&str>,
+    period: &SlaPeriod,
+    store: &dyn Store,
+    query_log: &QueryLog,
+) -> Result<Vec<SlaCounts>, ApiError> {
+    let counts = query_log
+        .time(
+            "sla_counts",
+            alias,
+            store.sla_counts(alias, period.from, period.until),
+        )
+        .await?;
+
+    Ok(counts)
+}
+
+/// Whether a website is up right now, for dashboards polling many of them
+#[derive(Serialize, ToSchema)]
+struct CurrentStatus {
+    alias: String,
+    /// up, down, or unknown until the website's first check
+    #[schema(example = "up")]
+    status: &'static str,
+    last_status_code: Option<i16>,
+    last_checked: Option<DateTime<Utc>>,
+    /// When the website last went up or down, its first check if it never did
+    since: Option<DateTime<Utc>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/status",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "The newest check and since when the website is up or down", body = CurrentStatus),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_current_status_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+) -> Result<Json<CurrentStatus>, ApiError> {
+    let summary = store
+        .website_summary(&alias)
+        .await
+        .map_err(ApiError::json)?
+        .ok_or(ApiError::json(ApiError::NotFound))?;
+
+    let Some(last_status_code) = summary.current_status else {
+        return Ok(Json(CurrentStatus {
+            alias: summary.alias,
+            status: "unknown",
+            last_status_code: None,
+            last_checked: None,
+            since: None,
+        }));
+    };
+    let up = last_status_code == 200;
+    let since = store
+        .status_since(&alias, up)
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok(Json(CurrentStatus {
+        alias: summary.alias,
+        status: if up { "up" } else { "down" },
+        last_status_code: Some(last_status_code),
+        last_checked: summary.last_checked,
+        since,
+    }))
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/stats",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), StatsParams),
+    responses(
+        (status = 200, description = "Uptime bucketed over the range", body = UptimeStats),
+        (status = 400, description = "Unsupported range or longer than a year", body = ErrorBody),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_stats_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<UptimeStats>, ApiError> {
+    let range = params
+        .range()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+    let website = store
+        .website(&alias)
+        .await
+        .map_err(ApiError::json)?
+        .ok_or(ApiError::json(ApiError::NotFound))?;
+
+    let until = Utc::now();
+    let from = range.first_bucket(until);
+    let buckets = get_stats(website.id, &alias, range, store.as_ref(), &query_log)
+        .await
+        .map_err(ApiError::json)?;
+    let (uptime_pct, _) = store
+        .window_stats(&alias, from, until)
+        .await
+        .map_err(ApiError::json)?;
+
+    Ok(Json(UptimeStats {
+        range: range.to_string(),
+        summary: StatsSummary {
+            from,
+            until,
+            uptime_pct,
+            checks: buckets.iter().map(|bucket| bucket.checks).sum(),
+        },
+        buckets,
+    }))
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/trends",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "This month against the last one", body = Trends),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn get_trends_by_alias(
+    State(store): State<Arc<dyn Store>>,
+    State(query_log): State<QueryLog>,
+    Path(alias): Path<String>,
+) -> Result<Json<Trends>, ApiError> {
+    let website = store.website(&alias).await?;
+    if website.is_none() {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(Json(get_trends(&alias, store.as_ref(), &query_log).await?))
+}
+
+async fn set_incident_impact(
+    State(store): State<Arc<dyn Store>>,
+    Path((alias, id)): Path<(String, i32)>,
+    Form(impact_override): Form<ImpactOverride>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let impact = impact_override
+        .impact
+        .parse::<Impact>()
+        .map_err(ApiError::BadRequest)?;
+
+    let result = store
+        .set_incident_impact(&alias, id, impact.as_str())
+        .await?;
+
+    if result == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Redirect::to(&format!("/websites/{alias}")))
+}
+
+async fn load_incident_page(store: &dyn Store, id: i32) -> Result<IncidentPage, ApiError> {
+    let detail = store.incident(id).await?.ok_or(ApiError::NotFound)?;
+    let resolved_at = store.incident_resolution(id).await?;
+    let updates = store.incident_updates(id).await?;
+
+    Ok(IncidentPage {
+        detail,
+        resolved_at,
+        updates,
+    })
+}
+
+async fn get_incident(
+    State(store): State<Arc<dyn Store>>,
+    Path(id): Path<i32>,
+) -> Result<impl AskamaIntoResponse, ApiError> {
+    load_incident_page(store.as_ref(), id).await
+}
+
+#[axum::debug_handler(state = ServerState)]
+async fn post_incident_update(
+    State(store): State<Arc<dyn Store>>,
+    State(admin_token): State<AdminToken>,
+    Path(id): Path<i32>,
+    Form(form): Form<IncidentUpdateForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    admin_token.authorize(&form.token)?;
+    let message = form.message.trim();
+    if message.is_empty() {
+        return Err(ApiError::BadRequest(
+            "The update needs a message".to_owned(),
+        ));
+    }
+    // Makes sure updates only go to incidents that exist
+    load_incident_page(store.as_ref(), id).await?;
+
+    store.insert_incident_update(id, message).await?;
+
+    Ok(Redirect::to(&format!("/incidents/{id}")))
+}
+
+/// Records who took the incident on, only the first acknowledgment counts
+#[axum::debug_handler(state = ServerState)]
+async fn acknowledge_incident(
+    State(store): State<Arc<dyn Store>>,
+    State(admin_token): State<AdminToken>,
+    Path(id): Path<i32>,
+    Form(form): Form<AcknowledgeForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    admin_token.authorize(&form.token)?;
+    let by = form.by.trim();
+    if by.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Say who acknowledges the incident".to_owned(),
+        ));
+    }
+    load_incident_page(store.as_ref(), id).await?;
+
+    store.acknowledge_incident(id, by, Utc::now()).await?;
+
+    Ok(Redirect::to(&format!("/incidents/{id}")))
+}
+
+/// Operator updates plus the automatic start and resolution of the incident
+async fn get_incident_feed(
+    State(store): State<Arc<dyn Store>>,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    let page = load_incident_page(store.as_ref(), id).await?;
+    let feed_id = format!("urn:uptime-ferris:incident:{id}");
+
+    let mut entries: Vec<FeedEntry> = page
+        .updates
+        .into_iter()
+        .map(|update| FeedEntry {
+            id: format!("{feed_id}:update:{}", update.id),
+            title: "Update".to_owned(),
+            content: update.message,
+            time: update.time,
+        })
+        .collect();
+    if let Some(resolved_at) = page.resolved_at {
+        entries.push(FeedEntry {
+            id: format!("{feed_id}:resolved"),
+            title: "Resolved".to_owned(),
+            content: format!("{} is answering again", page.detail.alias),
+            time: resolved_at,
+        });
+    }
+    entries.push(FeedEntry {
+        id: format!("{feed_id}:started"),
+        title: "Incident started".to_owned(),
+        content: format!(
+            "{} answered with status {}",
+            page.detail.alias, page.detail.incident.status
+        ),
+        time: page.detail.incident.time,
+    });
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+
+    Ok(AtomFeed {
+        id: feed_id,
+        title: format!("Incident on {}", page.detail.alias),
+        link: format!("/incidents/{id}"),
+        entries,
+    }
+    .into_atom_response())
+}
+
+async fn get_notifications(
+    State(store): State<Arc<dyn Store>>,
+) -> Result<Json<Vec<NotificationSummary>>, ApiError> {
+    let notifications = store.notifications().await?;
+    Ok(Json(notifications.into_iter().map(Into::into).collect()))
+}
+
+async fn create_notification(
+    State(store): State<Arc<dyn Store>>,
+    Json(notification): Json<NewNotification>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    if let Err(e) = notification.validate() {
+        return Err(ApiError::BadRequest(format!("Invalid webhook url: {e}")));
+    }
+
+    store.insert_notification(&notification.url).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+async fn delete_notification(
+    State(store): State<Arc<dyn Store>>,
+    Path(id): Path<i32>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let result = store.delete_notification(id).await?;
+
+    if result == 0 {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Delivers a made up transition once, without retries, so setups can be verified
+async fn test_notification(
+    State(store): State<Arc<dyn Store>>,
+    Path(id): Path<i32>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let url = store
+        .notification_url(id)
+        .await?
+        .ok_or(ApiError::json(ApiError::NotFound))?;
+
+    WebhookNotifier::new(reqwest::Client::new(), url, test_transition())
+        .notify()
+        .await
+        .map_err(ApiError::Delivery)?;
+
+    Ok("Test notification delivered")
+}
+
+/// Sends a sample embed to the `--discord-webhook-url` once, without retries
+async fn test_discord(
+    State(notification_settings): State<NotificationSettings>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let webhook_url = notification_settings
+        .discord_webhook_url
+        .ok_or_else(|| ApiError::BadRequest("No --discord-webhook-url is configured".to_owned()))?;
+
+    DiscordNotifier::new(
+        notification_settings.client,
+        webhook_url,
+        notification_settings.public_url,
+        vec![test_transition()],
+    )
+    .notify()
+    .await
+    .map_err(ApiError::Delivery)?;
+
+    Ok("Test notification delivered")
+}
+
+/// Made up outage of a website that doesn't exist, for verifying notification setups
+fn test_transition() -> Transition {
+    Transition {
+        alias: "uptime-ferris-test".to_owned(),
+        url: "https://example.com".to_owned(),
+        old_status: 200,
+        new_status: checker::CONNECTION_FAILED_STATUS,
+        timestamp: Utc::now(),
+        since: Utc::now(),
+        duration: 0,
+        inverted: false,
+        shared_host: None,
+        affected: Vec::new(),
+        reminder: false,
+        warning: Some(Warning::Test),
+        message: None,
+    }
+}
+
+async fn set_status_override(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+    Form(form): Form<StatusOverrideForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let expires_at = form
+        .expires_in_minutes
+        .map(|minutes| Utc::now() + chrono::Duration::minutes(minutes));
+    apply_status_override(store.as_ref(), &alias, &form.status, form.note, expires_at).await?;
+
+    Ok(Redirect::to(&format!("/websites/{alias}")))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/websites/{alias}/status-override",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    request_body = StatusOverrideRequest,
+    responses(
+        (status = 204, description = "Set, or cleared with the status none"),
+        (status = 400, description = "Unknown status or an expiry in the past", body = ErrorBody),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn put_status_override(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+    Json(request): Json<StatusOverrideRequest>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    apply_status_override(
+        store.as_ref(),
+        &alias,
+        &request.status,
+        request.note,
+        request.expires_at,
+    )
+    .await
+    .map_err(ApiError::json)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Sets or, for "none", clears the manual status of a website
+async fn apply_status_override(
+    store: &dyn Store,
+    alias: &str,
+    status: &str,
+    note: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<(), ApiError> {
+    let manual_status = manual_status::parse_manual_status(status).map_err(ApiError::BadRequest)?;
+    if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(ApiError::BadRequest(
+            "The status override has to expire in the future".to_owned(),
+        ));
+    }
+    let (note, expires_at) = match manual_status {
+        Some(_) => (note, expires_at),
+        None => (None, None),
+    };
+    let status = manual_status.map(|manual_status| manual_status.as_str());
+
+    let result = store
+        .update_status_override(alias, status, note, expires_at)
+        .await?;
+
+    if result == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Hides the website for 30 days, in which `POST /websites/{alias}/restore` brings it
+/// back, and purges it with its logs after that. Its alias stays taken until then,
+/// `POST /websites/{alias}/purge` frees it right away
+#[utoipa::path(
+    delete,
+    path = "/websites/{alias}",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias or it's deleted already, answered with the HTML not found page"),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn delete_website(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    delete_website_by_alias(store.as_ref(), &snapshots, &alias).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// `DELETE /websites/{alias}` by the id that stays the same through renames
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    delete,
+    path = "/api/websites/by-id/{id}",
+    tag = "websites",
+    params(("id" = i32, Path, description = "Id of the website")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this id or it's deleted already", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn delete_website_by_id(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(id): Path<i32>,
+) -> Result<Response, ApiError> {
+    let alias = alias_by_id(store.as_ref(), id)
+        .await
+        .map_err(ApiError::json)?;
+    delete_website(State(store), State(snapshots), Path(alias))
+        .await
+        .map(AxumIntoResponse::into_response)
+        .map_err(ApiError::json)
+}
+
+/// The same as `DELETE /websites/{alias}` for HTML forms, which can only submit GET and
+/// POST
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/websites/{alias}/delete",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "Deleted, for callers accepting application/json"),
+        (status = 303, description = "Deleted, browsers are sent back to the dashboard"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias or it's deleted already", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn delete_website_form(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let json = accepts_json(&headers);
+    delete_website_by_alias(store.as_ref(), &snapshots, &alias)
+        .await
+        .map_err(|e| e.negotiated(json))?;
+    info!("Deleted {alias}");
+
+    if json {
+        Ok(StatusCode::OK.into_response())
+    } else {
+        Ok(Redirect::to("/").into_response())
+    }
+}
+
+async fn delete_website_by_alias(
+    store: &dyn Store,
+    snapshots: &SnapshotCache,
+    alias: &str,
+) -> Result<(), ApiError> {
+    store.soft_delete_website(alias, Utc::now()).await?;
+    snapshots.forget_website(alias);
+
+    Ok(())
+}
+
+/// Removes the website for good without waiting out the 30 days, deleted or not, which
+/// frees its alias. Its checks, stats and incidents go along with it
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/websites/{alias}/purge",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "Purged, for callers accepting application/json"),
+        (status = 303, description = "Purged, browsers are sent back to the dashboard"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn purge_website(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let json = accepts_json(&headers);
+    store
+        .purge_website(&alias)
+        .await
+        .map_err(|e| e.negotiated(json))?;
+    snapshots.forget_website(&alias);
+    info!("Purged {alias}");
+
+    if json {
+        Ok(StatusCode::OK.into_response())
+    } else {
+        Ok(Redirect::to("/").into_response())
+    }
+}
+
+/// Brings back a website deleted in the last 30 days, with its history and settings
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    post,
+    path = "/websites/{alias}/restore",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "Restored, for callers accepting application/json"),
+        (status = 303, description = "Restored, browsers are sent to its page"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website with this alias was deleted in the last 30 days", body = ErrorBody),
+    )
+)]
+async fn restore_deleted_website(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let json = accepts_json(&headers);
+    store
+        .restore_website(&alias, Utc::now() - retention::RESTORE_WINDOW)
+        .await
+        .map_err(|e| e.negotiated(json))?;
+    snapshots.forget_website(&alias);
+    info!("Restored {alias}");
+
+    if json {
+        Ok(StatusCode::OK.into_response())
+    } else {
+        Ok(Redirect::to(&format!("/websites/{alias}")).into_response())
+    }
+}
+
+/// `before` in RFC 3339, the whole history when left out
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ClearHistoryParams {
+    /// Only checks older than this are deleted
+    before: Option<DateTime<Utc>>,
+}
+
+/// How many rows clearing a website's history deleted
+#[derive(Default, Serialize, ToSchema)]
+struct ClearedHistory {
+    logs: u64,
+    incident_updates: u64,
+    incident_escalations: u64,
+}
+
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    delete,
+    path = "/websites/{alias}/logs",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), ClearHistoryParams),
+    responses(
+        (status = 200, description = "Checks and incidents deleted, the website stays", body = ClearedHistory),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+        (status = 429, description = "Too many writes from this client, see `Retry-After`"),
+    )
+)]
+async fn clear_website_history(
+    State(store): State<Arc<dyn Store>>,
+    State(snapshots): State<SnapshotCache>,
+    Path(alias): Path<String>,
+    Query(params): Query<ClearHistoryParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let cleared = store
+        .clear_history(&alias, params.before)
+        .await
+        .map_err(ApiError::json)?;
+    snapshots.forget_website(&alias);
+    info!(
+        "Cleared the history of {alias}, {} checks deleted",
+        cleared.logs
+    );
+
+    let mut response = Json(cleared).into_response();
+    // The detail page's form reloads to show the emptied history
+    if headers.contains_key("HX-Request") {
+        response
+            .headers_mut()
+            .insert("HX-Refresh", HeaderValue::from_static("true"));
+    }
+    Ok(response)
+}
+
+/// Turns coalescing of simultaneous incidents on or off for all monitors of a host
+async fn set_shared_fate(
+    State(store): State<Arc<dyn Store>>,
+    Form(form): Form<SharedFateForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    store.set_shared_fate(&form.host, form.enabled).await?;
+    info!(
+        "Shared fate for {} {}",
+        form.host,
+        if form.enabled { "enabled" } else { "disabled" }
+    );
+
+    Ok(Redirect::to("/"))
+}
+
+async fn insert_channel(
+    store: &dyn Store,
+    name: &str,
+    config: &ChannelConfig,
+    template: Option<&MessageTemplate>,
+) -> Result<(), ApiError> {
+    if name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Channels need a name".to_owned()));
+    }
+
+    store
+        .insert_notification_channel(name.trim(), config.kind(), &config.config_json(template))
+        .await?;
+    info!("Added {} notification channel {name}", config.kind());
+
+    Ok(())
+}
+
+async fn get_notification_channels(
+    State(store): State<Arc<dyn Store>>,
+) -> Result<Json<Vec<ChannelSummary>>, ApiError> {
+    Ok(Json(store.notification_channels().await?))
+}
+
+async fn create_notification_channel(
+    State(store): State<Arc<dyn Store>>,
+    Json(channel): Json<NewChannel>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let template = MessageTemplate::from_config(&channel.config).map_err(ApiError::BadRequest)?;
+    let config =
+        ChannelConfig::from_value(&channel.kind, channel.config).map_err(ApiError::BadRequest)?;
+    insert_channel(store.as_ref(), &channel.name, &config, template.as_ref()).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Renders the template for a made up outage, to check its formatting before a real one
+async fn preview_message_template(
+    Json(request): Json<TemplatePreviewRequest>,
+) -> Result<Json<TemplatePreview>, ApiError> {
+    let mut transition = test_transition();
+    transition.duration = 754;
+    transition.warning = None;
+    if let Some(template) = request.template {
+        let template = MessageTemplate::parse(&template).map_err(ApiError::BadRequest)?;
+        transition.message = Some(template.render(&transition));
+    }
+
+    Ok(Json(TemplatePreview {
+        message: transition.headline(),
+    }))
+}
+
+/// Sends a made up outage marked as a test through the channel once, without retries,
+/// and answers with how the delivery went
+async fn test_notification_channel(
+    State(store): State<Arc<dyn Store>>,
+    State(notification_settings): State<NotificationSettings>,
+    Path(id): Path<i32>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let (kind, config) = store
+        .notification_channel(id)
+        .await?
+        .ok_or(ApiError::json(ApiError::NotFound))?;
+    let (config, template) = ChannelConfig::parse(&kind, &config).map_err(ApiError::Delivery)?;
+
+    notification_settings
+        .test(&config, template.as_ref(), test_transition())
+        .await
+        .map_err(ApiError::Delivery)?;
+    info!("Test notification delivered to channel {id}");
+
+    Ok("Test notification delivered")
+}
+
+/// Websites the channel was assigned to fall back to the global defaults unless they
+/// have further channels
+async fn delete_notification_channel(
+    State(store): State<Arc<dyn Store>>,
+    Path(id): Path<i32>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let deleted = store.delete_notification_channel(id).await?;
+
+    if deleted == 0 {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/notification-channels",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "Channels the website notifies, empty for unknown aliases", body = [ChannelSummary]),
+    )
+)]
+async fn get_website_channels(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+) -> Result<Json<Vec<ChannelSummary>>, ApiError> {
+    Ok(Json(store.website_channels(&alias).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/websites/{alias}/notification-channels/{id}",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), ("id" = i32, Path, description = "Id of the notification channel")),
+    responses(
+        (status = 200, description = "The website notifies the channel"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+    )
+)]
+async fn put_website_channel(
+    State(store): State<Arc<dyn Store>>,
+    Path((alias, id)): Path<(String, i32)>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    store.assign_channel(&alias, id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/websites/{alias}/notification-channels/{id}",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), ("id" = i32, Path, description = "Id of the notification channel")),
+    responses(
+        (status = 200, description = "The website no longer notifies the channel"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "The website doesn't notify this channel", body = ErrorBody),
+    )
+)]
+async fn delete_website_channel(
+    State(store): State<Arc<dyn Store>>,
+    Path((alias, id)): Path<(String, i32)>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let result = store.unassign_channel(&alias, id).await?;
+
+    if result == 0 {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/websites/{alias}/escalation-rules",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    responses(
+        (status = 200, description = "The website's escalation rules", body = [EscalationRule]),
+    )
+)]
+async fn get_escalation_rules(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+) -> Result<Json<Vec<EscalationRule>>, ApiError> {
+    Ok(Json(store.website_escalation_rules(&alias).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/websites/{alias}/escalation-rules",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website")),
+    request_body = NewEscalationRule,
+    responses(
+        (status = 201, description = "Created"),
+        (status = 400, description = "The rule failed validation", body = String, content_type = "text/plain"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "No website has this alias", body = ErrorBody),
+    )
+)]
+async fn create_escalation_rule(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+    Json(rule): Json<NewEscalationRule>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    if let Err(e) = rule.validate() {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid escalation rule: {e}"
+        )));
+    }
+
+    let inserted = store.insert_escalation_rule(&alias, &rule).await?;
+
+    if inserted == 0 {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/websites/{alias}/escalation-rules/{id}",
+    tag = "websites",
+    params(("alias" = String, Path, description = "Alias of the website"), ("id" = i32, Path, description = "Id of the escalation rule")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 401, description = "An API key is configured and the request doesn't carry it"),
+        (status = 404, description = "The website has no such rule", body = ErrorBody),
+    )
+)]
+async fn delete_escalation_rule(
+    State(store): State<Arc<dyn Store>>,
+    Path((alias, id)): Path<(String, i32)>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let deleted = store.delete_escalation_rule(&alias, id).await?;
+
+    if deleted == 0 {
+        return Err(ApiError::json(ApiError::NotFound));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+async fn get_notification_channels_page(
+    State(store): State<Arc<dyn Store>>,
+) -> Result<impl AskamaIntoResponse, ApiError> {
+    Ok(NotificationChannelsPage {
+        channels: store.notification_channels().await?,
+    })
+}
+
+async fn post_notification_channel_form(
+    State(store): State<Arc<dyn Store>>,
+    Form(form): Form<ChannelForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    let template = form
+        .template
+        .as_deref()
+        .map(MessageTemplate::parse)
+        .transpose()
+        .map_err(ApiError::BadRequest)?;
+    let config = ChannelConfig::from_form(
+        &form.kind,
+        form.target,
+        form.chat_id,
+        form.topic,
+        form.token,
+        form.insecure_tls,
+    )
+    .map_err(ApiError::BadRequest)?;
+    insert_channel(store.as_ref(), &form.name, &config, template.as_ref()).await?;
+
+    Ok(Redirect::to("/notification-channels"))
+}
+
+async fn post_website_channel_form(
+    State(store): State<Arc<dyn Store>>,
+    Path(alias): Path<String>,
+    Form(form): Form<AssignChannelForm>,
+) -> Result<impl AxumIntoResponse, ApiError> {
+    store.assign_channel(&alias, form.channel_id).await?;
+
+    Ok(Redirect::to(&format!("/websites/{alias}")))
+}
+
+/// The slowest queries with their labels and aliases, for the admin token as a bearer
+/// token
+async fn get_slow_queries(
+    State(query_log): State<QueryLog>,
+    State(admin_token): State<AdminToken>,
+    headers: HeaderMap,
+) -> Result<impl AskamaIntoResponse, ApiError> {
+    admin_token.authorize_bearer(&headers)?;
+
+    Ok(SlowQueriesPage {
+        queries: query_log.slowest(),
+    })
+}
+
+async fn get_slow_queries_json(
+    State(query_log): State<QueryLog>,
+    State(admin_token): State<AdminToken>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SlowQuery>>, ApiError> {
+    admin_token
+        .authorize_bearer(&headers)
+        .map_err(ApiError::json)?;
+
+    Ok(Json(query_log.slowest()))
+}
+
+/// The backend and its applied and pending migrations, for the admin token as a bearer
+/// token
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/admin/db",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Applied migrations oldest first and the pending ones", body = MigrationStatus),
+        (status = 403, description = "No admin token is configured or the request doesn't carry it"),
+        (status = 500, description = "The migrations couldn't be read", body = ErrorBody),
+    )
+)]
+async fn get_db_status(
+    State(state): State<AppState>,
+    State(admin_token): State<AdminToken>,
+    State(migrations_dir): State<MigrationsDir>,
+    headers: HeaderMap,
+) -> Result<Json<MigrationStatus>, ApiError> {
+    admin_token
+        .authorize_bearer(&headers)
+        .map_err(ApiError::json)?;
+    let status = state
+        .migration_status(migrations_dir.0.as_deref())
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ApiError::json(ApiError::Migrations(e))
+        })?;
+
+    Ok(Json(status))
+}
+
+/// A copy of the Sqlite database taken while the checks keep running, for the admin token
+/// as a bearer token
+#[axum::debug_handler(state = ServerState)]
+#[utoipa::path(
+    get,
+    path = "/api/admin/backup",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The database file", content_type = "application/vnd.sqlite3"),
+        (status = 400, description = "The backend isn't Sqlite, back it up with pg_dump or mysqldump", body = ErrorBody),
+        (status = 403, description = "No admin token is configured or the request doesn't carry it"),
+        (status = 500, description = "The backup couldn't be written or failed its integrity check", body = ErrorBody),
+    )
+)]
+async fn get_sqlite_backup(
+    State(state): State<AppState>,
+    State(admin_token): State<AdminToken>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    admin_token
+        .authorize_bearer(&headers)
+        .map_err(ApiError::json)?;
+    let pool = state
+        .sqlite_for_backup()
+        .map_err(|e| ApiError::json(ApiError::BadRequest(e)))?;
+
+    let now = Utc::now();
+    let file_name = format!("uptime-ferris-{}.db", now.format("%Y%m%d-%H%M%S"));
+    let path = std::env::temp_dir().join(format!(
+        "uptime-ferris-backup-{}.db",
+        now.timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let opened = match sqlite::backup_sqlite(pool, &path).await {
+        Ok(()) => tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| format!("Opening the backup at {} failed: {e}", path.display())),
+        Err(e) => Err(e),
+    };
+    // An open file stays readable once its name is gone, so nothing is left behind in
+    // the temp dir however the download ends
+    if let Err(e) = tokio::fs::remove_file(&path).await
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Removing the backup at {} failed: {e}", path.display());
+    }
+    let file = opened.map_err(|e| {
+        error!("{e}");
+        ApiError::json(ApiError::Backup(e))
+    })?;
+
+    let chunks = futures_util::stream::try_unfold(file, |mut file| async move {
+        let mut chunk = vec![0; 64 * 1024];
+        let read = tokio::io::AsyncReadExt::read(&mut file, &mut chunk).await?;
+        if read == 0 {
+            return Ok::<_, std::io::Error>(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((chunk, file)))
+    });
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/vnd.sqlite3")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{file_name}\""),
+        )
+        .body(axum::body::Body::from_stream(chunks))
+        .unwrap())
+}
+
+/// Tables and columns integrators can query, generated from the same definitions the
+/// read-only role's grants are
+async fn get_schema() -> Json<&'static [schema::Table]> {
+    Json(schema::TABLES)
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        extract::ConnectInfo,
+        http::{
+            Request,
+            header::{ACCEPT_ENCODING, CONTENT_ENCODING, COOKIE, IF_NONE_MATCH, SET_COOKIE},
+        },
+    };
+    use backup::RestoreStep;
+    use checker::Clock;
+    use chrono::DurationRound;
+    use std::io::Read;
+    use tower::ServiceExt;
+
+    /// Everything a handler may ask for, on `db` and without any notification channel
+    fn server_state(db: SqlitePool) -> ServerState {
+        let db = AppState::Sqlite(db);
+        let (deliveries, _) = DeliveryLog::new();
+        ServerState {
+            store: db.store(),
+            db,
+            snapshots: SnapshotCache::new(Duration::from_secs(5)),
+            admin_token: AdminToken::new(Some("admin")),
+            api_key: ApiKey::new(None),
+            secrets: Secrets::new(None),
+            notification_settings: NotificationSettings {
+                client: reqwest::Client::new(),
+                insecure_client: reqwest::Client::new(),
+                slack_webhook_url: None,
+                discord_webhook_url: None,
+                telegram: None,
+                mailer: None,
+                shared_fate_window: TimeDelta::minutes(5),
+                cooldown: TimeDelta::zero(),
+                ack_sla: None,
+                deliveries,
+                public_url: None,
+            },
+            query_log: QueryLog::new(Duration::from_secs(1), 10),
+            exec: None,
+            heartbeat: Heartbeat::default(),
+            wakeup: Wakeup::default(),
+            retention: Retention(None),
+            migrations_dir: MigrationsDir(None),
+            indexing: Indexing(false),
+        }
+    }
+
+    /// The whole router on a fresh in-memory database, without an API key
+    async fn app(compress: bool) -> Router {
+        router(
+            server_state(sqlite::memory_pool().await),
+            RateLimiter::new(60, false),
+            None,
+            compress,
+        )
+    }
+
+    /// The whole router on a fresh in-memory database with `api_key` configured
+    async fn keyed_app(api_key: &str) -> Router {
+        router(
+            ServerState {
+                api_key: ApiKey::new(Some(api_key)),
+                ..server_state(sqlite::memory_pool().await)
+            },
+            RateLimiter::new(60, false),
+            None,
+            false,
+        )
+    }
+
+    /// Sends `request` as if it came from a local client, which the rate limiter wants
+    async fn send(app: &Router, mut request: Request<Body>) -> Response {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn writes_past_the_limit_are_told_when_to_retry() {
+        let app = router(
+            server_state(sqlite::memory_pool().await),
+            RateLimiter::new(1, false),
+            None,
+            false,
+        );
+        let delete = || {
+            Request::delete("/websites/missing")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let first = send(&app, delete()).await;
+        let second = send(&app, delete()).await;
+        let read = send(&app, Request::get("/").body(Body::empty()).unwrap()).await;
+
+        assert_eq!(first.status(), StatusCode::NOT_FOUND);
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(second.headers()[RETRY_AFTER], "60");
+        assert_eq!(read.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn dashboard_is_gzipped_on_request() {
+        let app = app(true).await;
+        let (plain, compressed) = dashboard_as(&app, "gzip").await;
+
+        let mut html = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut html)
+            .unwrap();
+        assert_eq!(html, plain);
+    }
+
+    #[tokio::test]
+    async fn dashboard_is_brotli_compressed_on_request() {
+        let app = app(true).await;
+        let (plain, compressed) = dashboard_as(&app, "br").await;
+
+        let mut html = String::new();
+        brotli_decompressor::Decompressor::new(&compressed[..], 4096)
+            .read_to_string(&mut html)
+            .unwrap();
+        assert_eq!(html, plain);
+    }
+
+    /// The dashboard as sent without `Accept-Encoding`, then as sent with `encoding` accepted
+    async fn dashboard_as(app: &Router, encoding: &str) -> (String, axum::body::Bytes) {
+        let (status, plain) = page(app, "/").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let response = send(
+            app,
+            Request::get("/")
+                .header(ACCEPT_ENCODING, encoding)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_ENCODING], encoding);
+        let compressed = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (plain, compressed)
+    }
+
+    #[tokio::test]
+    async fn nothing_is_compressed_with_compression_off() {
+        let app = app(false).await;
+        let response = send(
+            &app,
+            Request::get("/")
+                .header(ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn badges_stay_uncompressed() {
+        let app = app(true).await;
+        let response = send(
+            &app,
+            Request::get("/badge/unknown.svg")
+                .header(ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn backup_needs_the_api_key() {
+        let app = keyed_app("key").await;
+        let without_key = send(
+            &app,
+            Request::get("/api/backup").body(Body::empty()).unwrap(),
+        )
+        .await;
+        let with_key = send(
+            &app,
+            Request::get("/api/backup")
+                .header("X-Api-Key", "key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(without_key.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(with_key.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn backup_secrets_need_the_admin_token() {
+        let app = keyed_app("key").await;
+        let without_token = send(
+            &app,
+            Request::get("/api/backup?include_secrets=true")
+                .header("X-Api-Key", "key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let with_token = send(
+            &app,
+            Request::get("/api/backup?include_secrets=true")
+                .header("X-Api-Key", "key")
+                .header(AUTHORIZATION, "Bearer admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(without_token.status(), StatusCode::FORBIDDEN);
+        assert_eq!(with_token.status(), StatusCode::OK);
+        let body = to_bytes(with_token.into_body(), usize::MAX).await.unwrap();
+        let backup: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(backup["includes_secrets"], true);
+    }
+
+    #[tokio::test]
+    async fn listed_webhooks_show_only_their_host() {
+        let db = sqlite::memory_pool().await;
+        db.insert_notification("https://hooks.slack.example/services/T0/B0/secret")
+            .await
+            .unwrap();
+        let app = router(
+            ServerState {
+                api_key: ApiKey::new(Some("key")),
+                ..server_state(db)
+            },
+            RateLimiter::new(60, false),
+            None,
+            false,
+        );
+
+        let (status, body) = page(&app, "/api/notifications").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body.contains("secret"), "{body}");
+        let listed: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(listed[0]["host"], "hooks.slack.example");
+    }
+
+    #[test]
+    fn only_the_configured_admin_token_authorizes() {
+        let admin_token = AdminToken::new(Some("admin"));
+        assert!(admin_token.authorize("admin").is_ok());
+        assert!(admin_token.authorize("admin ").is_err());
+        assert!(admin_token.authorize("").is_err());
+
+        for unset in [AdminToken::new(None), AdminToken::new(Some(""))] {
+            assert!(!unset.is_configured());
+            assert!(unset.authorize("").is_err());
+        }
+    }
+
+    /// The router with a database time budget of 200ms, the pool is the test's to hold
+    async fn budgeted_app() -> (Router, SqlitePool) {
+        let db = sqlite::memory_pool().await;
+        db.insert_website(&website("steady", serde_json::json!({})))
+            .await
+            .unwrap();
+        let app = router(
+            ServerState {
+                snapshots: SnapshotCache::new(Duration::from_millis(200)),
+                ..server_state(db.clone())
+            },
+            RateLimiter::new(60, false),
+            None,
+            false,
+        );
+        (app, db)
+    }
+
+    async fn page(app: &Router, path: &str) -> (StatusCode, String) {
+        let response = send(app, Request::get(path).body(Body::empty()).unwrap()).await;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    const STALE_BANNER: &str = "live data temporarily unavailable";
+
+    #[tokio::test]
+    async fn a_slow_database_serves_the_last_snapshot_of_the_pages() {
+        let (app, db) = budgeted_app().await;
+        let (_, live_dashboard) = page(&app, "/").await;
+        let (_, live_website) = page(&app, "/websites/steady").await;
+
+        // The in-memory pool has a single connection, holding it stalls every query
+        let _held = db.acquire().await.unwrap();
+        let (dashboard_status, dashboard) = page(&app, "/").await;
+        let (website_status, website) = page(&app, "/websites/steady").await;
+
+        assert!(!live_dashboard.contains(STALE_BANNER));
+        assert!(!live_website.contains(STALE_BANNER));
+        assert_eq!(dashboard_status, StatusCode::OK);
+        assert!(dashboard.contains(STALE_BANNER));
+        assert!(dashboard.contains("steady"));
+        assert_eq!(website_status, StatusCode::OK);
+        assert!(website.contains(STALE_BANNER));
+        assert!(website.contains("steady"));
+    }
+
+    #[tokio::test]
+    async fn a_slow_database_without_a_snapshot_is_an_error() {
+        let (app, db) = budgeted_app().await;
+
+        // Only the budget answers with a 503, a query waiting out the pool's acquire
+        // timeout would end in a 500
+        let _held = db.acquire().await.unwrap();
+        let (dashboard_status, dashboard) = page(&app, "/").await;
+        let (website_status, website) = page(&app, "/websites/steady").await;
+
+        assert_eq!(dashboard_status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(website_status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(dashboard.contains("Database didn't answer in time"));
+        assert!(website.contains("Database didn't answer in time"));
+    }
+
+    #[tokio::test]
+    async fn an_incident_inherits_its_website_weight_until_overridden() {
+        let db = sqlite::memory_pool().await;
+        let mut website = website("blog", serde_json::json!({}));
+        website.impact_weight = "minor".to_owned();
+        db.insert_website(&website).await.unwrap();
+        db.write_logs(&[check("blog", checker::CONNECTION_FAILED_STATUS, Utc::now())])
+            .await
+            .unwrap();
+        let log_id: i32 = sqlx::query_scalar("SELECT id FROM Logs")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let app = router(
+            server_state(db.clone()),
+            RateLimiter::new(60, false),
+            None,
+            false,
+        );
+        let overall = |app: Router| async move {
+            let (_, body) = page(&app, "/status.json").await;
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()["overall"].clone()
+        };
+
+        let inherited = overall(app.clone()).await;
+        let overridden = send(
+            &app,
+            Request::post(format!("/websites/blog/incidents/{log_id}/impact"))
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from("impact=critical"))
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(inherited, "partial outage");
+        assert_eq!(overridden.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            db.active_incident_impacts().await.unwrap(),
+            [("blog".to_owned(), "critical".to_owned())]
+        );
+        assert_eq!(overall(app).await, "major outage");
+    }
+
+    #[tokio::test]
+    async fn a_manual_status_shows_in_place_of_the_automated_one() {
+        let db = sqlite::memory_pool().await;
+        db.insert_website(&website("api", serde_json::json!({})))
+            .await
+            .unwrap();
+        db.write_logs(&[check("api", checker::CONNECTION_FAILED_STATUS, Utc::now())])
+            .await
+            .unwrap();
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+        let put_override = |body: serde_json::Value| {
+            Request::put("/api/websites/api/status-override")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let status = |app: Router| async move {
+            let (_, body) = page(&app, "/status.json").await;
+            let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+            (
+                status["overall"].clone(),
+                status["websites"][0]["status"].clone(),
+            )
+        };
+
+        let automated = status(app.clone()).await;
+        let in_the_past = send(
+            &app,
+            put_override(serde_json::json!({
+                "status": "maintenance",
+                "expires_at": Utc::now() - TimeDelta::hours(1),
+            })),
+        )
+        .await;
+        let set = send(
+            &app,
+            put_override(serde_json::json!({
+                "status": "maintenance",
+                "note": "Moving racks",
+                "expires_at": Utc::now() + TimeDelta::hours(1),
+            })),
+        )
+        .await;
+        let manual = status(app.clone()).await;
+        let (_, manual_badge) = page(&app, "/badge/api.svg").await;
+        let cleared = send(&app, put_override(serde_json::json!({"status": "none"}))).await;
+        let (_, automated_badge) = page(&app, "/badge/api.svg").await;
+
+        assert_eq!(automated, ("partial outage".into(), "down".into()));
+        assert_eq!(in_the_past.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(set.status(), StatusCode::NO_CONTENT);
+        assert_eq!(manual, ("all operational".into(), "maintenance".into()));
+        assert_eq!(cleared.status(), StatusCode::NO_CONTENT);
+        assert_eq!(status(app).await, automated);
+        assert!(manual_badge.contains("api: maintenance (manual)"));
+        assert!(automated_badge.contains("api: down"));
+    }
+
+    #[tokio::test]
+    async fn the_monthly_section_loads_lazily_with_a_plain_link_as_fallback() {
+        let db = sqlite::memory_pool().await;
+        db.insert_website(&website("lazy", serde_json::json!({})))
+            .await
+            .unwrap();
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+
+        let (detail_status, detail) = page(&app, "/websites/lazy").await;
+        let fragment = send(
+            &app,
+            Request::get("/websites/lazy/fragments/monthly")
+                .header("HX-Request", "true")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let fragment_status = fragment.status();
+        let fragment = to_bytes(fragment.into_body(), usize::MAX).await.unwrap();
+        let fragment = String::from_utf8(fragment.to_vec()).unwrap();
+        let (fallback_status, fallback) = page(&app, "/websites/lazy/fragments/monthly").await;
+        let (missing_status, _) = page(&app, "/websites/missing/fragments/monthly").await;
+
+        assert_eq!(detail_status, StatusCode::OK);
+        assert!(detail.contains(r#"hx-get="/websites/lazy/fragments/monthly""#));
+        assert!(detail.contains(r#"<a href="/websites/lazy/fragments/monthly">"#));
+        assert!(!detail.contains("monthly-data"));
+        assert_eq!(fragment_status, StatusCode::OK);
+        assert!(fragment.contains(r#"id="monthly-lazy""#));
+        assert!(!fragment.contains("<html"));
+        assert_eq!(fallback_status, StatusCode::OK);
+        assert!(fallback.contains("<html"));
+        assert!(fallback.contains(r#"id="monthly-lazy""#));
+        assert!(fallback.contains("Back to lazy"));
+        assert_eq!(missing_status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn the_dashboard_queries_show_up_in_the_slow_query_log() {
+        let app = app(false).await;
+
+        page(&app, "/?sort=uptime").await;
+        let (anonymous_status, _) = page(&app, "/api/slow-queries").await;
+        let (anonymous_page_status, _) = page(&app, "/slow-queries").await;
+        let response = send(
+            &app,
+            Request::get("/api/slow-queries")
+                .header(AUTHORIZATION, "Bearer admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert_eq!(anonymous_status, StatusCode::FORBIDDEN);
+        assert_eq!(anonymous_page_status, StatusCode::FORBIDDEN);
+        assert_eq!(status, StatusCode::OK);
+        let queries: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let labels: Vec<&str> = queries
+            .iter()
+            .map(|query| query["label"].as_str().unwrap())
+            .collect();
+        for label in ["content_version", "websites", "status_summaries"] {
+            assert!(labels.contains(&label), "{label} missing from {labels:?}");
+        }
+        let durations: Vec<u64> = queries
+            .iter()
+            .map(|query| query["duration_ms"].as_u64().unwrap())
+            .collect();
+        assert!(durations.is_sorted_by(|a, b| a >= b), "{durations:?}");
+    }
+
+    #[tokio::test]
+    async fn the_openapi_spec_is_served() {
+        let app = app(false).await;
+        let response = send(
+            &app,
+            Request::get("/api/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(spec["paths"]["/api/websites/{alias}/stats"].is_object());
+    }
+
+    #[tokio::test]
+    async fn dashboard_forms_send_browsers_to_login() {
+        let app = keyed_app("key").await;
+        let response = send(
+            &app,
+            Request::post("/websites/missing/restore")
+                .header(ACCEPT, "text/html")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[LOCATION], "/login");
+    }
+
+    fn login_request(api_key: &str) -> Request<Body> {
+        Request::post("/login")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!("api_key={api_key}")))
+            .unwrap()
+    }
+
+    /// Status of restoring a website that doesn't exist with `cookie`, a 404 once past
+    /// the login check
+    async fn restore_with_cookie(app: &Router, cookie: &str) -> StatusCode {
+        send(
+            app,
+            Request::post("/websites/missing/restore")
+                .header(ACCEPT, "text/html")
+                .header(COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .status()
+    }
+
+    #[tokio::test]
+    async fn login_cookie_stands_in_for_the_key_until_logout() {
+        let app = keyed_app("key").await;
+        let wrong = send(&app, login_request("wrong")).await;
+        let right = send(&app, login_request("key")).await;
+
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+        assert!(wrong.headers().get(SET_COOKIE).is_none());
+        assert_eq!(right.status(), StatusCode::SEE_OTHER);
+        let set_cookie = right.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.contains("; Max-Age=43200;"), "{set_cookie}");
+        assert!(!set_cookie.contains("Secure"), "{set_cookie}");
+        let cookie = set_cookie.split(';').next().unwrap().to_owned();
+        assert_eq!(
+            restore_with_cookie(&app, &cookie).await,
+            StatusCode::NOT_FOUND
+        );
+
+        let logout = send(
+            &app,
+            Request::post("/logout")
+                .header(COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(logout.status(), StatusCode::SEE_OTHER);
+        assert!(
+            logout.headers()[SET_COOKIE]
+                .to_str()
+                .unwrap()
+                .contains("Max-Age=0")
+        );
+        assert_eq!(
+            restore_with_cookie(&app, &cookie).await,
+            StatusCode::SEE_OTHER
+        );
+    }
+
+    #[tokio::test]
+    async fn login_cookie_is_secure_behind_https() {
+        let app = keyed_app("key").await;
+        let mut request = login_request("key");
+        request
+            .headers_mut()
+            .insert("X-Forwarded-Proto", "https".parse().unwrap());
+
+        let response = send(&app, request).await;
+
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.ends_with("; Secure"), "{set_cookie}");
+    }
+
+    #[tokio::test]
+    async fn the_key_digest_is_no_login_cookie() {
+        let app = keyed_app("key").await;
+        let cookie = format!(
+            "uptime_ferris_login={}",
+            Sha256::digest("key")
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        );
+
+        assert_eq!(
+            restore_with_cookie(&app, &cookie).await,
+            StatusCode::SEE_OTHER
+        );
+    }
+
+    #[tokio::test]
+    async fn login_cookie_is_no_api_key() {
+        let app = keyed_app("key").await;
+        let login = send(&app, login_request("key")).await;
+        let cookie = login.headers()[SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_owned();
+        let response = send(
+            &app,
+            Request::post("/api/notifications/1/test")
+                .header(COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn forms_posted_from_other_sites_are_refused() {
+        let app = app(false).await;
+        let cross_site = send(
+            &app,
+            Request::post("/websites/missing/restore")
+                .header("Sec-Fetch-Site", "cross-site")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let same_origin = send(
+            &app,
+            Request::post("/websites/missing/restore")
+                .header("Sec-Fetch-Site", "same-origin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(cross_site.status(), StatusCode::FORBIDDEN);
+        assert_eq!(same_origin.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// A website with nothing but its url and alias, stored on `db`
+    async fn stored_website(db: &SqlitePool, alias: &str) -> Website {
+        db.insert_website(&website(alias, serde_json::json!({})))
+            .await
+            .unwrap();
+        db.website(alias).await.unwrap().unwrap()
+    }
+
+    /// The body of `response` as JSON
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// A good row, a row with an invalid alias and one repeating the good alias
+    async fn import(db: &SqlitePool, mode: &str) -> serde_json::Value {
+        let app = router(
+            server_state(db.clone()),
+            RateLimiter::new(60, false),
+            None,
+            false,
+        );
+        let rows = serde_json::json!([
+            {"url": "https://shop.example", "alias": "shop"},
+            {"url": "https://blog.example", "alias": "a/b"},
+            {"url": "https://shop.example/again", "alias": "shop"},
+        ]);
+        let response = send(
+            &app,
+            Request::post(format!("/api/websites/import?mode={mode}"))
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(rows.to_string()))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        json_body(response).await
+    }
+
+    fn row_statuses(report: &serde_json::Value) -> Vec<&str> {
+        report["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["status"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn a_partial_import_keeps_the_good_rows() {
+        let db = sqlite::memory_pool().await;
+        let report = import(&db, "partial").await;
+
+        assert_eq!(report["committed"], true);
+        assert_eq!(
+            (&report["created"], &report["skipped"], &report["invalid"]),
+            (&1.into(), &1.into(), &1.into())
+        );
+        assert_eq!(row_statuses(&report), ["created", "invalid", "skipped"]);
+        let shop = db.website("shop").await.unwrap().unwrap();
+        assert_eq!(shop.url, "https://shop.example");
+    }
+
+    #[tokio::test]
+    async fn an_all_or_nothing_import_with_a_bad_row_keeps_nothing() {
+        let db = sqlite::memory_pool().await;
+        let report = import(&db, "all_or_nothing").await;
+
+        assert_eq!(report["committed"], false);
+        assert_eq!(report["created"], 0);
+        assert_eq!(row_statuses(&report), ["rolled_back", "invalid", "skipped"]);
+        assert!(db.website("shop").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_website_whose_config_does_not_decode_is_skipped() {
+        let db = sqlite::memory_pool().await;
+        stored_website(&db, "broken").await;
+        stored_website(&db, "fine").await;
+        sqlx::query("UPDATE Websites SET config = '{\"version\": 99}' WHERE alias = 'broken'")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let websites = db.monitored_websites().await.unwrap();
+
+        assert_eq!(websites.len(), 1);
+        assert_eq!(websites[0].alias, "fine");
+    }
+
+    /// A freshly migrated database named after the test on the Postgres server
+    /// `DATABASE_URL` points to. The tests calling this are ignored, so they only run when
+    /// asked for with `DATABASE_URL=postgres://… cargo test -- --ignored`
+    pub(crate) async fn postgres_pool(test: &str) -> PgPool {
+        let url = std::env::var("DATABASE_URL")
+            .expect("set DATABASE_URL to a Postgres server to run the ignored tests");
+        let server = PgPool::connect(&url)
+            .await
+            .expect("connecting to DATABASE_URL failed");
+        let database = format!("uptime_ferris_{test}");
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {database} WITH (FORCE)"))
+            .execute(&server)
+            .await
+            .unwrap();
+        sqlx::query(&format!("CREATE DATABASE {database}"))
+            .execute(&server)
+            .await
+            .unwrap();
+        let options = sqlx::postgres::PgConnectOptions::from_str(&url)
+            .unwrap()
+            .database(&database);
+        let pool = PgPool::connect_with(options).await.unwrap();
+        AppState::migrate_postgres(&pool, None).await.unwrap();
+        pool
+    }
+
+    pub(crate) async fn postgres(test: &str) -> AppState {
+        AppState::Postgres(postgres_pool(test).await)
+    }
+
+    /// Like [`postgres_pool`] on the MySQL server `MYSQL_URL` points to, run the tests
+    /// calling this with `MYSQL_URL=mysql://… cargo test -- --ignored`
+    pub(crate) async fn mysql(test: &str) -> AppState {
+        let url = std::env::var("MYSQL_URL")
+            .expect("set MYSQL_URL to a MySQL server to run the ignored tests");
+        let server = MySqlPool::connect(&url)
+            .await
+            .expect("connecting to MYSQL_URL failed");
+        let database = format!("uptime_ferris_{test}");
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {database}"))
+            .execute(&server)
+            .await
+            .unwrap();
+        sqlx::query(&format!("CREATE DATABASE {database}"))
+            .execute(&server)
+            .await
+            .unwrap();
+        let options = sqlx::mysql::MySqlConnectOptions::from_str(&url)
+            .unwrap()
+            .database(&database);
+        let pool = MySqlPool::connect_with(options).await.unwrap();
+        AppState::migrate_mysql(&pool, None).await.unwrap();
+        AppState::MySql(pool)
+    }
+
+    /// The website `alias` at https://{alias}.example as the form submits it, with the
+    /// form fields in `extra` on top
+    pub(crate) fn website(alias: &str, extra: serde_json::Value) -> Website {
+        let serde_json::Value::Object(mut fields) = extra else {
+            panic!("{extra} are no form fields");
+        };
+        fields
+            .entry("url")
+            .or_insert_with(|| format!("https://{alias}.example").into());
+        fields.insert("alias".to_owned(), alias.into());
+        serde_json::from_value(serde_json::Value::Object(fields)).unwrap()
+    }
+
+    /// A check of `alias` in the minute of `at` that ended with `status`
+    pub(crate) fn check(alias: &str, status: i16, at: DateTime<Utc>) -> checker::PendingLog {
+        let mut result = checker::CheckResult {
+            status,
+            ..checker::CheckResult::failed()
+        };
+        checker::PendingLog::take(alias, &mut result, at)
+    }
+
+    /// Runs the test `$test`, taking an [`AppState`], on an in-memory SQLite database and on
+    /// the Postgres and MySQL servers, which are ignored unless asked for. The server
+    /// databases are named after `$database`
+    macro_rules! on_every_backend {
+        ($test:ident, $database:literal) => {
+            mod $test {
+                #[tokio::test]
+                async fn on_sqlite() {
+                    super::$test($crate::AppState::Sqlite(
+                        $crate::sqlite::memory_pool().await,
+                    ))
+                    .await;
+                }
+
+                #[tokio::test]
+                #[ignore = "needs a Postgres server, run with DATABASE_URL set and -- --ignored"]
+                async fn on_postgres() {
+                    super::$test($crate::tests::postgres($database).await).await;
+                }
+
+                #[tokio::test]
+                #[ignore = "needs a MySQL server, run with MYSQL_URL set and -- --ignored"]
+                async fn on_mysql() {
+                    super::$test($crate::tests::mysql($database).await).await;
+                }
+            }
+        };
+    }
+
+    pub(crate) use on_every_backend;
+
+    async fn a_created_website_reads_back_its_created_at(db: AppState) {
+        let store = db.store();
+        let before = Utc::now() - TimeDelta::seconds(1);
+
+        let created = store
+            .insert_website(&website("new", serde_json::json!({})))
+            .await
+            .unwrap();
+        let listed = store.website_summaries(10, 0).await.unwrap();
+
+        let created_at = created.created_at.expect("created without created_at");
+        assert!(
+            before <= created_at && created_at <= Utc::now(),
+            "{created_at} isn't about now"
+        );
+        assert_eq!(listed[0].created_at, Some(created_at));
+    }
+
+    on_every_backend!(a_created_website_reads_back_its_created_at, "created_at");
 
-            sqlx::query(INSERT_INTO_LOGS_BY_ALIAS_RESPONSE_CODE_QUERY)
-                .bind(website.alias)
-                .bind(response.status().as_u16() as i16)
-                .execute(&db)
+    /// Client certificate path and webhooks, which reading a website leaves out
+    async fn unselected_columns(
+        db: &AppState,
+        alias: &str,
+    ) -> (Option<String>, Option<String>, Option<String>) {
+        let query = format!(
+            "SELECT client_cert_path, slack_webhook_url, discord_webhook_url
+            FROM Websites WHERE alias = '{alias}'"
+        );
+        on_db!(db, |db| sqlx::query_as(&query).fetch_one(db).await.unwrap())
+    }
+
+    async fn an_update_is_read_back(db: AppState) {
+        let store = db.store();
+        let original = website(
+            "api",
+            serde_json::json!({
+                "client_cert_path": "/certs/api.pem",
+                "slack_webhook_url": "https://hooks.slack.example/old",
+                "retention_days": "7",
+            }),
+        );
+        store.insert_website(&original).await.unwrap();
+        let mut changed = website(
+            "api-v2",
+            serde_json::json!({
+                "url": "https://api.example/health",
+                "ip_version": "v6only",
+                "impact_weight": "critical",
+                "invert": "on",
+                "client_cert_path": "/certs/ignored.pem",
+                "discord_webhook_url": "https://discord.example/hook",
+                "alert_email_to": "ops@example.com",
+                "retention_days": "30",
+            }),
+        );
+
+        let updated = store.update_website("api", &changed).await.unwrap();
+
+        assert_eq!(updated, 1);
+        assert_eq!(store.update_website("api", &changed).await.unwrap(), 0);
+        let read = store.website("api-v2").await.unwrap().unwrap();
+        assert_eq!(
+            (
+                read.url.as_str(),
+                read.ip_version.as_str(),
+                read.impact_weight.as_str()
+            ),
+            ("https://api.example/health", "v6only", "critical")
+        );
+        assert!(read.invert);
+        assert_eq!(read.alert_email_to.as_deref(), Some("ops@example.com"));
+        assert_eq!(read.retention_days, Some(30));
+        // Without a new certificate the client identity stays, left out webhooks too
+        assert_eq!(
+            unselected_columns(&db, "api-v2").await,
+            (
+                Some("/certs/api.pem".to_owned()),
+                Some("https://hooks.slack.example/old".to_owned()),
+                Some("https://discord.example/hook".to_owned()),
+            )
+        );
+
+        let expires_at = Utc::now().duration_trunc(TimeDelta::seconds(1)).unwrap();
+        changed.client_cert_fingerprint = Some("ab12".to_owned());
+        changed.client_cert_expires_at = Some(expires_at);
+        store.update_website("api-v2", &changed).await.unwrap();
+
+        let read = store.website("api-v2").await.unwrap().unwrap();
+        assert_eq!(read.client_cert_fingerprint.as_deref(), Some("ab12"));
+        assert_eq!(read.client_cert_expires_at, Some(expires_at));
+        assert_eq!(
+            unselected_columns(&db, "api-v2").await.0.as_deref(),
+            Some("/certs/ignored.pem")
+        );
+    }
+
+    on_every_backend!(an_update_is_read_back, "update");
+
+    async fn logs_vanish_with_their_website(db: AppState) {
+        let store = db.store();
+        for alias in ["purged", "kept"] {
+            store
+                .insert_website(&website(alias, serde_json::json!({})))
                 .await
                 .unwrap();
         }
+        let now = Utc::now();
+        let written = store
+            .write_logs(&[
+                check("purged", checker::CONNECTION_FAILED_STATUS, now),
+                check("kept", checker::CONNECTION_FAILED_STATUS, now),
+            ])
+            .await
+            .unwrap();
+
+        store.soft_delete_website("purged", now).await.unwrap();
+        store.purge_website("purged").await.unwrap();
+
+        let logs: i64 = on_db!(&db, |db| sqlx::query_scalar("SELECT COUNT(*) FROM Logs")
+            .fetch_one(db)
+            .await
+            .unwrap());
+        assert_eq!(written, 2);
+        assert_eq!(logs, 1);
+        assert!(store.website("purged").await.unwrap().is_none());
+    }
+
+    on_every_backend!(logs_vanish_with_their_website, "purge");
+
+    /// The steps `/api/restore` takes for the backup, with websites prepared like submitted
+    /// ones
+    fn restore_steps(backup: &str) -> Vec<RestoreStep> {
+        Backup::from_json(backup)
+            .unwrap()
+            .into_steps(|website| prepare_website(&Secrets::new(None), None, website))
     }
-}
 
-async fn check_websites_sqlite(db: SqlitePool) {
-    let mut interval = time::interval(Duration::from_secs(60));
-    loop {
-        interval.tick().await;
+    async fn a_backup_restores_what_changed_since(db: AppState) {
+        let store = db.store();
+        store
+            .insert_notification_channel("ops", "webhook", r#"{"url": "https://hooks.example"}"#)
+            .await
+            .unwrap();
+        let channel_id = store.notification_channels().await.unwrap()[0].id;
+        store
+            .insert_website(&website("api", serde_json::json!({})))
+            .await
+            .unwrap();
+        store.assign_channel("api", channel_id).await.unwrap();
+        store
+            .insert_website(&website("gone", serde_json::json!({})))
+            .await
+            .unwrap();
+        let rule = NewEscalationRule {
+            after_minutes: 5,
+            repeat_minutes: Some(30),
+            channel_id: Some(channel_id),
+        };
+        store.insert_escalation_rule("api", &rule).await.unwrap();
+        store
+            .update_status_override("api", Some("maintenance"), Some("Upgrade".to_owned()), None)
+            .await
+            .unwrap();
+        store
+            .insert_notification("https://hooks.example/legacy")
+            .await
+            .unwrap();
+        let now = Utc::now();
+        let backup = Backup::new(store.backup_tables(now).await.unwrap(), true, now);
+        let backup = serde_json::to_string(&backup).unwrap();
 
-        info!("Starting Website Uptime check");
-        let client = reqwest::Client::new();
+        let rule_id = store.website_escalation_rules("api").await.unwrap()[0].id;
+        store.delete_escalation_rule("api", rule_id).await.unwrap();
+        store.unassign_channel("api", channel_id).await.unwrap();
+        store
+            .update_status_override("api", None, None, None)
+            .await
+            .unwrap();
+        store.soft_delete_website("gone", now).await.unwrap();
+        store.purge_website("gone").await.unwrap();
 
-        let mut res = sqlx::query_as::<_, Website>(SELECT_URL_ALIAS_WEBSITES_QUERY).fetch(&db);
+        let dry_run = store
+            .restore_backup(restore_steps(&backup), true)
+            .await
+            .unwrap();
+        assert!(!dry_run.committed);
+        assert_eq!((dry_run.created, dry_run.updated), (1, 2));
+        assert!(store.website("gone").await.unwrap().is_none());
 
-        while let Some(website) = res.next().await {
-            let website = website.unwrap();
+        let restored = store
+            .restore_backup(restore_steps(&backup), false)
+            .await
+            .unwrap();
+        assert!(restored.committed);
+        assert_eq!((restored.created, restored.updated), (1, 2));
+        assert!(store.website("gone").await.unwrap().is_some());
+        let channels = store.website_channels("api").await.unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].id, channel_id);
+        let rules = store.website_escalation_rules("api").await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(
+            (
+                rules[0].after_minutes,
+                rules[0].repeat_minutes,
+                rules[0].channel_id
+            ),
+            (5, Some(30), Some(channel_id))
+        );
+        let status_override = store.active_status_overrides(Utc::now()).await.unwrap();
+        assert_eq!(status_override.len(), 1);
+        assert_eq!(
+            (
+                status_override[0].status.as_str(),
+                status_override[0].note.as_deref()
+            ),
+            ("maintenance", Some("Upgrade"))
+        );
+        assert_eq!(store.notifications().await.unwrap().len(), 1);
+    }
 
-            let response = client.get(website.url).send().await.unwrap();
+    on_every_backend!(a_backup_restores_what_changed_since, "restore");
 
-            sqlx::query(INSERT_INTO_LOGS_BY_ALIAS_RESPONSE_CODE_QUERY)
-                .bind(website.alias)
-                .bind(response.status().as_u16() as i16)
-                .execute(&db)
+    #[tokio::test]
+    async fn the_dashboard_stays_fast_with_100k_checks() {
+        let db = sqlite::memory_pool().await;
+        for n in 0..10 {
+            db.insert_website(&website(&format!("busy{n}"), serde_json::json!({})))
                 .await
                 .unwrap();
         }
+        sqlx::query(
+            "WITH RECURSIVE Minutes(n) AS (SELECT 0 UNION ALL SELECT n + 1 FROM Minutes WHERE n < 9999)
+            INSERT INTO Logs (website_id, status, response_time_ms, created_at)
+            SELECT Websites.id, CASE WHEN n % 50 = 0 THEN 500 ELSE 200 END, 100 + n % 300,
+            strftime('%Y-%m-%d %H:%M:00', 'now', '-' || n || ' minutes')
+            FROM Websites, Minutes",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        // A full pass over Logs is what makes the page slow as checks pile up, so the
+        // queries behind it have to look checks up through an index
+        for query in [
+            sqlite_queries::SELECT_STATUS_SUMMARIES,
+            sqlite_queries::SELECT_STATS_BY_HOUR,
+            sqlite_queries::SELECT_STATS_BY_DAY,
+        ] {
+            let plan: Vec<(i64, i64, i64, String)> =
+                sqlx::query_as(&format!("EXPLAIN QUERY PLAN {query}"))
+                    .fetch_all(&db)
+                    .await
+                    .unwrap();
+            let scans: Vec<&String> = plan
+                .iter()
+                .map(|(_, _, _, detail)| detail)
+                .filter(|detail| detail.starts_with("SCAN Logs") && !detail.contains("INDEX"))
+                .collect();
+            assert!(scans.is_empty(), "{scans:?} in {query}");
+        }
+
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+        let response = send(
+            &app,
+            Request::get("/?sort=uptime").body(Body::empty()).unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
     }
-}
 
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        signal::ctrl_c()
+    /// The rejected inputs of the website submitted with `fields`
+    fn rejected_fields(fields: serde_json::Value) -> Vec<(String, String)> {
+        let website: Website = serde_json::from_value(fields).unwrap();
+        match website.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => field_errors::field_errors(&errors)
+                .into_iter()
+                .map(|error| (error.field, error.message))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn a_valid_website_is_accepted() {
+        assert_eq!(
+            rejected_fields(serde_json::json!({
+                "url": "https://example.com/health",
+                "alias": "example.com_health-1~",
+            })),
+            []
+        );
+    }
+
+    #[test]
+    fn an_empty_alias_is_rejected() {
+        assert_eq!(
+            rejected_fields(serde_json::json!({"url": "https://example.com", "alias": ""})),
+            [(
+                "alias".to_owned(),
+                "must be 1 to 64 characters long".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn aliases_are_limited_to_characters_paths_take_as_they_are() {
+        for alias in ["a/b", "a b", "a?b", "a%2Fb", "ä"] {
+            assert_eq!(
+                rejected_fields(serde_json::json!({"url": "https://example.com", "alias": alias})),
+                [(
+                    "alias".to_owned(),
+                    "may only contain letters, digits, '-', '_', '.' and '~'".to_owned()
+                )],
+                "{alias}"
+            );
+        }
+    }
+
+    #[test]
+    fn dot_segments_are_no_aliases() {
+        for alias in [".", "..", "..."] {
+            assert_eq!(
+                rejected_fields(serde_json::json!({"url": "https://example.com", "alias": alias})),
+                [("alias".to_owned(), "may not be only dots".to_owned())],
+                "{alias}"
+            );
+        }
+        assert!(
+            rejected_fields(serde_json::json!({"url": "https://example.com", "alias": "v1.2"}))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn javascript_urls_are_rejected() {
+        for url in ["javascript:alert(1)", "JavaScript:alert(document.cookie)"] {
+            assert_eq!(
+                rejected_fields(serde_json::json!({"url": url, "alias": "xss"})),
+                [("url".to_owned(), "must be an http or https URL".to_owned())],
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn over_long_inputs_are_rejected() {
+        let rejected = rejected_fields(serde_json::json!({
+            "url": format!("https://example.com/{}", "a".repeat(2048)),
+            "alias": "a".repeat(65),
+            "expected_keyword": "k".repeat(1025),
+        }));
+
+        assert_eq!(
+            rejected,
+            [
+                (
+                    "alias".to_owned(),
+                    "must be 1 to 64 characters long".to_owned()
+                ),
+                (
+                    "expected_keyword".to_owned(),
+                    "must be at most 1024 characters long".to_owned()
+                ),
+                (
+                    "url".to_owned(),
+                    "must be at most 2048 characters long".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failing_channel_binding_leaves_no_website_behind() {
+        let db = sqlite::memory_pool().await;
+        sqlx::query(sqlite_queries::INSERT_NOTIFICATION_CHANNEL_QUERY)
+            .bind("ops")
+            .bind("webhook")
+            .bind(r#"{"url": "https://hooks.example"}"#)
+            .execute(&db)
             .await
-            .expect("failed to install Ctrl+C handler");
-    };
+            .unwrap();
+        let channel_id: i32 = sqlx::query_scalar("SELECT id FROM NotificationChannels")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        sqlx::query(
+            "CREATE TRIGGER fail_binding BEFORE INSERT ON WebsiteNotificationChannels
+            BEGIN SELECT RAISE(ABORT, 'injected failure'); END",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let website = website(
+            "bound",
+            serde_json::json!({"channel_id": channel_id.to_string()}),
+        );
 
-    #[cfg(unix)]
-    let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("failed to install signal handler")
-            .recv()
-            .await;
-    };
+        let created =
+            add_website(&db, &Secrets::new(None), None, &Wakeup::default(), website).await;
 
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
+        assert!(created.is_err());
+        assert!(db.website("bound").await.unwrap().is_none());
+        assert_eq!(db.website_count().await.unwrap(), 0);
+    }
 
-    tokio::select! {
-        _ = ctrl_c => {},
-        _ = terminate => {},
+    #[tokio::test]
+    async fn a_new_website_wakes_the_checker() {
+        let db = sqlite::memory_pool().await;
+        let wakeup = Wakeup::default();
+        let mut clock = checker::SystemClock::new(wakeup.clone());
+        // The first tick is right away, the next one would be a few seconds off
+        clock.tick().await;
+        let website = website("new", serde_json::json!({}));
+
+        add_website(&db, &Secrets::new(None), None, &wakeup, website)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), clock.tick())
+            .await
+            .expect("the checker wasn't woken");
+    }
+
+    #[tokio::test]
+    async fn websites_are_paged_from_the_store() {
+        let db = sqlite::memory_pool().await;
+        stored_website(&db, "first").await;
+        stored_website(&db, "second").await;
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+
+        let response = send(
+            &app,
+            Request::get("/api/websites?per_page=1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["X-Total-Count"], "2");
+        let websites = json_body(response).await;
+        assert_eq!(websites.as_array().unwrap().len(), 1);
+        assert_eq!(websites[0]["alias"], "first");
+    }
+
+    #[tokio::test]
+    async fn failed_checks_are_listed_as_incidents() {
+        let db = sqlite::memory_pool().await;
+        let down = stored_website(&db, "down").await;
+        let up = stored_website(&db, "up").await;
+        let now = Utc::now();
+        let written = db
+            .write_logs(&[
+                check(&down.alias, checker::CONNECTION_FAILED_STATUS, now),
+                check(&up.alias, 200, now),
+            ])
+            .await
+            .unwrap();
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+
+        let down_incidents = send(
+            &app,
+            Request::get("/api/websites/down/incidents")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let up_incidents = send(
+            &app,
+            Request::get("/api/websites/up/incidents")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(written, 2);
+        let down_incidents = json_body(down_incidents).await;
+        assert_eq!(down_incidents.as_array().unwrap().len(), 1);
+        assert_eq!(
+            down_incidents[0]["status"],
+            checker::CheckResult::failed().status
+        );
+        assert_eq!(json_body(up_incidents).await, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn a_second_check_in_the_same_minute_is_skipped_alone() {
+        let db = sqlite::memory_pool().await;
+        let website = stored_website(&db, "twice").await;
+        let minute = Utc::now().duration_trunc(TimeDelta::minutes(1)).unwrap();
+
+        let written = db
+            .write_logs(&[
+                check(&website.alias, checker::CONNECTION_FAILED_STATUS, minute),
+                check(
+                    &website.alias,
+                    checker::CONNECTION_FAILED_STATUS,
+                    minute + TimeDelta::seconds(59),
+                ),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(written, 1);
+    }
+
+    #[tokio::test]
+    async fn website_pages_read_through_the_store() {
+        let db = sqlite::memory_pool().await;
+        let website = stored_website(&db, "shown").await;
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+
+        let by_alias = send(
+            &app,
+            Request::get("/websites/shown")
+                .header(ACCEPT, "application/json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let by_id = send(
+            &app,
+            Request::get(format!("/api/websites/by-id/{}", website.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let stats = send(
+            &app,
+            Request::get("/api/websites/shown/stats")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(by_alias.status(), StatusCode::OK);
+        assert_eq!(json_body(by_alias).await["log"]["alias"], "shown");
+        assert_eq!(by_id.status(), StatusCode::OK);
+        assert_eq!(json_body(by_id).await["log"]["alias"], "shown");
+        assert_eq!(stats.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unknown_aliases_are_not_found() {
+        let app = app(false).await;
+
+        for path in [
+            "/api/websites/missing/incidents",
+            "/api/websites/missing/stats",
+            "/api/websites/by-id/1",
+        ] {
+            let response = send(&app, Request::get(path).body(Body::empty()).unwrap()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn status_json_lists_every_website() {
+        let db = sqlite::memory_pool().await;
+        stored_website(&db, "listed").await;
+        let app = router(server_state(db), RateLimiter::new(60, false), None, false);
+
+        let response = send(
+            &app,
+            Request::get("/status.json").body(Body::empty()).unwrap(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(ETAG));
+        let status = json_body(response).await.to_string();
+        assert!(status.contains("listed"), "{status}");
+    }
+
+    /// The router with indexing allowed or not, two websites of which `retired` is deleted
+    async fn indexing_app(allow: bool) -> Router {
+        let db = sqlite::memory_pool().await;
+        let store = AppState::Sqlite(db.clone()).store();
+        for alias in ["steady", "retired"] {
+            store
+                .insert_website(&website(alias, serde_json::json!({})))
+                .await
+                .unwrap();
+        }
+        store
+            .soft_delete_website("retired", Utc::now())
+            .await
+            .unwrap();
+        router(
+            ServerState {
+                indexing: Indexing(allow),
+                ..server_state(db)
+            },
+            RateLimiter::new(60, false),
+            None,
+            false,
+        )
+    }
+
+    /// Status, `X-Robots-Tag` and body of a request to `path` on status.example
+    async fn crawl(app: &Router, path: &str) -> (StatusCode, Option<String>, String) {
+        let response = send(
+            app,
+            Request::get(path)
+                .header(HOST, "status.example")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let status = response.status();
+        let robots_tag = response
+            .headers()
+            .get("X-Robots-Tag")
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            robots_tag,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn crawlers_are_turned_away_everywhere_by_default() {
+        let app = indexing_app(false).await;
+
+        let (_, _, robots_txt) = crawl(&app, "/robots.txt").await;
+        assert_eq!(robots_txt, "User-agent: *\nDisallow: /\n");
+        for path in ["/", "/websites/steady", "/websites/missing"] {
+            let (_, robots_tag, html) = crawl(&app, path).await;
+            assert_eq!(robots_tag.as_deref(), Some("noindex, nofollow"), "{path}");
+            assert!(
+                html.contains(r#"<meta name="robots" content="noindex, nofollow" />"#),
+                "{path}"
+            );
+        }
+        let (sitemap_status, _, _) = crawl(&app, "/sitemap.xml").await;
+        assert_eq!(sitemap_status, StatusCode::NOT_FOUND);
+        // Only HTML is marked, the JSON API and robots.txt itself aren't pages
+        for path in ["/robots.txt", "/api/websites"] {
+            let (_, robots_tag, _) = crawl(&app, path).await;
+            assert_eq!(robots_tag, None, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn allowed_indexing_opens_every_surface_and_lists_the_pages() {
+        let app = indexing_app(true).await;
+
+        let (_, _, robots_txt) = crawl(&app, "/robots.txt").await;
+        assert_eq!(
+            robots_txt,
+            "User-agent: *\nAllow: /\nSitemap: http://status.example/sitemap.xml\n"
+        );
+        for path in ["/", "/websites/steady"] {
+            let (_, robots_tag, html) = crawl(&app, path).await;
+            assert_eq!(robots_tag, None, "{path}");
+            assert!(
+                html.contains(r#"<meta name="robots" content="index, follow" />"#),
+                "{path}"
+            );
+        }
+        let (sitemap_status, _, sitemap) = crawl(&app, "/sitemap.xml").await;
+        assert_eq!(sitemap_status, StatusCode::OK);
+        assert!(sitemap.contains("<loc>http://status.example/</loc>"));
+        assert!(sitemap.contains("<loc>http://status.example/websites/steady</loc>"));
+        assert!(!sitemap.contains("retired"), "{sitemap}");
+    }
+
+    /// `path` requested with `accept`, as status, content type, `Vary` and body
+    async fn negotiate(app: &Router, path: &str, accept: &str) -> (StatusCode, String, Response) {
+        let response = send(
+            app,
+            Request::get(path)
+                .header(ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        let content_type = response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_eq!(response.headers()[VARY], "accept", "{path}");
+        (response.status(), content_type, response)
+    }
+
+    #[tokio::test]
+    async fn the_pages_answer_json_clients_with_their_data() {
+        let (app, _) = budgeted_app().await;
+
+        let (status, content_type, dashboard) = negotiate(&app, "/", "application/json").await;
+        assert_eq!(
+            (status, content_type.as_str()),
+            (StatusCode::OK, "application/json")
+        );
+        let dashboard = json_body(dashboard).await;
+        assert_eq!(dashboard["logs"][0]["alias"], "steady");
+        assert!(dashboard["logs"][0]["data"].is_array());
+
+        let (status, content_type, website) =
+            negotiate(&app, "/websites/steady", "application/json").await;
+        assert_eq!(
+            (status, content_type.as_str()),
+            (StatusCode::OK, "application/json")
+        );
+        let website = json_body(website).await;
+        assert_eq!(website["log"]["alias"], "steady");
+        assert!(website["incidents"].is_array());
+    }
+
+    #[tokio::test]
+    async fn browsers_and_unspecific_clients_keep_getting_the_pages() {
+        let (app, _) = budgeted_app().await;
+
+        for accept in ["text/html,application/xhtml+xml,*/*;q=0.8", "*/*"] {
+            for path in ["/", "/websites/steady"] {
+                let (status, content_type, page) = negotiate(&app, path, accept).await;
+                assert_eq!(status, StatusCode::OK);
+                assert!(
+                    content_type.starts_with("text/html"),
+                    "{path} {content_type}"
+                );
+                let body = to_bytes(page.into_body(), usize::MAX).await.unwrap();
+                assert!(String::from_utf8_lossy(&body).contains("<html"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_follow_the_negotiated_representation() {
+        let (app, _) = budgeted_app().await;
+
+        let (status, content_type, error) =
+            negotiate(&app, "/websites/missing", "application/json").await;
+        assert_eq!(
+            (status, content_type.as_str()),
+            (StatusCode::NOT_FOUND, "application/json")
+        );
+        assert_eq!(
+            json_body(error).await,
+            serde_json::json!({"error": "Not found"})
+        );
+
+        let (status, content_type, _) = negotiate(&app, "/websites/missing", "text/html").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(content_type.starts_with("text/html"), "{content_type}");
+    }
+
+    #[tokio::test]
+    async fn a_cached_page_does_not_validate_a_json_request() {
+        let (app, _) = budgeted_app().await;
+        let (_, _, page) = negotiate(&app, "/", "text/html").await;
+        let etag = page.headers()[ETAG].clone();
+
+        let response = send(
+            &app,
+            Request::get("/")
+                .header(ACCEPT, "application/json")
+                .header(IF_NONE_MATCH, etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["logs"][0]["alias"], "steady");
+    }
+
+    #[tokio::test]
+    async fn a_slow_database_serves_the_snapshot_in_the_requested_representation() {
+        let (app, db) = budgeted_app().await;
+        negotiate(&app, "/", "application/json").await;
+        negotiate(&app, "/websites/steady", "application/json").await;
+
+        let _held = db.acquire().await.unwrap();
+        for (path, alias) in [("/", "/logs/0/alias"), ("/websites/steady", "/log/alias")] {
+            let (status, content_type, stale) = negotiate(&app, path, "application/json").await;
+            assert_eq!(
+                (status, content_type.as_str()),
+                (StatusCode::OK, "application/json")
+            );
+            let stale = json_body(stale).await;
+            assert_eq!(
+                stale.pointer(alias),
+                Some(&serde_json::json!("steady")),
+                "{path}"
+            );
+            assert!(stale["stale_as_of"].is_string(), "{path}");
+        }
     }
 }
@@ -0,0 +1,171 @@
+use crate::impact::{self, Impact};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Status an operator sets by hand, shown instead of the automated state while active
+/// (checks keep running and recording underneath)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManualStatus {
+    Degraded,
+    Maintenance,
+    MajorOutage,
+}
+
+impl ManualStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Degraded => "degraded",
+            Self::Maintenance => "maintenance",
+            Self::MajorOutage => "major_outage",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Degraded => "Degraded performance",
+            Self::Maintenance => "Under maintenance",
+            Self::MajorOutage => "Major outage",
+        }
+    }
+
+    /// Planned maintenance doesn't count towards the overall indicator
+    pub fn impact(&self) -> Option<Impact> {
+        match self {
+            Self::Degraded => Some(Impact::Minor),
+            Self::Maintenance => None,
+            Self::MajorOutage => Some(Impact::Critical),
+        }
+    }
+}
+
+impl FromStr for ManualStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "degraded" => Ok(Self::Degraded),
+            "maintenance" => Ok(Self::Maintenance),
+            "major_outage" => Ok(Self::MajorOutage),
+            other => Err(format!(
+                "unknown status '{other}', expected one of none, degraded, maintenance, major_outage"
+            )),
+        }
+    }
+}
+
+/// "none" clears the override
+pub fn parse_manual_status(s: &str) -> Result<Option<ManualStatus>, String> {
+    match s {
+        "none" => Ok(None),
+        other => other.parse().map(Some),
+    }
+}
+
+#[derive(Clone, Serialize, sqlx::FromRow)]
+pub struct StatusOverride {
+    pub alias: String,
+    pub status: String,
+    pub note: Option<String>,
+    /// `None` keeps the override until it's cleared by hand
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StatusOverride {
+    pub fn manual_status(&self) -> Option<ManualStatus> {
+        self.status.parse().ok()
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.manual_status()
+            .map_or("Manual status", |manual_status| manual_status.label())
+    }
+}
+
+/// The worst impact across all websites and whether it comes from a manual override,
+/// websites under an override don't contribute their automated incidents
+pub fn overall_status(
+    active_incidents: &[(String, String)],
+    overrides: &[StatusOverride],
+) -> (Option<Impact>, bool) {
+    let automated = impact::overall_impact(
+        active_incidents
+            .iter()
+            .filter(|(alias, _)| !overrides.iter().any(|o| &o.alias == alias))
+            .map(|(_, impact)| impact.as_str()),
+    );
+    let manual = overrides
+        .iter()
+        .filter_map(|o| o.manual_status()?.impact())
+        .max();
+
+    match manual {
+        Some(manual) if Some(manual) > automated => (Some(manual), true),
+        _ => (automated, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incident(alias: &str, impact: &str) -> (String, String) {
+        (alias.to_owned(), impact.to_owned())
+    }
+
+    fn status_override(alias: &str, status: &str) -> StatusOverride {
+        StatusOverride {
+            alias: alias.to_owned(),
+            status: status.to_owned(),
+            note: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn the_banner_follows_the_worst_incident() {
+        let incidents = [incident("blog", "minor"), incident("payments", "critical")];
+
+        assert_eq!(
+            overall_status(&incidents, &[]),
+            (Some(Impact::Critical), false)
+        );
+        assert_eq!(overall_status(&[], &[]), (None, false));
+    }
+
+    #[test]
+    fn a_worse_manual_status_takes_over_the_banner() {
+        let incidents = [incident("blog", "minor")];
+        let overrides = [status_override("api", "major_outage")];
+
+        assert_eq!(
+            overall_status(&incidents, &overrides),
+            (Some(Impact::Critical), true)
+        );
+    }
+
+    #[test]
+    fn a_milder_manual_status_leaves_the_banner_automated() {
+        let incidents = [incident("payments", "major")];
+        let overrides = [status_override("blog", "degraded")];
+
+        assert_eq!(
+            overall_status(&incidents, &overrides),
+            (Some(Impact::Major), false)
+        );
+    }
+
+    #[test]
+    fn an_overridden_website_hides_its_own_incident() {
+        let incidents = [incident("payments", "critical")];
+
+        assert_eq!(
+            overall_status(&incidents, &[status_override("payments", "degraded")]),
+            (Some(Impact::Minor), true)
+        );
+        assert_eq!(
+            overall_status(&incidents, &[status_override("payments", "maintenance")]),
+            (None, false)
+        );
+    }
+}
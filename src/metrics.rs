@@ -0,0 +1,115 @@
+use std::fmt::Write;
+
+/// Latest check and check counts of one website, everything `/metrics` reports on it
+#[derive(sqlx::FromRow)]
+pub struct WebsiteMetrics {
+    pub alias: String,
+    pub url: String,
+    /// `None` until the website was checked the first time
+    pub status: Option<i16>,
+    pub response_time_ms: Option<i32>,
+    pub checks: i64,
+    pub failures: i64,
+}
+
+/// The websites in the Prometheus text exposition format, every metric family written
+/// as a whole since scrapers reject families split up between websites
+pub fn prometheus_text(websites: &[WebsiteMetrics]) -> String {
+    let mut text = String::new();
+
+    family(
+        &mut text,
+        "uptime_ferris_up",
+        "gauge",
+        "Whether the latest check of the website succeeded",
+    );
+    for website in websites {
+        if let Some(status) = website.status {
+            let _ = writeln!(
+                text,
+                "uptime_ferris_up{{alias=\"{}\",url=\"{}\"}} {}",
+                escape_label(&website.alias),
+                escape_label(&website.url),
+                u8::from(status == 200)
+            );
+        }
+    }
+
+    family(
+        &mut text,
+        "uptime_ferris_last_status_code",
+        "gauge",
+        "Status code of the latest check of the website",
+    );
+    for website in websites {
+        if let Some(status) = website.status {
+            let _ = writeln!(
+                text,
+                "uptime_ferris_last_status_code{{alias=\"{}\"}} {status}",
+                escape_label(&website.alias)
+            );
+        }
+    }
+
+    family(
+        &mut text,
+        "uptime_ferris_response_time_seconds",
+        "gauge",
+        "Response time of the latest check of the website",
+    );
+    for website in websites {
+        if let Some(response_time_ms) = website.response_time_ms {
+            let _ = writeln!(
+                text,
+                "uptime_ferris_response_time_seconds{{alias=\"{}\"}} {}",
+                escape_label(&website.alias),
+                f64::from(response_time_ms) / 1000.0
+            );
+        }
+    }
+
+    family(
+        &mut text,
+        "uptime_ferris_checks_total",
+        "counter",
+        "Checks of the website",
+    );
+    for website in websites {
+        let _ = writeln!(
+            text,
+            "uptime_ferris_checks_total{{alias=\"{}\"}} {}",
+            escape_label(&website.alias),
+            website.checks
+        );
+    }
+
+    family(
+        &mut text,
+        "uptime_ferris_check_failures_total",
+        "counter",
+        "Failed checks of the website",
+    );
+    for website in websites {
+        let _ = writeln!(
+            text,
+            "uptime_ferris_check_failures_total{{alias=\"{}\"}} {}",
+            escape_label(&website.alias),
+            website.failures
+        );
+    }
+
+    text
+}
+
+fn family(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} {kind}");
+}
+
+/// Label values are quoted, so backslashes, quotes and line breaks in them are escaped
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+use sqlx::{
+    Acquire,
+    migrate::{Migrate, MigrateError, Migrator},
+};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+use utoipa::ToSchema;
+
+/// `--migrations-dir`, the migrations the server was started with
+#[derive(Clone)]
+pub struct MigrationsDir(pub Option<PathBuf>);
+
+#[derive(Serialize, ToSchema)]
+pub struct Migration {
+    pub version: i64,
+    pub description: String,
+}
+
+/// Which migrations a database has and which it still lacks
+#[derive(Serialize, ToSchema)]
+pub struct MigrationStatus {
+    /// "postgres", "sqlite" or "mysql"
+    pub backend: &'static str,
+    /// Oldest first, including any the binary doesn't know
+    pub applied: Vec<Migration>,
+    pub pending: Vec<Migration>,
+    /// Version of a migration that started and didn't finish, the database needs fixing
+    /// by hand before more are applied
+    pub dirty: Option<i64>,
+}
+
+/// Applies the migrations built into the binary, or those in `dir` when one is given.
+/// Failures name the migration they happened in
+pub async fn run<'a, A>(embedded: Migrator, dir: Option<&Path>, pool: A) -> Result<(), String>
+where
+    A: Acquire<'a>,
+    <A::Connection as Deref>::Target: Migrate,
+{
+    let migrator = load(embedded, dir).await?;
+    migrator.run(pool).await.map_err(|e| describe(&migrator, e))
+}
+
+impl Display for MigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Backend: {}", self.backend)?;
+        for (label, migrations) in [("Applied", &self.applied), ("Pending", &self.pending)] {
+            writeln!(f, "{label}: {}", migrations.len())?;
+            for migration in migrations {
+                writeln!(f, "  {} {}", migration.version, migration.description)?;
+            }
+        }
+        if let Some(version) = self.dirty {
+            writeln!(
+                f,
+                "Dirty: {version} started and didn't finish, fix it by hand"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+async fn load(embedded: Migrator, dir: Option<&Path>) -> Result<Migrator, String> {
+    match dir {
+        Some(dir) => Migrator::new(dir)
+            .await
+            .map_err(|e| format!("Migrations couldn't be loaded from {}: {e}", dir.display())),
+        None => Ok(embedded),
+    }
+}
+
+/// Compares the applied migrations with the built in ones, or those in `dir`. Creates
+/// the table sqlx keeps track in when the database has none yet
+pub async fn status<'a, A>(
+    embedded: Migrator,
+    dir: Option<&Path>,
+    backend: &'static str,
+    pool: A,
+) -> Result<MigrationStatus, String>
+where
+    A: Acquire<'a>,
+    <A::Connection as Deref>::Target: Migrate,
+{
+    let migrator = load(embedded, dir).await?;
+    let mut connection = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Connecting to the database failed: {e}"))?;
+    connection
+        .ensure_migrations_table()
+        .await
+        .map_err(|e| format!("Creating the migrations table failed: {e}"))?;
+    let dirty = connection
+        .dirty_version()
+        .await
+        .map_err(|e| format!("Reading the migration state failed: {e}"))?;
+    let applied_versions = connection
+        .list_applied_migrations()
+        .await
+        .map_err(|e| format!("Reading the applied migrations failed: {e}"))?;
+
+    let description = |version: i64| {
+        migrator
+            .iter()
+            .find(|migration| migration.version == version)
+            .map_or_else(
+                || "unknown to this binary".to_owned(),
+                |migration| migration.description.to_string(),
+            )
+    };
+    let applied: HashSet<i64> = applied_versions
+        .iter()
+        .map(|migration| migration.version)
+        .collect();
+    Ok(MigrationStatus {
+        backend,
+        applied: applied_versions
+            .iter()
+            .map(|migration| Migration {
+                version: migration.version,
+                description: description(migration.version),
+            })
+            .collect(),
+        pending: migrator
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| Migration {
+                version: migration.version,
+                description: migration.description.to_string(),
+            })
+            .collect(),
+        dirty,
+    })
+}
+
+fn describe(migrator: &Migrator, e: MigrateError) -> String {
+    let version = match &e {
+        MigrateError::ExecuteMigration(_, version)
+        | MigrateError::VersionMissing(version)
+        | MigrateError::VersionMismatch(version)
+        | MigrateError::Dirty(version) => *version,
+        _ => return format!("Migrations failed: {e}"),
+    };
+    match migrator
+        .iter()
+        .find(|migration| migration.version == version)
+    {
+        Some(migration) => format!(
+            "Migration {}_{} failed: {e}",
+            migration.version, migration.description
+        ),
+        None => format!("Migration {version} failed: {e}"),
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{
+    deserialize_checkbox, deserialize_connection_mode, deserialize_optional_number,
+    deserialize_optional_text, exec,
+};
+use chrono::TimeDelta;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Shape written by this build, older ones are migrated when read
+pub const CURRENT_VERSION: u32 = 2;
+
+/// A website's check options, stored as one versioned JSON document in Websites.config.
+/// Deserializes from the website form, `to_json` and `from_json` handle the stored shape
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, ToSchema, Validate)]
+pub struct MonitorConfig {
+    /// `None` follows the global `--fresh-connections` setting
+    #[serde(default, deserialize_with = "deserialize_connection_mode")]
+    #[schema(value_type = Option<String>, example = "fresh")]
+    pub fresh_connection: Option<bool>,
+    /// Checks follow this cron expression instead of the fixed interval when set
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[validate(length(max = 255))]
+    pub cron_schedule: Option<String>,
+    /// Successful checks slower than this are flagged as degraded
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    #[validate(range(min = 1))]
+    pub degraded_threshold_ms: Option<i32>,
+    /// Degraded checks count as down for the uptime percentage
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub strict: bool,
+    /// The check fails when the body doesn't contain this
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[validate(length(max = 1024))]
+    pub expected_keyword: Option<String>,
+    /// The check fails when the body contains this
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[validate(length(max = 1024))]
+    pub forbidden_keyword: Option<String>,
+    /// Records an event whenever the body changes between checks
+    #[serde(default, deserialize_with = "deserialize_checkbox")]
+    pub track_content: bool,
+    /// Reminds every this many minutes while the website stays down
+    #[serde(default, deserialize_with = "deserialize_optional_number")]
+    #[validate(range(min = 1))]
+    pub renotify_minutes: Option<i32>,
+    /// Arguments of exec monitors' program, the form has one per line
+    #[serde(default, deserialize_with = "deserialize_lines")]
+    #[schema(value_type = Option<String>)]
+    pub exec_args: Vec<String>,
+}
+
+/// Version 1, what the migration folded the former Websites columns into
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigV1 {
+    #[serde(rename = "version")]
+    _version: u32,
+    #[serde(default)]
+    fresh_connection: Option<bool>,
+    #[serde(default)]
+    cron_schedule: Option<String>,
+    #[serde(default)]
+    degraded_threshold_ms: Option<i32>,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    expected_keyword: Option<String>,
+    #[serde(default)]
+    forbidden_keyword: Option<String>,
+    #[serde(default)]
+    track_content: bool,
+    #[serde(default)]
+    renotify_minutes: Option<i32>,
+    /// One argument per line like the column was
+    #[serde(default)]
+    exec_args: Option<String>,
+}
+
+/// Version 2, exec arguments became a list
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigV2 {
+    version: u32,
+    #[serde(default)]
+    fresh_connection: Option<bool>,
+    #[serde(default)]
+    cron_schedule: Option<String>,
+    #[serde(default)]
+    degraded_threshold_ms: Option<i32>,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    expected_keyword: Option<String>,
+    #[serde(default)]
+    forbidden_keyword: Option<String>,
+    #[serde(default)]
+    track_content: bool,
+    #[serde(default)]
+    renotify_minutes: Option<i32>,
+    #[serde(default)]
+    exec_args: Vec<String>,
+}
+
+fn migrate_v1(v1: ConfigV1) -> ConfigV2 {
+    ConfigV2 {
+        version: 2,
+        fresh_connection: v1.fresh_connection,
+        cron_schedule: v1.cron_schedule,
+        degraded_threshold_ms: v1.degraded_threshold_ms,
+        strict: v1.strict,
+        expected_keyword: v1.expected_keyword,
+        forbidden_keyword: v1.forbidden_keyword,
+        track_content: v1.track_content,
+        renotify_minutes: v1.renotify_minutes,
+        exec_args: exec::arguments(v1.exec_args.as_deref().unwrap_or_default())
+            .map(str::to_owned)
+            .collect(),
+    }
+}
+
+impl From<ConfigV2> for MonitorConfig {
+    fn from(stored: ConfigV2) -> Self {
+        Self {
+            fresh_connection: stored.fresh_connection,
+            cron_schedule: stored.cron_schedule,
+            degraded_threshold_ms: stored.degraded_threshold_ms,
+            strict: stored.strict,
+            expected_keyword: stored.expected_keyword,
+            forbidden_keyword: stored.forbidden_keyword,
+            track_content: stored.track_content,
+            renotify_minutes: stored.renotify_minutes,
+            exec_args: stored.exec_args,
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// Always the current version
+    pub fn to_json(&self) -> String {
+        let stored = ConfigV2 {
+            version: CURRENT_VERSION,
+            fresh_connection: self.fresh_connection,
+            cron_schedule: self.cron_schedule.clone(),
+            degraded_threshold_ms: self.degraded_threshold_ms,
+            strict: self.strict,
+            expected_keyword: self.expected_keyword.clone(),
+            forbidden_keyword: self.forbidden_keyword.clone(),
+            track_content: self.track_content,
+            renotify_minutes: self.renotify_minutes,
+            exec_args: self.exec_args.clone(),
+        };
+        serde_json::to_string(&stored).expect("monitor configs always serialize")
+    }
+
+    /// Migrates older versions step by step. Unknown fields and versions newer than this
+    /// build are errors rather than being dropped, so nothing is lost by a later write
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+
+        let Versioned { version } = serde_json::from_str(json)
+            .map_err(|e| format!("monitor config without a version: {e}"))?;
+        let current = match version {
+            1 => migrate_v1(parse(json, version)?),
+            CURRENT_VERSION => parse(json, version)?,
+            newer if newer > CURRENT_VERSION => {
+                return Err(format!(
+                    "monitor config version {newer} is newer than the supported {CURRENT_VERSION}"
+                ));
+            }
+            unknown => return Err(format!("unknown monitor config version {unknown}")),
+        };
+
+        Ok(current.into())
+    }
+
+    /// Whether checks open a new connection, `default` is `--fresh-connections`
+    pub fn uses_fresh_connection(&self, default: bool) -> bool {
+        self.fresh_connection.unwrap_or(default)
+    }
+
+    pub fn has_keyword_assertions(&self) -> bool {
+        self.expected_keyword.is_some() || self.forbidden_keyword.is_some()
+    }
+
+    pub fn renotify_interval(&self) -> Option<TimeDelta> {
+        self.renotify_minutes
+            .map(|minutes| TimeDelta::minutes(minutes.into()))
+    }
+}
+
+impl TryFrom<String> for MonitorConfig {
+    type Error = String;
+
+    fn try_from(json: String) -> Result<Self, Self::Error> {
+        Self::from_json(&json)
+    }
+}
+
+fn parse<'a, T: Deserialize<'a>>(json: &'a str, version: u32) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| format!("invalid version {version} monitor config: {e}"))
+}
+
+/// The form's textarea, one exec argument per line
+fn deserialize_lines<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(exec::arguments(value.as_deref().unwrap_or_default())
+        .map(str::to_owned)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_option() -> MonitorConfig {
+        MonitorConfig {
+            fresh_connection: Some(true),
+            cron_schedule: Some("0 */5 * * * *".to_owned()),
+            degraded_threshold_ms: Some(800),
+            strict: true,
+            expected_keyword: Some("Welcome".to_owned()),
+            forbidden_keyword: Some("Error".to_owned()),
+            track_content: true,
+            renotify_minutes: Some(30),
+            exec_args: vec!["--host".to_owned(), "db.internal".to_owned()],
+        }
+    }
+
+    #[test]
+    fn every_option_survives_a_round_trip() {
+        let config = every_option();
+
+        assert_eq!(MonitorConfig::from_json(&config.to_json()), Ok(config));
+    }
+
+    #[test]
+    fn the_defaults_survive_a_round_trip() {
+        let config = MonitorConfig::default();
+
+        assert_eq!(MonitorConfig::from_json(&config.to_json()), Ok(config));
+    }
+
+    #[test]
+    fn writes_carry_the_current_version() {
+        let stored: serde_json::Value = serde_json::from_str(&every_option().to_json()).unwrap();
+
+        assert_eq!(stored["version"], CURRENT_VERSION);
+    }
+
+    #[test]
+    fn version_1_is_migrated_with_its_exec_arguments_split_into_a_list() {
+        let v1 = r#"{
+            "version": 1,
+            "fresh_connection": true,
+            "cron_schedule": "0 */5 * * * *",
+            "degraded_threshold_ms": 800,
+            "strict": true,
+            "expected_keyword": "Welcome",
+            "forbidden_keyword": "Error",
+            "track_content": true,
+            "renotify_minutes": 30,
+            "exec_args": "--host\r\n\ndb.internal\n"
+        }"#;
+
+        assert_eq!(MonitorConfig::from_json(v1), Ok(every_option()));
+    }
+
+    #[test]
+    fn version_1_without_any_option_is_migrated_to_the_defaults() {
+        assert_eq!(
+            MonitorConfig::from_json(r#"{"version": 1, "exec_args": null}"#),
+            Ok(MonitorConfig::default())
+        );
+    }
+
+    #[test]
+    fn a_migrated_config_is_written_as_the_current_version() {
+        let migrated = MonitorConfig::from_json(r#"{"version": 1, "exec_args": "a\nb"}"#).unwrap();
+
+        assert_eq!(
+            MonitorConfig::from_json(&migrated.to_json()),
+            Ok(migrated.clone())
+        );
+        assert_eq!(migrated.exec_args, ["a", "b"]);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let error = MonitorConfig::from_json(r#"{"version": 2, "retries": 3}"#).unwrap_err();
+
+        assert!(error.contains("retries"), "{error}");
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let error = MonitorConfig::from_json(r#"{"version": 3}"#).unwrap_err();
+
+        assert!(error.contains("newer"), "{error}");
+    }
+
+    #[test]
+    fn documents_without_a_version_are_rejected() {
+        assert!(MonitorConfig::from_json(r#"{"strict": true}"#).is_err());
+        assert!(MonitorConfig::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn version_0_is_unknown() {
+        let error = MonitorConfig::from_json(r#"{"version": 0}"#).unwrap_err();
+
+        assert!(error.contains("unknown"), "{error}");
+    }
+}
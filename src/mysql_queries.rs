@@ -0,0 +1,686 @@
+use crate::store::LatencyQuery;
+use sqlx::{MySqlConnection, MySqlPool};
+
+pub(crate) use crate::store::bind_positional as bind;
+
+crate::store::impl_store!(MySqlPool, MySqlConnection);
+
+pub(crate) const LATENCY_QUERY: LatencyQuery = LatencyQuery::Samples(SELECT_LATENCY_SAMPLES);
+
+pub const INSERT_INTO_WEBSITES_QUERY: &str = "INSERT INTO Websites
+    (url, alias, ip_version, impact_weight, config, monitor_type, client_cert_path,
+    client_key_path, client_cert_encrypted, client_key_encrypted, client_cert_fingerprint,
+    client_cert_expires_at, slack_webhook_url, discord_webhook_url, invert, alert_email_to,
+    retention_days, created_at)
+    VALUES (?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)";
+/// Webhooks left empty keep the current ones, the client identity is only replaced
+/// when a new certificate comes with the update. Without numbered placeholders the new
+/// fingerprint is bound again before each client identity column and the current alias
+/// goes last
+pub const UPDATE_WEBSITE_BY_ALIAS_QUERY: &str = "UPDATE Websites SET
+    url = ?, alias = ?, ip_version = ?, impact_weight = ?, config = ?, monitor_type = ?,
+    client_cert_path = CASE WHEN ? IS NULL THEN client_cert_path ELSE ? END,
+    client_key_path = CASE WHEN ? IS NULL THEN client_key_path ELSE ? END,
+    client_cert_encrypted = CASE WHEN ? IS NULL THEN client_cert_encrypted ELSE ? END,
+    client_key_encrypted = CASE WHEN ? IS NULL THEN client_key_encrypted ELSE ? END,
+    client_cert_fingerprint = COALESCE(?, client_cert_fingerprint),
+    client_cert_expires_at = CASE WHEN ? IS NULL THEN client_cert_expires_at ELSE ? END,
+    slack_webhook_url = COALESCE(?, slack_webhook_url),
+    discord_webhook_url = COALESCE(?, discord_webhook_url),
+    invert = ?, alert_email_to = ?, retention_days = ?
+    WHERE alias = ? AND deleted_at IS NULL";
+/// Everything else refers to the website by id, so its history stays with it. Binds the
+/// new alias first
+pub const RENAME_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET alias = ? WHERE alias = ? AND deleted_at IS NULL";
+pub const SELECT_WEBSITE_SUMMARIES_QUERY: &str = "
+            SELECT Websites.id, Websites.alias, Websites.url, Websites.created_at,
+            Latest.status as current_status, Latest.created_at as last_checked
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            WHERE Websites.deleted_at IS NULL
+            ORDER BY Websites.alias
+            LIMIT ? OFFSET ?
+            ";
+pub const SELECT_WEBSITE_SUMMARY_BY_ALIAS_QUERY: &str = "
+            SELECT Websites.id, Websites.alias, Websites.url, Websites.created_at,
+            Latest.status as current_status, Latest.created_at as last_checked
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            WHERE Websites.alias = ? AND Websites.deleted_at IS NULL
+            ";
+/// Start of the newest run of checks on the same side of up and down as the first bind,
+/// the newest check whose side differs ends the previous run. That check is found walking
+/// the website's checks back from the latest, the run's start is then a range on the
+/// same (website_id, created_at) index
+pub const SELECT_STATUS_SINCE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT MIN(Logs.created_at) FROM Websites
+            LEFT JOIN Logs AS Opposite ON Opposite.id =
+            (SELECT Previous.id FROM Logs AS Previous
+            WHERE Previous.website_id = Websites.id AND (Previous.status = 200) <> ?
+            ORDER BY Previous.created_at DESC LIMIT 1)
+            INNER JOIN Logs ON Logs.website_id = Websites.id
+            AND (Opposite.id IS NULL OR Logs.created_at > Opposite.created_at)
+            WHERE Websites.alias = ?
+            ";
+pub const SELECT_URL_ALIAS_WEBSITES_QUERY: &str =
+    "SELECT id, url, alias, invert FROM Websites WHERE deleted_at IS NULL ORDER BY id";
+pub const SELECT_WEBSITES_COUNT_QUERY: &str =
+    "SELECT COUNT(*) FROM Websites WHERE deleted_at IS NULL";
+/// Latest check of every website and how many checks it had and failed overall
+pub const SELECT_WEBSITE_METRICS_QUERY: &str = "
+            SELECT Websites.alias, Websites.url,
+            Latest.status, Latest.response_time_ms,
+            COALESCE(Counts.checks, 0) as checks, COALESCE(Counts.failures, 0) as failures
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            LEFT JOIN (SELECT website_id, COUNT(*) as checks,
+                COUNT(CASE WHEN status <> 200 THEN 1 END) as failures
+                FROM Logs GROUP BY website_id) AS Counts
+            ON Counts.website_id = Websites.id
+            WHERE Websites.deleted_at IS NULL
+            ORDER BY Websites.alias
+            ";
+pub const SELECT_MONITORED_WEBSITES_QUERY: &str = "SELECT id, url, alias, ip_version, config,
+    monitor_type, client_cert_path, client_key_path, client_cert_encrypted,
+    client_key_encrypted, slack_webhook_url, discord_webhook_url, invert, alert_email_to,
+    tls_cert_serial, tls_cert_expires_at, tls_expiry_notified_days FROM Websites
+    WHERE deleted_at IS NULL";
+/// What the export writes, webhooks and client certificates stay out of it
+pub const SELECT_EXPORTED_WEBSITES_QUERY: &str = "SELECT url, alias, monitor_type, ip_version,
+    impact_weight, config, invert, alert_email_to, retention_days FROM Websites
+    WHERE deleted_at IS NULL ORDER BY alias";
+/// What backups write, the webhooks and client certificate paths only with the secrets
+pub const SELECT_BACKUP_WEBSITES_QUERY: &str = "SELECT url, alias, monitor_type, ip_version,
+    impact_weight, config, invert, alert_email_to, retention_days, slack_webhook_url,
+    discord_webhook_url, client_cert_path, client_key_path FROM Websites
+    WHERE deleted_at IS NULL ORDER BY alias";
+pub const SELECT_WEBSITE_BY_ALIAS_QUERY: &str = "SELECT id, url, alias, monitor_type, ip_version,
+    impact_weight, config, client_cert_fingerprint, client_cert_expires_at, invert,
+    alert_email_to, retention_days FROM Websites
+    WHERE alias = ? AND deleted_at IS NULL LIMIT 1";
+pub const SELECT_LAST_CHECK_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT MAX(Logs.created_at) FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ?
+            ";
+pub const SELECT_INCIDENT_BY_ID_QUERY: &str = "
+            SELECT Logs.id, Logs.created_at as time,
+            Logs.status, Logs.ip_family,
+            COALESCE(Logs.impact, Websites.impact_weight) as impact,
+            Logs.error, Logs.reason_phrase, Logs.server_header, Logs.response_headers,
+            Logs.acknowledged_by, Logs.acknowledged_at,
+            Websites.alias, Websites.url from Logs
+            INNER JOIN Websites on Websites.id = Logs.website_id
+            where Logs.id = ? and Logs.status <> 200
+            ";
+pub const SELECT_INCIDENT_RESOLUTION_QUERY: &str = "
+            SELECT MIN(Later.created_at) FROM Logs AS Later
+            INNER JOIN Logs AS Incident ON Incident.website_id = Later.website_id
+            WHERE Incident.id = ? AND Later.status = 200
+            AND Later.created_at > Incident.created_at
+            ";
+pub const SELECT_INCIDENT_UPDATES_QUERY: &str = "
+            SELECT id, created_at as time, message FROM IncidentUpdates
+            WHERE log_id = ?
+            ORDER BY created_at DESC, id DESC
+            ";
+pub const INSERT_INCIDENT_UPDATE_QUERY: &str =
+    "INSERT INTO IncidentUpdates (log_id, message) VALUES (?, ?)";
+pub const SELECT_OPEN_INCIDENTS_QUERY: &str = "
+            SELECT Websites.alias, Logs.id, Logs.created_at as started_at, Logs.status,
+            Logs.acknowledged_by, Logs.acknowledged_at, Logs.ack_escalated_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Logs.id IN
+            (SELECT MIN(Failed.id) FROM Logs AS Failed
+            WHERE Failed.status <> 200
+            AND NOT EXISTS (SELECT 1 FROM Logs AS Up
+            WHERE Up.website_id = Failed.website_id AND Up.status = 200
+            AND Up.created_at > Failed.created_at)
+            GROUP BY Failed.website_id)
+            AND Websites.deleted_at IS NULL
+            ";
+pub const ACKNOWLEDGE_INCIDENT_QUERY: &str = "UPDATE Logs
+    SET acknowledged_by = ?, acknowledged_at = ?
+    WHERE id = ? AND status <> 200 AND acknowledged_at IS NULL";
+pub const MARK_ACK_ESCALATED_QUERY: &str = "UPDATE Logs SET ack_escalated_at = ? WHERE id = ?";
+pub const SELECT_ACKNOWLEDGED_INCIDENTS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.created_at, Logs.acknowledged_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ? AND Logs.acknowledged_at IS NOT NULL
+            AND Logs.created_at >= ?
+            ";
+pub const SELECT_ACTIVE_INCIDENT_IMPACTS_QUERY: &str = "
+            SELECT Websites.alias, COALESCE(Logs.impact, Websites.impact_weight) as impact
+            FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Logs.id IN (SELECT MAX(id) FROM Logs GROUP BY website_id)
+            AND Logs.status <> 200
+            AND Websites.deleted_at IS NULL
+            ";
+pub const SELECT_ACTIVE_STATUS_OVERRIDES_QUERY: &str = "
+            SELECT alias, status_override as status, status_override_note as note,
+            status_override_expires_at as expires_at
+            FROM Websites
+            WHERE status_override IS NOT NULL AND deleted_at IS NULL
+            AND (status_override_expires_at IS NULL OR status_override_expires_at > ?)
+            ";
+pub const UPDATE_STATUS_OVERRIDE_BY_ALIAS_QUERY: &str = "UPDATE Websites
+        SET status_override = ?, status_override_note = ?, status_override_expires_at = ?
+        WHERE alias = ?";
+pub const CLEAR_EXPIRED_STATUS_OVERRIDES_QUERY: &str = "UPDATE Websites
+        SET status_override = NULL, status_override_note = NULL, status_override_expires_at = NULL
+        WHERE status_override_expires_at <= ?";
+pub const UPDATE_CERTIFICATE_BY_ALIAS_QUERY: &str = "UPDATE Websites
+        SET tls_cert_serial = ?, tls_cert_expires_at = ?, tls_expiry_notified_days = ?
+        WHERE alias = ?";
+pub const UPDATE_INCIDENT_IMPACT_QUERY: &str = "UPDATE Logs SET impact = ?
+        WHERE id = ? AND status <> 200
+        AND website_id = (SELECT id FROM Websites WHERE alias = ?)";
+/// Binds the time first, then the alias
+pub const SOFT_DELETE_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET deleted_at = ? WHERE alias = ? AND deleted_at IS NULL";
+pub const RESTORE_DELETED_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET deleted_at = NULL WHERE alias = ? AND deleted_at >= ?";
+pub const SELECT_DELETED_WEBSITES_QUERY: &str = "SELECT alias, url, deleted_at FROM Websites
+    WHERE deleted_at >= ? ORDER BY deleted_at DESC";
+pub const SELECT_PURGEABLE_WEBSITES_QUERY: &str = "SELECT alias FROM Websites WHERE deleted_at < ?";
+pub const DELETE_WEBSITE_BY_ALIAS_QUERY: &str = "DELETE FROM Websites WHERE alias = ?";
+pub const SELECT_WEBSITE_ID_BY_ALIAS_QUERY: &str = "SELECT id FROM Websites WHERE alias = ?";
+/// Deleted websites included, deleting one of those again answers 404 as by alias
+pub const SELECT_WEBSITE_ALIAS_BY_ID_QUERY: &str = "SELECT alias FROM Websites WHERE id = ?";
+pub const SELECT_WEBSITE_IDS_QUERY: &str = "SELECT alias, id FROM Websites";
+pub const INSERT_INTO_LOGS_QUERY: &str = r#"INSERT INTO Logs
+                (website_id, status, ip_family, response_time_ms, connection_reused, degraded, error,
+                reason_phrase, server_header, response_headers, content_hash, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#;
+pub const SELECT_LAST_STATUS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.status, Logs.created_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ?
+            ORDER BY Logs.created_at DESC, Logs.id DESC
+            LIMIT 1";
+pub const SELECT_LAST_CONTENT_HASH_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.content_hash FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ? AND Logs.content_hash IS NOT NULL
+            ORDER BY Logs.created_at DESC, Logs.id DESC
+            LIMIT 1";
+pub const SELECT_LAST_FAILURE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.error FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ? AND Logs.status <> 200
+            ORDER BY Logs.created_at DESC
+            LIMIT 1
+            ";
+pub const SELECT_CONNECTION_REUSE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT COUNT(CASE WHEN Logs.connection_reused THEN 1 END) as reused,
+            COUNT(Logs.connection_reused) as total
+            FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = ?
+            ";
+pub const INSERT_EVENT_BY_ALIAS_QUERY: &str = r#"INSERT INTO Events (website_id, kind, message)
+                VALUES
+                ((SELECT id FROM Websites WHERE alias = ?), ?, ?)"#;
+pub const SELECT_EVENTS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Events.created_at as time, Events.kind, Events.message
+            FROM Events
+            INNER JOIN Websites ON Websites.id = Events.website_id
+            WHERE Websites.alias = ?
+            ORDER BY Events.created_at DESC
+            LIMIT 50
+            ";
+pub const SELECT_NOTIFICATION_COUNT_BY_URL_QUERY: &str =
+    "SELECT COUNT(*) FROM Notifications WHERE url = ?";
+pub const INSERT_NOTIFICATION_QUERY: &str = "INSERT INTO Notifications (url) VALUES (?)";
+pub const SELECT_NOTIFICATION_URLS_QUERY: &str = "SELECT url FROM Notifications";
+pub const SELECT_NOTIFICATIONS_QUERY: &str = "SELECT id, url FROM Notifications ORDER BY id";
+pub const SELECT_NOTIFICATION_URL_BY_ID_QUERY: &str = "SELECT url FROM Notifications WHERE id = ?";
+pub const DELETE_NOTIFICATION_BY_ID_QUERY: &str = "DELETE FROM Notifications WHERE id = ?";
+pub const SELECT_SHARED_FATE_HOSTS_QUERY: &str = "SELECT host FROM SharedFateGroups";
+pub const ENABLE_SHARED_FATE_QUERY: &str =
+    "INSERT INTO SharedFateGroups (host) VALUES (?) ON DUPLICATE KEY UPDATE host = host";
+pub const DISABLE_SHARED_FATE_QUERY: &str = "DELETE FROM SharedFateGroups WHERE host = ?";
+pub const SELECT_NOTIFICATION_STATES_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationStates.channel, NotificationStates.status,
+                NotificationStates.state_since, NotificationStates.notified_at
+            FROM NotificationStates
+            INNER JOIN Websites ON Websites.id = NotificationStates.website_id
+            WHERE Websites.alias = ?";
+pub const UPSERT_NOTIFICATION_STATE_QUERY: &str = r#"INSERT INTO NotificationStates
+                (website_id, channel, status, state_since, notified_at)
+                VALUES
+                ((SELECT id FROM Websites WHERE alias = ?), ?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                status = VALUES(status),
+                state_since = VALUES(state_since),
+                notified_at = VALUES(notified_at)"#;
+pub const INSERT_NOTIFICATION_LOG_QUERY: &str = r#"INSERT INTO NotificationLog
+                (website_id, channel, kind, delivered, error)
+                VALUES
+                ((SELECT id FROM Websites WHERE alias = ?), ?, ?, ?, ?)"#;
+pub const SELECT_NOTIFICATION_LOG_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationLog.created_at as time, NotificationLog.channel,
+                NotificationLog.kind, NotificationLog.delivered, NotificationLog.error
+            FROM NotificationLog
+            INNER JOIN Websites ON Websites.id = NotificationLog.website_id
+            WHERE Websites.alias = ?
+            ORDER BY NotificationLog.created_at DESC
+            LIMIT 50
+";
+pub const SELECT_NOTIFICATION_CHANNELS_QUERY: &str =
+    "SELECT id, name, kind FROM NotificationChannels ORDER BY name";
+pub const SELECT_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "SELECT kind, config FROM NotificationChannels WHERE id = ?";
+pub const SELECT_NOTIFICATION_CHANNEL_SETTINGS_QUERY: &str =
+    "SELECT name, kind, config FROM NotificationChannels ORDER BY name";
+pub const SELECT_NOTIFICATION_CHANNEL_ID_BY_NAME_QUERY: &str =
+    "SELECT id FROM NotificationChannels WHERE name = ?";
+/// Binds the id last
+pub const UPDATE_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "UPDATE NotificationChannels SET kind = ?, config = ? WHERE id = ?";
+pub const INSERT_NOTIFICATION_CHANNEL_QUERY: &str =
+    "INSERT INTO NotificationChannels (name, kind, config) VALUES (?, ?, ?)";
+pub const DELETE_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "DELETE FROM NotificationChannels WHERE id = ?";
+pub const DELETE_CHANNEL_ASSIGNMENTS_BY_CHANNEL_ID_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE channel_id = ?";
+pub const SELECT_CHANNELS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationChannels.id, NotificationChannels.name, NotificationChannels.kind
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id
+            WHERE Websites.alias = ?
+            ORDER BY NotificationChannels.name";
+/// Binds the channel id first
+pub const ASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY: &str = r#"INSERT INTO WebsiteNotificationChannels
+                (website_id, channel_id)
+                SELECT id, ? FROM Websites WHERE alias = ?
+                ON DUPLICATE KEY UPDATE
+                channel_id = WebsiteNotificationChannels.channel_id"#;
+pub const UNASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = ?) AND channel_id = ?";
+pub const DELETE_CHANNEL_ASSIGNMENTS_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = ?)";
+pub const SELECT_CHANNEL_ASSIGNMENT_NAMES_QUERY: &str = "
+            SELECT Websites.alias, NotificationChannels.name
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id
+            ORDER BY NotificationChannels.name";
+pub const SELECT_CHANNEL_ROUTES_QUERY: &str = "
+            SELECT Websites.alias, NotificationChannels.id, NotificationChannels.kind,
+                NotificationChannels.config
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id";
+pub const SELECT_ESCALATION_RULES_QUERY: &str = "
+            SELECT EscalationRules.id, Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, EscalationRules.channel_id,
+                NotificationChannels.kind AS channel_kind,
+                NotificationChannels.config AS channel_config
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            ORDER BY EscalationRules.after_minutes";
+pub const SELECT_BACKUP_ESCALATION_RULES_QUERY: &str = "
+            SELECT Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, NotificationChannels.name
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            ORDER BY EscalationRules.after_minutes";
+pub const SELECT_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT EscalationRules.id, Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, EscalationRules.channel_id,
+                NotificationChannels.kind AS channel_kind,
+                NotificationChannels.config AS channel_config
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            WHERE Websites.alias = ?
+            ORDER BY EscalationRules.after_minutes";
+/// Binds the alias last
+pub const INSERT_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY: &str = r#"INSERT INTO EscalationRules
+                (website_id, after_minutes, repeat_minutes, channel_id)
+                SELECT id, ?, ?, ? FROM Websites WHERE alias = ?"#;
+pub const DELETE_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM EscalationRules WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = ?) AND id = ?";
+pub const DELETE_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM EscalationRules WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = ?)";
+pub const DETACH_ESCALATION_RULES_BY_CHANNEL_ID_QUERY: &str =
+    "UPDATE EscalationRules SET channel_id = NULL WHERE channel_id = ?";
+pub const SELECT_FIRED_ESCALATION_STEPS_QUERY: &str =
+    "SELECT steps FROM IncidentEscalations WHERE log_id = ? AND rule_id = ?";
+pub const UPSERT_FIRED_ESCALATION_STEPS_QUERY: &str = r#"INSERT INTO IncidentEscalations
+                (log_id, rule_id, steps, fired_at)
+                VALUES (?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                steps = VALUES(steps),
+                fired_at = VALUES(fired_at)"#;
+pub const DELETE_INCIDENT_ESCALATIONS_BY_RULE_QUERY: &str =
+    "DELETE FROM IncidentEscalations WHERE rule_id IN
+        (SELECT EscalationRules.id
+        FROM EscalationRules
+        INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+        WHERE Websites.alias = ? AND EscalationRules.id = ?)";
+pub const SELECT_LAST_DIGEST_QUERY: &str = "SELECT MAX(period_end) FROM Digests";
+pub const INSERT_DIGEST_QUERY: &str = r#"INSERT INTO Digests (period_start, period_end)
+                VALUES (?, ?)
+                ON DUPLICATE KEY UPDATE period_end = period_end"#;
+/// Readiness probe, answered without touching any table
+pub const SELECT_ONE_QUERY: &str = "SELECT 1";
+/// Read on every poll of the dashboard and status APIs, only the primary keys are looked at
+pub const SELECT_CONTENT_VERSION_QUERY: &str = r#"SELECT
+                CAST(COALESCE((SELECT MAX(id) FROM Logs), 0) AS SIGNED) AS last_log_id,
+                (SELECT COUNT(*) FROM Websites WHERE deleted_at IS NULL) AS websites,
+                CAST(COALESCE((SELECT MAX(id) FROM Websites), 0) AS SIGNED) AS last_website_id"#;
+/// Binds the website's id and the start of the range, then both again for the raw checks
+pub const SELECT_STATS_BY_DAY: &str = r#"
+                SELECT CAST(DATE_FORMAT(Buckets.bucket_start, '%Y-%m-%d 00:00:00') AS DATETIME) as time,
+                CAST(SUM(Buckets.successes - CASE WHEN Websites.strict
+                    THEN Buckets.degraded_successes ELSE 0 END) * 100 DIV SUM(Buckets.checks) AS SIGNED) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 DIV SUM(Buckets.checks) AS SIGNED) as degraded_pct,
+                CAST(SUM(Buckets.checks) AS SIGNED) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = ?
+                    AND bucket_start >= ?
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    CASE WHEN status = 200 THEN 1 ELSE 0 END,
+                    CASE WHEN degraded THEN 1 ELSE 0 END,
+                    CASE WHEN status = 200 AND degraded THEN 1 ELSE 0 END
+                    FROM Logs
+                    WHERE website_id = ?
+                    AND created_at >= ?
+                    AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + INTERVAL 1 HOUR
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '1000-01-01')
+                ) AS Buckets
+                INNER JOIN Websites ON Websites.id = Buckets.website_id
+                GROUP BY time
+                ORDER BY time ASC
+                "#;
+/// Binds the website's id and the start of the range, then both again for the raw checks
+pub const SELECT_STATS_BY_HOUR: &str = r#"
+                SELECT CAST(DATE_FORMAT(Buckets.bucket_start, '%Y-%m-%d %H:00:00') AS DATETIME) as time,
+                CAST(SUM(Buckets.successes - CASE WHEN Websites.strict
+                    THEN Buckets.degraded_successes ELSE 0 END) * 100 DIV SUM(Buckets.checks) AS SIGNED) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 DIV SUM(Buckets.checks) AS SIGNED) as degraded_pct,
+                CAST(SUM(Buckets.checks) AS SIGNED) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = ?
+                    AND bucket_start >= ?
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    CASE WHEN status = 200 THEN 1 ELSE 0 END,
+                    CASE WHEN degraded THEN 1 ELSE 0 END,
+                    CASE WHEN status = 200 AND degraded THEN 1 ELSE 0 END
+                    FROM Logs
+                    WHERE website_id = ?
+                    AND created_at >= ?
+                    AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + INTERVAL 1 HOUR
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '1000-01-01')
+                ) AS Buckets
+                INNER JOIN Websites ON Websites.id = Buckets.website_id
+                GROUP BY time
+                ORDER BY time ASC
+                "#;
+/// MySQL has no percentile function either, the response times are bucketed in Rust
+pub const SELECT_LATENCY_SAMPLES: &str = r#"
+                SELECT Logs.created_at, Logs.response_time_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ? AND Logs.created_at >= ?
+                AND Logs.response_time_ms IS NOT NULL
+                ORDER BY Logs.created_at ASC
+                "#;
+pub const SELECT_HOURLY_LATENCY_SERIES: &str = r#"
+                SELECT CAST(AVG(Logs.response_time_ms) AS DOUBLE) as latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.response_time_ms IS NOT NULL
+                AND Logs.created_at >= UTC_TIMESTAMP() - INTERVAL 169 HOUR
+                GROUP BY DATE_FORMAT(Logs.created_at, '%Y-%m-%d %H')
+                ORDER BY DATE_FORMAT(Logs.created_at, '%Y-%m-%d %H') ASC
+                "#;
+/// WEEKDAY counts 0 = Monday to 6 = Sunday already
+pub const SELECT_UPTIME_HEATMAP: &str = r#"
+                SELECT WEEKDAY(Logs.created_at) as weekday,
+                HOUR(Logs.created_at) as hour,
+                CAST(COUNT(CASE WHEN status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100 DIV COUNT(*) AS SIGNED) as uptime_pct,
+                COUNT(*) as samples
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.created_at >= UTC_TIMESTAMP() - INTERVAL ? WEEK
+                GROUP BY weekday, hour
+                "#;
+/// Uptime like the daily and monthly stats, over the window from the second bind until
+/// before the third
+pub const SELECT_WINDOW_STATS: &str = r#"
+                SELECT CAST(COUNT(CASE WHEN status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0 / NULLIF(COUNT(*), 0) AS DOUBLE) as uptime_pct,
+                CAST(AVG(Logs.response_time_ms) AS DOUBLE) as average_latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.created_at >= ? AND Logs.created_at < ?
+                "#;
+pub const SELECT_STATUS_CODE_COUNTS: &str = r#"
+                SELECT Logs.status, COUNT(*) as count
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.created_at >= ? AND Logs.status IS NOT NULL
+                GROUP BY Logs.status
+                ORDER BY count DESC, Logs.status
+                "#;
+pub const SELECT_WINDOW_STATUSES: &str = r#"
+                SELECT Logs.created_at, Logs.status
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.created_at >= ? AND Logs.created_at < ?
+                ORDER BY Logs.created_at ASC
+                "#;
+pub const SELECT_WINDOW_LOGS: &str = r#"
+                SELECT Logs.created_at, Logs.status, Logs.response_time_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ?
+                AND Logs.created_at >= ? AND Logs.created_at < ?
+                ORDER BY Logs.created_at ASC
+                "#;
+/// Latest check, uptime since the later and the earlier start and newest failed check
+/// since the earlier start of every website. Binds the later start twice, then the
+/// earlier one
+pub const SELECT_STATUS_SUMMARIES: &str = r#"
+                SELECT Websites.alias, Latest.status as latest_status,
+                CAST(COUNT(CASE WHEN Logs.created_at >= ? AND Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0
+                    / NULLIF(COUNT(CASE WHEN Logs.created_at >= ? THEN 1 END), 0)
+                    AS DOUBLE) as uptime_24h,
+                CAST(COUNT(CASE WHEN Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0 / NULLIF(COUNT(Logs.id), 0) AS DOUBLE) as uptime_30d,
+                MAX(CASE WHEN Logs.status <> 200 THEN Logs.created_at END) as last_incident
+                FROM Websites
+                LEFT JOIN Logs ON Logs.website_id = Websites.id AND Logs.created_at >= ?
+                LEFT JOIN Logs AS Latest ON Latest.id =
+                (SELECT Newest.id FROM Logs AS Newest WHERE Newest.website_id = Websites.id
+                ORDER BY Newest.created_at DESC LIMIT 1)
+                WHERE Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias, Websites.strict, Latest.status
+                ORDER BY Websites.alias
+                "#;
+/// Uptime, nearest rank p95 latency and outages started per calendar month from the
+/// second bind until before the third, outages counted by their first failed check
+pub const SELECT_MONTH_TRENDS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.created_at, Logs.status, Logs.response_time_ms,
+                    DATE_FORMAT(Logs.created_at, '%Y-%m') as month,
+                    Websites.strict AND COALESCE(Logs.degraded, FALSE) as strict_degraded,
+                    LAG(Logs.status) OVER (ORDER BY Logs.created_at) as previous_status
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE Websites.alias = ?
+                    AND Logs.created_at >= ? AND Logs.created_at < ?
+                ),
+                Latencies AS (
+                    SELECT month, response_time_ms,
+                    ROW_NUMBER() OVER (PARTITION BY month ORDER BY response_time_ms) as position,
+                    COUNT(*) OVER (PARTITION BY month) as samples
+                    FROM Checks
+                    WHERE response_time_ms IS NOT NULL
+                )
+                SELECT Checks.month,
+                CAST(COUNT(CASE WHEN status = 200 AND NOT strict_degraded
+                    THEN 1 END) * 100.0 / COUNT(*) AS DOUBLE) as uptime_pct,
+                (SELECT CAST(MIN(Latencies.response_time_ms) AS DOUBLE) FROM Latencies
+                    WHERE Latencies.month = Checks.month
+                    AND Latencies.position >= 0.95 * Latencies.samples) as latency_p95_ms,
+                COUNT(CASE WHEN status <> 200
+                    AND (previous_status IS NULL OR previous_status = 200)
+                    THEN 1 END) as incidents,
+                MIN(created_at) as first_check_at
+                FROM Checks
+                GROUP BY Checks.month
+                ORDER BY Checks.month ASC
+                "#;
+/// Binds the cutoff twice
+pub const DELETE_INCIDENT_UPDATES_BEFORE: &str = r#"
+                DELETE FROM IncidentUpdates WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ? AND (? IS NULL OR Logs.created_at < ?))
+                "#;
+/// Binds the cutoff twice
+pub const DELETE_INCIDENT_ESCALATIONS_BEFORE: &str = r#"
+                DELETE FROM IncidentEscalations WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ? AND (? IS NULL OR Logs.created_at < ?))
+                "#;
+/// MySQL can't delete from a table its subquery reads, so this joins instead. Binds the
+/// cutoff twice
+pub const DELETE_LOGS_BEFORE: &str = r#"
+                DELETE Logs FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = ? AND (? IS NULL OR Logs.created_at < ?)
+                "#;
+/// Folds every website's checks from the hours before the bound time, which is on the
+/// hour, that aren't folded yet into StatsRollup
+pub const FOLD_STATS_ROLLUP: &str = r#"
+                INSERT INTO StatsRollup
+                (website_id, bucket_start, checks, successes, degraded, degraded_successes, avg_latency)
+                SELECT website_id, CAST(DATE_FORMAT(created_at, '%Y-%m-%d %H:00:00') AS DATETIME) as bucket,
+                COUNT(*),
+                COUNT(CASE WHEN status = 200 THEN 1 END),
+                COUNT(CASE WHEN degraded THEN 1 END),
+                COUNT(CASE WHEN status = 200 AND degraded THEN 1 END),
+                AVG(response_time_ms)
+                FROM Logs
+                WHERE created_at < ?
+                AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + INTERVAL 1 HOUR
+                    FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '1000-01-01')
+                GROUP BY website_id, bucket
+                "#;
+/// Binds the cutoff twice like DELETE_LOGS_BEFORE
+pub const DELETE_STATS_ROLLUP_BEFORE: &str = r#"
+                DELETE StatsRollup FROM StatsRollup
+                INNER JOIN Websites ON Websites.id = StatsRollup.website_id
+                WHERE Websites.alias = ? AND (? IS NULL OR StatsRollup.bucket_start < ?)
+                "#;
+/// Every filter is bound twice, the limit once
+pub const SELECT_FILTERED_INCIDENTS: &str = r#"
+                SELECT Logs.id, Logs.created_at as time,
+                Logs.status, Logs.ip_family,
+                COALESCE(Logs.impact, Websites.impact_weight) as impact,
+                Logs.error, Logs.reason_phrase, Logs.server_header, Logs.response_headers,
+                Logs.acknowledged_by FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Logs.website_id = ? AND Logs.status <> 200
+                AND (? IS NULL OR Logs.created_at >= ?)
+                AND (? IS NULL OR Logs.created_at < ?)
+                AND (? IS NULL OR Logs.status = ?)
+                ORDER BY Logs.created_at DESC, Logs.id DESC
+                LIMIT ?
+                "#;
+/// Checks, good checks, outages started and seconds down of every website or just one
+/// from the start until before the end. A failed check counts as down until the next
+/// check, the last one until the end. Binds the alias twice, the start, the end twice
+/// and the alias twice again
+pub const SELECT_SLA_COUNTS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.website_id, Logs.created_at, Logs.status,
+                    Websites.strict AND COALESCE(Logs.degraded, FALSE) as strict_degraded,
+                    LAG(Logs.status) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as previous_status,
+                    LEAD(Logs.created_at) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as next_check_at
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE (? IS NULL OR Websites.alias = ?)
+                    AND Logs.created_at >= ? AND Logs.created_at < ?
+                )
+                SELECT Websites.alias,
+                COUNT(Checks.created_at) as total_checks,
+                COUNT(CASE WHEN Checks.status = 200 AND NOT Checks.strict_degraded
+                    THEN 1 END) as successful_checks,
+                COUNT(CASE WHEN Checks.status <> 200
+                    AND (Checks.previous_status IS NULL OR Checks.previous_status = 200)
+                    THEN 1 END) as incidents,
+                CAST(COALESCE(SUM(CASE WHEN Checks.status <> 200
+                    THEN TIMESTAMPDIFF(SECOND, Checks.created_at,
+                        COALESCE(Checks.next_check_at, ?)) END), 0) AS SIGNED)
+                    as downtime_secs
+                FROM Websites
+                LEFT JOIN Checks ON Checks.website_id = Websites.id
+                WHERE (? IS NULL OR Websites.alias = ?) AND Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias
+                ORDER BY Websites.alias
+                "#;
+/// Up to the last bind of the checks before the first, oldest first, of the websites
+/// whose retention_days is the second and third bind, NULL for the ones going by
+/// --retention-days. Failed checks no successful one followed belong to an incident that
+/// is still open and are kept. The
+/// batch is a derived table, which MySQL materializes for the LIMIT, as it can't delete
+/// from a table its subquery reads
+pub const DELETE_EXPIRED_LOGS_BATCH: &str = r#"
+                DELETE Logs FROM Logs
+                INNER JOIN (SELECT Old.id FROM Logs AS Old
+                WHERE Old.created_at < ?
+                AND (Old.status = 200 OR EXISTS (SELECT 1 FROM Logs AS Up
+                    WHERE Up.website_id = Old.website_id AND Up.status = 200
+                    AND Up.created_at > Old.created_at))
+                AND Old.website_id IN (SELECT id FROM Websites
+                    WHERE retention_days = ? OR (? IS NULL AND retention_days IS NULL))
+                ORDER BY Old.id
+                LIMIT ?) AS Batch ON Batch.id = Logs.id
+                "#;
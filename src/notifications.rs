@@ -0,0 +1,1014 @@
+use crate::{
+    AppState, Website,
+    acknowledgment::{OpenIncident, duration_text},
+    digest::DigestStats,
+    on_db, shared_fate,
+};
+use alertmanager::{AlertmanagerBatch, AlertmanagerNotifier};
+use chrono::{DateTime, TimeDelta, Utc};
+use discord::{DiscordBatch, DiscordNotifier};
+use email::EmailNotifier;
+use gotify::GotifyNotifier;
+use lettre::message::Mailbox;
+use ntfy::NtfyNotifier;
+use routing::{ChannelConfig, Route};
+use serde::Serialize;
+use slack::SlackNotifier;
+use std::collections::{HashMap, HashSet};
+use telegram::TelegramNotifier;
+use template::MessageTemplate;
+use throttle::{Decision, NotificationThrottle, NotifiedState};
+use tokio::{
+    sync::mpsc,
+    time::{self, Duration},
+};
+use tracing::{info, warn};
+use webhook::WebhookNotifier;
+
+pub mod alertmanager;
+pub mod discord;
+pub mod email;
+pub mod gotify;
+pub mod ntfy;
+pub mod routing;
+pub mod slack;
+pub mod telegram;
+pub mod template;
+pub mod throttle;
+pub mod webhook;
+
+/// Deliveries are attempted this often before a notification is given up on
+const DELIVERY_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry, doubled for every further one
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How long a channel's server gets to accept a message, per SMTP command for mails
+const NOTIFIER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where transitions are delivered to besides the webhooks in the Notifications table
+#[derive(Clone)]
+pub struct NotificationSettings {
+    pub client: reqwest::Client,
+    /// Accepts any certificate, for channels whose server has a self-signed one
+    pub insecure_client: reqwest::Client,
+    /// Slack incoming webhook used for websites without their own
+    pub slack_webhook_url: Option<String>,
+    /// Discord webhook used for websites without their own
+    pub discord_webhook_url: Option<String>,
+    pub telegram: Option<telegram::TelegramChat>,
+    pub mailer: Option<email::Mailer>,
+    /// How long after a shared fate host's first failure other monitors still join
+    /// its umbrella incident
+    pub shared_fate_window: TimeDelta,
+    /// Least time between two notifications about a website on the same channel
+    pub cooldown: TimeDelta,
+    /// How long incidents may go unacknowledged before they are escalated
+    pub ack_sla: Option<TimeDelta>,
+    pub deliveries: DeliveryLog,
+    /// Base URL of the dashboard for links in notifications
+    pub public_url: Option<String>,
+}
+
+/// A website going down or coming back up
+#[derive(Clone, Debug, Serialize)]
+pub struct Transition {
+    pub alias: String,
+    pub url: String,
+    pub old_status: i16,
+    pub new_status: i16,
+    pub timestamp: DateTime<Utc>,
+    /// When the website entered the state it just left
+    pub since: DateTime<Utc>,
+    /// Seconds the website spent in the state it just left
+    pub duration: i64,
+    /// The website is expected to be offline, so going down means it became reachable
+    pub inverted: bool,
+    /// Set when the transition stands for all monitors of a shared fate host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared_host: Option<String>,
+    /// Monitors the shared fate host took down with it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub affected: Vec<String>,
+    /// Nothing changed, the website is still down and asked to be reminded of that
+    pub reminder: bool,
+    /// Set when the notification isn't about the status changing
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<Warning>,
+    /// What the channel's message template made of it, replaces the headline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Notifications about a website whose status didn't change
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "warning", rename_all = "snake_case")]
+pub enum Warning {
+    /// The website's TLS certificate runs out soon
+    CertificateExpiry {
+        certificate_expires_at: DateTime<Utc>,
+    },
+    /// Nobody acknowledged the incident, which started at `since`, within the SLA
+    Unacknowledged { incident_id: i32 },
+    /// The incident is open for long enough to hit one of the website's escalation rules,
+    /// `step` counts its repeats from 1
+    Escalated { incident_id: i32, step: i32 },
+    /// The website's numbers over the period from `since` until `timestamp`
+    Digest(DigestStats),
+    /// Sent on request to verify a channel, nothing happened to any website
+    Test,
+}
+
+impl Transition {
+    /// Good news, which digests count as too
+    pub fn is_recovery(&self) -> bool {
+        is_up(self.new_status) && matches!(self.warning, None | Some(Warning::Digest(_)))
+    }
+
+    /// What the NotificationLog calls it
+    pub fn kind(&self) -> &'static str {
+        match (&self.warning, self.reminder, self.is_recovery()) {
+            (Some(Warning::CertificateExpiry { .. }), ..) => "cert_expiry",
+            (Some(Warning::Unacknowledged { .. }), ..) => "ack_overdue",
+            (Some(Warning::Escalated { .. }), ..) => "escalation",
+            (Some(Warning::Digest(_)), ..) => "digest",
+            (Some(Warning::Test), ..) => "test",
+            (None, true, _) => "reminder",
+            (None, false, true) => "recovery",
+            (None, false, false) => "down",
+        }
+    }
+
+    pub fn certificate_expires_at(&self) -> Option<DateTime<Utc>> {
+        match self.warning {
+            Some(Warning::CertificateExpiry {
+                certificate_expires_at,
+            }) => Some(certificate_expires_at),
+            _ => None,
+        }
+    }
+
+    /// The alias, or the host for transitions of shared fate hosts
+    pub fn subject(&self) -> &str {
+        self.shared_host.as_deref().unwrap_or(&self.alias)
+    }
+
+    /// Headline of notifications, worded for what up and down mean for the website
+    pub fn headline(&self) -> String {
+        // Neither templates nor anything else can drop the marker of tests
+        if let Some(Warning::Test) = self.warning {
+            let transition = Transition {
+                warning: None,
+                ..self.clone()
+            };
+            return format!("TEST — this is only a test: {}", transition.headline());
+        }
+
+        // Templates word what happened to the website, digests keep their numbers
+        if let Some(Warning::Digest(stats)) = &self.warning {
+            return self.digest_headline(stats);
+        }
+
+        let escalated = matches!(self.warning, Some(Warning::Escalated { .. }));
+        if let Some(message) = &self.message {
+            // Templates can't drop the marker, it's what sets escalations apart
+            return if escalated {
+                format!("ESCALATED: {message}")
+            } else {
+                message.clone()
+            };
+        }
+
+        if escalated {
+            return format!(
+                "ESCALATED: {} is still down after {}",
+                self.alias,
+                self.duration_text()
+            );
+        }
+
+        if let Some(Warning::Unacknowledged { .. }) = self.warning {
+            return format!(
+                "{} is down and nobody acknowledged it for {}",
+                self.alias,
+                self.duration_text()
+            );
+        }
+
+        if let Some(expires_at) = self.certificate_expires_at() {
+            let host = shared_fate::host_of(&self.url);
+            let expiry = expires_at.format("%Y-%m-%d %H:%M:%S UTC");
+            let days = (expires_at - self.timestamp).num_days();
+            return if expires_at <= self.timestamp {
+                format!(
+                    "TLS certificate of {} ({host}) expired on {expiry}",
+                    self.alias
+                )
+            } else {
+                format!(
+                    "TLS certificate of {} ({host}) expires in {days} days, on {expiry}",
+                    self.alias
+                )
+            };
+        }
+
+        if let Some(shared_host) = &self.shared_host {
+            let affected = self.affected.join(", ");
+            return if self.is_recovery() {
+                format!(
+                    "{shared_host} recovered after {}, all {} monitors are back: {affected}",
+                    self.duration_text(),
+                    self.affected.len()
+                )
+            } else {
+                format!(
+                    "{shared_host} is down for {} monitors: {affected}",
+                    self.affected.len()
+                )
+            };
+        }
+
+        if self.reminder {
+            return if self.inverted {
+                format!(
+                    "{} is still reachable after {}",
+                    self.alias,
+                    self.duration_text()
+                )
+            } else {
+                format!(
+                    "{} is still down after {}",
+                    self.alias,
+                    self.duration_text()
+                )
+            };
+        }
+
+        match (self.inverted, self.is_recovery()) {
+            (false, false) => format!("{} is down", self.alias),
+            (false, true) => format!("{} recovered after {}", self.alias, self.duration_text()),
+            (true, false) => format!("{} is reachable although it should be offline", self.alias),
+            (true, true) => format!(
+                "{} is offline again after being reachable for {}",
+                self.alias,
+                self.duration_text()
+            ),
+        }
+    }
+
+    fn digest_headline(&self, stats: &DigestStats) -> String {
+        let period = match self.duration {
+            86400 => "day".to_owned(),
+            604800 => "week".to_owned(),
+            _ => self.duration_text(),
+        };
+        let Some(uptime_pct) = stats.uptime_pct else {
+            return format!("{} wasn't checked during the last {period}", self.alias);
+        };
+        let mut headline = format!(
+            "{} over the last {period}: {uptime_pct:.2}% up, {} incident{}",
+            self.alias,
+            stats.incidents,
+            if stats.incidents == 1 { "" } else { "s" }
+        );
+        if stats.incidents > 0 {
+            headline.push_str(&format!(
+                ", longest outage {}",
+                duration_text(stats.longest_outage_secs)
+            ));
+        }
+        if let Some(average_latency_ms) = stats.average_latency_ms {
+            headline.push_str(&format!(", {average_latency_ms:.0} ms on average"));
+        }
+
+        headline
+    }
+
+    /// How long the website was in the state it just left, rounded to minutes once
+    /// it's longer than one
+    pub fn duration_text(&self) -> String {
+        match self.duration {
+            ..60 => format!("{}s", self.duration.max(0)),
+            60..3600 => format!("{}m", self.duration / 60),
+            _ => format!("{}h {}m", self.duration / 3600, self.duration % 3600 / 60),
+        }
+    }
+}
+
+fn is_up(status: i16) -> bool {
+    status == 200
+}
+
+/// What a check means for a website's channels
+pub enum Notice {
+    Transition(Transition),
+    /// Notified for a whole shared fate host, goes out regardless of throttling
+    Umbrella(Transition),
+    /// The website's change is part of a shared fate incident and notified with it
+    Covered,
+    Nothing,
+}
+
+/// Somewhere a website's notifications go, each with its own throttling
+pub enum Channel {
+    Webhook(String),
+    Slack(String),
+    Discord(String),
+    Telegram(telegram::TelegramChat),
+    Email(email::Mailer, Mailbox),
+    /// The flag skips verifying the server's certificate
+    Ntfy(ntfy::NtfyTopic, bool),
+    Gotify(gotify::GotifyApp, bool),
+    Alertmanager(String),
+}
+
+/// What's collected during a tick to be sent together
+#[derive(Default)]
+struct Batches {
+    discord: DiscordBatch,
+    alertmanager: AlertmanagerBatch,
+}
+
+impl Batches {
+    fn dispatch(&mut self, notification_settings: &NotificationSettings) {
+        self.discord.dispatch(notification_settings);
+        self.alertmanager.dispatch(notification_settings);
+    }
+}
+
+impl NotificationSettings {
+    /// The website's assigned channels under the name their throttling state and
+    /// deliveries are recorded under, the global defaults when it has none
+    fn channels(
+        &self,
+        website: &Website,
+        webhooks: &[(i32, String)],
+        routes: &HashMap<String, Vec<Route>>,
+    ) -> Vec<(String, Channel, Option<MessageTemplate>)> {
+        match routes.get(&website.alias) {
+            Some(assigned) if !assigned.is_empty() => assigned
+                .iter()
+                .filter_map(|(id, config, template)| {
+                    self.routed_channel(config)
+                        .map(|channel| (format!("channel:{id}"), channel, template.clone()))
+                })
+                .collect(),
+            _ => self
+                .default_channels(website, webhooks)
+                .into_iter()
+                .map(|(key, channel)| (key, channel, None))
+                .collect(),
+        }
+    }
+
+    fn routed_channel(&self, config: &ChannelConfig) -> Option<Channel> {
+        match config {
+            ChannelConfig::Webhook { url } => Some(Channel::Webhook(url.clone())),
+            ChannelConfig::Slack { webhook_url } => Some(Channel::Slack(webhook_url.clone())),
+            ChannelConfig::Discord { webhook_url } => Some(Channel::Discord(webhook_url.clone())),
+            ChannelConfig::Telegram { bot_token, chat_id } => {
+                Some(Channel::Telegram(telegram::TelegramChat {
+                    bot_token: bot_token.clone(),
+                    chat_id: chat_id.clone(),
+                }))
+            }
+            ChannelConfig::Email { to } => {
+                let Some(mailer) = &self.mailer else {
+                    warn!("Email channel to {to} is assigned, but no --smtp-host is configured");
+                    return None;
+                };
+                Some(Channel::Email(mailer.clone(), to.parse().ok()?))
+            }
+            ChannelConfig::Ntfy {
+                server_url,
+                topic,
+                access_token,
+                insecure_tls,
+            } => Some(Channel::Ntfy(
+                ntfy::NtfyTopic {
+                    server_url: server_url.clone(),
+                    topic: topic.clone(),
+                    access_token: access_token.clone(),
+                },
+                *insecure_tls,
+            )),
+            ChannelConfig::Gotify {
+                server_url,
+                app_token,
+                insecure_tls,
+            } => Some(Channel::Gotify(
+                gotify::GotifyApp {
+                    server_url: server_url.clone(),
+                    app_token: app_token.clone(),
+                },
+                *insecure_tls,
+            )),
+            ChannelConfig::Alertmanager { url } => Some(Channel::Alertmanager(url.clone())),
+        }
+    }
+
+    /// Channels configured globally or for the website, `webhooks` are the id and url
+    /// of every row in the Notifications table
+    fn default_channels(
+        &self,
+        website: &Website,
+        webhooks: &[(i32, String)],
+    ) -> Vec<(String, Channel)> {
+        let mut channels: Vec<(String, Channel)> = webhooks
+            .iter()
+            .map(|(id, url)| (format!("webhook:{id}"), Channel::Webhook(url.clone())))
+            .collect();
+        if let Some(slack_webhook_url) = website
+            .slack_webhook_url
+            .as_ref()
+            .or(self.slack_webhook_url.as_ref())
+        {
+            channels.push((
+                "slack".to_owned(),
+                Channel::Slack(slack_webhook_url.clone()),
+            ));
+        }
+        if let Some(discord_webhook_url) = website
+            .discord_webhook_url
+            .as_ref()
+            .or(self.discord_webhook_url.as_ref())
+        {
+            channels.push((
+                "discord".to_owned(),
+                Channel::Discord(discord_webhook_url.clone()),
+            ));
+        }
+        if let Some(telegram_chat) = &self.telegram {
+            channels.push((
+                "telegram".to_owned(),
+                Channel::Telegram(telegram_chat.clone()),
+            ));
+        }
+        if let Some(mailer) = &self.mailer
+            && let Some(to) = mailer.recipient(website.alert_email_to.as_deref())
+        {
+            channels.push(("email".to_owned(), Channel::Email(mailer.clone(), to)));
+        }
+
+        channels
+    }
+
+    fn client_for(&self, insecure_tls: bool) -> reqwest::Client {
+        if insecure_tls {
+            self.insecure_client.clone()
+        } else {
+            self.client.clone()
+        }
+    }
+
+    /// Delivers in the background worded by the template if the channel has one,
+    /// Discord messages and Alertmanager alerts are only collected in the batches
+    fn send(
+        &self,
+        key: String,
+        channel: Channel,
+        template: Option<&MessageTemplate>,
+        mut transition: Transition,
+        batches: &mut Batches,
+    ) {
+        transition.message = template.map(|template| template.render(&transition));
+        let delivery = Delivery::new(key.clone(), std::slice::from_ref(&transition));
+        match channel {
+            Channel::Webhook(url) => dispatch(
+                WebhookNotifier::new(self.client.clone(), url, transition),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Slack(webhook_url) => dispatch(
+                SlackNotifier::new(
+                    self.client.clone(),
+                    webhook_url,
+                    self.public_url.clone(),
+                    transition,
+                ),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Discord(webhook_url) => batches.discord.add(&webhook_url, transition),
+            Channel::Telegram(telegram_chat) => dispatch(
+                TelegramNotifier::new(
+                    self.client.clone(),
+                    telegram_chat,
+                    self.public_url.clone(),
+                    transition,
+                ),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Email(mailer, to) => dispatch(
+                EmailNotifier::new(mailer, to, self.public_url.clone(), transition),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Ntfy(topic, insecure_tls) => dispatch(
+                NtfyNotifier::new(
+                    self.client_for(insecure_tls),
+                    topic,
+                    self.public_url.clone(),
+                    transition,
+                ),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Gotify(app, insecure_tls) => dispatch(
+                GotifyNotifier::new(
+                    self.client_for(insecure_tls),
+                    app,
+                    self.public_url.clone(),
+                    transition,
+                ),
+                delivery,
+                &self.deliveries,
+            ),
+            Channel::Alertmanager(url) => batches.alertmanager.add(key, &url, transition),
+        }
+    }
+
+    /// Delivers to the channel once and right away, with the same notifiers `send` uses
+    /// but without retries or batching, so the outcome can be told whoever asked for it
+    pub async fn test(
+        &self,
+        config: &ChannelConfig,
+        template: Option<&MessageTemplate>,
+        mut transition: Transition,
+    ) -> Result<(), String> {
+        let channel = self
+            .routed_channel(config)
+            .ok_or_else(|| format!("the {} channel can't be used as configured", config.kind()))?;
+        transition.message = template.map(|template| template.render(&transition));
+        match channel {
+            Channel::Webhook(url) => {
+                WebhookNotifier::new(self.client.clone(), url, transition)
+                    .notify()
+                    .await
+            }
+            Channel::Slack(webhook_url) => {
+                SlackNotifier::new(
+                    self.client.clone(),
+                    webhook_url,
+                    self.public_url.clone(),
+                    transition,
+                )
+                .notify()
+                .await
+            }
+            Channel::Discord(webhook_url) => {
+                DiscordNotifier::new(
+                    self.client.clone(),
+                    webhook_url,
+                    self.public_url.clone(),
+                    vec![transition],
+                )
+                .notify()
+                .await
+            }
+            Channel::Telegram(telegram_chat) => {
+                TelegramNotifier::new(
+                    self.client.clone(),
+                    telegram_chat,
+                    self.public_url.clone(),
+                    transition,
+                )
+                .notify()
+                .await
+            }
+            Channel::Email(mailer, to) => {
+                EmailNotifier::new(mailer, to, self.public_url.clone(), transition)
+                    .notify()
+                    .await
+            }
+            Channel::Ntfy(topic, insecure_tls) => {
+                NtfyNotifier::new(
+                    self.client_for(insecure_tls),
+                    topic,
+                    self.public_url.clone(),
+                    transition,
+                )
+                .notify()
+                .await
+            }
+            Channel::Gotify(app, insecure_tls) => {
+                GotifyNotifier::new(
+                    self.client_for(insecure_tls),
+                    app,
+                    self.public_url.clone(),
+                    transition,
+                )
+                .notify()
+                .await
+            }
+            Channel::Alertmanager(url) => {
+                AlertmanagerNotifier::new(
+                    self.client.clone(),
+                    url,
+                    self.public_url.clone(),
+                    vec![transition],
+                )
+                .notify()
+                .await
+            }
+        }
+    }
+}
+
+/// Decides which channels hear about each check and sends to them, the checker keeps one
+/// for its whole lifetime
+pub struct Outbox {
+    settings: NotificationSettings,
+    throttle: NotificationThrottle,
+    /// Rows of the Notifications table, reloaded every tick
+    webhooks: Vec<(i32, String)>,
+    /// Channels assigned to each website, reloaded every tick
+    routes: HashMap<String, Vec<Route>>,
+    /// Websites whose ongoing incident someone acknowledged, reloaded every tick
+    acknowledged: HashSet<String>,
+    batches: Batches,
+}
+
+impl Outbox {
+    pub fn new(settings: NotificationSettings) -> Self {
+        Self {
+            throttle: NotificationThrottle::new(settings.cooldown),
+            settings,
+            webhooks: Vec::new(),
+            routes: HashMap::new(),
+            acknowledged: HashSet::new(),
+            batches: Batches::default(),
+        }
+    }
+
+    pub fn set_webhooks(&mut self, webhooks: Vec<(i32, String)>) {
+        self.webhooks = webhooks;
+    }
+
+    pub fn set_routes(&mut self, routes: HashMap<String, Vec<Route>>) {
+        self.routes = routes;
+    }
+
+    /// Acknowledged incidents get no more reminders, acknowledgments stay on the
+    /// incident so the next one starts without
+    pub fn set_acknowledged(&mut self, open_incidents: &[OpenIncident]) {
+        self.acknowledged = open_incidents
+            .iter()
+            .filter(|incident| incident.acknowledged_at.is_some())
+            .map(|incident| incident.alias.clone())
+            .collect();
+    }
+
+    pub fn ack_sla(&self) -> Option<TimeDelta> {
+        self.settings.ack_sla
+    }
+
+    pub fn knows(&self, website_id: i32) -> bool {
+        self.throttle.knows(website_id)
+    }
+
+    pub fn seed(
+        &mut self,
+        website_id: i32,
+        states: impl IntoIterator<Item = (String, NotifiedState)>,
+    ) {
+        self.throttle.seed(website_id, states);
+    }
+
+    /// Sends what the check means to every channel that should hear about it, returns
+    /// the channel states that changed so they can be persisted
+    pub fn notify(
+        &mut self,
+        website: &Website,
+        status: i16,
+        notice: Notice,
+        now: DateTime<Utc>,
+    ) -> Vec<(String, NotifiedState)> {
+        if let Notice::Transition(transition) | Notice::Umbrella(transition) = &notice {
+            info!(
+                "{} ({} -> {})",
+                transition.headline(),
+                transition.old_status,
+                transition.new_status
+            );
+        }
+
+        let acknowledged = self.acknowledged.contains(&website.alias);
+        let mut changed = Vec::new();
+        for (key, channel, template) in
+            self.settings
+                .channels(website, &self.webhooks, &self.routes)
+        {
+            let decision = self
+                .throttle
+                .decide(website, &key, status, &notice, acknowledged, now);
+            let state = match decision {
+                Decision::Send(transition) => {
+                    let state = NotifiedState {
+                        status: transition.new_status,
+                        since: if transition.reminder {
+                            transition.since
+                        } else {
+                            transition.timestamp
+                        },
+                        notified_at: now,
+                    };
+                    self.settings.send(
+                        key.clone(),
+                        channel,
+                        template.as_ref(),
+                        transition,
+                        &mut self.batches,
+                    );
+                    state
+                }
+                Decision::Record(state) => state,
+                Decision::Keep => continue,
+            };
+            self.throttle.record(website.id, key.clone(), state);
+            changed.push((key, state));
+        }
+
+        changed
+    }
+
+    /// Sends a warning that isn't about the website's status to all its channels, it
+    /// neither counts for nor is held back by the throttling
+    pub fn warn(&mut self, website: &Website, warning: Transition) {
+        info!("{}", warning.headline());
+        for (key, channel, template) in
+            self.settings
+                .channels(website, &self.webhooks, &self.routes)
+        {
+            self.settings.send(
+                key,
+                channel,
+                template.as_ref(),
+                warning.clone(),
+                &mut self.batches,
+            );
+        }
+    }
+
+    /// Sends an escalation to the rule's channel, or like `warn` to all the website's
+    /// channels when the rule has none
+    pub fn escalate(&mut self, website: &Website, route: Option<&Route>, escalation: Transition) {
+        let Some((id, config, template)) = route else {
+            self.warn(website, escalation);
+            return;
+        };
+        info!("{}", escalation.headline());
+        if let Some(channel) = self.settings.routed_channel(config) {
+            self.settings.send(
+                format!("channel:{id}"),
+                channel,
+                template.as_ref(),
+                escalation,
+                &mut self.batches,
+            );
+        }
+    }
+
+    /// Sends what was collected for Discord and Alertmanager receivers during the tick
+    pub fn flush(&mut self) {
+        self.batches.dispatch(&self.settings);
+    }
+
+    /// Forgets websites that were deleted in the meantime
+    pub fn retain(&mut self, websites: &[Website]) {
+        self.throttle.retain(websites);
+    }
+}
+
+/// What a dispatched notification was about, recorded in the NotificationLog table once
+/// it was delivered or given up on
+pub struct Delivery {
+    channel: String,
+    /// Alias and kind of every transition the notification covers
+    entries: Vec<(String, &'static str)>,
+    error: Option<String>,
+}
+
+impl Delivery {
+    pub fn new(channel: String, transitions: &[Transition]) -> Self {
+        Self {
+            channel,
+            entries: transitions
+                .iter()
+                .map(|transition| (transition.alias.clone(), transition.kind()))
+                .collect(),
+            error: None,
+        }
+    }
+}
+
+/// Hands finished deliveries to the task recording them
+#[derive(Clone)]
+pub struct DeliveryLog(mpsc::UnboundedSender<Delivery>);
+
+impl DeliveryLog {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Delivery>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self(sender), receiver)
+    }
+}
+
+/// Writes deliveries to the NotificationLog table until every sender is gone
+pub async fn record_deliveries(
+    app_state: AppState,
+    mut deliveries: mpsc::UnboundedReceiver<Delivery>,
+) {
+    while let Some(delivery) = deliveries.recv().await {
+        for (alias, kind) in delivery.entries {
+            let result = on_db!(&app_state, |db| sqlx::query(INSERT_NOTIFICATION_LOG_QUERY)
+                .bind(alias)
+                .bind(&delivery.channel)
+                .bind(kind)
+                .bind(delivery.error.is_none())
+                .bind(&delivery.error)
+                .execute(db)
+                .await
+                .map(|_| ()));
+            if let Err(e) = result {
+                warn!(
+                    "Recording a notification to {} failed: {e}",
+                    delivery.channel
+                );
+            }
+        }
+    }
+}
+
+/// A notification about one or more transitions, ready to be delivered somewhere
+pub trait Notifier {
+    /// What goes where, for the logs
+    fn target(&self) -> String;
+
+    fn notify(&self) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+/// Delivers in a spawned task, so retries and slow receivers never hold up checks
+pub fn dispatch(
+    notifier: impl Notifier + Send + Sync + 'static,
+    mut delivery: Delivery,
+    deliveries: &DeliveryLog,
+) {
+    let deliveries = deliveries.clone();
+    tokio::spawn(async move {
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=DELIVERY_ATTEMPTS {
+            match notifier.notify().await {
+                Ok(()) => {
+                    info!("Notified {}", notifier.target());
+                    break;
+                }
+                Err(e) if attempt < DELIVERY_ATTEMPTS => {
+                    warn!(
+                        "Notifying {} failed (attempt {attempt}): {e}, retrying in {}s",
+                        notifier.target(),
+                        backoff.as_secs()
+                    );
+                    time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    warn!(
+                        "Giving up notifying {} after {attempt} attempts: {e}",
+                        notifier.target()
+                    );
+                    delivery.error = Some(e);
+                }
+            }
+        }
+        let _ = deliveries.0.send(delivery);
+    });
+}
+
+/// Remembers each website's last status and since when it's been up or down
+#[derive(Default)]
+pub struct TransitionWatch {
+    last_seen: HashMap<String, (i16, DateTime<Utc>)>,
+}
+
+impl TransitionWatch {
+    pub fn knows(&self, alias: &str) -> bool {
+        self.last_seen.contains_key(alias)
+    }
+
+    /// Starts from the last logged check, so transitions across restarts aren't missed
+    /// (the state then counts as started with that check)
+    pub fn seed(&mut self, alias: &str, last_logged: Option<(i16, DateTime<Utc>)>) {
+        if let Some(last_logged) = last_logged {
+            self.last_seen.insert(alias.to_owned(), last_logged);
+        }
+    }
+
+    pub fn observe(
+        &mut self,
+        website: &Website,
+        status: i16,
+        now: DateTime<Utc>,
+    ) -> Option<Transition> {
+        let Some((old_status, since)) = self.last_seen.get(&website.alias).copied() else {
+            self.last_seen.insert(website.alias.clone(), (status, now));
+            return None;
+        };
+
+        if is_up(old_status) == is_up(status) {
+            self.last_seen
+                .insert(website.alias.clone(), (status, since));
+            return None;
+        }
+
+        self.last_seen.insert(website.alias.clone(), (status, now));
+        Some(Transition {
+            alias: website.alias.clone(),
+            url: website.url.clone(),
+            old_status,
+            new_status: status,
+            timestamp: now,
+            since,
+            duration: (now - since).num_seconds(),
+            inverted: website.invert,
+            shared_host: None,
+            affected: Vec::new(),
+            reminder: false,
+            warning: None,
+            message: None,
+        })
+    }
+
+    /// Forgets websites that were deleted in the meantime
+    pub fn retain(&mut self, websites: &[Website]) {
+        self.last_seen
+            .retain(|alias, _| websites.iter().any(|website| &website.alias == alias));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(old_status: i16, new_status: i16, inverted: bool) -> Transition {
+        let timestamp: DateTime<Utc> = "2026-10-16T08:00:00Z".parse().unwrap();
+        Transition {
+            alias: "retired".to_owned(),
+            url: "https://retired.example/health".to_owned(),
+            old_status,
+            new_status,
+            timestamp,
+            since: timestamp - TimeDelta::minutes(3),
+            duration: 3 * 60,
+            inverted,
+            shared_host: None,
+            affected: Vec::new(),
+            reminder: false,
+            warning: None,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn transitions_are_worded_for_regular_monitors() {
+        assert_eq!(transition(200, 599, false).headline(), "retired is down");
+        assert_eq!(
+            transition(599, 200, false).headline(),
+            "retired recovered after 3m"
+        );
+    }
+
+    #[test]
+    fn transitions_are_worded_for_inverted_monitors() {
+        let reachable = transition(200, 596, true);
+        let offline = transition(596, 200, true);
+
+        assert_eq!(
+            reachable.headline(),
+            "retired is reachable although it should be offline"
+        );
+        assert_eq!(reachable.kind(), "down");
+        assert_eq!(
+            offline.headline(),
+            "retired is offline again after being reachable for 3m"
+        );
+        assert_eq!(offline.kind(), "recovery");
+    }
+
+    #[test]
+    fn reminders_are_worded_for_both_kinds_of_monitors() {
+        let reminder = |status, inverted| Transition {
+            reminder: true,
+            ..transition(status, status, inverted)
+        };
+
+        assert_eq!(
+            reminder(599, false).headline(),
+            "retired is still down after 3m"
+        );
+        assert_eq!(
+            reminder(596, true).headline(),
+            "retired is still reachable after 3m"
+        );
+    }
+}
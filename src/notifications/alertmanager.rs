@@ -0,0 +1,510 @@
+use super::{
+    Delivery, NOTIFIER_TIMEOUT, NotificationSettings, Notifier, Transition, Warning, dispatch,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Version of Alertmanager's webhook payload this sends
+const PAYLOAD_VERSION: &str = "4";
+
+/// What receivers are told sent the alerts
+const RECEIVER: &str = "uptime-ferris";
+
+/// Alertmanager's zero `endsAt` of alerts that are still firing
+const UNRESOLVED: &str = "0001-01-01T00:00:00Z";
+
+/// POSTs transitions in the payload format of Alertmanager's webhook receivers, so
+/// pipelines built for it take them as they are
+pub struct AlertmanagerNotifier {
+    client: reqwest::Client,
+    url: String,
+    /// Where the dashboard is reachable, alerts link back to it as their generator
+    public_url: Option<String>,
+    transitions: Vec<Transition>,
+}
+
+impl AlertmanagerNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        url: String,
+        public_url: Option<String>,
+        transitions: Vec<Transition>,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            public_url,
+            transitions,
+        }
+    }
+
+    /// A recovery resolves the alert the website going down fired, since both carry the
+    /// same labels. Reminders and escalations fire it again with what changed in the
+    /// annotations
+    fn alert(&self, transition: &Transition) -> Value {
+        let labels = labels(transition);
+        let status = if transition.is_recovery() {
+            "resolved"
+        } else {
+            "firing"
+        };
+        let mut annotations = json!({
+            "summary": transition.headline(),
+            "description": format!(
+                "{} answered with status {}",
+                transition.url, transition.new_status
+            ),
+            "url": transition.url,
+            "kind": transition.kind(),
+        });
+        if let Some(shared_host) = &transition.shared_host {
+            annotations["shared_host"] = json!(shared_host);
+        }
+        let ends_at = if transition.is_recovery() {
+            rfc3339(transition.timestamp)
+        } else {
+            UNRESOLVED.to_owned()
+        };
+
+        json!({
+            "status": status,
+            "fingerprint": fingerprint(&labels),
+            "labels": labels,
+            "annotations": annotations,
+            "startsAt": rfc3339(starts_at(transition)),
+            "endsAt": ends_at,
+            "generatorURL": self
+                .public_url
+                .as_ref()
+                .map(|public_url| {
+                    format!(
+                        "{}/websites/{}",
+                        public_url.trim_end_matches('/'),
+                        transition.alias
+                    )
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// One payload for all the transitions, firing as long as any alert in it is
+    fn payload(&self) -> Value {
+        let alerts: Vec<Value> = self
+            .transitions
+            .iter()
+            .map(|transition| self.alert(transition))
+            .collect();
+        let status = if alerts.iter().any(|alert| alert["status"] == "firing") {
+            "firing"
+        } else {
+            "resolved"
+        };
+        let common_labels = common(&alerts, "labels");
+
+        json!({
+            "version": PAYLOAD_VERSION,
+            "groupKey": format!("{{}}:{}", group_key(&common_labels)),
+            "truncatedAlerts": 0,
+            "status": status,
+            "receiver": RECEIVER,
+            "groupLabels": {},
+            "commonLabels": common_labels,
+            "commonAnnotations": common(&alerts, "annotations"),
+            "externalURL": self.public_url.clone().unwrap_or_default(),
+            "alerts": alerts,
+        })
+    }
+}
+
+impl Notifier for AlertmanagerNotifier {
+    fn target(&self) -> String {
+        format!(
+            "Alertmanager receiver {} about {}",
+            self.url,
+            self.transitions
+                .iter()
+                .map(|transition| transition.alias.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.url)
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&self.payload())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("Alertmanager receiver answered with {status}")),
+        }
+    }
+}
+
+/// Transitions of one checker tick, grouped by the receiver they go to and sent as one
+/// payload each
+#[derive(Default)]
+pub struct AlertmanagerBatch {
+    /// The channel key and transitions of every receiver URL
+    transitions: HashMap<String, (String, Vec<Transition>)>,
+}
+
+impl AlertmanagerBatch {
+    pub fn add(&mut self, key: String, url: &str, transition: Transition) {
+        self.transitions
+            .entry(url.to_owned())
+            .or_insert_with(|| (key, Vec::new()))
+            .1
+            .push(transition);
+    }
+
+    pub fn dispatch(&mut self, notification_settings: &NotificationSettings) {
+        for (url, (key, transitions)) in self.transitions.drain() {
+            let delivery = Delivery::new(key, &transitions);
+            dispatch(
+                AlertmanagerNotifier::new(
+                    notification_settings.client.clone(),
+                    url,
+                    notification_settings.public_url.clone(),
+                    transitions,
+                ),
+                delivery,
+                &notification_settings.deliveries,
+            );
+        }
+    }
+}
+
+/// Outages are one alert per website from going down to recovering, certificates
+/// running out and digests separate ones
+fn labels(transition: &Transition) -> Value {
+    let (alertname, severity) = match transition.warning {
+        Some(Warning::CertificateExpiry { .. }) => ("CertificateExpiry", "warning"),
+        Some(Warning::Digest(_)) => ("UptimeDigest", "info"),
+        Some(Warning::Test) => ("UptimeFerrisTest", "info"),
+        _ => ("WebsiteDown", "critical"),
+    };
+
+    json!({
+        "alertname": alertname,
+        "alias": transition.alias,
+        "severity": severity,
+    })
+}
+
+/// When the alert started firing, the outage's start for everything about one that's
+/// already going on
+fn starts_at(transition: &Transition) -> DateTime<Utc> {
+    match (&transition.warning, transition.reminder) {
+        (Some(Warning::CertificateExpiry { .. }), _) => transition.timestamp,
+        (Some(_), _) | (None, true) => transition.since,
+        (None, false) if transition.is_recovery() => transition.since,
+        (None, false) => transition.timestamp,
+    }
+}
+
+/// RFC 3339 in UTC with a `Z` like Alertmanager writes them
+fn rfc3339(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Identifies the alert by its labels like Alertmanager's fingerprints do, so receivers
+/// can match a resolution to what fired
+fn fingerprint(labels: &Value) -> String {
+    let mut hasher = Sha256::new();
+    if let Value::Object(labels) = labels {
+        let mut names: Vec<&String> = labels.keys().collect();
+        names.sort();
+        for name in names {
+            hasher.update(name.as_bytes());
+            hasher.update([0xff]);
+            hasher.update(labels[name].as_str().unwrap_or_default().as_bytes());
+            hasher.update([0xff]);
+        }
+    }
+
+    hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The `field` entries every alert has with the same value
+fn common(alerts: &[Value], field: &str) -> Value {
+    let Some((first, rest)) = alerts.split_first() else {
+        return json!({});
+    };
+    let Value::Object(entries) = &first[field] else {
+        return json!({});
+    };
+    let shared: Map<String, Value> = entries
+        .iter()
+        .filter(|(name, value)| {
+            rest.iter()
+                .all(|alert| &alert[field][name.as_str()] == *value)
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+
+    Value::Object(shared)
+}
+
+/// The labels as Alertmanager writes them into group keys, `{alias="a"}`
+fn group_key(labels: &Value) -> String {
+    let Value::Object(labels) = labels else {
+        return "{}".to_owned();
+    };
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    pairs.sort();
+
+    format!("{{{}}}", pairs.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::DeliveryLog;
+    use axum::{Json, Router, extract::State, routing::post};
+    use chrono::TimeDelta;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        "2026-10-16T08:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minute)
+    }
+
+    /// `alias` changing from `old_status` to `new_status` at minute 10, in its old state
+    /// since minute 3
+    fn transition(alias: &str, old_status: i16, new_status: i16) -> Transition {
+        Transition {
+            alias: alias.to_owned(),
+            url: format!("https://{alias}.example/health"),
+            old_status,
+            new_status,
+            timestamp: at(10),
+            since: at(3),
+            duration: 7 * 60,
+            inverted: false,
+            shared_host: None,
+            affected: Vec::new(),
+            reminder: false,
+            warning: None,
+            message: None,
+        }
+    }
+
+    fn payload(transitions: Vec<Transition>) -> Value {
+        AlertmanagerNotifier::new(
+            reqwest::Client::new(),
+            "http://alertmanager.example/".to_owned(),
+            Some("https://status.example/".to_owned()),
+            transitions,
+        )
+        .payload()
+    }
+
+    /// The fields and types of Alertmanager's webhook receiver payload, version 4
+    fn assert_documented_schema(payload: &Value) {
+        assert_eq!(payload["version"], "4");
+        for field in ["groupKey", "status", "receiver", "externalURL"] {
+            assert!(payload[field].is_string(), "{field}");
+        }
+        assert!(payload["truncatedAlerts"].is_u64());
+        for field in ["groupLabels", "commonLabels", "commonAnnotations"] {
+            assert!(payload[field].is_object(), "{field}");
+        }
+        for alert in payload["alerts"].as_array().unwrap() {
+            assert!(["firing", "resolved"].contains(&alert["status"].as_str().unwrap()));
+            for field in ["startsAt", "endsAt", "generatorURL", "fingerprint"] {
+                assert!(alert[field].is_string(), "{field}");
+            }
+            for (_, value) in alert["labels"].as_object().unwrap() {
+                assert!(value.is_string());
+            }
+            for (_, value) in alert["annotations"].as_object().unwrap() {
+                assert!(value.is_string());
+            }
+            for label in ["alertname", "alias", "severity"] {
+                assert!(alert["labels"][label].is_string(), "{label}");
+            }
+            for timestamp in ["startsAt", "endsAt"] {
+                DateTime::parse_from_rfc3339(alert[timestamp].as_str().unwrap()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn going_down_fires_and_recovering_resolves_the_same_alert() {
+        let down = payload(vec![transition("api", 200, 599)]);
+        let up = payload(vec![transition("api", 599, 200)]);
+        assert_documented_schema(&down);
+        assert_documented_schema(&up);
+
+        let (fired, resolved) = (&down["alerts"][0], &up["alerts"][0]);
+        assert_eq!(
+            (&down["status"], &fired["status"]),
+            (&json!("firing"), &json!("firing"))
+        );
+        assert_eq!(fired["startsAt"], "2026-10-16T08:10:00.000Z");
+        assert_eq!(fired["endsAt"], UNRESOLVED);
+        assert_eq!(
+            fired["labels"],
+            json!({"alertname": "WebsiteDown", "alias": "api", "severity": "critical"})
+        );
+        assert_eq!(fired["annotations"]["kind"], "down");
+        assert_eq!(fired["generatorURL"], "https://status.example/websites/api");
+
+        assert_eq!(
+            (&up["status"], &resolved["status"]),
+            (&json!("resolved"), &json!("resolved"))
+        );
+        // Resolved over the outage it ended, which started when the website went down
+        assert_eq!(resolved["startsAt"], "2026-10-16T08:03:00.000Z");
+        assert_eq!(resolved["endsAt"], "2026-10-16T08:10:00.000Z");
+        assert_eq!(resolved["fingerprint"], fired["fingerprint"]);
+        assert_eq!(resolved["labels"], fired["labels"]);
+    }
+
+    #[test]
+    fn reminders_and_warnings_fire_from_when_the_outage_started() {
+        let reminder = Transition {
+            reminder: true,
+            ..transition("api", 599, 599)
+        };
+        let expiring = Transition {
+            warning: Some(Warning::CertificateExpiry {
+                certificate_expires_at: at(60 * 24 * 7),
+            }),
+            ..transition("api", 200, 200)
+        };
+        let alerts = payload(vec![reminder, expiring]);
+        assert_documented_schema(&alerts);
+
+        let (reminded, warned) = (&alerts["alerts"][0], &alerts["alerts"][1]);
+        assert_eq!(reminded["status"], "firing");
+        assert_eq!(reminded["startsAt"], "2026-10-16T08:03:00.000Z");
+        assert_eq!(warned["status"], "firing");
+        assert_eq!(warned["startsAt"], "2026-10-16T08:10:00.000Z");
+        assert_eq!(warned["labels"]["alertname"], "CertificateExpiry");
+        assert_eq!(warned["labels"]["severity"], "warning");
+        assert_ne!(reminded["fingerprint"], warned["fingerprint"]);
+    }
+
+    #[test]
+    fn simultaneous_transitions_are_one_payload() {
+        let batch = payload(vec![
+            transition("api", 200, 599),
+            transition("blog", 200, 599),
+            transition("shop", 599, 200),
+        ]);
+        assert_documented_schema(&batch);
+
+        assert_eq!(batch["alerts"].as_array().unwrap().len(), 3);
+        assert_eq!(batch["status"], "firing");
+        assert_eq!(
+            batch["commonLabels"],
+            json!({"alertname": "WebsiteDown", "severity": "critical"})
+        );
+        assert_eq!(
+            batch["groupKey"],
+            r#"{}:{alertname="WebsiteDown",severity="critical"}"#
+        );
+        assert_eq!(batch["externalURL"], "https://status.example/");
+
+        let recovered = payload(vec![
+            transition("api", 599, 200),
+            transition("blog", 599, 200),
+        ]);
+        assert_eq!(recovered["status"], "resolved");
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339_in_utc() {
+        assert_eq!(rfc3339(at(0)), "2026-10-16T08:00:00.000Z");
+        let precise = at(0) + TimeDelta::microseconds(1_234_567);
+        assert_eq!(rfc3339(precise), "2026-10-16T08:00:01.234Z");
+        assert_eq!(
+            DateTime::parse_from_rfc3339(UNRESOLVED)
+                .unwrap()
+                .timestamp(),
+            "0001-01-01T00:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .timestamp()
+        );
+    }
+
+    #[tokio::test]
+    async fn a_tick_posts_one_payload_per_receiver() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let app =
+            Router::new()
+                .route(
+                    "/:receiver",
+                    post(
+                        |State(received): State<Arc<Mutex<Vec<Value>>>>,
+                         Json(payload): Json<Value>| async move {
+                            received.lock().unwrap().push(payload);
+                        },
+                    ),
+                )
+                .with_state(received.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let (deliveries, mut delivered) = DeliveryLog::new();
+        let settings = NotificationSettings {
+            client: reqwest::Client::new(),
+            insecure_client: reqwest::Client::new(),
+            slack_webhook_url: None,
+            discord_webhook_url: None,
+            telegram: None,
+            mailer: None,
+            shared_fate_window: TimeDelta::minutes(5),
+            cooldown: TimeDelta::zero(),
+            ack_sla: None,
+            deliveries,
+            public_url: None,
+        };
+
+        let mut batch = AlertmanagerBatch::default();
+        batch.add(
+            "channel:1".into(),
+            &format!("{url}/ops"),
+            transition("api", 200, 599),
+        );
+        batch.add(
+            "channel:1".into(),
+            &format!("{url}/ops"),
+            transition("blog", 200, 599),
+        );
+        batch.add(
+            "channel:2".into(),
+            &format!("{url}/dev"),
+            transition("api", 200, 599),
+        );
+        batch.dispatch(&settings);
+        drop(settings);
+        while delivered.recv().await.is_some() {}
+
+        let mut alerts: Vec<usize> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .inspect(|payload| assert_documented_schema(payload))
+            .map(|payload| payload["alerts"].as_array().unwrap().len())
+            .collect();
+        alerts.sort();
+        assert_eq!(alerts, [1, 2]);
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Delivery, NOTIFIER_TIMEOUT, NotificationSettings, Notifier, Transition, dispatch};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Discord rejects messages with more embeds than this
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+
+const DOWN_COLOR: u32 = 0xd50200;
+const RECOVERED_COLOR: u32 = 0x2eb886;
+
+/// Posts one embed per transition to a Discord webhook, the webhook URL is a secret and
+/// never logged
+pub struct DiscordNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+    /// Where the dashboard is reachable, embeds link back to it when set
+    public_url: Option<String>,
+    transitions: Vec<Transition>,
+}
+
+impl DiscordNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        webhook_url: String,
+        public_url: Option<String>,
+        transitions: Vec<Transition>,
+    ) -> Self {
+        Self {
+            client,
+            webhook_url,
+            public_url,
+            transitions,
+        }
+    }
+
+    fn embed(&self, transition: &Transition) -> serde_json::Value {
+        let color = if transition.is_recovery() {
+            RECOVERED_COLOR
+        } else {
+            DOWN_COLOR
+        };
+
+        let mut embed = json!({
+            "title": transition.headline(),
+            "color": color,
+            "timestamp": transition.timestamp.to_rfc3339(),
+            "fields": [
+                { "name": "Status code", "value": transition.new_status.to_string(), "inline": true },
+                { "name": "URL", "value": transition.url, "inline": true },
+            ],
+        });
+        if let Some(public_url) = &self.public_url {
+            embed["url"] = json!(format!(
+                "{}/websites/{}",
+                public_url.trim_end_matches('/'),
+                transition.alias
+            ));
+        }
+
+        embed
+    }
+
+    fn message(&self) -> serde_json::Value {
+        json!({
+            "embeds": self
+                .transitions
+                .iter()
+                .map(|transition| self.embed(transition))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn target(&self) -> String {
+        format!(
+            "Discord about {}",
+            self.transitions
+                .iter()
+                .map(|transition| transition.alias.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&self.message())
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("Discord answered with {status}")),
+        }
+    }
+}
+
+/// Transitions of one checker tick, grouped by the Discord webhook they go to
+#[derive(Default)]
+pub struct DiscordBatch {
+    transitions: HashMap<String, Vec<Transition>>,
+}
+
+impl DiscordBatch {
+    pub fn add(&mut self, webhook_url: &str, transition: Transition) {
+        self.transitions
+            .entry(webhook_url.to_owned())
+            .or_default()
+            .push(transition);
+    }
+
+    /// Sends a message per webhook, split once a webhook has more transitions than
+    /// fit into one
+    pub fn dispatch(&mut self, notification_settings: &NotificationSettings) {
+        for (webhook_url, transitions) in self.transitions.drain() {
+            for chunk in transitions.chunks(MAX_EMBEDS_PER_MESSAGE) {
+                dispatch(
+                    DiscordNotifier::new(
+                        notification_settings.client.clone(),
+                        webhook_url.clone(),
+                        notification_settings.public_url.clone(),
+                        chunk.to_vec(),
+                    ),
+                    Delivery::new("discord".to_owned(), chunk),
+                    &notification_settings.deliveries,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+use askama::Template;
+use chrono::{DateTime, Utc};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+};
+
+/// How the connection to the SMTP server is secured
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum SmtpTls {
+    /// Upgrades a plain connection, usually on port 587
+    Starttls,
+    /// TLS from the first byte, usually on port 465
+    Implicit,
+    /// Unencrypted, only meant for relays on the local network
+    None,
+}
+
+impl SmtpTls {
+    fn default_port(&self) -> u16 {
+        match self {
+            Self::Starttls => 587,
+            Self::Implicit => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// SMTP connection and addresses alert mails are sent with
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    /// Recipient for websites without their own
+    default_to: Option<Mailbox>,
+}
+
+pub struct SmtpSettings<'a> {
+    pub host: &'a str,
+    pub port: Option<u16>,
+    pub tls: SmtpTls,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    pub from: &'a str,
+    pub default_to: Option<&'a str>,
+}
+
+impl Mailer {
+    /// Fails on a host or address that can't be used, which are startup errors
+    pub fn new(settings: SmtpSettings) -> Result<Self, String> {
+        let builder = match settings.tls {
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(settings.host)
+                    .map_err(|e| format!("--smtp-host is not valid for TLS: {e}"))?
+            }
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(settings.host)
+                .map_err(|e| format!("--smtp-host is not valid for TLS: {e}"))?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(settings.host),
+        };
+        let builder = builder
+            .port(settings.port.unwrap_or(settings.tls.default_port()))
+            .timeout(Some(NOTIFIER_TIMEOUT));
+        let builder = match (settings.username, settings.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.to_owned(), password.to_owned()))
+            }
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from: settings
+                .from
+                .parse()
+                .map_err(|e| format!("--smtp-from is not a valid address: {e}"))?,
+            default_to: settings
+                .default_to
+                .map(|default_to| {
+                    default_to
+                        .parse()
+                        .map_err(|e| format!("--alert-email-to is not a valid address: {e}"))
+                })
+                .transpose()?,
+        })
+    }
+
+    /// The website's own recipient if it has one, the default one otherwise
+    pub fn recipient(&self, website_to: Option<&str>) -> Option<Mailbox> {
+        match website_to {
+            Some(website_to) => website_to.parse().ok(),
+            None => self.default_to.clone(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "alert_email.txt")]
+struct AlertText<'a> {
+    transition: &'a Transition,
+    link: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "alert_email.html")]
+struct AlertHtml<'a> {
+    transition: &'a Transition,
+    link: Option<String>,
+}
+
+/// Mails a transition, recoveries reply to the mail about the outage they end so mail
+/// clients show both in one thread
+pub struct EmailNotifier {
+    mailer: Mailer,
+    to: Mailbox,
+    /// Where the dashboard is reachable, mails link back to it when set
+    public_url: Option<String>,
+    transition: Transition,
+}
+
+impl EmailNotifier {
+    pub fn new(
+        mailer: Mailer,
+        to: Mailbox,
+        public_url: Option<String>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            mailer,
+            to,
+            public_url,
+            transition,
+        }
+    }
+
+    /// Stable for an outage, the recovery derives it from when the outage started
+    fn message_id(&self, started_at: DateTime<Utc>) -> String {
+        format!(
+            "<{}.{}@{}>",
+            self.transition.subject(),
+            started_at.timestamp_millis(),
+            self.mailer.from.email.domain()
+        )
+    }
+
+    fn message(&self) -> Result<Message, String> {
+        let transition = &self.transition;
+        let link = self.public_url.as_ref().map(|public_url| {
+            format!(
+                "{}/websites/{}",
+                public_url.trim_end_matches('/'),
+                transition.alias
+            )
+        });
+        let text = AlertText {
+            transition,
+            link: link.clone(),
+        }
+        .render()
+        .map_err(|e| e.to_string())?;
+        let html = AlertHtml { transition, link }
+            .render()
+            .map_err(|e| e.to_string())?;
+
+        let builder = Message::builder()
+            .from(self.mailer.from.clone())
+            .to(self.to.clone())
+            .subject(transition.headline());
+        let builder = if transition.is_recovery() {
+            let outage_id = self.message_id(transition.since);
+            builder.in_reply_to(outage_id.clone()).references(outage_id)
+        } else {
+            builder.message_id(Some(self.message_id(transition.timestamp)))
+        };
+
+        builder
+            .multipart(MultiPart::alternative_plain_html(text, html))
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn target(&self) -> String {
+        format!("{} about {}", self.to.email, self.transition.alias)
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        self.mailer
+            .transport
+            .send(self.message()?)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,92 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+use serde_json::json;
+
+/// Gotify treats 8 and above as important enough to alert on
+const DOWN_PRIORITY: u8 = 8;
+const RECOVERED_PRIORITY: u8 = 4;
+
+/// Application on a Gotify server, the app token is a secret and never logged
+#[derive(Clone)]
+pub struct GotifyApp {
+    pub server_url: String,
+    pub app_token: String,
+}
+
+/// Creates a message through the server's /message endpoint
+pub struct GotifyNotifier {
+    client: reqwest::Client,
+    app: GotifyApp,
+    /// Where the dashboard is reachable, tapping the notification opens it when set
+    public_url: Option<String>,
+    transition: Transition,
+}
+
+impl GotifyNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        app: GotifyApp,
+        public_url: Option<String>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            client,
+            app,
+            public_url,
+            transition,
+        }
+    }
+
+    fn message(&self) -> serde_json::Value {
+        let transition = &self.transition;
+        let mut message = json!({
+            "title": transition.headline(),
+            "message": format!("URL: {}\nStatus code: {}", transition.url, transition.new_status),
+            "priority": if transition.is_recovery() {
+                RECOVERED_PRIORITY
+            } else {
+                DOWN_PRIORITY
+            },
+        });
+        if let Some(public_url) = &self.public_url {
+            message["extras"] = json!({
+                "client::notification": {
+                    "click": {
+                        "url": format!(
+                            "{}/websites/{}",
+                            public_url.trim_end_matches('/'),
+                            transition.alias
+                        ),
+                    },
+                },
+            });
+        }
+
+        message
+    }
+}
+
+impl Notifier for GotifyNotifier {
+    fn target(&self) -> String {
+        format!("Gotify about {}", self.transition.alias)
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/message",
+                self.app.server_url.trim_end_matches('/')
+            ))
+            .timeout(NOTIFIER_TIMEOUT)
+            .header("X-Gotify-Key", &self.app.app_token)
+            .json(&self.message())
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("Gotify answered with {status}")),
+        }
+    }
+}
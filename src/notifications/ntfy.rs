@@ -0,0 +1,94 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+use serde_json::json;
+
+/// ntfy's "high" and "default" priorities
+const HIGH_PRIORITY: u8 = 4;
+const DEFAULT_PRIORITY: u8 = 3;
+
+/// Topic on an ntfy server, on public servers the topic name is as good as a password and
+/// never logged
+#[derive(Clone)]
+pub struct NtfyTopic {
+    pub server_url: String,
+    pub topic: String,
+    /// Needed for topics with access control
+    pub access_token: Option<String>,
+}
+
+/// Publishes to the topic, outages as high priority so phones make noise
+pub struct NtfyNotifier {
+    client: reqwest::Client,
+    topic: NtfyTopic,
+    /// Where the dashboard is reachable, tapping the notification opens it when set
+    public_url: Option<String>,
+    transition: Transition,
+}
+
+impl NtfyNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        topic: NtfyTopic,
+        public_url: Option<String>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            client,
+            topic,
+            public_url,
+            transition,
+        }
+    }
+
+    /// Published as JSON, so headlines aren't limited to what fits into headers
+    fn message(&self) -> serde_json::Value {
+        let transition = &self.transition;
+        let (priority, tag) = if transition.is_recovery() {
+            (DEFAULT_PRIORITY, "white_check_mark")
+        } else {
+            (HIGH_PRIORITY, "rotating_light")
+        };
+        let mut message = json!({
+            "topic": self.topic.topic,
+            "title": transition.headline(),
+            "message": format!("URL: {}\nStatus code: {}", transition.url, transition.new_status),
+            "priority": priority,
+            "tags": [tag],
+        });
+        if let Some(public_url) = &self.public_url {
+            message["click"] = json!(format!(
+                "{}/websites/{}",
+                public_url.trim_end_matches('/'),
+                transition.alias
+            ));
+        }
+
+        message
+    }
+}
+
+impl Notifier for NtfyNotifier {
+    fn target(&self) -> String {
+        format!("ntfy about {}", self.transition.alias)
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(self.topic.server_url.trim_end_matches('/'))
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&self.message());
+        if let Some(access_token) = &self.topic.access_token {
+            request = request.bearer_auth(access_token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("ntfy answered with {status}")),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use super::template::MessageTemplate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tracing::warn;
+use validator::ValidateUrl;
+
+/// What a NotificationChannels row delivers to, stored as its kind and the JSON of the
+/// variant's fields. That JSON may also hold the channel's message `template`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", content = "config", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    Webhook {
+        url: String,
+    },
+    Slack {
+        webhook_url: String,
+    },
+    Discord {
+        webhook_url: String,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+    },
+    /// Sent through the `--smtp-host`, skipped while none is configured
+    Email {
+        to: String,
+    },
+    Ntfy {
+        server_url: String,
+        topic: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        access_token: Option<String>,
+        /// Accept self-signed certificates of the server
+        #[serde(default)]
+        insecure_tls: bool,
+    },
+    Gotify {
+        server_url: String,
+        app_token: String,
+        #[serde(default)]
+        insecure_tls: bool,
+    },
+    /// Receives Alertmanager's webhook payload
+    Alertmanager {
+        url: String,
+    },
+}
+
+impl ChannelConfig {
+    /// Reads back what `kind()` and `config_json()` stored
+    pub fn parse(kind: &str, config: &str) -> Result<(Self, Option<MessageTemplate>), String> {
+        let config: Value = serde_json::from_str(config).map_err(|e| e.to_string())?;
+        let template = MessageTemplate::from_config(&config)?;
+        Ok((Self::from_value(kind, config)?, template))
+    }
+
+    pub fn from_value(kind: &str, config: Value) -> Result<Self, String> {
+        let channel: Self = serde_json::from_value(json!({ "kind": kind, "config": config }))
+            .map_err(|e| format!("invalid {kind} channel: {e}"))?;
+        channel.validate()?;
+        Ok(channel)
+    }
+
+    /// Built from the management page's form, `target` is whatever the kind is sent to
+    /// and `token` the ntfy access token or Gotify app token
+    pub fn from_form(
+        kind: &str,
+        target: String,
+        chat_id: Option<String>,
+        topic: Option<String>,
+        token: Option<String>,
+        insecure_tls: bool,
+    ) -> Result<Self, String> {
+        let channel = match kind {
+            "webhook" => Self::Webhook { url: target },
+            "slack" => Self::Slack {
+                webhook_url: target,
+            },
+            "discord" => Self::Discord {
+                webhook_url: target,
+            },
+            "telegram" => Self::Telegram {
+                bot_token: target,
+                chat_id: chat_id.ok_or("telegram channels need a chat id")?,
+            },
+            "email" => Self::Email { to: target },
+            "ntfy" => Self::Ntfy {
+                server_url: target,
+                topic: topic.ok_or("ntfy channels need a topic")?,
+                access_token: token,
+                insecure_tls,
+            },
+            "gotify" => Self::Gotify {
+                server_url: target,
+                app_token: token.ok_or("Gotify channels need an app token")?,
+                insecure_tls,
+            },
+            "alertmanager" => Self::Alertmanager { url: target },
+            other => return Err(format!("unknown channel kind '{other}'")),
+        };
+        channel.validate()?;
+        Ok(channel)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let valid = match self {
+            Self::Webhook { url }
+            | Self::Slack { webhook_url: url }
+            | Self::Discord { webhook_url: url }
+            | Self::Alertmanager { url } => url.validate_url(),
+            Self::Telegram { bot_token, chat_id } => !bot_token.is_empty() && !chat_id.is_empty(),
+            Self::Email { to } => to.parse::<lettre::message::Mailbox>().is_ok(),
+            Self::Ntfy {
+                server_url, topic, ..
+            } => server_url.validate_url() && !topic.is_empty() && !topic.contains('/'),
+            Self::Gotify {
+                server_url,
+                app_token,
+                ..
+            } => server_url.validate_url() && !app_token.is_empty(),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("invalid {} channel settings", self.kind()))
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::Slack { .. } => "slack",
+            Self::Discord { .. } => "discord",
+            Self::Telegram { .. } => "telegram",
+            Self::Email { .. } => "email",
+            Self::Ntfy { .. } => "ntfy",
+            Self::Gotify { .. } => "gotify",
+            Self::Alertmanager { .. } => "alertmanager",
+        }
+    }
+
+    /// The variant's fields and the template for the config column
+    pub fn config_json(&self, template: Option<&MessageTemplate>) -> String {
+        let mut tagged = serde_json::to_value(self).expect("channel configs always serialize");
+        let mut config = tagged["config"].take();
+        if let Some(template) = template {
+            config["template"] = json!(template.source());
+        }
+        config.to_string()
+    }
+}
+
+/// A website's channel with its id and message template
+pub type Route = (i32, ChannelConfig, Option<MessageTemplate>);
+
+/// Groups the rows of the WebsiteNotificationChannels join, given as alias, channel id,
+/// kind and config, by website
+pub fn routes(rows: Vec<(String, i32, String, String)>) -> HashMap<String, Vec<Route>> {
+    let mut routes: HashMap<String, Vec<Route>> = HashMap::new();
+    for (alias, id, kind, config) in rows {
+        match ChannelConfig::parse(&kind, &config) {
+            Ok((config, template)) => routes
+                .entry(alias)
+                .or_default()
+                .push((id, config, template)),
+            Err(e) => warn!("Skipping notification channel {id} of {alias}: {e}"),
+        }
+    }
+
+    routes
+}
@@ -0,0 +1,93 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+use serde_json::json;
+
+const DOWN_COLOR: &str = "#d50200";
+const RECOVERED_COLOR: &str = "#2eb886";
+
+/// Posts to a Slack incoming webhook, the webhook URL is a secret and never logged
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+    /// Where the dashboard is reachable, messages link back to it when set
+    public_url: Option<String>,
+    transition: Transition,
+}
+
+impl SlackNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        webhook_url: String,
+        public_url: Option<String>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            client,
+            webhook_url,
+            public_url,
+            transition,
+        }
+    }
+
+    fn message(&self) -> serde_json::Value {
+        let transition = &self.transition;
+        let (title, color) = if transition.is_recovery() {
+            (
+                format!(":large_green_circle: {}", transition.headline()),
+                RECOVERED_COLOR,
+            )
+        } else {
+            (
+                format!(":red_circle: {}", transition.headline()),
+                DOWN_COLOR,
+            )
+        };
+
+        let mut fields = vec![
+            json!({ "type": "mrkdwn", "text": format!("*URL*\n{}", transition.url) }),
+            json!({ "type": "mrkdwn", "text": format!("*Status code*\n{}", transition.new_status) }),
+        ];
+        if let Some(public_url) = &self.public_url {
+            fields.push(json!({
+                "type": "mrkdwn",
+                "text": format!(
+                    "*Details*\n<{}/websites/{}|Open in Uptime Ferris>",
+                    public_url.trim_end_matches('/'),
+                    transition.alias
+                ),
+            }));
+        }
+
+        json!({
+            "text": title,
+            "attachments": [{
+                "color": color,
+                "blocks": [
+                    { "type": "section", "text": { "type": "mrkdwn", "text": format!("*{title}*") } },
+                    { "type": "section", "fields": fields },
+                ],
+            }],
+        })
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn target(&self) -> String {
+        format!("Slack about {}", self.transition.alias)
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&self.message())
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("Slack answered with {status}")),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::{self, Duration};
+
+/// Longest `retry_after` waited for, longer waits are left to the regular retries
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Bot and chat alerts are sent to, the bot token is a secret and never logged
+#[derive(Clone)]
+pub struct TelegramChat {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+/// Sends a message through the Bot API's sendMessage
+pub struct TelegramNotifier {
+    client: reqwest::Client,
+    chat: TelegramChat,
+    /// Where the dashboard is reachable, messages link back to it when set
+    public_url: Option<String>,
+    transition: Transition,
+}
+
+/// The parts of a Bot API answer needed to tell why a message was refused
+#[derive(Deserialize)]
+struct BotApiResponse {
+    description: Option<String>,
+    parameters: Option<ResponseParameters>,
+}
+
+#[derive(Deserialize)]
+struct ResponseParameters {
+    retry_after: Option<u64>,
+}
+
+impl TelegramNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        chat: TelegramChat,
+        public_url: Option<String>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            client,
+            chat,
+            public_url,
+            transition,
+        }
+    }
+
+    fn text(&self) -> String {
+        let transition = &self.transition;
+        let icon = if transition.is_recovery() {
+            "🟢"
+        } else {
+            "🔴"
+        };
+        let mut text = format!(
+            "{icon} *{}*\nURL: {}\nStatus code: {}",
+            escape_markdown(&transition.headline()),
+            escape_markdown(&transition.url),
+            transition.new_status
+        );
+        if let Some(public_url) = &self.public_url {
+            text.push_str(&format!(
+                "\n[Open in Uptime Ferris]({}/websites/{})",
+                escape_link(public_url.trim_end_matches('/')),
+                escape_link(&transition.alias)
+            ));
+        }
+
+        text
+    }
+
+    async fn send_message(&self) -> Result<(), SendError> {
+        let response = self
+            .client
+            .post(format!(
+                "https://api.telegram.org/bot{}/sendMessage",
+                self.chat.bot_token
+            ))
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&json!({
+                "chat_id": self.chat.chat_id,
+                "text": self.text(),
+                "parse_mode": "MarkdownV2",
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await
+            .map_err(|e| SendError::Failed(e.without_url().to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let answer = response.json::<BotApiResponse>().await.ok();
+        let retry_after = answer
+            .as_ref()
+            .and_then(|answer| answer.parameters.as_ref()?.retry_after);
+        let description = answer
+            .and_then(|answer| answer.description)
+            .unwrap_or_default();
+
+        match retry_after {
+            Some(retry_after) if status == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                Err(SendError::RateLimited(Duration::from_secs(retry_after)))
+            }
+            _ => Err(SendError::Failed(format!(
+                "Telegram answered with {status} {description}"
+            ))),
+        }
+    }
+}
+
+enum SendError {
+    /// Telegram asks to wait this long before sending again
+    RateLimited(Duration),
+    Failed(String),
+}
+
+impl Notifier for TelegramNotifier {
+    fn target(&self) -> String {
+        format!("Telegram about {}", self.transition.alias)
+    }
+
+    /// Waits out a rate limit once before reporting the failure
+    async fn notify(&self) -> Result<(), String> {
+        let mut result = self.send_message().await;
+        if let Err(SendError::RateLimited(retry_after)) = &result
+            && *retry_after <= MAX_RETRY_AFTER
+        {
+            time::sleep(*retry_after).await;
+            result = self.send_message().await;
+        }
+
+        result.map_err(|e| match e {
+            SendError::RateLimited(retry_after) => format!(
+                "Telegram is rate limiting, retry after {}s",
+                retry_after.as_secs()
+            ),
+            SendError::Failed(e) => e,
+        })
+    }
+}
+
+/// Escapes text for MarkdownV2, where all of these characters are reserved
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes the target of a MarkdownV2 inline link, only `)` and `\` are reserved there
+fn escape_link(url: &str) -> String {
+    url.replace('\\', "\\\\").replace(')', "\\)")
+}
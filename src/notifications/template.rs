@@ -0,0 +1,84 @@
+use super::Transition;
+use serde_json::Value;
+
+/// What templates can refer to as `{{name}}`
+const PLACEHOLDERS: [&str; 5] = ["alias", "url", "status", "duration", "timestamp"];
+
+/// A channel's own wording of its notifications, replaces the headline. Channels without
+/// one keep the built in headlines
+#[derive(Clone, Debug)]
+pub struct MessageTemplate(String);
+
+impl MessageTemplate {
+    /// Rejects empty templates, unclosed braces and placeholders that don't exist
+    pub fn parse(source: &str) -> Result<Self, String> {
+        if source.trim().is_empty() {
+            return Err("message templates can't be empty".to_owned());
+        }
+        expand(source, |name| {
+            if PLACEHOLDERS.contains(&name) {
+                Ok(String::new())
+            } else {
+                Err(format!(
+                    "unknown placeholder {{{{{name}}}}} in the message template, available are {}",
+                    PLACEHOLDERS
+                        .map(|name| format!("{{{{{name}}}}}"))
+                        .join(", ")
+                ))
+            }
+        })?;
+
+        Ok(Self(source.to_owned()))
+    }
+
+    /// The `template` key of a channel's config, which the channel kinds themselves ignore
+    pub fn from_config(config: &Value) -> Result<Option<Self>, String> {
+        match config.get("template") {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(source)) => Self::parse(source).map(Some),
+            Some(_) => Err("the message template has to be a string".to_owned()),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.0
+    }
+
+    pub fn render(&self, transition: &Transition) -> String {
+        let rendered = expand(&self.0, |name| {
+            Ok(match name {
+                "alias" => transition.alias.clone(),
+                "url" => transition.url.clone(),
+                "status" => transition.new_status.to_string(),
+                "duration" => transition.duration_text(),
+                _ => transition
+                    .timestamp
+                    .format("%Y-%m-%d %H:%M:%S UTC")
+                    .to_string(),
+            })
+        });
+
+        // Templates were validated when the channel was saved
+        rendered.unwrap_or_else(|_| self.0.clone())
+    }
+}
+
+/// Replaces every `{{name}}` with what `value` gives for the trimmed name
+fn expand(source: &str, value: impl Fn(&str) -> Result<String, String>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        expanded.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            return Err(format!(
+                "unclosed placeholder '{}' in the message template",
+                &rest[start..]
+            ));
+        };
+        expanded.push_str(&value(rest[start + 2..start + end].trim())?);
+        rest = &rest[start + end + 2..];
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
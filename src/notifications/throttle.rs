@@ -0,0 +1,172 @@
+use super::{Notice, Transition, is_up};
+use crate::Website;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+
+/// What a channel last heard about a website, persisted in NotificationStates
+#[derive(Clone, Copy)]
+pub struct NotifiedState {
+    pub status: i16,
+    /// When the notified state began
+    pub since: DateTime<Utc>,
+    pub notified_at: DateTime<Utc>,
+}
+
+/// What to do about a channel after a check
+pub enum Decision {
+    Send(Transition),
+    /// Remember the state without telling the channel
+    Record(NotifiedState),
+    Keep,
+}
+
+/// Remembers per website and channel what was notified last, so channels only hear about
+/// changes of state, at most once per cooldown, and about outages that go on as often as
+/// the website's reminder asks for until someone acknowledges them. Websites are told
+/// apart by id, so a rename keeps their window
+pub struct NotificationThrottle {
+    cooldown: TimeDelta,
+    states: HashMap<i32, HashMap<String, NotifiedState>>,
+}
+
+impl NotificationThrottle {
+    pub fn new(cooldown: TimeDelta) -> Self {
+        Self {
+            cooldown,
+            states: HashMap::new(),
+        }
+    }
+
+    pub fn knows(&self, website_id: i32) -> bool {
+        self.states.contains_key(&website_id)
+    }
+
+    /// Starts from the persisted states, so a restart neither repeats nor drops anything
+    pub fn seed(
+        &mut self,
+        website_id: i32,
+        states: impl IntoIterator<Item = (String, NotifiedState)>,
+    ) {
+        self.states.insert(website_id, states.into_iter().collect());
+    }
+
+    pub fn decide(
+        &self,
+        website: &Website,
+        channel: &str,
+        status: i16,
+        notice: &Notice,
+        acknowledged: bool,
+        now: DateTime<Utc>,
+    ) -> Decision {
+        let last = self
+            .states
+            .get(&website.id)
+            .and_then(|states| states.get(channel));
+        let unnotified = NotifiedState {
+            status,
+            since: now,
+            notified_at: now,
+        };
+
+        match (last, notice) {
+            (_, Notice::Umbrella(transition)) => Decision::Send(transition.clone()),
+            (_, Notice::Covered) => Decision::Record(unnotified),
+            (None, Notice::Transition(transition)) => Decision::Send(transition.clone()),
+            (None, Notice::Nothing) => Decision::Record(unnotified),
+            (Some(last), _) if is_up(last.status) == is_up(status) => {
+                match website
+                    .config
+                    .renotify_interval()
+                    .filter(|_| !is_up(status) && !acknowledged)
+                {
+                    Some(renotify_interval) if now - last.notified_at >= renotify_interval => {
+                        Decision::Send(Transition {
+                            alias: website.alias.clone(),
+                            url: website.url.clone(),
+                            old_status: status,
+                            new_status: status,
+                            timestamp: now,
+                            since: last.since,
+                            duration: (now - last.since).num_seconds(),
+                            inverted: website.invert,
+                            shared_host: None,
+                            affected: Vec::new(),
+                            reminder: true,
+                            warning: None,
+                            message: None,
+                        })
+                    }
+                    _ => Decision::Keep,
+                }
+            }
+            // Flapping, the change is caught up on once the cooldown is over
+            (Some(last), _) if now - last.notified_at < self.cooldown => Decision::Keep,
+            (Some(_), Notice::Transition(transition)) => Decision::Send(transition.clone()),
+            // The change happened while the channel was cooling down
+            (Some(last), Notice::Nothing) => Decision::Send(Transition {
+                alias: website.alias.clone(),
+                url: website.url.clone(),
+                old_status: last.status,
+                new_status: status,
+                timestamp: now,
+                since: last.since,
+                duration: (now - last.since).num_seconds(),
+                inverted: website.invert,
+                shared_host: None,
+                affected: Vec::new(),
+                reminder: false,
+                warning: None,
+                message: None,
+            }),
+        }
+    }
+
+    pub fn record(&mut self, website_id: i32, channel: String, state: NotifiedState) {
+        self.states
+            .entry(website_id)
+            .or_default()
+            .insert(channel, state);
+    }
+
+    /// Forgets websites that were deleted in the meantime
+    pub fn retain(&mut self, websites: &[Website]) {
+        self.states
+            .retain(|id, _| websites.iter().any(|website| website.id == *id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::website;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        "2026-10-16T08:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minute)
+    }
+
+    #[test]
+    fn a_renamed_website_keeps_its_window() {
+        let mut throttle = NotificationThrottle::new(TimeDelta::minutes(5));
+        let mut api = website("api", serde_json::json!({}));
+        api.id = 7;
+        throttle.record(
+            api.id,
+            "webhook:1".to_owned(),
+            NotifiedState {
+                status: 200,
+                since: at(0),
+                notified_at: at(0),
+            },
+        );
+        let mut renamed = website("api-v2", serde_json::json!({}));
+        renamed.id = api.id;
+
+        throttle.retain(std::slice::from_ref(&renamed));
+        let decision = throttle.decide(&renamed, "webhook:1", 599, &Notice::Nothing, false, at(2));
+
+        assert!(throttle.knows(7));
+        // Still cooling down from the last notification instead of starting over
+        assert!(matches!(decision, Decision::Keep));
+    }
+}
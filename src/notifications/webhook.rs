@@ -0,0 +1,40 @@
+use super::{NOTIFIER_TIMEOUT, Notifier, Transition};
+
+/// POSTs the transition as JSON to a URL
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+    transition: Transition,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: reqwest::Client, url: String, transition: Transition) -> Self {
+        Self {
+            client,
+            url,
+            transition,
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn target(&self) -> String {
+        format!("webhook {} about {}", self.url, self.transition.alias)
+    }
+
+    async fn notify(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&self.url)
+            .timeout(NOTIFIER_TIMEOUT)
+            .json(&self.transition)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(format!("receiver answered with {status}")),
+        }
+    }
+}
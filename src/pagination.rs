@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+/// Websites per page unless asked otherwise
+const DEFAULT_PER_PAGE: u32 = 25;
+/// However many are asked for
+const MAX_PER_PAGE: u32 = 100;
+
+/// `?page=&per_page=`, pages count from 1
+#[derive(Clone, Copy, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// 1 when left out
+    page: Option<u32>,
+    /// 25 when left out and at most 100
+    per_page: Option<u32>,
+}
+
+impl PageParams {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page().into()
+    }
+
+    /// Pages past the last one start past the end and come back empty
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page() - 1) * self.limit()
+    }
+
+    /// Which of `total` items are on the page
+    pub fn range(&self, total: usize) -> std::ops::Range<usize> {
+        let start = usize::try_from(self.offset())
+            .unwrap_or(usize::MAX)
+            .min(total);
+        let end = start.saturating_add(self.per_page() as usize).min(total);
+        start..end
+    }
+
+    pub fn with_total(&self, total: i64) -> Page {
+        Page {
+            page: self.page(),
+            per_page: self.per_page(),
+            total,
+        }
+    }
+}
+
+/// Where a page is among all of them, for the links between them
+#[derive(Clone, Serialize)]
+pub struct Page {
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+}
+
+impl Page {
+    pub fn previous(&self) -> Option<u32> {
+        (self.page > 1).then(|| self.page - 1)
+    }
+
+    pub fn next(&self) -> Option<u32> {
+        (i64::from(self.page) * i64::from(self.per_page) < self.total).then(|| self.page + 1)
+    }
+
+    pub fn last(&self) -> u32 {
+        let pages = u64::try_from(self.total)
+            .unwrap_or_default()
+            .div_ceil(self.per_page.into());
+        u32::try_from(pages).unwrap_or(u32::MAX).max(1)
+    }
+}
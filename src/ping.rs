@@ -0,0 +1,126 @@
+use crate::checker::{CheckResult, TIMEOUT_STATUS};
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr},
+    sync::atomic::{AtomicU16, Ordering},
+};
+use surge_ping::{Client, Config, ICMP, PingIdentifier, PingSequence};
+use tokio::{
+    net::{TcpStream, lookup_host},
+    time::{self, Duration, Instant},
+};
+use tracing::warn;
+
+/// How long a ping waits for the echo reply or the TCP handshake
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The echo service port, hosts answering with a reset are just as reachable
+const TCP_FALLBACK_PORT: u16 = 7;
+
+/// Sends ICMP echo requests, or connects to the echo port over TCP when this process
+/// isn't allowed to open ICMP sockets
+pub struct Pinger {
+    v4: Option<Client>,
+    v6: Option<Client>,
+    identifier: AtomicU16,
+}
+
+impl Pinger {
+    pub fn new() -> Self {
+        Self {
+            v4: icmp_client(ICMP::V4, "IPv4"),
+            v6: icmp_client(ICMP::V6, "IPv6"),
+            identifier: AtomicU16::new(std::process::id() as u16),
+        }
+    }
+
+    pub async fn ping(&self, target: &str) -> CheckResult {
+        let ip = match resolve(target).await {
+            Some(ip) => ip,
+            None => {
+                return CheckResult {
+                    error: Some(format!("could not resolve '{target}'")),
+                    ..CheckResult::failed()
+                };
+            }
+        };
+        let client = match ip {
+            IpAddr::V4(_) => self.v4.as_ref(),
+            IpAddr::V6(_) => self.v6.as_ref(),
+        };
+
+        let started = Instant::now();
+        let outcome = match client {
+            Some(client) => self.icmp_echo(client, ip).await,
+            None => tcp_connect(ip).await,
+        };
+
+        match outcome {
+            Ok(()) => CheckResult {
+                status: 200,
+                ip_family: Some(if ip.is_ipv4() { "v4" } else { "v6" }),
+                response_time_ms: Some(started.elapsed().as_millis().min(i32::MAX as u128) as i32),
+                ..CheckResult::failed()
+            },
+            Err(error) => CheckResult {
+                status: TIMEOUT_STATUS,
+                error: Some(error),
+                ..CheckResult::failed()
+            },
+        }
+    }
+
+    async fn icmp_echo(&self, client: &Client, ip: IpAddr) -> Result<(), String> {
+        let identifier = PingIdentifier(self.identifier.fetch_add(1, Ordering::Relaxed));
+        let mut pinger = client.pinger(ip, identifier).await;
+        pinger.timeout(PING_TIMEOUT);
+        pinger
+            .ping(PingSequence(0), &[0; 56])
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("no echo reply: {e}"))
+    }
+}
+
+/// `None` when ICMP sockets can't be opened, which usually takes CAP_NET_RAW or a
+/// matching net.ipv4.ping_group_range
+fn icmp_client(kind: ICMP, family: &str) -> Option<Client> {
+    match Client::new(&Config::builder().kind(kind).build()) {
+        Ok(client) => Some(client),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            warn!(
+                "Not permitted to open {family} ICMP sockets (needs CAP_NET_RAW or a matching net.ipv4.ping_group_range), ping monitors fall back to a TCP connect to port {TCP_FALLBACK_PORT}"
+            );
+            None
+        }
+        Err(e) => {
+            warn!(
+                "Opening {family} ICMP sockets failed: {e}, ping monitors fall back to a TCP connect to port {TCP_FALLBACK_PORT}"
+            );
+            None
+        }
+    }
+}
+
+async fn resolve(target: &str) -> Option<IpAddr> {
+    if let Ok(ip) = target.parse() {
+        return Some(ip);
+    }
+
+    lookup_host((target, 0))
+        .await
+        .ok()?
+        .next()
+        .map(|addr| addr.ip())
+}
+
+/// A refused connection still proves the host is up
+async fn tcp_connect(ip: IpAddr) -> Result<(), String> {
+    let addr = SocketAddr::new(ip, TCP_FALLBACK_PORT);
+    match time::timeout(PING_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) if e.kind() == ErrorKind::ConnectionRefused => Ok(()),
+        Ok(Err(e)) => Err(format!("TCP connect to {addr} failed: {e}")),
+        Err(_) => Err(format!("TCP connect to {addr} timed out")),
+    }
+}
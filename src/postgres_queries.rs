@@ -1,20 +1,337 @@
-pub const SELECT_MONTHLY_STATS: &str = r#"
-                Select date_trunc('day', created_at) as time,
-                CAST(COUNT(case when status = 200 then 1 end) * 100 / COUNT(*) AS int2) AS uptime_pct
+use crate::store::LatencyQuery;
+use sqlx::{PgConnection, PgPool};
+
+pub use crate::shared_queries::*;
+pub(crate) use crate::store::bind_numbered as bind;
+
+crate::store::impl_store!(PgPool, PgConnection);
+
+pub(crate) const LATENCY_QUERY: LatencyQuery = LatencyQuery::Percentiles {
+    by_hour: SELECT_LATENCY_BY_HOUR,
+    by_day: SELECT_LATENCY_BY_DAY,
+};
+
+pub const INSERT_INTO_LOGS_QUERY: &str = r#"INSERT INTO Logs
+                (website_id, status, ip_family, response_time_ms, connection_reused, degraded, error,
+                reason_phrase, server_header, response_headers, content_hash, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#;
+pub const SELECT_STATS_BY_DAY: &str = r#"
+                SELECT date_trunc('day', Buckets.bucket_start) as time,
+                CAST(SUM(Buckets.successes - case when Websites.strict
+                    then Buckets.degraded_successes else 0 end) * 100 / SUM(Buckets.checks) as int2) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 / SUM(Buckets.checks) as int2) as degraded_pct,
+                SUM(Buckets.checks) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = $1
+                    AND bucket_start >= $2
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    case when status = 200 then 1 else 0 end,
+                    case when degraded then 1 else 0 end,
+                    case when status = 200 AND degraded then 1 else 0 end
+                    FROM Logs
+                    WHERE website_id = $1
+                    AND created_at >= $2
+                    AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + interval '1 hour'
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '-infinity')
+                ) AS Buckets
+                INNER JOIN Websites on Websites.id = Buckets.website_id
+                GROUP BY time
+                ORDER BY time asc
+                "#;
+pub const SELECT_STATS_BY_HOUR: &str = r#"
+                SELECT date_trunc('hour', Buckets.bucket_start) as time,
+                CAST(SUM(Buckets.successes - case when Websites.strict
+                    then Buckets.degraded_successes else 0 end) * 100 / SUM(Buckets.checks) as int2) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 / SUM(Buckets.checks) as int2) as degraded_pct,
+                SUM(Buckets.checks) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = $1
+                    AND bucket_start >= $2
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    case when status = 200 then 1 else 0 end,
+                    case when degraded then 1 else 0 end,
+                    case when status = 200 AND degraded then 1 else 0 end
+                    FROM Logs
+                    WHERE website_id = $1
+                    AND created_at >= $2
+                    AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + interval '1 hour'
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '-infinity')
+                ) AS Buckets
+                INNER JOIN Websites on Websites.id = Buckets.website_id
+                GROUP BY time
+                ORDER BY time asc
+                "#;
+pub const SELECT_LATENCY_BY_DAY: &str = r#"
+                SELECT date_trunc('day', Logs.created_at) as time,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p50_ms,
+                percentile_cont(0.9) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p90_ms,
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p95_ms,
+                percentile_cont(0.99) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p99_ms,
+                CAST(MAX(Logs.response_time_ms) AS float8) as max_ms,
+                COUNT(*) as samples
                 FROM Logs
-                LEFT JOIN Websites ON Websites.id = Logs.website_id
-                WHERE Websites.alias = $1
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND Logs.created_at >= $2
+                AND Logs.response_time_ms IS NOT NULL
                 GROUP BY time
                 ORDER BY time asc
-                LIMIT 30
-            "#;
-pub const SELECT_DAILY_STATS: &str = r#"
-                SELECT date_trunc('hour', created_at) as time,
-                CAST(COUNT(case when status = 200 then 1 end) * 100 / COUNT(*) as int2) as uptime_pct
+                "#;
+pub const SELECT_LATENCY_BY_HOUR: &str = r#"
+                SELECT date_trunc('hour', Logs.created_at) as time,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p50_ms,
+                percentile_cont(0.9) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p90_ms,
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p95_ms,
+                percentile_cont(0.99) WITHIN GROUP (ORDER BY Logs.response_time_ms) as p99_ms,
+                CAST(MAX(Logs.response_time_ms) AS float8) as max_ms,
+                COUNT(*) as samples
                 FROM Logs
-                LEFT JOIN Websites on Websites.id = Logs.website_id
-                WHERE Websites.alias = $1
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND Logs.created_at >= $2
+                AND Logs.response_time_ms IS NOT NULL
                 GROUP BY time
                 ORDER BY time asc
-                LIMIT 24
                 "#;
+pub const SELECT_HOURLY_LATENCY_SERIES: &str = r#"
+                SELECT CAST(AVG(Logs.response_time_ms) AS float8) as latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.response_time_ms IS NOT NULL
+                AND Logs.created_at >= now() - interval '7 days 1 hour'
+                GROUP BY date_trunc('hour', Logs.created_at)
+                ORDER BY date_trunc('hour', Logs.created_at) asc
+                "#;
+/// ISODOW counts 1 = Monday to 7 = Sunday, shifted to start at 0
+pub const SELECT_UPTIME_HEATMAP: &str = r#"
+                SELECT CAST(EXTRACT(ISODOW FROM Logs.created_at) - 1 AS int4) as weekday,
+                CAST(EXTRACT(HOUR FROM Logs.created_at) AS int4) as hour,
+                CAST(COUNT(case when status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, false))
+                    then 1 end) * 100 / COUNT(*) as int2) as uptime_pct,
+                COUNT(*) as samples
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= now() - make_interval(weeks => $2)
+                GROUP BY weekday, hour
+                "#;
+/// Uptime like the daily and monthly stats, over the window from $2 until before $3
+pub const SELECT_WINDOW_STATS: &str = r#"
+                SELECT CAST(COUNT(case when status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, false))
+                    then 1 end) * 100.0 / NULLIF(COUNT(*), 0) as float8) as uptime_pct,
+                CAST(AVG(Logs.response_time_ms) AS float8) as average_latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= $2 AND Logs.created_at < $3
+                "#;
+pub const SELECT_STATUS_CODE_COUNTS: &str = r#"
+                SELECT Logs.status, COUNT(*) as count
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= $2 AND Logs.status IS NOT NULL
+                GROUP BY Logs.status
+                ORDER BY count DESC, Logs.status
+                "#;
+pub const SELECT_WINDOW_STATUSES: &str = r#"
+                SELECT Logs.created_at, Logs.status
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= $2 AND Logs.created_at < $3
+                ORDER BY Logs.created_at asc
+                "#;
+pub const SELECT_WINDOW_LOGS: &str = r#"
+                SELECT Logs.created_at, Logs.status, Logs.response_time_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= $2 AND Logs.created_at < $3
+                ORDER BY Logs.created_at ASC
+                "#;
+/// Latest check, uptime since $1 and since $2 and newest failed check since $2 of every
+/// website, $1 being the later of the two
+pub const SELECT_STATUS_SUMMARIES: &str = r#"
+                SELECT Websites.alias, Latest.status as latest_status,
+                CAST(COUNT(case when Logs.created_at >= $1 AND Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, false))
+                    then 1 end) * 100.0
+                    / NULLIF(COUNT(case when Logs.created_at >= $1 then 1 end), 0)
+                    as float8) as uptime_24h,
+                CAST(COUNT(case when Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, false))
+                    then 1 end) * 100.0 / NULLIF(COUNT(Logs.id), 0) as float8) as uptime_30d,
+                MAX(case when Logs.status <> 200 then Logs.created_at end) as last_incident
+                FROM Websites
+                LEFT JOIN Logs ON Logs.website_id = Websites.id AND Logs.created_at >= $2
+                LEFT JOIN Logs AS Latest ON Latest.id =
+                (SELECT Newest.id FROM Logs AS Newest WHERE Newest.website_id = Websites.id
+                ORDER BY Newest.created_at DESC LIMIT 1)
+                WHERE Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias, Websites.strict, Latest.status
+                ORDER BY Websites.alias
+                "#;
+/// Uptime, nearest rank p95 latency and outages started per calendar month from $2
+/// until before $3, outages counted by their first failed check
+pub const SELECT_MONTH_TRENDS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.created_at, Logs.status, Logs.response_time_ms,
+                    Websites.strict AND COALESCE(Logs.degraded, false) as strict_degraded,
+                    LAG(Logs.status) OVER (ORDER BY Logs.created_at) as previous_status
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE Websites.alias = $1
+                    AND Logs.created_at >= $2 AND Logs.created_at < $3
+                )
+                SELECT to_char(date_trunc('month', created_at), 'YYYY-MM') as month,
+                CAST(COUNT(case when status = 200 AND NOT strict_degraded
+                    then 1 end) * 100.0 / COUNT(*) as float8) as uptime_pct,
+                CAST(percentile_disc(0.95) WITHIN GROUP (ORDER BY response_time_ms)
+                    as float8) as latency_p95_ms,
+                COUNT(case when status <> 200
+                    AND (previous_status IS NULL OR previous_status = 200)
+                    then 1 end) as incidents,
+                MIN(created_at) as first_check_at
+                FROM Checks
+                GROUP BY month
+                ORDER BY month asc
+                "#;
+pub const DELETE_INCIDENT_UPDATES_BEFORE: &str = r#"
+                DELETE FROM IncidentUpdates WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < $2))
+                "#;
+pub const DELETE_INCIDENT_ESCALATIONS_BEFORE: &str = r#"
+                DELETE FROM IncidentEscalations WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < $2))
+                "#;
+pub const DELETE_LOGS_BEFORE: &str = r#"
+                DELETE FROM Logs WHERE id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < $2))
+                "#;
+/// Folds every website's checks from the hours before $1, which is on the hour, that
+/// aren't folded yet into StatsRollup
+pub const FOLD_STATS_ROLLUP: &str = r#"
+                INSERT INTO StatsRollup
+                (website_id, bucket_start, checks, successes, degraded, degraded_successes, avg_latency)
+                SELECT website_id, date_trunc('hour', created_at) as bucket,
+                COUNT(*),
+                COUNT(case when status = 200 then 1 end),
+                COUNT(case when degraded then 1 end),
+                COUNT(case when status = 200 AND degraded then 1 end),
+                AVG(response_time_ms)
+                FROM Logs
+                WHERE created_at < $1
+                AND created_at >= COALESCE((SELECT MAX(Folded.bucket_start) + interval '1 hour'
+                    FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '-infinity')
+                GROUP BY website_id, bucket
+                "#;
+pub const DELETE_STATS_ROLLUP_BEFORE: &str = r#"
+                DELETE FROM StatsRollup WHERE website_id IN
+                (SELECT id FROM Websites WHERE alias = $1)
+                AND ($2 IS NULL OR bucket_start < $2)
+                "#;
+pub const SELECT_FILTERED_INCIDENTS: &str = r#"
+                SELECT Logs.id, Logs.created_at as time,
+                Logs.status, Logs.ip_family,
+                COALESCE(Logs.impact, Websites.impact_weight) as impact,
+                Logs.error, Logs.reason_phrase, Logs.server_header, Logs.response_headers,
+                Logs.acknowledged_by FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Logs.website_id = $1 AND Logs.status <> 200
+                AND ($2 IS NULL OR Logs.created_at >= $2)
+                AND ($3 IS NULL OR Logs.created_at < $3)
+                AND ($4 IS NULL OR Logs.status = $4)
+                ORDER BY Logs.created_at DESC, Logs.id DESC
+                LIMIT $5
+                "#;
+/// Checks, good checks, outages started and seconds down of every website or just $1
+/// from $2 until before $3. A failed check counts as down until the next check, the last
+/// one until $3
+pub const SELECT_SLA_COUNTS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.website_id, Logs.created_at, Logs.status,
+                    Websites.strict AND COALESCE(Logs.degraded, false) as strict_degraded,
+                    LAG(Logs.status) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as previous_status,
+                    LEAD(Logs.created_at) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as next_check_at
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE ($1::text IS NULL OR Websites.alias = $1)
+                    AND Logs.created_at >= $2 AND Logs.created_at < $3
+                )
+                SELECT Websites.alias,
+                COUNT(Checks.created_at) as total_checks,
+                COUNT(case when Checks.status = 200 AND NOT Checks.strict_degraded
+                    then 1 end) as successful_checks,
+                COUNT(case when Checks.status <> 200
+                    AND (Checks.previous_status IS NULL OR Checks.previous_status = 200)
+                    then 1 end) as incidents,
+                CAST(COALESCE(SUM(case when Checks.status <> 200
+                    then EXTRACT(EPOCH FROM COALESCE(Checks.next_check_at, $3)
+                        - Checks.created_at) end), 0) as bigint) as downtime_secs
+                FROM Websites
+                LEFT JOIN Checks ON Checks.website_id = Websites.id
+                WHERE ($1::text IS NULL OR Websites.alias = $1) AND Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias
+                ORDER BY Websites.alias
+                "#;
+/// Up to $2 of the checks before $1 of the websites whose retention_days is $3, NULL
+/// for the ones going by --retention-days, oldest first. Failed checks no successful one
+/// followed belong to an incident that is still open and are kept
+pub const DELETE_EXPIRED_LOGS_BATCH: &str = r#"
+                DELETE FROM Logs WHERE id IN
+                (SELECT Old.id FROM Logs AS Old
+                WHERE Old.created_at < $1
+                AND (Old.status = 200 OR EXISTS (SELECT 1 FROM Logs AS Up
+                    WHERE Up.website_id = Old.website_id AND Up.status = 200
+                    AND Up.created_at > Old.created_at))
+                AND Old.website_id IN (SELECT id FROM Websites
+                    WHERE retention_days = $3 OR ($3 IS NULL AND retention_days IS NULL))
+                ORDER BY Old.id
+                LIMIT $2)
+                "#;
+pub const SELECT_ANY_WEBSITE: &str = "SELECT EXISTS (SELECT 1 FROM Websites)";
+/// Websites whose alias is taken already are left as they are, so `migrate-data` can be
+/// run again. A NULL created_at becomes the time of the copy. Returns no id when skipped
+pub const INSERT_COPIED_WEBSITE: &str = "INSERT INTO Websites
+    (url, alias, created_at, ip_version, impact_weight, config, monitor_type,
+    client_cert_path, client_key_path, client_cert_encrypted, client_key_encrypted,
+    client_cert_fingerprint, client_cert_expires_at, slack_webhook_url, discord_webhook_url,
+    invert, alert_email_to, tls_cert_serial, tls_cert_expires_at, tls_expiry_notified_days,
+    status_override, status_override_note, status_override_expires_at, deleted_at,
+    retention_days)
+    VALUES ($1, $2, COALESCE($3, now()), $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+    $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25)
+    ON CONFLICT (alias) DO NOTHING
+    RETURNING id";
+/// The website an earlier `migrate-data` copied, with the url and created_at of the
+/// Sqlite one. Without a created_at the copy got the time it ran, so only the url is
+/// compared
+pub const SELECT_COPIED_WEBSITE_ID: &str = "SELECT id FROM Websites
+    WHERE alias = $1 AND url = $2 AND ($3::timestamptz IS NULL OR created_at = $3)";
+/// A check its website has at the same time already is skipped
+pub const INSERT_COPIED_LOG: &str = "INSERT INTO Logs
+    (website_id, status, created_at, ip_family, impact, response_time_ms, connection_reused,
+    degraded, error, reason_phrase, server_header, response_headers, content_hash,
+    acknowledged_by, acknowledged_at, ack_escalated_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+    ON CONFLICT (website_id, created_at) DO NOTHING";
+pub const INSERT_COPIED_STATS_ROLLUP: &str = "INSERT INTO StatsRollup
+    (website_id, bucket_start, checks, successes, degraded, degraded_successes, avg_latency)
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+    ON CONFLICT (website_id, bucket_start) DO NOTHING";
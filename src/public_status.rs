@@ -0,0 +1,104 @@
+use crate::{
+    impact::Impact,
+    manual_status::{self, StatusOverride},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Bumped whenever a field changes meaning or goes away, new fields don't bump it
+const VERSION: u32 = 1;
+
+/// How long clients and proxies may reuse the status, it's polled a lot
+pub const CACHE_CONTROL: &str = "public, max-age=15";
+
+#[derive(Clone, Copy, Serialize, ToSchema)]
+pub enum OverallState {
+    #[serde(rename = "all operational")]
+    AllOperational,
+    #[serde(rename = "partial outage")]
+    PartialOutage,
+    #[serde(rename = "major outage")]
+    MajorOutage,
+}
+
+impl From<Option<Impact>> for OverallState {
+    fn from(impact: Option<Impact>) -> Self {
+        match impact {
+            None => Self::AllOperational,
+            Some(Impact::Minor | Impact::Major) => Self::PartialOutage,
+            Some(Impact::Critical) => Self::MajorOutage,
+        }
+    }
+}
+
+/// One website as the status queries aggregate it
+#[derive(sqlx::FromRow)]
+pub struct StatusSummary {
+    pub alias: String,
+    /// `None` before the first check
+    pub latest_status: Option<i16>,
+    pub uptime_24h: Option<f64>,
+    pub uptime_30d: Option<f64>,
+    /// Newest failed check within the last 30 days
+    pub last_incident: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WebsiteStatus {
+    pub alias: String,
+    /// "up", "down" or "unknown" from the latest check, the manual status instead while
+    /// an operator overrides it
+    #[schema(value_type = String)]
+    pub status: &'static str,
+    pub uptime_24h: Option<f64>,
+    pub uptime_30d: Option<f64>,
+    pub last_incident: Option<DateTime<Utc>>,
+}
+
+/// Everything `/status.json` reports, the overall state as on the dashboard's banner
+#[derive(Serialize, ToSchema)]
+pub struct PublicStatus {
+    pub version: u32,
+    pub overall: OverallState,
+    pub websites: Vec<WebsiteStatus>,
+}
+
+impl PublicStatus {
+    pub fn new(
+        summaries: Vec<StatusSummary>,
+        active_incidents: &[(String, String)],
+        overrides: &[StatusOverride],
+    ) -> Self {
+        let (overall_impact, _) = manual_status::overall_status(active_incidents, overrides);
+        let websites = summaries
+            .into_iter()
+            .map(|summary| {
+                let manual_status = overrides
+                    .iter()
+                    .find(|status_override| status_override.alias == summary.alias)
+                    .and_then(StatusOverride::manual_status);
+                let status = match (manual_status, summary.latest_status) {
+                    (Some(manual_status), _) => manual_status.as_str(),
+                    (None, Some(200)) => "up",
+                    (None, Some(_)) => "down",
+                    (None, None) => "unknown",
+                };
+
+                WebsiteStatus {
+                    alias: summary.alias,
+                    status,
+                    uptime_24h: summary.uptime_24h,
+                    uptime_30d: summary.uptime_30d,
+                    last_incident: summary.last_incident,
+                }
+            })
+            .collect();
+
+        Self {
+            version: VERSION,
+            overall: overall_impact.into(),
+            websites,
+        }
+    }
+}
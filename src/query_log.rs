@@ -0,0 +1,149 @@
+use askama::Template;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Remembers the slowest dashboard queries since startup, so problem queries can be found
+/// without attaching external tooling
+#[derive(Clone)]
+pub struct QueryLog {
+    /// Queries slower than this are logged as they happen
+    warn_threshold: Duration,
+    capacity: usize,
+    slowest: Arc<Mutex<Vec<SlowQuery>>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SlowQuery {
+    pub label: &'static str,
+    pub alias: Option<String>,
+    pub duration_ms: u64,
+    pub time: DateTime<Utc>,
+}
+
+impl QueryLog {
+    pub fn new(warn_threshold: Duration, capacity: usize) -> Self {
+        Self {
+            warn_threshold,
+            capacity,
+            slowest: Arc::new(Mutex::new(Vec::with_capacity(capacity + 1))),
+        }
+    }
+
+    /// Awaits the query and records how long it took under `label`
+    pub async fn time<T>(
+        &self,
+        label: &'static str,
+        alias: Option<&str>,
+        query: impl Future<Output = T>,
+    ) -> T {
+        let started = Instant::now();
+        let output = query.await;
+        self.record(label, alias, started.elapsed());
+        output
+    }
+
+    fn record(&self, label: &'static str, alias: Option<&str>, duration: Duration) {
+        if duration > self.warn_threshold {
+            warn!(
+                "Slow query {label}{} took {}ms",
+                alias
+                    .map(|alias| format!(" for {alias}"))
+                    .unwrap_or_default(),
+                duration.as_millis()
+            );
+        }
+
+        let duration_ms = duration.as_millis() as u64;
+        let mut slowest = self.slowest.lock().unwrap();
+        if slowest.len() == self.capacity
+            && slowest
+                .last()
+                .is_none_or(|fastest| fastest.duration_ms >= duration_ms)
+        {
+            return;
+        }
+        // Kept sorted slowest first, so the fastest entry is the one that drops out
+        let position = slowest.partition_point(|query| query.duration_ms >= duration_ms);
+        slowest.insert(
+            position,
+            SlowQuery {
+                label,
+                alias: alias.map(str::to_owned),
+                duration_ms,
+                time: Utc::now(),
+            },
+        );
+        slowest.truncate(self.capacity);
+    }
+
+    /// Slowest first
+    pub fn slowest(&self) -> Vec<SlowQuery> {
+        self.slowest.lock().unwrap().clone()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "slow_queries.html")]
+pub struct SlowQueriesPage {
+    pub queries: Vec<SlowQuery>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::sleep;
+
+    /// A query taking `ms` milliseconds
+    async fn query(ms: u64) -> u64 {
+        sleep(Duration::from_millis(ms)).await;
+        ms
+    }
+
+    fn labels(query_log: &QueryLog) -> Vec<&'static str> {
+        query_log
+            .slowest()
+            .iter()
+            .map(|query| query.label)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn queries_are_kept_slowest_first() {
+        let query_log = QueryLog::new(Duration::from_secs(60), 10);
+
+        assert_eq!(query_log.time("fast", None, query(5)).await, 5);
+        query_log.time("slow", Some("blog"), query(60)).await;
+        query_log.time("medium", None, query(30)).await;
+
+        assert_eq!(labels(&query_log), ["slow", "medium", "fast"]);
+        let slowest = &query_log.slowest()[0];
+        assert_eq!(slowest.alias.as_deref(), Some("blog"));
+        assert!(slowest.duration_ms >= 60, "{}", slowest.duration_ms);
+    }
+
+    #[tokio::test]
+    async fn only_the_slowest_are_kept_once_full() {
+        let query_log = QueryLog::new(Duration::from_secs(60), 2);
+
+        query_log.time("medium", None, query(30)).await;
+        query_log.time("fast", None, query(5)).await;
+        query_log.time("slow", None, query(60)).await;
+        query_log.time("faster", None, query(1)).await;
+
+        assert_eq!(labels(&query_log), ["slow", "medium"]);
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_log() {
+        let query_log = QueryLog::new(Duration::from_secs(60), 10);
+
+        query_log.clone().time("cloned", None, query(1)).await;
+
+        assert_eq!(labels(&query_log), ["cloned"]);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::ApiError;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How often buckets that filled up again are dropped
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token buckets per client IP for the endpoints that write, each holding a minute's
+/// worth of requests and refilling continuously
+#[derive(Clone)]
+pub struct RateLimiter {
+    per_minute: u32,
+    /// Clients are taken from `X-Forwarded-For` as the proxy in front saw them
+    trust_proxy: bool,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32, trust_proxy: bool) -> Self {
+        Self {
+            per_minute: per_minute.max(1),
+            trust_proxy,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn per_second(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+
+    /// Takes a token from the client's bucket, how long until the next one otherwise
+    fn acquire(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(self.per_minute);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.per_second()).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.per_second(),
+            ))
+        }
+    }
+
+    /// Drops buckets that are full again, those clients start over with a full one anyway
+    fn evict_stale(&self, now: Instant) {
+        let full_after = Duration::from_secs(60);
+        self.buckets
+            .lock()
+            .unwrap()
+            .retain(|_, bucket| now.saturating_duration_since(bucket.refilled_at) < full_after);
+    }
+
+    fn client(&self, headers: &HeaderMap, peer: IpAddr) -> IpAddr {
+        if !self.trust_proxy {
+            return peer;
+        }
+
+        // The last entry is the one the trusted proxy added, earlier ones came from the
+        // client and could be anything
+        headers
+            .get_all("X-Forwarded-For")
+            .iter()
+            .filter_map(|forwarded_for| forwarded_for.to_str().ok())
+            .flat_map(|forwarded_for| forwarded_for.split(','))
+            .next_back()
+            .and_then(|client| client.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+}
+
+/// Evicts stale buckets for as long as the server runs
+pub async fn evict_stale_buckets(rate_limiter: RateLimiter) {
+    let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        rate_limiter.evict_stale(Instant::now());
+    }
+}
+
+/// Answers 429 with `Retry-After` once the client used up its bucket
+pub async fn limit(
+    State(rate_limiter): State<RateLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = rate_limiter.client(request.headers(), peer.ip());
+    match rate_limiter.acquire(client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => ApiError::TooManyRequests(retry_after).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+    const PROXY: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn a_bucket_runs_out_and_refills_over_time() {
+        let rate_limiter = RateLimiter::new(60, false);
+        let start = Instant::now();
+        for _ in 0..60 {
+            rate_limiter.acquire(CLIENT, start).unwrap();
+        }
+        assert_eq!(
+            rate_limiter.acquire(CLIENT, start),
+            Err(Duration::from_secs(1))
+        );
+
+        let later = start + Duration::from_millis(2500);
+        rate_limiter.acquire(CLIENT, later).unwrap();
+        rate_limiter.acquire(CLIENT, later).unwrap();
+        assert_eq!(
+            rate_limiter.acquire(CLIENT, later),
+            Err(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn clients_have_buckets_of_their_own() {
+        let rate_limiter = RateLimiter::new(1, false);
+        let now = Instant::now();
+        rate_limiter.acquire(CLIENT, now).unwrap();
+        assert!(rate_limiter.acquire(CLIENT, now).is_err());
+        rate_limiter.acquire(PROXY, now).unwrap();
+    }
+
+    #[test]
+    fn buckets_untouched_for_a_minute_are_evicted() {
+        let rate_limiter = RateLimiter::new(60, false);
+        let start = Instant::now();
+        rate_limiter.acquire(CLIENT, start).unwrap();
+        rate_limiter
+            .acquire(PROXY, start + Duration::from_secs(30))
+            .unwrap();
+
+        rate_limiter.evict_stale(start + Duration::from_secs(59));
+        assert_eq!(rate_limiter.buckets.lock().unwrap().len(), 2);
+
+        rate_limiter.evict_stale(start + Duration::from_secs(60));
+        let buckets = rate_limiter.buckets.lock().unwrap();
+        assert!(!buckets.contains_key(&CLIENT));
+        assert!(buckets.contains_key(&PROXY));
+    }
+
+    fn forwarded_for(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("X-Forwarded-For", value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_unless_the_proxy_is_trusted() {
+        let headers = forwarded_for(&["192.0.2.1"]);
+        assert_eq!(RateLimiter::new(60, false).client(&headers, PROXY), PROXY);
+    }
+
+    #[test]
+    fn a_trusted_proxy_names_the_client_in_the_last_entry() {
+        let rate_limiter = RateLimiter::new(60, true);
+        let headers = forwarded_for(&["203.0.113.9, 198.51.100.7", "192.0.2.1"]);
+        assert_eq!(rate_limiter.client(&headers, PROXY), CLIENT);
+
+        let headers = forwarded_for(&["203.0.113.9, 192.0.2.1 "]);
+        assert_eq!(rate_limiter.client(&headers, PROXY), CLIENT);
+
+        let headers = forwarded_for(&["192.0.2.1, not-an-ip"]);
+        assert_eq!(rate_limiter.client(&headers, PROXY), PROXY);
+        assert_eq!(rate_limiter.client(&HeaderMap::new(), PROXY), PROXY);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    AppState, mysql_queries, on_db, postgres_queries, rollup,
+    shared_queries::SELECT_RETENTION_OVERRIDES_QUERY, sqlite_queries, store::Store,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{sync::Arc, time::Duration};
+use tokio::time;
+use tracing::{info, warn};
+
+/// Checks deleted per transaction, few enough that the database isn't held for long
+const BATCH_SIZE: i64 = 5000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Lets the checker and page loads have the database between two batches
+const BATCH_PAUSE: Duration = Duration::from_millis(100);
+/// How long deleted websites can be restored before they're purged
+pub const RESTORE_WINDOW: TimeDelta = TimeDelta::days(30);
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// `--retention-days`, what websites without their own retention keep
+#[derive(Clone, Copy)]
+pub struct Retention(pub Option<u32>);
+
+impl Retention {
+    /// Days a website's checks are kept for, `None` when they're kept forever. The
+    /// website's own retention takes precedence
+    pub fn effective(self, retention_days: Option<i32>) -> Option<u32> {
+        retention_days
+            .and_then(|days| u32::try_from(days).ok())
+            .or(self.0)
+    }
+}
+
+/// Deletes the checks older than their website's retention right away and then once a
+/// day, until the process ends. Websites sharing a retention are pruned together.
+/// Incident updates and escalations go along with their check. Checks are folded into
+/// the hourly stats first and only folded ones are deleted, so the stats outlive them
+pub async fn prune_logs(app_state: AppState, retention: Retention, vacuum: bool) {
+    let mut interval = time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        let folded_until = rollup::fold_cutoff(now);
+        if let Err(e) = rollup::fold_before(&app_state, folded_until).await {
+            warn!("Folding the checks from before {folded_until} failed, pruning waits: {e}");
+            continue;
+        }
+        let overrides = match retention_overrides(&app_state).await {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                warn!("Looking up the retention of the websites failed: {e}");
+                continue;
+            }
+        };
+        for retention_days in overrides {
+            let Some(days) = retention.effective(retention_days) else {
+                continue;
+            };
+            let cutoff = (now - TimeDelta::days(days.into())).min(folded_until);
+            match prune_before(&app_state, cutoff, retention_days).await {
+                Ok(pruned) => info!(
+                    "Pruned {pruned} checks from before {cutoff} of websites keeping {days} days"
+                ),
+                Err(e) => warn!("Pruning the checks from before {cutoff} failed: {e}"),
+            }
+        }
+        if vacuum
+            && let AppState::Sqlite(s) = &app_state
+            && let Err(e) = sqlx::query(sqlite_queries::INCREMENTAL_VACUUM)
+                .execute(s)
+                .await
+        {
+            warn!("Vacuuming the Sqlite database failed: {e}");
+        }
+    }
+}
+
+/// Every distinct `Websites.retention_days`, `None` standing for the websites going by
+/// `--retention-days`
+async fn retention_overrides(app_state: &AppState) -> Result<Vec<Option<i32>>, sqlx::Error> {
+    on_db!(app_state, |db| {
+        sqlx::query_scalar(SELECT_RETENTION_OVERRIDES_QUERY)
+            .fetch_all(db)
+            .await
+    })
+}
+
+/// Batch after batch until one comes back short, only of the websites whose
+/// `retention_days` is `retention_days`
+async fn prune_before(
+    app_state: &AppState,
+    cutoff: DateTime<Utc>,
+    retention_days: Option<i32>,
+) -> Result<u64, sqlx::Error> {
+    let mut pruned = 0;
+    loop {
+        let deleted = match app_state {
+            AppState::Postgres(p) => sqlx::query(postgres_queries::DELETE_EXPIRED_LOGS_BATCH)
+                .bind(cutoff)
+                .bind(BATCH_SIZE)
+                .bind(retention_days)
+                .execute(p)
+                .await?
+                .rows_affected(),
+            AppState::Sqlite(s) => sqlx::query(sqlite_queries::DELETE_EXPIRED_LOGS_BATCH)
+                .bind(cutoff)
+                .bind(BATCH_SIZE)
+                .bind(retention_days)
+                .execute(s)
+                .await?
+                .rows_affected(),
+            AppState::MySql(m) => sqlx::query(mysql_queries::DELETE_EXPIRED_LOGS_BATCH)
+                .bind(cutoff)
+                .bind(retention_days)
+                .bind(retention_days)
+                .bind(BATCH_SIZE)
+                .execute(m)
+                .await?
+                .rows_affected(),
+        };
+        pruned += deleted;
+        if deleted < BATCH_SIZE as u64 {
+            return Ok(pruned);
+        }
+        time::sleep(BATCH_PAUSE).await;
+    }
+}
+
+/// Purges the websites deleted longer ago than the restore window right away and then
+/// once an hour, until the process ends. One failing doesn't hold up the others
+pub async fn purge_deleted_websites(store: Arc<dyn Store>) {
+    let mut interval = time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let deleted_before = Utc::now() - RESTORE_WINDOW;
+        let aliases = match store.purgeable_websites(deleted_before).await {
+            Ok(aliases) => aliases,
+            Err(e) => {
+                warn!("Looking up the deleted websites to purge failed: {e:?}");
+                continue;
+            }
+        };
+        for alias in aliases {
+            match store.purge_website(&alias).await {
+                Ok(()) => info!("Purged {alias}, deleted before {deleted_before}"),
+                Err(e) => warn!("Purging {alias} failed: {e:?}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{check, on_every_backend, website};
+
+    /// A website keeping a day and one going by the default, each pruned on its own. The
+    /// failed check without an up one after it is the start of an open incident and stays
+    async fn only_expired_checks_of_the_retention_are_pruned(db: AppState) {
+        let store = db.store();
+        for (alias, retention_days) in [("short", Some("1")), ("default", None)] {
+            let website = website(alias, serde_json::json!({"retention_days": retention_days}));
+            store.insert_website(&website).await.unwrap();
+        }
+        let now = Utc::now();
+        store
+            .write_logs(&[
+                check("short", 200, now - TimeDelta::days(3)),
+                check(
+                    "short",
+                    599,
+                    now - TimeDelta::days(3) + TimeDelta::minutes(1),
+                ),
+                check("short", 200, now - TimeDelta::hours(1)),
+                check("default", 200, now - TimeDelta::days(4)),
+                check("default", 599, now - TimeDelta::days(3)),
+            ])
+            .await
+            .unwrap();
+        let cutoff = now - TimeDelta::days(1);
+
+        let short = prune_before(&db, cutoff, Some(1)).await.unwrap();
+        let default = prune_before(&db, cutoff, None).await.unwrap();
+
+        let mut overrides = retention_overrides(&db).await.unwrap();
+        overrides.sort();
+        assert_eq!(overrides, [None, Some(1)]);
+        assert_eq!((short, default), (2, 1));
+        let left: Vec<(i16,)> = on_db!(&db, |db| sqlx::query_as(
+            "SELECT status FROM Logs ORDER BY created_at"
+        )
+        .fetch_all(db)
+        .await
+        .unwrap());
+        assert_eq!(left, [(599,), (200,)]);
+    }
+
+    on_every_backend!(only_expired_checks_of_the_retention_are_pruned, "retention");
+}
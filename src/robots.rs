@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Request, State},
+    http::header::{CONTENT_TYPE, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// Whether `--allow-indexing` was given, search engines are kept out otherwise
+#[derive(Clone, Copy)]
+pub struct Indexing(pub bool);
+
+tokio::task_local! {
+    /// The setting of the request being handled, for the layout rendered inside it
+    static ALLOW_INDEXING: bool;
+}
+
+/// Content of the layout's `<meta name="robots">`, noindex outside of a request
+pub fn meta_content() -> &'static str {
+    if ALLOW_INDEXING.try_with(|allow| *allow).unwrap_or(false) {
+        "index, follow"
+    } else {
+        "noindex, nofollow"
+    }
+}
+
+/// Points crawlers at the sitemap when indexing is allowed, turns all of them away
+/// otherwise
+pub fn robots_txt(Indexing(allow): Indexing, sitemap_url: Option<&str>) -> String {
+    if !allow {
+        return "User-agent: *\nDisallow: /\n".to_owned();
+    }
+
+    let mut robots = "User-agent: *\nAllow: /\n".to_owned();
+    if let Some(sitemap_url) = sitemap_url {
+        robots.push_str(&format!("Sitemap: {sitemap_url}\n"));
+    }
+    robots
+}
+
+/// The dashboard and every website's page, `base_url` without a trailing slash
+pub fn sitemap_xml<'a>(base_url: &str, aliases: impl IntoIterator<Item = &'a str>) -> String {
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    sitemap.push_str(&format!("  <url><loc>{}/</loc></url>\n", escape(base_url)));
+    for alias in aliases {
+        sitemap.push_str(&format!(
+            "  <url><loc>{}/websites/{}</loc></url>\n",
+            escape(base_url),
+            escape(alias)
+        ));
+    }
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+/// Marks HTML responses as not to be indexed unless indexing is allowed, for crawlers
+/// that never read robots.txt, and renders the layout's meta tag to match
+pub async fn noindex_html(
+    State(Indexing(allow)): State<Indexing>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = ALLOW_INDEXING.scope(allow, next.run(request)).await;
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if is_html && !allow {
+        response.headers_mut().insert(
+            "X-Robots-Tag",
+            HeaderValue::from_static("noindex, nofollow"),
+        );
+    }
+
+    response
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,107 @@
+use crate::{AppState, on_db};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use std::time::Duration;
+use tokio::time;
+use tracing::{info, warn};
+
+/// Checks stay raw for this long before they're folded into hourly buckets
+const RAW_HOURS: i64 = 48;
+const FOLD_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Checks from before this are folded, it's on the hour so no bucket is folded while
+/// checks can still arrive for it
+pub fn fold_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    let cutoff = now - TimeDelta::hours(RAW_HOURS);
+    cutoff.duration_trunc(TimeDelta::hours(1)).unwrap_or(cutoff)
+}
+
+/// Folds the checks that got old enough right away and then once an hour, until the
+/// process ends
+pub async fn fold_stats(app_state: AppState) {
+    let mut interval = time::interval(FOLD_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = fold_cutoff(Utc::now());
+        match fold_before(&app_state, cutoff).await {
+            Ok(folded) => info!("Folded checks from before {cutoff} into {folded} hourly buckets"),
+            Err(e) => warn!("Folding the checks from before {cutoff} failed: {e}"),
+        }
+    }
+}
+
+/// Only the hours after each website's last bucket are folded, so this can run any
+/// number of times without counting a check twice
+pub async fn fold_before(app_state: &AppState, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let folded = on_db!(app_state, |db| sqlx::query(FOLD_STATS_ROLLUP)
+        .bind(cutoff)
+        .execute(db)
+        .await?
+        .rows_affected());
+    Ok(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{CONNECTION_FAILED_STATUS, PendingLog};
+    use crate::stats_range::SplitBy;
+    use crate::tests::{check, on_every_backend, website};
+
+    /// Three hours of checks three days back, every seventh failed and every fifth
+    /// degraded, counting as down since the website is strict
+    async fn website_with_old_checks(db: &AppState) -> i32 {
+        let store = db.store();
+        let website = website(
+            "rolled",
+            serde_json::json!({"strict": "on", "degraded_threshold_ms": "500"}),
+        );
+        let id = store.insert_website(&website).await.unwrap().id;
+        let start = fold_cutoff(Utc::now()) - TimeDelta::days(1);
+        let checks: Vec<_> = (0..180)
+            .map(|minute| {
+                let at = start + TimeDelta::minutes(minute.into());
+                if minute % 7 == 0 {
+                    return check("rolled", CONNECTION_FAILED_STATUS, at);
+                }
+                PendingLog {
+                    response_time_ms: Some(100 + minute),
+                    degraded: Some(minute % 5 == 0),
+                    ..check("rolled", 200, at)
+                }
+            })
+            .collect();
+        store.write_logs(&checks).await.unwrap();
+        id
+    }
+
+    async fn stats(db: &AppState, id: i32, split_by: SplitBy) -> Vec<(String, Option<i16>, i64)> {
+        let first_bucket = Utc::now() - TimeDelta::days(7);
+        db.store()
+            .stats(id, split_by, first_bucket)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|stats| (stats.time.to_rfc3339(), stats.uptime_pct, stats.checks))
+            .collect()
+    }
+
+    async fn rolled_up_uptime_matches_the_raw_checks(db: AppState) {
+        let id = website_with_old_checks(&db).await;
+        let raw_hours = stats(&db, id, SplitBy::Hour).await;
+        let raw_days = stats(&db, id, SplitBy::Day).await;
+
+        let folded = fold_before(&db, fold_cutoff(Utc::now())).await.unwrap();
+        let refolded = fold_before(&db, fold_cutoff(Utc::now())).await.unwrap();
+        on_db!(&db, |db| {
+            sqlx::query("DELETE FROM Logs").execute(db).await.unwrap();
+        });
+
+        assert_eq!(folded, 3);
+        assert_eq!(refolded, 0);
+        assert_eq!(raw_hours.len(), 3);
+        assert_eq!(stats(&db, id, SplitBy::Hour).await, raw_hours);
+        assert_eq!(stats(&db, id, SplitBy::Day).await, raw_days);
+    }
+
+    on_every_backend!(rolled_up_uptime_matches_the_raw_checks, "rollup");
+}
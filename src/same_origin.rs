@@ -0,0 +1,93 @@
+use crate::ApiError;
+use axum::{
+    extract::Request,
+    http::{
+        HeaderMap, Method,
+        header::{HOST, ORIGIN},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+const SEC_FETCH_SITE: &str = "Sec-Fetch-Site";
+
+/// Refuses changes a page on another site made the browser send, the login cookie goes
+/// along with those. Reads are let through like `require_api_key` does
+pub async fn require_same_origin(request: Request, next: Next) -> Response {
+    let read_only = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    if read_only || is_same_origin(request.headers()) {
+        return next.run(request).await;
+    }
+
+    ApiError::Forbidden.into_response()
+}
+
+/// `Sec-Fetch-Site` when the browser sends it, otherwise `Origin` has to name the host the
+/// request went to. Clients that send neither, like curl, aren't browsers and pass
+fn is_same_origin(headers: &HeaderMap) -> bool {
+    if let Some(site) = headers.get(SEC_FETCH_SITE) {
+        // "none" is the user typing the address or following a bookmark
+        return site == "same-origin" || site == "none";
+    }
+
+    let Some(origin) = headers.get(ORIGIN) else {
+        return true;
+    };
+    let origin_host = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.split_once("://"))
+        .map(|(_, host)| host);
+    let host = headers.get(HOST).and_then(|host| host.to_str().ok());
+    origin_host.is_some_and(|origin_host| Some(origin_host) == host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn fetch_metadata_decides_when_present() {
+        assert!(is_same_origin(&headers(&[(SEC_FETCH_SITE, "same-origin")])));
+        assert!(is_same_origin(&headers(&[(SEC_FETCH_SITE, "none")])));
+        assert!(!is_same_origin(&headers(&[(SEC_FETCH_SITE, "cross-site")])));
+        assert!(!is_same_origin(&headers(&[(SEC_FETCH_SITE, "same-site")])));
+        assert!(!is_same_origin(&headers(&[
+            (SEC_FETCH_SITE, "cross-site"),
+            ("host", "status.example.com"),
+            ("origin", "https://status.example.com"),
+        ])));
+    }
+
+    #[test]
+    fn origin_has_to_match_the_host() {
+        assert!(is_same_origin(&headers(&[
+            ("host", "localhost:3000"),
+            ("origin", "http://localhost:3000"),
+        ])));
+        assert!(!is_same_origin(&headers(&[
+            ("host", "localhost:3000"),
+            ("origin", "https://evil.example"),
+        ])));
+        assert!(!is_same_origin(&headers(&[
+            ("host", "localhost:3000"),
+            ("origin", "null"),
+        ])));
+    }
+
+    #[test]
+    fn clients_without_either_header_pass() {
+        assert!(is_same_origin(&headers(&[("host", "localhost:3000")])));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::Website;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use std::{collections::HashMap, str::FromStr};
+use tracing::warn;
+
+/// How often websites without a cron schedule are checked
+pub const CHECK_INTERVAL_SECS: i64 = 60;
+
+/// Parses a cron expression, the classic five field form is accepted as well and runs
+/// at second zero
+pub fn parse_cron(expression: &str) -> Result<Schedule, cron::error::Error> {
+    let expression = expression.trim();
+    if expression.split_whitespace().count() == 5 {
+        Schedule::from_str(&format!("0 {expression}"))
+    } else {
+        Schedule::from_str(expression)
+    }
+}
+
+/// When a website is due next after `after`, falling back to the fixed interval
+/// for websites without (or with an unparsable) cron schedule
+pub fn next_run(cron_schedule: Option<&str>, after: DateTime<Utc>) -> DateTime<Utc> {
+    let schedule = cron_schedule.and_then(|expression| match parse_cron(expression) {
+        Ok(schedule) => Some(schedule),
+        Err(e) => {
+            warn!("Ignoring invalid cron schedule '{expression}': {e}");
+            None
+        }
+    });
+
+    schedule
+        .and_then(|schedule| schedule.after(&after).next())
+        .unwrap_or(after + chrono::Duration::seconds(CHECK_INTERVAL_SECS))
+}
+
+/// Tracks when each website is due for its next check
+#[derive(Default)]
+pub struct Scheduler {
+    next_due: HashMap<String, DateTime<Utc>>,
+}
+
+impl Scheduler {
+    /// Websites seen for the first time are due right away, unless they follow a
+    /// cron schedule, then they wait for its first run
+    pub fn is_due(&mut self, website: &Website, now: DateTime<Utc>) -> bool {
+        let due = *self
+            .next_due
+            .entry(website.alias.clone())
+            .or_insert_with(|| match website.config.cron_schedule.as_deref() {
+                Some(cron_schedule) => next_run(Some(cron_schedule), now),
+                None => now,
+            });
+
+        due <= now
+    }
+
+    pub fn schedule_next(&mut self, website: &Website, checked_at: DateTime<Utc>) {
+        self.next_due.insert(
+            website.alias.clone(),
+            next_run(website.config.cron_schedule.as_deref(), checked_at),
+        );
+    }
+
+    /// Forgets websites that were deleted in the meantime
+    pub fn retain(&mut self, websites: &[Website]) {
+        self.next_due
+            .retain(|alias, _| websites.iter().any(|website| &website.alias == alias));
+    }
+}
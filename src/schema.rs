@@ -0,0 +1,457 @@
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Whether integrators may build on a table or column, unstable ones can change or
+/// disappear in any release and are left out of the read-only role
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stability {
+    Stable,
+    Unstable,
+}
+
+#[derive(Serialize)]
+pub struct Table {
+    pub name: &'static str,
+    pub stability: Stability,
+    pub description: &'static str,
+    pub columns: &'static [Column],
+}
+
+#[derive(Serialize)]
+pub struct Column {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub sql_type: &'static str,
+    pub stability: Stability,
+    pub description: &'static str,
+}
+
+/// Declares the documented tables, types are the portable ones shared by both databases
+macro_rules! tables {
+    ($(
+        $table_stability:ident $table:ident $table_doc:literal {
+            $($stability:ident $column:ident: $sql_type:literal $doc:literal,)*
+        }
+    )*) => {
+        pub const TABLES: &[Table] = &[$(Table {
+            name: stringify!($table),
+            stability: Stability::$table_stability,
+            description: $table_doc,
+            columns: &[$(Column {
+                name: stringify!($column),
+                sql_type: $sql_type,
+                stability: Stability::$stability,
+                description: $doc,
+            },)*],
+        },)*];
+    };
+}
+
+tables! {
+    Stable Websites "Monitored websites and ping targets" {
+        Stable id: "integer" "Primary key",
+        Stable url: "text" "Checked URL, hostname or IP address for ping monitors, program for exec monitors",
+        Stable alias: "text" "Unique name shown on the dashboard and used in URLs",
+        Stable monitor_type: "text" "http, ping or exec",
+        Stable ip_version: "text" "auto, v4only or v6only",
+        Stable impact_weight: "text" "minor, major or critical, impact of an outage",
+        Stable cron_schedule: "text" "Cron expression checks follow, NULL checks every minute. Generated from config",
+        Stable degraded_threshold_ms: "integer" "Checks slower than this count as degraded. Generated from config",
+        Stable strict: "boolean" "Degraded checks count as down for the uptime percentage. Generated from config",
+        Stable config: "text" "Check options as versioned JSON, its version field tells the shape",
+        Stable invert: "boolean" "Expected offline, reachable checks count as down",
+        Stable status_override: "text" "degraded, maintenance or major_outage set by an operator",
+        Stable status_override_note: "text" "Operator note shown with the status override",
+        Stable status_override_expires_at: "timestamptz" "When the status override lapses",
+        Stable created_at: "timestamptz" "When it was added",
+        Stable deleted_at: "timestamptz" "When it was deleted, purged 30 days later. NULL while it is monitored",
+        Unstable client_cert_path: "text" "Client certificate file for mutual TLS",
+        Unstable client_key_path: "text" "Client key file for mutual TLS",
+        Unstable client_cert_encrypted: "text" "Uploaded client certificate, encrypted",
+        Unstable client_key_encrypted: "text" "Uploaded client key, encrypted",
+        Unstable client_cert_fingerprint: "text" "SHA-256 fingerprint of the client certificate",
+        Unstable client_cert_expires_at: "timestamptz" "Expiry of the client certificate",
+        Unstable slack_webhook_url: "text" "Secret, overrides --slack-webhook-url",
+        Unstable discord_webhook_url: "text" "Secret, overrides --discord-webhook-url",
+        Unstable alert_email_to: "text" "Overrides --alert-email-to",
+        Unstable retention_days: "integer" "Overrides --retention-days",
+        Stable tls_cert_expires_at: "timestamptz" "Expiry of the server certificate seen last",
+        Unstable tls_cert_serial: "text" "Serial of the server certificate seen last",
+        Unstable tls_expiry_notified_days: "integer" "Tightest expiry warning sent for that certificate",
+    }
+    Stable Logs "One row per check, failed checks are incidents" {
+        Stable id: "integer" "Primary key, identifies incidents in /incidents/{id}",
+        Stable website_id: "integer" "References Websites.id",
+        Stable status: "smallint" "HTTP status, 200 is up. 595 check command failed, 596 reachable although expected offline, 597 content assertion failed, 598 timeout, 599 no connection",
+        Stable created_at: "timestamptz" "When the check ran",
+        Stable response_time_ms: "integer" "Latency of the check",
+        Stable ip_family: "text" "v4 or v6, the address family the check connected over",
+        Stable degraded: "boolean" "Slower than the website's degraded threshold",
+        Stable impact: "text" "Operator override of the website's impact_weight",
+        Stable error: "text" "Why the check failed",
+        Stable reason_phrase: "text" "Reason phrase of non-200 responses",
+        Stable server_header: "text" "Server header of non-200 responses",
+        Unstable response_headers: "text" "JSON object of the allow-listed headers of non-200 responses",
+        Unstable connection_reused: "boolean" "Whether a pooled connection was used",
+        Unstable content_hash: "text" "SHA-256 of the normalized body when tracking content",
+        Stable acknowledged_at: "timestamptz" "When someone acknowledged the incident, set on its first failed check",
+        Unstable acknowledged_by: "text" "Who acknowledged the incident",
+        Unstable ack_escalated_at: "timestamptz" "When the incident was escalated for going unacknowledged",
+    }
+    Stable Events "Noteworthy things that aren't incidents, like latency anomalies" {
+        Stable id: "integer" "Primary key",
+        Stable website_id: "integer" "References Websites.id",
+        Stable kind: "text" "latency_anomaly or content_changed",
+        Stable message: "text" "Human readable description",
+        Stable created_at: "timestamptz" "When it happened",
+    }
+    Stable IncidentUpdates "Updates operators posted on an incident" {
+        Stable id: "integer" "Primary key",
+        Stable log_id: "integer" "References Logs.id of the failed check the incident started with",
+        Stable message: "text" "The update",
+        Stable created_at: "timestamptz" "When it was posted",
+    }
+    Unstable StatsRollup "Checks older than two days, folded into one row per website and hour" {
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable bucket_start: "timestamptz" "Start of the hour",
+        Unstable checks: "integer" "Checks in the hour",
+        Unstable successes: "integer" "Checks with status 200",
+        Unstable degraded: "integer" "Checks slower than the degraded threshold",
+        Unstable degraded_successes: "integer" "Checks with status 200 that were degraded, strict websites don't count them as up",
+        Unstable avg_latency: "double precision" "Average response time in milliseconds",
+    }
+    Unstable Notifications "Webhooks notified about transitions, the URLs are secrets" {
+        Unstable id: "integer" "Primary key",
+        Unstable url: "text" "Webhook URL",
+        Unstable created_at: "timestamptz" "When it was added",
+    }
+    Unstable SharedFateGroups "Hosts whose monitors' incidents are notified as one" {
+        Unstable host: "text" "Primary key",
+        Unstable created_at: "timestamptz" "When shared fate was enabled",
+    }
+    Unstable NotificationStates "What each channel last heard about a website" {
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable channel: "text" "Channel key",
+        Unstable status: "smallint" "Status the channel was told about",
+        Unstable state_since: "timestamptz" "Since when the website has that status",
+        Unstable notified_at: "timestamptz" "When the channel was last notified",
+    }
+    Unstable NotificationLog "Every notification sent and whether it was delivered" {
+        Unstable id: "integer" "Primary key",
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable channel: "text" "Channel key",
+        Unstable kind: "text" "down, recovery, reminder, cert_expiry, ack_overdue, escalation or digest",
+        Unstable delivered: "boolean" "Whether delivery succeeded",
+        Unstable error: "text" "Why delivery failed",
+        Unstable created_at: "timestamptz" "When it was sent",
+    }
+    Unstable NotificationChannels "Named channels websites can be routed to" {
+        Unstable id: "integer" "Primary key",
+        Unstable name: "text" "Unique name",
+        Unstable kind: "text" "webhook, slack, discord, telegram, email, ntfy, gotify or alertmanager",
+        Unstable config: "text" "JSON settings of the kind, holds secrets",
+        Unstable created_at: "timestamptz" "When it was added",
+    }
+    Unstable WebsiteNotificationChannels "Channels a website notifies instead of the defaults" {
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable channel_id: "integer" "References NotificationChannels.id",
+    }
+    Unstable Digests "Periods a digest was sent for" {
+        Unstable id: "integer" "Primary key",
+        Unstable period_start: "timestamptz" "Start of the period it covered",
+        Unstable period_end: "timestamptz" "End of the period, when it was due",
+        Unstable sent_at: "timestamptz" "When it was sent",
+    }
+    Unstable EscalationRules "Extra notifications once an incident stays open long enough" {
+        Unstable id: "integer" "Primary key",
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable after_minutes: "integer" "How long the incident is open before the first one",
+        Unstable repeat_minutes: "integer" "Interval of further ones, NULL sends one",
+        Unstable channel_id: "integer" "References NotificationChannels.id, NULL uses the website's channels",
+        Unstable created_at: "timestamptz" "When it was added",
+    }
+    Unstable IncidentEscalations "Escalation steps that fired for an incident" {
+        Unstable log_id: "integer" "References Logs.id of the incident's first failed check",
+        Unstable rule_id: "integer" "References EscalationRules.id",
+        Unstable steps: "integer" "How many steps fired",
+        Unstable fired_at: "timestamptz" "When the last one fired",
+    }
+    Unstable Users "Not used yet" {
+        Unstable id: "integer" "Primary key",
+        Unstable username: "text" "Unique login name",
+        Unstable password_hash: "text" "Secret, Argon2 hash of the password",
+        Unstable salt: "text" "Secret, salt of the password hash",
+        Unstable created_at: "timestamptz" "When the user was added",
+    }
+    Unstable Permissions "Not used yet" {
+        Unstable id: "integer" "Primary key",
+        Unstable name: "text" "read or create_modify",
+        Unstable description: "text" "What the permission allows",
+    }
+    Unstable User_Permissions "Not used yet" {
+        Unstable id: "integer" "Primary key",
+        Unstable user_id: "integer" "References Users.id",
+        Unstable website_id: "integer" "References Websites.id",
+        Unstable permission_id: "integer" "References Permissions.id",
+    }
+}
+
+/// Grants on the stable columns of the stable tables, so secrets like webhook URLs and
+/// client keys stay out of reach of the role
+fn select_grants(role: &str) -> Vec<String> {
+    TABLES
+        .iter()
+        .filter(|table| table.stability == Stability::Stable)
+        .map(|table| {
+            let columns = table
+                .columns
+                .iter()
+                .filter(|column| column.stability == Stability::Stable)
+                .map(|column| column.name)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("GRANT SELECT ({columns}) ON {} TO \"{role}\"", table.name)
+        })
+        .collect()
+}
+
+/// Role names end up in statements verbatim, so only plain lowercase identifiers are allowed
+fn is_plain_identifier(name: &str) -> bool {
+    name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Creates or updates a login role that can read the stable tables, for BI tools and
+/// integrators querying the database directly
+pub async fn create_readonly_role(pool: &PgPool, role: &str, password: &str) -> Result<(), String> {
+    if !is_plain_identifier(role) {
+        return Err(format!(
+            "Role name {role:?} has to be a lowercase identifier of letters, digits and underscores"
+        ));
+    }
+    let password = password.replace('\'', "''");
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pg_roles WHERE rolname = $1)")
+            .bind(role)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    let database: String = sqlx::query_scalar("SELECT current_database()")
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut statements = vec![
+        if exists {
+            format!("ALTER ROLE \"{role}\" LOGIN PASSWORD '{password}'")
+        } else {
+            format!("CREATE ROLE \"{role}\" LOGIN PASSWORD '{password}'")
+        },
+        format!(
+            "GRANT CONNECT ON DATABASE \"{}\" TO \"{role}\"",
+            database.replace('"', "\"\"")
+        ),
+        format!("GRANT USAGE ON SCHEMA public TO \"{role}\""),
+    ];
+    statements.extend(select_grants(role));
+    for statement in statements {
+        sqlx::query(&statement)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sqlite, tests::postgres_pool};
+    use sqlx::postgres::PgConnectOptions;
+    use std::collections::BTreeSet;
+
+    fn documented() -> BTreeSet<(String, String)> {
+        TABLES
+            .iter()
+            .flat_map(|table| {
+                table
+                    .columns
+                    .iter()
+                    .map(|column| (table.name.to_lowercase(), column.name.to_string()))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn the_documented_tables_match_the_sqlite_migrations() {
+        let db = sqlite::memory_pool().await;
+        let migrated: BTreeSet<(String, String)> = sqlx::query_as(
+            "SELECT lower(m.name), p.name FROM sqlite_master m, pragma_table_xinfo(m.name) p
+            WHERE m.type = 'table' AND m.name NOT LIKE '\\_%' ESCAPE '\\'
+            AND m.name NOT LIKE 'sqlite%'",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(documented(), migrated);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server, run with DATABASE_URL set and -- --ignored"]
+    async fn the_documented_tables_match_the_postgres_migrations() {
+        let pool = postgres_pool("schema_columns").await;
+        let migrated: BTreeSet<(String, String)> = sqlx::query_as(
+            "SELECT lower(table_name), column_name FROM information_schema.columns
+            WHERE table_schema = 'public' AND table_name NOT LIKE '\\_%'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(documented(), migrated);
+    }
+
+    #[test]
+    fn the_schema_is_served_with_stability_and_types() {
+        let schema = serde_json::to_value(TABLES).unwrap();
+        let websites = &schema[0];
+        assert_eq!(websites["name"], "Websites");
+        assert_eq!(websites["stability"], "stable");
+        assert_eq!(websites["columns"][0]["name"], "id");
+        assert_eq!(websites["columns"][0]["type"], "integer");
+        let notifications = schema
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|table| table["name"] == "Notifications")
+            .unwrap();
+        assert_eq!(notifications["stability"], "unstable");
+    }
+
+    #[test]
+    fn unstable_tables_and_columns_are_never_granted() {
+        let grants = select_grants("reporting");
+        let stable = TABLES
+            .iter()
+            .filter(|table| table.stability == Stability::Stable)
+            .count();
+        assert_eq!(grants.len(), stable);
+        assert_eq!(
+            grants[0],
+            "GRANT SELECT (id, url, alias, monitor_type, ip_version, impact_weight, \
+            cron_schedule, degraded_threshold_ms, strict, config, invert, status_override, \
+            status_override_note, status_override_expires_at, created_at, deleted_at, \
+            tls_cert_expires_at) \
+            ON Websites TO \"reporting\""
+        );
+        for table in TABLES {
+            let granted = grants
+                .iter()
+                .find(|grant| grant.ends_with(&format!(" ON {} TO \"reporting\"", table.name)));
+            match table.stability {
+                Stability::Unstable => assert!(granted.is_none(), "{} is granted", table.name),
+                Stability::Stable => {
+                    let granted = granted.unwrap();
+                    for column in table.columns {
+                        let listed = granted.contains(&format!("({}, ", column.name))
+                            || granted.contains(&format!(", {}, ", column.name))
+                            || granted.contains(&format!(", {})", column.name));
+                        assert_eq!(
+                            listed,
+                            column.stability == Stability::Stable,
+                            "{}.{}",
+                            table.name,
+                            column.name
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn role_names_are_plain_identifiers() {
+        assert!(is_plain_identifier("reporting"));
+        assert!(is_plain_identifier("_bi_2"));
+        assert!(!is_plain_identifier("Reporting"));
+        assert!(!is_plain_identifier("2bi"));
+        assert!(!is_plain_identifier("bi\" SUPERUSER --"));
+        assert!(!is_plain_identifier(""));
+        assert!(!is_plain_identifier(&"a".repeat(64)));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server, run with DATABASE_URL set and -- --ignored"]
+    async fn the_postgres_readonly_role_reads_only_the_stable_columns() {
+        let pool = postgres_pool("readonly_role").await;
+        let role = "uptime_ferris_readonly_test";
+        sqlx::query(&format!("DROP OWNED BY {role}"))
+            .execute(&pool)
+            .await
+            .ok();
+        sqlx::query(&format!("DROP ROLE IF EXISTS {role}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO Websites (url, alias, slack_webhook_url)
+            VALUES ('https://a.example', 'a', 'https://hooks.slack.com/secret')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        create_readonly_role(&pool, role, "first").await.unwrap();
+        // Running it again updates the password instead of failing on the existing role
+        create_readonly_role(&pool, role, "it's").await.unwrap();
+        assert!(
+            create_readonly_role(&pool, "bad\"name", "x")
+                .await
+                .unwrap_err()
+                .contains("lowercase identifier")
+        );
+
+        let options = pool.connect_options().as_ref().clone();
+        let reader = PgPool::connect_with(
+            PgConnectOptions::clone(&options)
+                .username(role)
+                .password("it's"),
+        )
+        .await
+        .unwrap();
+        let alias: String = sqlx::query_scalar("SELECT alias FROM Websites")
+            .fetch_one(&reader)
+            .await
+            .unwrap();
+        assert_eq!(alias, "a");
+        for denied in [
+            "SELECT slack_webhook_url FROM Websites",
+            "SELECT * FROM Websites",
+            "SELECT url FROM Notifications",
+            "SELECT config FROM NotificationChannels",
+            "UPDATE Websites SET alias = 'b'",
+            "DELETE FROM Logs",
+        ] {
+            let error = sqlx::query(denied).execute(&reader).await.unwrap_err();
+            assert!(
+                error.to_string().contains("permission denied"),
+                "{denied}: {error}"
+            );
+        }
+        reader.close().await;
+        sqlx::query(&format!("DROP OWNED BY {role}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(&format!("DROP ROLE {role}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{Website, notifications::Transition};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::info;
+
+/// The host a website's checks end up at, ping monitors hold it in the url directly
+pub fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_else(|| url.trim().to_owned())
+        .to_lowercase()
+}
+
+/// Monitors checking the same host, worth grouping so one dead host makes one alert
+#[derive(Clone, Serialize)]
+pub struct HostGroup {
+    pub host: String,
+    pub aliases: Vec<String>,
+    pub shared_fate: bool,
+}
+
+/// Hosts with more than one monitor, `websites` are (url, alias) pairs
+pub fn host_groups<'a>(
+    websites: impl IntoIterator<Item = (&'a str, &'a str)>,
+    shared_fate_hosts: &[String],
+) -> Vec<HostGroup> {
+    let mut aliases_by_host = BTreeMap::<String, Vec<String>>::new();
+    for (url, alias) in websites {
+        aliases_by_host
+            .entry(host_of(url))
+            .or_default()
+            .push(alias.to_owned());
+    }
+
+    aliases_by_host
+        .into_iter()
+        .filter(|(_, aliases)| aliases.len() > 1)
+        .map(|(host, aliases)| HostGroup {
+            shared_fate: shared_fate_hosts.contains(&host),
+            host,
+            aliases,
+        })
+        .collect()
+}
+
+/// Outage of a shared fate host, notified once for all the monitors it took down
+struct Umbrella {
+    opened_at: DateTime<Utc>,
+    /// Monitors that went down within the coalescing window
+    members: Vec<String>,
+    still_down: HashSet<String>,
+    /// The first member's website and transition until the opening notification is sent
+    opening: Option<(Website, Transition)>,
+}
+
+/// Coalesces the transitions of monitors on shared fate hosts. The first monitor going
+/// down opens an umbrella incident, monitors going down within the window join it
+/// silently, and the umbrella resolves once its last member recovered. Each monitor
+/// still logs its own incident
+pub struct SharedFate {
+    window: TimeDelta,
+    hosts: HashSet<String>,
+    umbrellas: HashMap<String, Umbrella>,
+}
+
+impl SharedFate {
+    pub fn new(window: TimeDelta) -> Self {
+        Self {
+            window,
+            hosts: HashSet::new(),
+            umbrellas: HashMap::new(),
+        }
+    }
+
+    /// Hosts that currently have shared fate enabled, umbrellas of others are dropped
+    pub fn set_hosts(&mut self, hosts: Vec<String>) {
+        self.hosts = hosts.into_iter().collect();
+        self.umbrellas.retain(|host, _| self.hosts.contains(host));
+    }
+
+    /// The transition to notify about right away, `None` when an umbrella takes it over
+    pub fn coalesce(&mut self, website: &Website, transition: Transition) -> Option<Transition> {
+        let host = host_of(&website.url);
+        if !self.hosts.contains(&host) {
+            return Some(transition);
+        }
+
+        if transition.is_recovery() {
+            let Some(umbrella) = self.umbrellas.get_mut(&host) else {
+                return Some(transition);
+            };
+            if !umbrella.still_down.remove(&transition.alias) {
+                return Some(transition);
+            }
+            if !umbrella.still_down.is_empty() {
+                info!(
+                    "{} recovered, {} more monitors on {host} are still down",
+                    transition.alias,
+                    umbrella.still_down.len()
+                );
+                return None;
+            }
+
+            let umbrella = self.umbrellas.remove(&host)?;
+            // Nobody heard of the outage yet when it ends within the tick it started
+            if umbrella.opening.is_some() {
+                return None;
+            }
+            return Some(Transition {
+                since: umbrella.opened_at,
+                duration: (transition.timestamp - umbrella.opened_at).num_seconds(),
+                shared_host: Some(host),
+                affected: umbrella.members,
+                ..transition
+            });
+        }
+
+        match self.umbrellas.get_mut(&host) {
+            Some(umbrella) if transition.timestamp - umbrella.opened_at <= self.window => {
+                info!(
+                    "{} went down with {host}, coalesced into its shared fate incident",
+                    transition.alias
+                );
+                umbrella.members.push(transition.alias.clone());
+                umbrella.still_down.insert(transition.alias);
+                None
+            }
+            // Too late to share the host's fate, likely a problem of its own
+            Some(_) => Some(transition),
+            None => {
+                self.umbrellas.insert(
+                    host,
+                    Umbrella {
+                        opened_at: transition.timestamp,
+                        members: vec![transition.alias.clone()],
+                        still_down: HashSet::from([transition.alias.clone()]),
+                        opening: Some((website.clone(), transition)),
+                    },
+                );
+                None
+            }
+        }
+    }
+
+    /// Umbrellas opened since the last call with everything that failed along so far,
+    /// taken once the tick's checks are done so simultaneous failures are listed together
+    pub fn take_opened(&mut self) -> Vec<(Website, Transition)> {
+        self.umbrellas
+            .iter_mut()
+            .filter_map(|(host, umbrella)| {
+                let (website, transition) = umbrella.opening.take()?;
+                Some((
+                    website,
+                    Transition {
+                        shared_host: Some(host.clone()),
+                        affected: umbrella.members.clone(),
+                        ..transition
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+
+    const HOST: &str = "shop.example";
+
+    fn website(alias: &str) -> Website {
+        tests::website(
+            alias,
+            serde_json::json!({"url": format!("https://{HOST}/{alias}")}),
+        )
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        "2026-10-16T08:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minute)
+    }
+
+    fn transition(alias: &str, new_status: i16, minute: i64) -> Transition {
+        Transition {
+            alias: alias.to_owned(),
+            url: format!("https://{HOST}/{alias}"),
+            old_status: if new_status == 200 { 599 } else { 200 },
+            new_status,
+            timestamp: at(minute),
+            since: at(0),
+            duration: 0,
+            inverted: false,
+            shared_host: None,
+            affected: Vec::new(),
+            reminder: false,
+            warning: None,
+            message: None,
+        }
+    }
+
+    fn shared_fate() -> SharedFate {
+        let mut shared_fate = SharedFate::new(TimeDelta::minutes(5));
+        shared_fate.set_hosts(vec![HOST.to_owned()]);
+        shared_fate
+    }
+
+    /// Coalesces the transition of `alias`, which has to be taken over by an umbrella
+    fn coalesced(shared_fate: &mut SharedFate, alias: &str, new_status: i16, minute: i64) {
+        let notified = shared_fate.coalesce(&website(alias), transition(alias, new_status, minute));
+        assert!(notified.is_none(), "{alias} was notified on its own");
+    }
+
+    #[test]
+    fn monitors_on_one_host_are_grouped() {
+        let groups = host_groups(
+            [
+                ("https://Shop.example/cart", "cart"),
+                ("https://shop.example/api", "api"),
+                ("https://blog.example/", "blog"),
+                ("10.0.0.1", "router"),
+            ],
+            &[HOST.to_owned()],
+        );
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].host, HOST);
+        assert_eq!(groups[0].aliases, ["cart", "api"]);
+        assert!(groups[0].shared_fate);
+        assert_eq!(host_of(" 10.0.0.1 "), "10.0.0.1");
+    }
+
+    #[test]
+    fn simultaneous_outages_open_one_umbrella() {
+        let mut shared_fate = shared_fate();
+
+        coalesced(&mut shared_fate, "cart", 599, 0);
+        coalesced(&mut shared_fate, "api", 599, 0);
+        coalesced(&mut shared_fate, "checkout", 503, 2);
+        let opened = shared_fate.take_opened();
+
+        assert_eq!(opened.len(), 1);
+        let (website, umbrella) = &opened[0];
+        assert_eq!(website.alias, "cart");
+        assert_eq!(umbrella.shared_host.as_deref(), Some(HOST));
+        assert_eq!(umbrella.affected, ["cart", "api", "checkout"]);
+        assert_eq!(
+            umbrella.headline(),
+            "shop.example is down for 3 monitors: cart, api, checkout"
+        );
+        assert!(shared_fate.take_opened().is_empty());
+    }
+
+    #[test]
+    fn an_outage_after_the_window_is_notified_on_its_own() {
+        let mut shared_fate = shared_fate();
+        coalesced(&mut shared_fate, "cart", 599, 0);
+        shared_fate.take_opened();
+
+        let late = shared_fate.coalesce(&website("api"), transition("api", 599, 6));
+
+        assert_eq!(late.unwrap().shared_host, None);
+    }
+
+    #[test]
+    fn the_umbrella_resolves_when_its_last_member_recovers() {
+        let mut shared_fate = shared_fate();
+        coalesced(&mut shared_fate, "cart", 599, 0);
+        coalesced(&mut shared_fate, "api", 599, 1);
+        shared_fate.take_opened();
+
+        coalesced(&mut shared_fate, "cart", 200, 10);
+        let resolved = shared_fate
+            .coalesce(&website("api"), transition("api", 200, 42))
+            .unwrap();
+
+        assert_eq!(resolved.shared_host.as_deref(), Some(HOST));
+        assert_eq!(resolved.since, at(0));
+        assert_eq!(resolved.duration, 42 * 60);
+        assert_eq!(
+            resolved.headline(),
+            "shop.example recovered after 42m, all 2 monitors are back: cart, api"
+        );
+        // The next outage opens a new umbrella
+        coalesced(&mut shared_fate, "cart", 599, 50);
+        assert_eq!(shared_fate.take_opened()[0].1.affected, ["cart"]);
+    }
+
+    #[test]
+    fn an_outage_over_within_its_tick_is_never_notified() {
+        let mut shared_fate = shared_fate();
+
+        coalesced(&mut shared_fate, "cart", 599, 0);
+        coalesced(&mut shared_fate, "cart", 200, 0);
+
+        assert!(shared_fate.take_opened().is_empty());
+    }
+
+    #[test]
+    fn hosts_without_shared_fate_notify_every_monitor() {
+        let mut shared_fate = shared_fate();
+        shared_fate.set_hosts(Vec::new());
+
+        let notified = shared_fate.coalesce(&website("cart"), transition("cart", 599, 0));
+
+        assert_eq!(notified.unwrap().shared_host, None);
+        assert!(shared_fate.take_opened().is_empty());
+    }
+
+    #[test]
+    fn disabling_shared_fate_drops_open_umbrellas() {
+        let mut shared_fate = shared_fate();
+        coalesced(&mut shared_fate, "cart", 599, 0);
+
+        shared_fate.set_hosts(Vec::new());
+
+        assert!(shared_fate.take_opened().is_empty());
+    }
+}
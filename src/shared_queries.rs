@@ -1,19 +1,380 @@
-pub const INSERT_INTO_WEBSITES_QUERY: &str = "INSERT INTO Websites (url, alias) VALUES ($1,$2)";
-pub const SELECT_URL_ALIAS_WEBSITES_QUERY: &str = "SELECT url, alias FROM Websites";
-pub const SELECT_URL_ALIAS_WEBSITES_TOP_ONE_WHERE_ALIAS_QUERY: &str =
-    "SELECT url, alias FROM Websites WHERE alias = $1 LIMIT 1";
-pub const SELECT_INCIDENTS_BY_WEBSITE_ALIAS_QUERY: &str = "
-            SELECT Logs.created_at as time,
-            Logs.status from Logs
-            LEFT JOIN Websites on Websites.id = Logs.website_id
-            where Websites.Alias = $1 and Logs.status <> 200
-            ";
-pub const DELETE_LOGS_BY_WEBSITE_ALIAS_QUERY: &str = "DELETE FROM Logs WHERE id IN
-        (SELECT Logs.id
-        FROM Logs
-        LEFT JOIN Websites ON Websites.id = Logs.website_id
-        WHERE Websites.alias = $1)";
+pub const INSERT_INTO_WEBSITES_QUERY: &str = "INSERT INTO Websites
+    (url, alias, ip_version, impact_weight, config, monitor_type, client_cert_path,
+    client_key_path, client_cert_encrypted, client_key_encrypted, client_cert_fingerprint,
+    client_cert_expires_at, slack_webhook_url, discord_webhook_url, invert, alert_email_to,
+    retention_days, created_at)
+    VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18)";
+/// Webhooks left empty keep the current ones, the client identity is only replaced
+/// when a new certificate comes with the update, $12 being its fingerprint
+pub const UPDATE_WEBSITE_BY_ALIAS_QUERY: &str = "UPDATE Websites SET
+    url = $2, alias = $3, ip_version = $4, impact_weight = $5, config = $6, monitor_type = $7,
+    client_cert_path = CASE WHEN $12 IS NULL THEN client_cert_path ELSE $8 END,
+    client_key_path = CASE WHEN $12 IS NULL THEN client_key_path ELSE $9 END,
+    client_cert_encrypted = CASE WHEN $12 IS NULL THEN client_cert_encrypted ELSE $10 END,
+    client_key_encrypted = CASE WHEN $12 IS NULL THEN client_key_encrypted ELSE $11 END,
+    client_cert_fingerprint = COALESCE($12, client_cert_fingerprint),
+    client_cert_expires_at = CASE WHEN $12 IS NULL THEN client_cert_expires_at ELSE $13 END,
+    slack_webhook_url = COALESCE($14, slack_webhook_url),
+    discord_webhook_url = COALESCE($15, discord_webhook_url),
+    invert = $16, alert_email_to = $17, retention_days = $18
+    WHERE alias = $1 AND deleted_at IS NULL";
+/// Everything else refers to the website by id, so its history stays with it
+pub const RENAME_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET alias = $2 WHERE alias = $1 AND deleted_at IS NULL";
+/// SQLite's Websites.created_at defaulted to the text 'now' before inserts set it, those
+/// come back as NULL
+pub const SELECT_WEBSITE_SUMMARIES_QUERY: &str = "
+            SELECT Websites.id, Websites.alias, Websites.url,
+            NULLIF(Websites.created_at, 'now') as created_at,
+            Latest.status as current_status, Latest.created_at as last_checked
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            WHERE Websites.deleted_at IS NULL
+            ORDER BY Websites.alias
+            LIMIT $1 OFFSET $2
+            ";
+pub const SELECT_WEBSITE_SUMMARY_BY_ALIAS_QUERY: &str = "
+            SELECT Websites.id, Websites.alias, Websites.url,
+            NULLIF(Websites.created_at, 'now') as created_at,
+            Latest.status as current_status, Latest.created_at as last_checked
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            WHERE Websites.alias = $1 AND Websites.deleted_at IS NULL
+            ";
+/// Start of the newest run of checks on the same side of up and down as $2, the newest
+/// check whose side differs ends the previous run. That check is found walking the
+/// website's checks back from the latest, the run's start is then a range on the same
+/// (website_id, created_at) index
+pub const SELECT_STATUS_SINCE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT MIN(Logs.created_at) FROM Websites
+            LEFT JOIN Logs AS Opposite ON Opposite.id =
+            (SELECT Previous.id FROM Logs AS Previous
+            WHERE Previous.website_id = Websites.id AND (Previous.status = 200) <> $2
+            ORDER BY Previous.created_at DESC LIMIT 1)
+            INNER JOIN Logs ON Logs.website_id = Websites.id
+            AND (Opposite.id IS NULL OR Logs.created_at > Opposite.created_at)
+            WHERE Websites.alias = $1
+            ";
+pub const SELECT_URL_ALIAS_WEBSITES_QUERY: &str =
+    "SELECT id, url, alias, invert FROM Websites WHERE deleted_at IS NULL ORDER BY id";
+pub const SELECT_WEBSITES_COUNT_QUERY: &str =
+    "SELECT COUNT(*) FROM Websites WHERE deleted_at IS NULL";
+/// Latest check of every website and how many checks it had and failed overall
+pub const SELECT_WEBSITE_METRICS_QUERY: &str = "
+            SELECT Websites.alias, Websites.url,
+            Latest.status, Latest.response_time_ms,
+            COALESCE(Counts.checks, 0) as checks, COALESCE(Counts.failures, 0) as failures
+            FROM Websites
+            LEFT JOIN Logs AS Latest ON Latest.id =
+            (SELECT Logs.id FROM Logs WHERE Logs.website_id = Websites.id
+            ORDER BY Logs.created_at DESC LIMIT 1)
+            LEFT JOIN (SELECT website_id, COUNT(*) as checks,
+                COUNT(CASE WHEN status <> 200 THEN 1 END) as failures
+                FROM Logs GROUP BY website_id) AS Counts
+            ON Counts.website_id = Websites.id
+            WHERE Websites.deleted_at IS NULL
+            ORDER BY Websites.alias
+            ";
+pub const SELECT_MONITORED_WEBSITES_QUERY: &str = "SELECT id, url, alias, ip_version, config,
+    monitor_type, client_cert_path, client_key_path, client_cert_encrypted,
+    client_key_encrypted, slack_webhook_url, discord_webhook_url, invert, alert_email_to,
+    tls_cert_serial, tls_cert_expires_at, tls_expiry_notified_days FROM Websites
+    WHERE deleted_at IS NULL";
+/// What the export writes, webhooks and client certificates stay out of it
+pub const SELECT_EXPORTED_WEBSITES_QUERY: &str = "SELECT url, alias, monitor_type, ip_version,
+    impact_weight, config, invert, alert_email_to, retention_days FROM Websites
+    WHERE deleted_at IS NULL ORDER BY alias";
+/// What backups write, the webhooks and client certificate paths only with the secrets
+pub const SELECT_BACKUP_WEBSITES_QUERY: &str = "SELECT url, alias, monitor_type, ip_version,
+    impact_weight, config, invert, alert_email_to, retention_days, slack_webhook_url,
+    discord_webhook_url, client_cert_path, client_key_path FROM Websites
+    WHERE deleted_at IS NULL ORDER BY alias";
+pub const SELECT_WEBSITE_BY_ALIAS_QUERY: &str = "SELECT id, url, alias, monitor_type, ip_version,
+    impact_weight, config, client_cert_fingerprint, client_cert_expires_at, invert,
+    alert_email_to, retention_days FROM Websites
+    WHERE alias = $1 AND deleted_at IS NULL LIMIT 1";
+pub const SELECT_LAST_CHECK_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT MAX(Logs.created_at) FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1
+            ";
+pub const SELECT_INCIDENT_BY_ID_QUERY: &str = "
+            SELECT Logs.id, Logs.created_at as time,
+            Logs.status, Logs.ip_family,
+            COALESCE(Logs.impact, Websites.impact_weight) as impact,
+            Logs.error, Logs.reason_phrase, Logs.server_header, Logs.response_headers,
+            Logs.acknowledged_by, Logs.acknowledged_at,
+            Websites.alias, Websites.url from Logs
+            INNER JOIN Websites on Websites.id = Logs.website_id
+            where Logs.id = $1 and Logs.status <> 200
+            ";
+pub const SELECT_INCIDENT_RESOLUTION_QUERY: &str = "
+            SELECT MIN(Later.created_at) FROM Logs AS Later
+            INNER JOIN Logs AS Incident ON Incident.website_id = Later.website_id
+            WHERE Incident.id = $1 AND Later.status = 200
+            AND Later.created_at > Incident.created_at
+            ";
+pub const SELECT_INCIDENT_UPDATES_QUERY: &str = "
+            SELECT id, created_at as time, message FROM IncidentUpdates
+            WHERE log_id = $1
+            ORDER BY created_at DESC, id DESC
+            ";
+pub const INSERT_INCIDENT_UPDATE_QUERY: &str =
+    "INSERT INTO IncidentUpdates (log_id, message) VALUES ($1, $2)";
+pub const SELECT_OPEN_INCIDENTS_QUERY: &str = "
+            SELECT Websites.alias, Logs.id, Logs.created_at as started_at, Logs.status,
+            Logs.acknowledged_by, Logs.acknowledged_at, Logs.ack_escalated_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Logs.id IN
+            (SELECT MIN(Failed.id) FROM Logs AS Failed
+            WHERE Failed.status <> 200
+            AND NOT EXISTS (SELECT 1 FROM Logs AS Up
+            WHERE Up.website_id = Failed.website_id AND Up.status = 200
+            AND Up.created_at > Failed.created_at)
+            GROUP BY Failed.website_id)
+            AND Websites.deleted_at IS NULL
+            ";
+pub const ACKNOWLEDGE_INCIDENT_QUERY: &str = "UPDATE Logs
+    SET acknowledged_by = $1, acknowledged_at = $2
+    WHERE id = $3 AND status <> 200 AND acknowledged_at IS NULL";
+pub const MARK_ACK_ESCALATED_QUERY: &str = "UPDATE Logs SET ack_escalated_at = $1 WHERE id = $2";
+pub const SELECT_ACKNOWLEDGED_INCIDENTS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.created_at, Logs.acknowledged_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1 AND Logs.acknowledged_at IS NOT NULL
+            AND Logs.created_at >= $2
+            ";
+pub const SELECT_ACTIVE_INCIDENT_IMPACTS_QUERY: &str = "
+            SELECT Websites.alias, COALESCE(Logs.impact, Websites.impact_weight) as impact
+            FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Logs.id IN (SELECT MAX(id) FROM Logs GROUP BY website_id)
+            AND Logs.status <> 200
+            AND Websites.deleted_at IS NULL
+            ";
+pub const SELECT_ACTIVE_STATUS_OVERRIDES_QUERY: &str = "
+            SELECT alias, status_override as status, status_override_note as note,
+            status_override_expires_at as expires_at
+            FROM Websites
+            WHERE status_override IS NOT NULL AND deleted_at IS NULL
+            AND (status_override_expires_at IS NULL OR status_override_expires_at > $1)
+            ";
+pub const UPDATE_STATUS_OVERRIDE_BY_ALIAS_QUERY: &str = "UPDATE Websites
+        SET status_override = $1, status_override_note = $2, status_override_expires_at = $3
+        WHERE alias = $4";
+pub const CLEAR_EXPIRED_STATUS_OVERRIDES_QUERY: &str = "UPDATE Websites
+        SET status_override = NULL, status_override_note = NULL, status_override_expires_at = NULL
+        WHERE status_override_expires_at <= $1";
+pub const UPDATE_CERTIFICATE_BY_ALIAS_QUERY: &str = "UPDATE Websites
+        SET tls_cert_serial = $1, tls_cert_expires_at = $2, tls_expiry_notified_days = $3
+        WHERE alias = $4";
+pub const UPDATE_INCIDENT_IMPACT_QUERY: &str = "UPDATE Logs SET impact = $1
+        WHERE id = $2 AND status <> 200
+        AND website_id = (SELECT id FROM Websites WHERE alias = $3)";
+pub const SOFT_DELETE_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET deleted_at = $2 WHERE alias = $1 AND deleted_at IS NULL";
+pub const RESTORE_DELETED_WEBSITE_BY_ALIAS_QUERY: &str =
+    "UPDATE Websites SET deleted_at = NULL WHERE alias = $1 AND deleted_at >= $2";
+pub const SELECT_DELETED_WEBSITES_QUERY: &str = "SELECT alias, url, deleted_at FROM Websites
+    WHERE deleted_at >= $1 ORDER BY deleted_at DESC";
+pub const SELECT_PURGEABLE_WEBSITES_QUERY: &str =
+    "SELECT alias FROM Websites WHERE deleted_at < $1";
 pub const DELETE_WEBSITE_BY_ALIAS_QUERY: &str = "DELETE FROM Websites WHERE alias = $1";
-pub const INSERT_INTO_LOGS_BY_ALIAS_RESPONSE_CODE_QUERY: &str = r#"INSERT INTO Logs (website_id, status)
+pub const SELECT_WEBSITE_ID_BY_ALIAS_QUERY: &str = "SELECT id FROM Websites WHERE alias = $1";
+/// Deleted websites included, deleting one of those again answers 404 as by alias
+pub const SELECT_WEBSITE_ALIAS_BY_ID_QUERY: &str = "SELECT alias FROM Websites WHERE id = $1";
+pub const SELECT_WEBSITE_IDS_QUERY: &str = "SELECT alias, id FROM Websites";
+/// Deleted websites are pruned like the others until they're purged
+pub const SELECT_RETENTION_OVERRIDES_QUERY: &str = "SELECT DISTINCT retention_days FROM Websites";
+pub const SELECT_LAST_STATUS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.status, Logs.created_at FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1
+            ORDER BY Logs.created_at DESC, Logs.id DESC
+            LIMIT 1";
+pub const SELECT_LAST_CONTENT_HASH_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.content_hash FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1 AND Logs.content_hash IS NOT NULL
+            ORDER BY Logs.created_at DESC, Logs.id DESC
+            LIMIT 1";
+pub const SELECT_LAST_FAILURE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Logs.error FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1 AND Logs.status <> 200
+            ORDER BY Logs.created_at DESC
+            LIMIT 1
+            ";
+pub const SELECT_CONNECTION_REUSE_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT COUNT(CASE WHEN Logs.connection_reused THEN 1 END) as reused,
+            COUNT(Logs.connection_reused) as total
+            FROM Logs
+            INNER JOIN Websites ON Websites.id = Logs.website_id
+            WHERE Websites.alias = $1
+            ";
+pub const INSERT_EVENT_BY_ALIAS_QUERY: &str = r#"INSERT INTO Events (website_id, kind, message)
+                VALUES
+                ((SELECT id FROM Websites WHERE alias = $1), $2, $3)"#;
+pub const SELECT_EVENTS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT Events.created_at as time, Events.kind, Events.message
+            FROM Events
+            INNER JOIN Websites ON Websites.id = Events.website_id
+            WHERE Websites.alias = $1
+            ORDER BY Events.created_at DESC
+            LIMIT 50
+            ";
+pub const SELECT_NOTIFICATION_COUNT_BY_URL_QUERY: &str =
+    "SELECT COUNT(*) FROM Notifications WHERE url = $1";
+pub const INSERT_NOTIFICATION_QUERY: &str = "INSERT INTO Notifications (url) VALUES ($1)";
+pub const SELECT_NOTIFICATION_URLS_QUERY: &str = "SELECT url FROM Notifications";
+pub const SELECT_NOTIFICATIONS_QUERY: &str = "SELECT id, url FROM Notifications ORDER BY id";
+pub const SELECT_NOTIFICATION_URL_BY_ID_QUERY: &str = "SELECT url FROM Notifications WHERE id = $1";
+pub const DELETE_NOTIFICATION_BY_ID_QUERY: &str = "DELETE FROM Notifications WHERE id = $1";
+pub const SELECT_SHARED_FATE_HOSTS_QUERY: &str = "SELECT host FROM SharedFateGroups";
+pub const ENABLE_SHARED_FATE_QUERY: &str =
+    "INSERT INTO SharedFateGroups (host) VALUES ($1) ON CONFLICT (host) DO NOTHING";
+pub const DISABLE_SHARED_FATE_QUERY: &str = "DELETE FROM SharedFateGroups WHERE host = $1";
+pub const SELECT_NOTIFICATION_STATES_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationStates.channel, NotificationStates.status,
+                NotificationStates.state_since, NotificationStates.notified_at
+            FROM NotificationStates
+            INNER JOIN Websites ON Websites.id = NotificationStates.website_id
+            WHERE Websites.alias = $1";
+pub const UPSERT_NOTIFICATION_STATE_QUERY: &str = r#"INSERT INTO NotificationStates
+                (website_id, channel, status, state_since, notified_at)
+                VALUES
+                ((SELECT id FROM Websites WHERE alias = $1), $2, $3, $4, $5)
+                ON CONFLICT (website_id, channel) DO UPDATE SET
+                status = excluded.status,
+                state_since = excluded.state_since,
+                notified_at = excluded.notified_at"#;
+pub const INSERT_NOTIFICATION_LOG_QUERY: &str = r#"INSERT INTO NotificationLog
+                (website_id, channel, kind, delivered, error)
                 VALUES
-                ((SELECT id FROM Websites WHERE alias = $1), $2)"#;
+                ((SELECT id FROM Websites WHERE alias = $1), $2, $3, $4, $5)"#;
+pub const SELECT_NOTIFICATION_LOG_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationLog.created_at as time, NotificationLog.channel,
+                NotificationLog.kind, NotificationLog.delivered, NotificationLog.error
+            FROM NotificationLog
+            INNER JOIN Websites ON Websites.id = NotificationLog.website_id
+            WHERE Websites.alias = $1
+            ORDER BY NotificationLog.created_at DESC
+            LIMIT 50
+";
+pub const SELECT_NOTIFICATION_CHANNELS_QUERY: &str =
+    "SELECT id, name, kind FROM NotificationChannels ORDER BY name";
+pub const SELECT_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "SELECT kind, config FROM NotificationChannels WHERE id = $1";
+pub const SELECT_NOTIFICATION_CHANNEL_SETTINGS_QUERY: &str =
+    "SELECT name, kind, config FROM NotificationChannels ORDER BY name";
+pub const SELECT_NOTIFICATION_CHANNEL_ID_BY_NAME_QUERY: &str =
+    "SELECT id FROM NotificationChannels WHERE name = $1";
+pub const UPDATE_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "UPDATE NotificationChannels SET kind = $2, config = $3 WHERE id = $1";
+pub const INSERT_NOTIFICATION_CHANNEL_QUERY: &str =
+    "INSERT INTO NotificationChannels (name, kind, config) VALUES ($1, $2, $3)";
+pub const DELETE_NOTIFICATION_CHANNEL_BY_ID_QUERY: &str =
+    "DELETE FROM NotificationChannels WHERE id = $1";
+pub const DELETE_CHANNEL_ASSIGNMENTS_BY_CHANNEL_ID_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE channel_id = $1";
+pub const SELECT_CHANNELS_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT NotificationChannels.id, NotificationChannels.name, NotificationChannels.kind
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id
+            WHERE Websites.alias = $1
+            ORDER BY NotificationChannels.name";
+pub const ASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY: &str = r#"INSERT INTO WebsiteNotificationChannels
+                (website_id, channel_id)
+                SELECT id, $2 FROM Websites WHERE alias = $1
+                ON CONFLICT (website_id, channel_id) DO NOTHING"#;
+pub const UNASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE channel_id = $2 AND website_id IN
+        (SELECT id FROM Websites WHERE alias = $1)";
+pub const DELETE_CHANNEL_ASSIGNMENTS_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM WebsiteNotificationChannels WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = $1)";
+pub const SELECT_CHANNEL_ASSIGNMENT_NAMES_QUERY: &str = "
+            SELECT Websites.alias, NotificationChannels.name
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id
+            ORDER BY NotificationChannels.name";
+pub const SELECT_CHANNEL_ROUTES_QUERY: &str = "
+            SELECT Websites.alias, NotificationChannels.id, NotificationChannels.kind,
+                NotificationChannels.config
+            FROM WebsiteNotificationChannels
+            INNER JOIN NotificationChannels
+                ON NotificationChannels.id = WebsiteNotificationChannels.channel_id
+            INNER JOIN Websites ON Websites.id = WebsiteNotificationChannels.website_id";
+pub const SELECT_ESCALATION_RULES_QUERY: &str = "
+            SELECT EscalationRules.id, Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, EscalationRules.channel_id,
+                NotificationChannels.kind AS channel_kind,
+                NotificationChannels.config AS channel_config
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            ORDER BY EscalationRules.after_minutes";
+pub const SELECT_BACKUP_ESCALATION_RULES_QUERY: &str = "
+            SELECT Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, NotificationChannels.name
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            ORDER BY EscalationRules.after_minutes";
+pub const SELECT_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY: &str = "
+            SELECT EscalationRules.id, Websites.alias, EscalationRules.after_minutes,
+                EscalationRules.repeat_minutes, EscalationRules.channel_id,
+                NotificationChannels.kind AS channel_kind,
+                NotificationChannels.config AS channel_config
+            FROM EscalationRules
+            INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+            LEFT JOIN NotificationChannels
+                ON NotificationChannels.id = EscalationRules.channel_id
+            WHERE Websites.alias = $1
+            ORDER BY EscalationRules.after_minutes";
+pub const INSERT_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY: &str = r#"INSERT INTO EscalationRules
+                (website_id, after_minutes, repeat_minutes, channel_id)
+                SELECT id, $2, $3, $4 FROM Websites WHERE alias = $1"#;
+pub const DELETE_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM EscalationRules WHERE id = $2 AND website_id IN
+        (SELECT id FROM Websites WHERE alias = $1)";
+pub const DELETE_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY: &str =
+    "DELETE FROM EscalationRules WHERE website_id IN
+        (SELECT id FROM Websites WHERE alias = $1)";
+pub const DETACH_ESCALATION_RULES_BY_CHANNEL_ID_QUERY: &str =
+    "UPDATE EscalationRules SET channel_id = NULL WHERE channel_id = $1";
+pub const SELECT_FIRED_ESCALATION_STEPS_QUERY: &str =
+    "SELECT steps FROM IncidentEscalations WHERE log_id = $1 AND rule_id = $2";
+pub const UPSERT_FIRED_ESCALATION_STEPS_QUERY: &str = r#"INSERT INTO IncidentEscalations
+                (log_id, rule_id, steps, fired_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (log_id, rule_id) DO UPDATE SET
+                steps = excluded.steps,
+                fired_at = excluded.fired_at"#;
+pub const DELETE_INCIDENT_ESCALATIONS_BY_RULE_QUERY: &str =
+    "DELETE FROM IncidentEscalations WHERE rule_id IN
+        (SELECT EscalationRules.id
+        FROM EscalationRules
+        INNER JOIN Websites ON Websites.id = EscalationRules.website_id
+        WHERE Websites.alias = $1 AND EscalationRules.id = $2)";
+pub const SELECT_LAST_DIGEST_QUERY: &str = "SELECT MAX(period_end) FROM Digests";
+pub const INSERT_DIGEST_QUERY: &str = r#"INSERT INTO Digests (period_start, period_end)
+                VALUES ($1, $2)
+                ON CONFLICT (period_end) DO NOTHING"#;
+/// Readiness probe, answered without touching any table
+pub const SELECT_ONE_QUERY: &str = "SELECT 1";
+/// Read on every poll of the dashboard and status APIs, only the primary keys are looked at
+pub const SELECT_CONTENT_VERSION_QUERY: &str = r#"SELECT
+                CAST(COALESCE((SELECT MAX(id) FROM Logs), 0) AS BIGINT) AS last_log_id,
+                (SELECT COUNT(*) FROM Websites WHERE deleted_at IS NULL) AS websites,
+                CAST(COALESCE((SELECT MAX(id) FROM Websites), 0) AS BIGINT) AS last_website_id"#;
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Months, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// The window unless asked otherwise
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+
+/// The longest window reports can be asked for
+const MAX_WINDOW_DAYS: i64 = 365;
+
+/// Either a window ending now or a calendar month, the last 30 days when left out
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlaParams {
+    /// Days up to now like `30d` or `90d`, at most `365d`
+    window: Option<String>,
+    /// A calendar month in UTC like `2024-05`, the current one counts until now
+    month: Option<String>,
+}
+
+impl SlaParams {
+    pub fn period(&self, now: DateTime<Utc>) -> Result<SlaPeriod, String> {
+        match (self.window.as_deref(), self.month.as_deref()) {
+            (Some(_), Some(_)) => Err("Give either a window or a month, not both".to_owned()),
+            (None, None) => Ok(SlaPeriod::days(DEFAULT_WINDOW_DAYS, now)),
+            (Some(window), None) => {
+                let days = window
+                    .strip_suffix('d')
+                    .and_then(|days| days.parse::<i64>().ok())
+                    .filter(|days| (1..=MAX_WINDOW_DAYS).contains(days))
+                    .ok_or_else(|| {
+                        format!("Unsupported window '{window}', use days like 30d up to 365d")
+                    })?;
+                Ok(SlaPeriod::days(days, now))
+            }
+            (None, Some(month)) => {
+                let from = NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+                    .map_err(|_| format!("Unsupported month '{month}', use YYYY-MM"))?
+                    .and_hms_opt(0, 0, 0)
+                    .expect("midnight exists")
+                    .and_utc();
+                if from > now {
+                    return Err(format!("{month} hasn't begun yet"));
+                }
+                Ok(SlaPeriod {
+                    label: month.to_owned(),
+                    from,
+                    until: (from + Months::new(1)).min(now),
+                })
+            }
+        }
+    }
+}
+
+/// What a report covers, from inclusive and until exclusive
+pub struct SlaPeriod {
+    /// The window or month as asked for, `30d` or `2024-05`
+    pub label: String,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+impl SlaPeriod {
+    fn days(days: i64, now: DateTime<Utc>) -> Self {
+        Self {
+            label: format!("{days}d"),
+            from: now - TimeDelta::days(days),
+            until: now,
+        }
+    }
+}
+
+/// One website's raw counts as the backend queries aggregate them, the division is
+/// left to [`SlaSummary`] so it isn't rounded away in SQL
+#[derive(sqlx::FromRow)]
+pub struct SlaCounts {
+    pub alias: String,
+    pub total_checks: i64,
+    pub successful_checks: i64,
+    /// Outages started, counted by their first failed check
+    pub incidents: i64,
+    /// Every failed check lasts until the next check or the end of the period
+    pub downtime_secs: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SlaSummary {
+    pub total_checks: i64,
+    /// Up and, for strict websites, not degraded either
+    pub successful_checks: i64,
+    /// Rounded down to two decimals so 99.999 isn't reported as 100, `None` without a
+    /// single check
+    pub uptime_pct: Option<f64>,
+    /// `None` without a single check. Summed over the websites for all of them
+    pub downtime_minutes: Option<i64>,
+    pub incidents: i64,
+}
+
+impl SlaSummary {
+    fn new(total_checks: i64, successful_checks: i64, incidents: i64, downtime_secs: i64) -> Self {
+        let checked = total_checks > 0;
+        Self {
+            total_checks,
+            successful_checks,
+            // Floored in hundredths of a percent first, flooring the float could land a
+            // hundredth too low
+            uptime_pct: checked.then(|| (successful_checks * 10_000 / total_checks) as f64 / 100.0),
+            downtime_minutes: checked.then_some((downtime_secs + 30) / 60),
+            incidents,
+        }
+    }
+}
+
+impl From<&SlaCounts> for SlaSummary {
+    fn from(counts: &SlaCounts) -> Self {
+        Self::new(
+            counts.total_checks,
+            counts.successful_checks,
+            counts.incidents,
+            counts.downtime_secs,
+        )
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WebsiteSla {
+    pub alias: String,
+    #[serde(flatten)]
+    pub summary: SlaSummary,
+}
+
+/// What `/api/websites/{alias}/sla` and `/api/sla` answer with, the websites only for
+/// the latter
+#[derive(Serialize, ToSchema)]
+pub struct SlaReport {
+    /// `30d` or `2024-05`, as asked for
+    pub period: String,
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    #[serde(flatten)]
+    pub summary: SlaSummary,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub websites: Vec<WebsiteSla>,
+}
+
+impl SlaReport {
+    /// Every check of every website counts the same, like one website checked that often
+    pub fn new(period: SlaPeriod, counts: &[SlaCounts], per_website: bool) -> Self {
+        let sum = |count: fn(&SlaCounts) -> i64| counts.iter().map(count).sum::<i64>();
+        Self {
+            period: period.label,
+            from: period.from,
+            until: period.until,
+            summary: SlaSummary::new(
+                sum(|counts| counts.total_checks),
+                sum(|counts| counts.successful_checks),
+                sum(|counts| counts.incidents),
+                sum(|counts| counts.downtime_secs),
+            ),
+            websites: if per_website {
+                counts
+                    .iter()
+                    .map(|counts| WebsiteSla {
+                        alias: counts.alias.clone(),
+                        summary: counts.into(),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
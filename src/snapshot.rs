@@ -0,0 +1,71 @@
+use crate::{SingleWebsiteLog, WebsiteLogs, dashboard_view::DashboardView, pagination::PageParams};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Keeps the last successfully loaded page data around, so the HTML routes can fall
+/// back to it when the database doesn't answer within the time budget
+#[derive(Clone)]
+pub struct SnapshotCache {
+    budget: Duration,
+    inner: Arc<Mutex<Snapshots>>,
+}
+
+#[derive(Default)]
+struct Snapshots {
+    index: Option<(DateTime<Utc>, WebsiteLogs)>,
+    websites: HashMap<String, (DateTime<Utc>, SingleWebsiteLog)>,
+}
+
+impl SnapshotCache {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            inner: Arc::new(Mutex::new(Snapshots::default())),
+        }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn store_index(&self, logs: &WebsiteLogs) {
+        self.inner.lock().unwrap().index = Some((Utc::now(), logs.clone()));
+    }
+
+    /// Only when the snapshot is of the same page, sorted and filtered the same way
+    pub fn stale_index(&self, pages: PageParams, view: DashboardView) -> Option<WebsiteLogs> {
+        let snapshots = self.inner.lock().unwrap();
+        let (taken_at, logs) = snapshots.index.as_ref().filter(|(_, logs)| {
+            logs.page.page == pages.page()
+                && logs.page.per_page == pages.per_page()
+                && logs.view == view
+        })?;
+        let mut logs = logs.clone();
+        logs.stale_as_of = Some(taken_at.format("%H:%M").to_string());
+        Some(logs)
+    }
+
+    pub fn store_website(&self, alias: &str, log: &SingleWebsiteLog) {
+        self.inner
+            .lock()
+            .unwrap()
+            .websites
+            .insert(alias.to_owned(), (Utc::now(), log.clone()));
+    }
+
+    pub fn stale_website(&self, alias: &str) -> Option<SingleWebsiteLog> {
+        let snapshots = self.inner.lock().unwrap();
+        let (taken_at, log) = snapshots.websites.get(alias)?;
+        let mut log = log.clone();
+        log.stale_as_of = Some(taken_at.format("%H:%M").to_string());
+        Some(log)
+    }
+
+    pub fn forget_website(&self, alias: &str) {
+        self.inner.lock().unwrap().websites.remove(alias);
+    }
+}
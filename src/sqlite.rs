@@ -1,11 +1,301 @@
-use sqlx::{SqlitePool, migrate::Migrator};
+use crate::{migrations, sqlite_queries};
+use sqlx::{
+    Connection, Sqlite, SqliteConnection, SqlitePool,
+    pool::PoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous},
+};
+use std::{path::Path, str::FromStr, time::Duration};
+use tracing::{info, warn};
 
-pub async fn migrate_sqlite(pool: &SqlitePool) {
-    let migrator = Migrator::new(std::path::Path::new("./migrations_sq"))
+/// Database opened when neither --pg, --mysql nor --sqlite is given
+pub const DEFAULT_SQLITE_PATH: &str = "uptime_ferris.db";
+
+/// Sqlite's journal mode, WAL lets dashboard reads go on while the checker writes
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum JournalMode {
+    Wal,
+    Delete,
+    Truncate,
+    Persist,
+}
+
+impl JournalMode {
+    fn pragma(self) -> SqliteJournalMode {
+        match self {
+            Self::Wal => SqliteJournalMode::Wal,
+            Self::Delete => SqliteJournalMode::Delete,
+            Self::Truncate => SqliteJournalMode::Truncate,
+            Self::Persist => SqliteJournalMode::Persist,
+        }
+    }
+}
+
+/// How often Sqlite syncs to disk. NORMAL can only lose the last commits on a power
+/// loss with WAL, FULL and EXTRA don't even do that at the cost of slower writes
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+impl Synchronous {
+    fn pragma(self) -> SqliteSynchronous {
+        match self {
+            Self::Off => SqliteSynchronous::Off,
+            Self::Normal => SqliteSynchronous::Normal,
+            Self::Full => SqliteSynchronous::Full,
+            Self::Extra => SqliteSynchronous::Extra,
+        }
+    }
+}
+
+/// Pragmas every pooled Sqlite connection is opened with
+pub struct Pragmas {
+    /// Statements wait this long for a lock the checker or another request holds
+    pub busy_timeout: Duration,
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+}
+
+/// Foreign keys are off while migrating, dropping a table that is rebuilt to change its
+/// constraints would otherwise cascade to everything referencing it. sqlx runs every
+/// migration in a transaction, where the pragma does nothing, so it's set on the
+/// connection beforehand
+pub async fn migrate_sqlite(pool: &SqlitePool, dir: Option<&Path>) -> Result<(), String> {
+    let mut connection = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Connecting to the Sqlite database failed: {e}"))?;
+    sqlx::query(sqlite_queries::FOREIGN_KEYS_OFF)
+        .execute(&mut *connection)
+        .await
+        .map_err(|e| format!("Turning off foreign keys for the migrations failed: {e}"))?;
+    let migrated = migrations::run(sqlx::migrate!("./migrations_sq"), dir, &mut *connection).await;
+    sqlx::query(sqlite_queries::FOREIGN_KEYS_ON)
+        .execute(&mut *connection)
+        .await
+        .map_err(|e| format!("Turning foreign keys back on failed: {e}"))?;
+    migrated
+}
+
+/// Opens a plain path, created if missing, or a full `sqlite:` connection string. The
+/// directories leading to the file are created first, relative paths resolve against
+/// the working directory. Foreign keys are always enforced. In-memory databases can't
+/// use WAL and keep their memory journal
+pub async fn connect_sqlite(
+    database: &str,
+    pool: PoolOptions<Sqlite>,
+    pragmas: Pragmas,
+) -> Result<SqlitePool, String> {
+    let options = if database.starts_with("sqlite:") {
+        SqliteConnectOptions::from_str(database)
+            .map_err(|e| format!("--sqlite is no valid connection string: {e}"))?
+    } else {
+        SqliteConnectOptions::new()
+            .filename(database)
+            .create_if_missing(true)
+    };
+
+    if database.contains(":memory:") || database.contains("mode=memory") {
+        info!("Opening an in-memory Sqlite database");
+    } else {
+        let path = std::path::absolute(options.get_filename())
+            .map_err(|e| format!("Resolving the Sqlite database path failed: {e}"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Creating {} failed: {e}", parent.display()))?;
+        }
+        info!("Opening the Sqlite database at {}", path.display());
+    }
+
+    info!(
+        "Sqlite statements wait up to {:?} for locks, synchronous is {:?}",
+        pragmas.busy_timeout, pragmas.synchronous
+    );
+    let options = options
+        .busy_timeout(pragmas.busy_timeout)
+        .journal_mode(pragmas.journal_mode.pragma())
+        .synchronous(pragmas.synchronous.pragma())
+        .foreign_keys(true);
+    let pool = pool
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Opening the Sqlite database failed: {e}"))?;
+
+    let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("Reading the Sqlite journal mode failed: {e}"))?;
+    let requested = format!("{:?}", pragmas.journal_mode);
+    if journal_mode.eq_ignore_ascii_case(&requested) {
+        info!("Sqlite journal mode is {journal_mode}");
+    } else {
+        warn!("Sqlite journal mode is {journal_mode} instead of the requested {requested}");
+    }
+    Ok(pool)
+}
+
+/// A migrated in-memory database for tests. Every connection would open a database of
+/// its own, so the pool keeps a single one for good
+#[cfg(test)]
+pub async fn memory_pool() -> SqlitePool {
+    let pool = connect_sqlite(
+        "sqlite::memory:",
+        PoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None),
+        Pragmas {
+            busy_timeout: Duration::from_secs(5),
+            journal_mode: JournalMode::Delete,
+            synchronous: Synchronous::Off,
+        },
+    )
+    .await
+    .expect("opening the in-memory database failed");
+    migrate_sqlite(&pool, None)
+        .await
+        .expect("migrating the in-memory database failed");
+    pool
+}
+
+/// Writes a consistent copy of the database to `out` with VACUUM INTO, which only reads,
+/// so the checker keeps writing meanwhile. `out` mustn't exist yet. The copy is opened
+/// and has to pass an integrity check
+pub async fn backup_sqlite(pool: &SqlitePool, out: &Path) -> Result<(), String> {
+    if out.exists() {
+        return Err(format!("{} exists already", out.display()));
+    }
+    let out_path = out
+        .to_str()
+        .ok_or_else(|| format!("{} is no valid UTF-8 path", out.display()))?;
+    sqlx::query(sqlite_queries::VACUUM_INTO)
+        .bind(out_path)
+        .execute(pool)
         .await
-        .expect("Migrations folder couldn't be found");
-    migrator
-        .run(pool)
+        .map_err(|e| format!("Writing the backup to {} failed: {e}", out.display()))?;
+
+    let mut backup =
+        SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(out).read_only(true))
+            .await
+            .map_err(|e| format!("Opening the backup at {} failed: {e}", out.display()))?;
+    let problems: Vec<String> = sqlx::query_scalar(sqlite_queries::INTEGRITY_CHECK)
+        .fetch_all(&mut backup)
         .await
-        .expect("Sqlite migration(s) failed");
+        .map_err(|e| format!("Checking the backup at {} failed: {e}", out.display()))?;
+    let _ = backup.close().await;
+    if problems != ["ok"] {
+        return Err(format!(
+            "The backup at {} failed its integrity check: {}",
+            out.display(),
+            problems.join("; ")
+        ));
+    }
+
+    info!("Backed up the Sqlite database to {}", out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Store;
+    use crate::tests::{check, website};
+    use chrono::{TimeDelta, Utc};
+
+    /// A migrated database file with a website "busy", opened with the defaults of the
+    /// command line but for `busy_timeout`
+    async fn file_pool(name: &str, busy_timeout: Duration) -> SqlitePool {
+        let path =
+            std::env::temp_dir().join(format!("uptime_ferris_{name}_{}.db", std::process::id()));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+        let pool = connect_sqlite(
+            path.to_str().unwrap(),
+            PoolOptions::new().max_connections(4),
+            Pragmas {
+                busy_timeout,
+                journal_mode: JournalMode::Wal,
+                synchronous: Synchronous::Normal,
+            },
+        )
+        .await
+        .unwrap();
+        migrate_sqlite(&pool, None).await.unwrap();
+        pool.insert_website(&website("busy", serde_json::json!({})))
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn checks_are_written_while_a_read_is_open() {
+        // Without a busy timeout a write that had to wait for the read fails right away
+        let pool = file_pool("open_read", Duration::ZERO).await;
+        let mut read = pool.begin().await.unwrap();
+        let before: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Logs")
+            .fetch_one(&mut *read)
+            .await
+            .unwrap();
+
+        let written = pool.write_logs(&[check("busy", 200, Utc::now())]).await;
+        let during: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Logs")
+            .fetch_one(&mut *read)
+            .await
+            .unwrap();
+        read.commit().await.unwrap();
+
+        assert_eq!(written.unwrap(), 1);
+        assert_eq!((before, during), (0, 0));
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+    }
+
+    #[tokio::test]
+    async fn concurrent_reads_and_writes_all_succeed() {
+        let pool = file_pool("concurrent", Duration::from_secs(5)).await;
+        let start = Utc::now() - TimeDelta::days(1);
+
+        let writer = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                for minute in 0..200 {
+                    let at = start + TimeDelta::minutes(minute);
+                    pool.write_logs(&[check("busy", 200, at)]).await.unwrap();
+                }
+            }
+        });
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    for _ in 0..50 {
+                        let now = Utc::now();
+                        pool.status_summaries(
+                            now - TimeDelta::hours(24),
+                            now - TimeDelta::days(30),
+                        )
+                        .await
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        writer.await.unwrap();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+
+        let logs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Logs")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(logs, 200);
+    }
 }
@@ -1,20 +1,323 @@
-pub const SELECT_MONTHLY_STATS: &str = r#"
-                SELECT strftime('%Y-%m-%d 00:00:00', created_at) as time,
-                CAST(COUNT(CASE WHEN status = 200 THEN 1 END) * 100 / COUNT(*) AS INTEGER) as uptime_pct
-                FROM Logs
-                LEFT JOIN Websites ON Websites.id = Logs.website_id
-                WHERE Websites.alias = $1
+use crate::store::LatencyQuery;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub use crate::shared_queries::*;
+pub(crate) use crate::store::bind_numbered as bind;
+
+crate::store::impl_store!(SqlitePool, SqliteConnection);
+
+pub(crate) const LATENCY_QUERY: LatencyQuery = LatencyQuery::Samples(SELECT_LATENCY_SAMPLES);
+
+pub const INSERT_INTO_LOGS_QUERY: &str = r#"INSERT INTO Logs
+                (website_id, status, ip_family, response_time_ms, connection_reused, degraded, error,
+                reason_phrase, server_header, response_headers, content_hash, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, datetime($12))"#;
+pub const SELECT_STATS_BY_DAY: &str = r#"
+                SELECT strftime('%Y-%m-%d 00:00:00', Buckets.bucket_start) as time,
+                CAST(SUM(Buckets.successes - CASE WHEN Websites.strict
+                    THEN Buckets.degraded_successes ELSE 0 END) * 100 / SUM(Buckets.checks) AS INTEGER) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 / SUM(Buckets.checks) AS INTEGER) as degraded_pct,
+                SUM(Buckets.checks) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = $1
+                    AND bucket_start >= datetime($2)
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    CASE WHEN status = 200 THEN 1 ELSE 0 END,
+                    CASE WHEN degraded THEN 1 ELSE 0 END,
+                    CASE WHEN status = 200 AND degraded THEN 1 ELSE 0 END
+                    FROM Logs
+                    WHERE website_id = $1
+                    AND created_at >= datetime($2)
+                    AND created_at >= COALESCE((SELECT datetime(MAX(Folded.bucket_start), '+1 hour')
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '')
+                ) AS Buckets
+                INNER JOIN Websites ON Websites.id = Buckets.website_id
                 GROUP BY time
                 ORDER BY time ASC
-                LIMIT 30
-            "#;
-pub const SELECT_DAILY_STATS: &str = r#"
-                SELECT strftime('%Y-%m-%d %H:00:00', created_at) as time,
-                CAST(COUNT(CASE WHEN status = 200 THEN 1 END) * 100 / COUNT(*) AS INTEGER) as uptime_pct
-                FROM Logs
-                LEFT JOIN Websites ON Websites.id = Logs.website_id
-                WHERE Websites.alias = $1
+                "#;
+pub const SELECT_STATS_BY_HOUR: &str = r#"
+                SELECT strftime('%Y-%m-%d %H:00:00', Buckets.bucket_start) as time,
+                CAST(SUM(Buckets.successes - CASE WHEN Websites.strict
+                    THEN Buckets.degraded_successes ELSE 0 END) * 100 / SUM(Buckets.checks) AS INTEGER) as uptime_pct,
+                CAST(SUM(Buckets.degraded) * 100 / SUM(Buckets.checks) AS INTEGER) as degraded_pct,
+                SUM(Buckets.checks) as checks
+                FROM (
+                    SELECT website_id, bucket_start, checks, successes, degraded, degraded_successes
+                    FROM StatsRollup
+                    WHERE website_id = $1
+                    AND bucket_start >= datetime($2)
+                    UNION ALL
+                    SELECT website_id, created_at, 1,
+                    CASE WHEN status = 200 THEN 1 ELSE 0 END,
+                    CASE WHEN degraded THEN 1 ELSE 0 END,
+                    CASE WHEN status = 200 AND degraded THEN 1 ELSE 0 END
+                    FROM Logs
+                    WHERE website_id = $1
+                    AND created_at >= datetime($2)
+                    AND created_at >= COALESCE((SELECT datetime(MAX(Folded.bucket_start), '+1 hour')
+                        FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '')
+                ) AS Buckets
+                INNER JOIN Websites ON Websites.id = Buckets.website_id
                 GROUP BY time
                 ORDER BY time ASC
-                LIMIT 24
                 "#;
+/// SQLite has no percentile function, the response times are bucketed in Rust instead
+pub const SELECT_LATENCY_SAMPLES: &str = r#"
+                SELECT Logs.created_at, Logs.response_time_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND Logs.created_at >= datetime($2)
+                AND Logs.response_time_ms IS NOT NULL
+                ORDER BY Logs.created_at ASC
+                "#;
+pub const SELECT_HOURLY_LATENCY_SERIES: &str = r#"
+                SELECT AVG(Logs.response_time_ms) as latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.response_time_ms IS NOT NULL
+                AND Logs.created_at >= strftime('%Y-%m-%d %H:%M:%S', 'now', '-7 days', '-1 hour')
+                GROUP BY strftime('%Y-%m-%d %H', Logs.created_at)
+                ORDER BY strftime('%Y-%m-%d %H', Logs.created_at) ASC
+                "#;
+/// strftime's %w counts 0 = Sunday to 6 = Saturday, shifted to start at 0 = Monday
+pub const SELECT_UPTIME_HEATMAP: &str = r#"
+                SELECT (CAST(strftime('%w', Logs.created_at) AS INTEGER) + 6) % 7 as weekday,
+                CAST(strftime('%H', Logs.created_at) AS INTEGER) as hour,
+                CAST(COUNT(CASE WHEN status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100 / COUNT(*) AS INTEGER) as uptime_pct,
+                COUNT(*) as samples
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= strftime('%Y-%m-%d %H:%M:%S', 'now', '-' || ($2 * 7) || ' days')
+                GROUP BY weekday, hour
+                "#;
+/// Uptime like the daily and monthly stats, over the window from $2 until before $3.
+/// datetime() brings the bound timestamps into the stored format
+pub const SELECT_WINDOW_STATS: &str = r#"
+                SELECT CAST(COUNT(CASE WHEN status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0 / NULLIF(COUNT(*), 0) AS REAL) as uptime_pct,
+                AVG(Logs.response_time_ms) as average_latency_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= datetime($2) AND Logs.created_at < datetime($3)
+                "#;
+pub const SELECT_STATUS_CODE_COUNTS: &str = r#"
+                SELECT Logs.status, COUNT(*) as count
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= datetime($2) AND Logs.status IS NOT NULL
+                GROUP BY Logs.status
+                ORDER BY count DESC, Logs.status
+                "#;
+pub const SELECT_WINDOW_STATUSES: &str = r#"
+                SELECT Logs.created_at, Logs.status
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= datetime($2) AND Logs.created_at < datetime($3)
+                ORDER BY Logs.created_at ASC
+                "#;
+pub const SELECT_WINDOW_LOGS: &str = r#"
+                SELECT Logs.created_at, Logs.status, Logs.response_time_ms
+                FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1
+                AND Logs.created_at >= datetime($2) AND Logs.created_at < datetime($3)
+                ORDER BY Logs.created_at ASC
+                "#;
+/// Latest check, uptime since $1 and since $2 and newest failed check since $2 of every
+/// website, $1 being the later of the two
+pub const SELECT_STATUS_SUMMARIES: &str = r#"
+                SELECT Websites.alias, Latest.status as latest_status,
+                CAST(COUNT(CASE WHEN Logs.created_at >= datetime($1) AND Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0
+                    / NULLIF(COUNT(CASE WHEN Logs.created_at >= datetime($1) THEN 1 END), 0)
+                    AS REAL) as uptime_24h,
+                CAST(COUNT(CASE WHEN Logs.status = 200
+                    AND NOT (Websites.strict AND COALESCE(Logs.degraded, FALSE))
+                    THEN 1 END) * 100.0 / NULLIF(COUNT(Logs.id), 0) AS REAL) as uptime_30d,
+                MAX(CASE WHEN Logs.status <> 200 THEN Logs.created_at END) as last_incident
+                FROM Websites
+                LEFT JOIN Logs ON Logs.website_id = Websites.id
+                    AND Logs.created_at >= datetime($2)
+                LEFT JOIN Logs AS Latest ON Latest.id =
+                (SELECT Newest.id FROM Logs AS Newest WHERE Newest.website_id = Websites.id
+                ORDER BY Newest.created_at DESC LIMIT 1)
+                WHERE Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias, Websites.strict, Latest.status
+                ORDER BY Websites.alias
+                "#;
+/// Uptime, nearest rank p95 latency and outages started per calendar month from $2
+/// until before $3, outages counted by their first failed check
+pub const SELECT_MONTH_TRENDS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.created_at, Logs.status, Logs.response_time_ms,
+                    strftime('%Y-%m', Logs.created_at) as month,
+                    Websites.strict AND COALESCE(Logs.degraded, FALSE) as strict_degraded,
+                    LAG(Logs.status) OVER (ORDER BY Logs.created_at) as previous_status
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE Websites.alias = $1
+                    AND Logs.created_at >= datetime($2) AND Logs.created_at < datetime($3)
+                ),
+                Latencies AS (
+                    SELECT month, response_time_ms,
+                    ROW_NUMBER() OVER (PARTITION BY month ORDER BY response_time_ms) as position,
+                    COUNT(*) OVER (PARTITION BY month) as samples
+                    FROM Checks
+                    WHERE response_time_ms IS NOT NULL
+                )
+                SELECT Checks.month,
+                CAST(COUNT(CASE WHEN status = 200 AND NOT strict_degraded
+                    THEN 1 END) * 100.0 / COUNT(*) AS REAL) as uptime_pct,
+                (SELECT CAST(MIN(Latencies.response_time_ms) AS REAL) FROM Latencies
+                    WHERE Latencies.month = Checks.month
+                    AND Latencies.position >= 0.95 * Latencies.samples) as latency_p95_ms,
+                COUNT(CASE WHEN status <> 200
+                    AND (previous_status IS NULL OR previous_status = 200)
+                    THEN 1 END) as incidents,
+                MIN(created_at) as first_check_at
+                FROM Checks
+                GROUP BY Checks.month
+                ORDER BY Checks.month ASC
+                "#;
+pub const DELETE_INCIDENT_UPDATES_BEFORE: &str = r#"
+                DELETE FROM IncidentUpdates WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < datetime($2)))
+                "#;
+pub const DELETE_INCIDENT_ESCALATIONS_BEFORE: &str = r#"
+                DELETE FROM IncidentEscalations WHERE log_id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < datetime($2)))
+                "#;
+pub const DELETE_LOGS_BEFORE: &str = r#"
+                DELETE FROM Logs WHERE id IN
+                (SELECT Logs.id FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Websites.alias = $1 AND ($2 IS NULL OR Logs.created_at < datetime($2)))
+                "#;
+/// Folds every website's checks from the hours before $1, which is on the hour, that
+/// aren't folded yet into StatsRollup
+pub const FOLD_STATS_ROLLUP: &str = r#"
+                INSERT INTO StatsRollup
+                (website_id, bucket_start, checks, successes, degraded, degraded_successes, avg_latency)
+                SELECT website_id, strftime('%Y-%m-%d %H:00:00', created_at) as bucket,
+                COUNT(*),
+                COUNT(CASE WHEN status = 200 THEN 1 END),
+                COUNT(CASE WHEN degraded THEN 1 END),
+                COUNT(CASE WHEN status = 200 AND degraded THEN 1 END),
+                AVG(response_time_ms)
+                FROM Logs
+                WHERE created_at < datetime($1)
+                AND created_at >= COALESCE((SELECT datetime(MAX(Folded.bucket_start), '+1 hour')
+                    FROM StatsRollup AS Folded WHERE Folded.website_id = Logs.website_id), '')
+                GROUP BY website_id, bucket
+                "#;
+pub const DELETE_STATS_ROLLUP_BEFORE: &str = r#"
+                DELETE FROM StatsRollup WHERE website_id IN
+                (SELECT id FROM Websites WHERE alias = $1)
+                AND ($2 IS NULL OR bucket_start < datetime($2))
+                "#;
+pub const SELECT_FILTERED_INCIDENTS: &str = r#"
+                SELECT Logs.id, Logs.created_at as time,
+                Logs.status, Logs.ip_family,
+                COALESCE(Logs.impact, Websites.impact_weight) as impact,
+                Logs.error, Logs.reason_phrase, Logs.server_header, Logs.response_headers,
+                Logs.acknowledged_by FROM Logs
+                INNER JOIN Websites ON Websites.id = Logs.website_id
+                WHERE Logs.website_id = $1 AND Logs.status <> 200
+                AND ($2 IS NULL OR Logs.created_at >= datetime($2))
+                AND ($3 IS NULL OR Logs.created_at < datetime($3))
+                AND ($4 IS NULL OR Logs.status = $4)
+                ORDER BY Logs.created_at DESC, Logs.id DESC
+                LIMIT $5
+                "#;
+/// Checks, good checks, outages started and seconds down of every website or just $1
+/// from $2 until before $3. A failed check counts as down until the next check, the last
+/// one until $3
+pub const SELECT_SLA_COUNTS: &str = r#"
+                WITH Checks AS (
+                    SELECT Logs.website_id, Logs.created_at, Logs.status,
+                    Websites.strict AND COALESCE(Logs.degraded, FALSE) as strict_degraded,
+                    LAG(Logs.status) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as previous_status,
+                    LEAD(Logs.created_at) OVER (PARTITION BY Logs.website_id
+                        ORDER BY Logs.created_at) as next_check_at
+                    FROM Logs
+                    INNER JOIN Websites ON Websites.id = Logs.website_id
+                    WHERE ($1 IS NULL OR Websites.alias = $1)
+                    AND Logs.created_at >= datetime($2) AND Logs.created_at < datetime($3)
+                )
+                SELECT Websites.alias,
+                COUNT(Checks.created_at) as total_checks,
+                COUNT(CASE WHEN Checks.status = 200 AND NOT Checks.strict_degraded
+                    THEN 1 END) as successful_checks,
+                COUNT(CASE WHEN Checks.status <> 200
+                    AND (Checks.previous_status IS NULL OR Checks.previous_status = 200)
+                    THEN 1 END) as incidents,
+                CAST(ROUND(COALESCE(SUM(CASE WHEN Checks.status <> 200
+                    THEN (julianday(COALESCE(Checks.next_check_at, datetime($3)))
+                        - julianday(Checks.created_at)) * 86400 END), 0)) AS INTEGER)
+                    as downtime_secs
+                FROM Websites
+                LEFT JOIN Checks ON Checks.website_id = Websites.id
+                WHERE ($1 IS NULL OR Websites.alias = $1) AND Websites.deleted_at IS NULL
+                GROUP BY Websites.id, Websites.alias
+                ORDER BY Websites.alias
+                "#;
+/// Up to $2 of the checks before $1 of the websites whose retention_days is $3, NULL
+/// for the ones going by --retention-days, oldest first. Failed checks no successful one
+/// followed belong to an incident that is still open and are kept
+pub const DELETE_EXPIRED_LOGS_BATCH: &str = r#"
+                DELETE FROM Logs WHERE id IN
+                (SELECT Old.id FROM Logs AS Old
+                WHERE Old.created_at < datetime($1)
+                AND (Old.status = 200 OR EXISTS (SELECT 1 FROM Logs AS Up
+                    WHERE Up.website_id = Old.website_id AND Up.status = 200
+                    AND Up.created_at > Old.created_at))
+                AND Old.website_id IN (SELECT id FROM Websites
+                    WHERE retention_days = $3 OR ($3 IS NULL AND retention_days IS NULL))
+                ORDER BY Old.id
+                LIMIT $2)
+                "#;
+/// Hands the pages freed by deletes back to the file system, only with auto_vacuum set
+/// to INCREMENTAL
+pub const INCREMENTAL_VACUUM: &str = "PRAGMA incremental_vacuum";
+/// Copies the database into the file $1 in one read transaction
+pub const VACUUM_INTO: &str = "VACUUM INTO $1";
+/// One row "ok", or a row per problem found
+pub const INTEGRITY_CHECK: &str = "PRAGMA integrity_check";
+/// Only takes effect outside a transaction
+pub const FOREIGN_KEYS_OFF: &str = "PRAGMA foreign_keys = OFF";
+pub const FOREIGN_KEYS_ON: &str = "PRAGMA foreign_keys = ON";
+/// Every website `migrate-data` copies, deleted ones included. Websites created before
+/// inserts set created_at hold the text 'now'
+pub const SELECT_WEBSITES_TO_COPY: &str = "SELECT id, url, alias,
+    NULLIF(created_at, 'now') as created_at, ip_version, impact_weight, config, monitor_type,
+    client_cert_path, client_key_path, client_cert_encrypted, client_key_encrypted,
+    client_cert_fingerprint, client_cert_expires_at, slack_webhook_url, discord_webhook_url,
+    invert, alert_email_to, tls_cert_serial, tls_cert_expires_at, tls_expiry_notified_days,
+    status_override, status_override_note, status_override_expires_at, deleted_at,
+    retention_days FROM Websites ORDER BY id";
+/// Up to $2 of the checks after id $1
+pub const SELECT_LOGS_TO_COPY: &str = "SELECT id, website_id, status, created_at, ip_family,
+    impact, response_time_ms, connection_reused, degraded, error, reason_phrase,
+    server_header, response_headers, content_hash, acknowledged_by, acknowledged_at,
+    ack_escalated_at FROM Logs WHERE id > $1 ORDER BY id LIMIT $2";
+/// Up to $2 of the hourly buckets after rowid $1
+pub const SELECT_STATS_ROLLUP_TO_COPY: &str = "SELECT rowid as id, website_id, bucket_start,
+    checks, successes, degraded, degraded_successes, avg_latency FROM StatsRollup
+    WHERE rowid > $1 ORDER BY rowid LIMIT $2";
@@ -0,0 +1,159 @@
+use crate::WebsiteStats;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use utoipa::{IntoParams, ToSchema};
+
+/// What the stats serve unless asked otherwise
+pub const DEFAULT_RANGE: StatsRange = StatsRange::DAY;
+
+/// The longest range stats can be asked for
+const MAX_HOURS: i64 = 365 * 24;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SplitBy {
+    Hour,
+    Day,
+}
+
+impl SplitBy {
+    pub fn duration(&self) -> TimeDelta {
+        match self {
+            Self::Hour => TimeDelta::hours(1),
+            Self::Day => TimeDelta::days(1),
+        }
+    }
+
+    fn suffix(&self) -> char {
+        match self {
+            Self::Hour => 'h',
+            Self::Day => 'd',
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+}
+
+/// How far back uptime stats go and how they're bucketed, hourly for ranges given in
+/// hours like `48h` and daily for ranges given in days like `7d`
+#[derive(Clone, Copy, PartialEq)]
+pub struct StatsRange {
+    splits: i32,
+    split_by: SplitBy,
+}
+
+impl StatsRange {
+    /// 24 hourly buckets
+    pub const DAY: Self = Self {
+        splits: 24,
+        split_by: SplitBy::Hour,
+    };
+    /// 30 daily buckets
+    pub const MONTH: Self = Self {
+        splits: 30,
+        split_by: SplitBy::Day,
+    };
+
+    pub fn split_by(&self) -> SplitBy {
+        self.split_by
+    }
+
+    pub fn splits(&self) -> i32 {
+        self.splits
+    }
+
+    /// What the chart is labeled with, e.g. "Last 7 days"
+    pub fn label(&self) -> String {
+        match self.splits {
+            1 => format!("Last {}", self.split_by.unit()),
+            splits => format!("Last {splits} {}s", self.split_by.unit()),
+        }
+    }
+
+    /// Name the stats queries are timed under in the slow query log
+    pub fn query_name(&self) -> &'static str {
+        match self.split_by {
+            SplitBy::Hour => "hourly_stats",
+            SplitBy::Day => "daily_stats",
+        }
+    }
+
+    /// Start of the first bucket, the last one is the one `now` is in
+    pub fn first_bucket(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let bucket = self.split_by.duration();
+        let current = now.duration_trunc(bucket).unwrap_or(now);
+
+        current - bucket * (self.splits - 1)
+    }
+}
+
+impl Display for StatsRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.splits, self.split_by.suffix())
+    }
+}
+
+impl FromStr for StatsRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unsupported =
+            || format!("Unsupported range '{s}', use a number of hours or days like 48h or 7d");
+        let (splits, split_by) = match (s.strip_suffix('h'), s.strip_suffix('d')) {
+            (Some(hours), _) => (hours, SplitBy::Hour),
+            (_, Some(days)) => (days, SplitBy::Day),
+            _ => return Err(unsupported()),
+        };
+        let splits = splits
+            .parse::<i32>()
+            .ok()
+            .filter(|splits| *splits > 0)
+            .ok_or_else(unsupported)?;
+        if i64::from(splits) * split_by.duration().num_hours() > MAX_HOURS {
+            return Err(format!("Range '{s}' is longer than a year"));
+        }
+
+        Ok(Self { splits, split_by })
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsParams {
+    /// Hours or days like 48h or 7d, at most a year, 24h when left out
+    range: Option<String>,
+}
+
+impl StatsParams {
+    pub fn range(&self) -> Result<StatsRange, String> {
+        self.range
+            .as_deref()
+            .map_or(Ok(DEFAULT_RANGE), StatsRange::from_str)
+    }
+}
+
+/// The whole range at once
+#[derive(Serialize, ToSchema)]
+pub struct StatsSummary {
+    pub from: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// `None` when there wasn't a single check in the range
+    pub uptime_pct: Option<f64>,
+    pub checks: i64,
+}
+
+/// Uptime of a website bucketed over a range, oldest bucket first
+#[derive(Serialize, ToSchema)]
+pub struct UptimeStats {
+    pub range: String,
+    pub buckets: Vec<WebsiteStats>,
+    pub summary: StatsSummary,
+}
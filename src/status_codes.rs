@@ -0,0 +1,70 @@
+use crate::checker::{
+    CONNECTION_FAILED_STATUS, CONTENT_FAILURE_STATUS, TIMEOUT_STATUS, UNEXPECTEDLY_REACHABLE_STATUS,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// How many checks of a range ended with one status, as counted by the backend queries
+#[derive(Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct StatusCount {
+    /// HTTP status, or one of the 596 to 599 sentinels for checks that got none
+    pub status: i16,
+    pub count: i64,
+}
+
+impl StatusCount {
+    /// What the sentinels stand for, the plain code for anything else
+    pub fn label(&self) -> String {
+        match self.status {
+            CONNECTION_FAILED_STATUS => "connection failed".to_owned(),
+            TIMEOUT_STATUS => "timeout".to_owned(),
+            CONTENT_FAILURE_STATUS => "content check failed".to_owned(),
+            UNEXPECTEDLY_REACHABLE_STATUS => "unexpectedly reachable".to_owned(),
+            status => status.to_string(),
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self.status {
+            200 => "status-bar-up",
+            _ => "status-bar-down",
+        }
+    }
+}
+
+/// The status distribution of the website page, most frequent first
+#[derive(Clone, Serialize)]
+pub struct StatusCodes {
+    pub counts: Vec<StatusCount>,
+}
+
+impl StatusCodes {
+    pub fn new(counts: Vec<StatusCount>) -> Self {
+        Self { counts }
+    }
+
+    /// Bar length relative to the most frequent status, in percent
+    pub fn width_pct(&self, count: &StatusCount) -> i64 {
+        let max = self
+            .counts
+            .iter()
+            .map(|count| count.count)
+            .max()
+            .unwrap_or(0);
+        if max == 0 {
+            0
+        } else {
+            (count.count * 100 / max).max(1)
+        }
+    }
+
+    /// Share of all checks in the range, in percent
+    pub fn share_pct(&self, count: &StatusCount) -> f64 {
+        let total: i64 = self.counts.iter().map(|count| count.count).sum();
+        if total == 0 {
+            0.0
+        } else {
+            (count.count as f64 * 1000.0 / total as f64).round() / 10.0
+        }
+    }
+}
@@ -0,0 +1,1924 @@
+use crate::{
+    ApiError, ChannelSummary, ClearedHistory, ConnectionReuse, DeletedWebsite, Event, Incident,
+    IncidentDetail, IncidentUpdate, NotificationLogEntry, NotificationTarget, Website,
+    WebsiteStats, WebsiteSummary,
+    acknowledgment::OpenIncident,
+    backup::{BackupTables, RestoreReport, RestoreStep},
+    checker::PendingLog,
+    escalation::{EscalationRule, NewEscalationRule},
+    etag::ContentVersion,
+    heatmap::HeatmapSample,
+    incident_filter::IncidentFilter,
+    latency::LatencyPercentiles,
+    manual_status::StatusOverride,
+    metrics::WebsiteMetrics,
+    notifications::throttle::NotifiedState,
+    public_status::StatusSummary,
+    sla::SlaCounts,
+    stats_range::{SplitBy, StatsRange},
+    status_codes::StatusCount,
+    trends::MonthSample,
+    website_import::{ImportMode, ImportReport},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+use sqlx::{ColumnIndex, Decode, FromRow, Row, Type};
+
+/// Rows of an import by the alias they were given, with the website if it was valid
+pub type ImportRows = Vec<(Option<String>, Result<Website, ApiError>)>;
+
+/// Time, status and response time of a check
+pub type CheckRow = (DateTime<Utc>, i16, Option<i32>);
+
+/// Storage of websites for the handlers and the checker, which take it as
+/// `Arc<dyn Store>` and don't need to know the backend behind it. Every backend
+/// implements it with `impl_store!`
+#[async_trait]
+pub(crate) trait Store: Send + Sync {
+    /// The website together with its channel, either both are there afterwards or
+    /// neither is
+    async fn insert_website(&self, website: &Website) -> Result<WebsiteSummary, ApiError>;
+
+    /// Every row in one transaction, each in a savepoint of its own so a failing one
+    /// doesn't take the others with it unless the mode says so
+    async fn import_websites(
+        &self,
+        rows: ImportRows,
+        mode: ImportMode,
+    ) -> Result<ImportReport, ApiError>;
+
+    /// Every website in the shape the import takes
+    async fn export_websites(&self) -> Result<Vec<Website>, sqlx::Error>;
+
+    /// Hides the website and stops its checks, its history stays until it's purged
+    async fn soft_delete_website(&self, alias: &str, now: DateTime<Utc>) -> Result<(), ApiError>;
+
+    /// Brings back a website deleted at or after `deleted_since`
+    async fn restore_website(
+        &self,
+        alias: &str,
+        deleted_since: DateTime<Utc>,
+    ) -> Result<(), ApiError>;
+
+    /// Websites deleted at or after `deleted_since`, the latest first
+    async fn deleted_websites(
+        &self,
+        deleted_since: DateTime<Utc>,
+    ) -> Result<Vec<DeletedWebsite>, sqlx::Error>;
+
+    /// Aliases of the websites deleted before `deleted_before`
+    async fn purgeable_websites(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<String>, sqlx::Error>;
+
+    /// The website for good, its checks, stats and everything else referencing it go
+    /// along through ON DELETE CASCADE
+    async fn purge_website(&self, alias: &str) -> Result<(), ApiError>;
+
+    /// The website's checks and incidents from before `before`, or all of them
+    async fn clear_history(
+        &self,
+        alias: &str,
+        before: Option<DateTime<Utc>>,
+    ) -> Result<ClearedHistory, ApiError>;
+
+    /// Changes with every check and website, the dashboard's ETag is made from it
+    async fn content_version(&self) -> Result<ContentVersion, sqlx::Error>;
+
+    /// Every website the dashboard lists, deleted ones left out
+    async fn websites(&self) -> Result<Vec<Website>, sqlx::Error>;
+
+    /// One page of the websites by alias
+    async fn website_summaries(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<WebsiteSummary>, sqlx::Error>;
+
+    async fn website_count(&self) -> Result<i64, sqlx::Error>;
+
+    async fn website(&self, alias: &str) -> Result<Option<Website>, sqlx::Error>;
+
+    async fn website_id(&self, alias: &str) -> Result<Option<i32>, sqlx::Error>;
+
+    /// The alias the website with this id goes by now
+    async fn website_alias(&self, id: i32) -> Result<Option<String>, sqlx::Error>;
+
+    /// The ongoing incident of every website that is down
+    async fn open_incidents(&self) -> Result<Vec<OpenIncident>, sqlx::Error>;
+
+    /// Manual statuses that haven't expired by `now`
+    async fn active_status_overrides(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<StatusOverride>, sqlx::Error>;
+
+    async fn shared_fate_hosts(&self) -> Result<Vec<String>, sqlx::Error>;
+
+    /// Alias and impact of every website with an ongoing incident
+    async fn active_incident_impacts(&self) -> Result<Vec<(String, String)>, sqlx::Error>;
+
+    /// Uptime of every website since `day_ago` and `month_ago` with its last incident,
+    /// aggregated in one query
+    async fn status_summaries(
+        &self,
+        day_ago: DateTime<Utc>,
+        month_ago: DateTime<Utc>,
+    ) -> Result<Vec<StatusSummary>, sqlx::Error>;
+
+    /// Uptime per hour or day from `first_bucket` on, oldest first. Buckets without
+    /// checks are left out
+    async fn stats(
+        &self,
+        website_id: i32,
+        split_by: SplitBy,
+        first_bucket: DateTime<Utc>,
+    ) -> Result<Vec<WebsiteStats>, sqlx::Error>;
+
+    /// Failed checks of the website, newest first
+    async fn incidents(
+        &self,
+        website_id: i32,
+        filter: IncidentFilter,
+    ) -> Result<Vec<Incident>, sqlx::Error>;
+
+    /// A tick's checks in one transaction, each in a savepoint so one that fails to
+    /// insert is logged and skipped without the others. Returns how many were written
+    async fn write_logs(&self, logs: &[PendingLog]) -> Result<usize, sqlx::Error>;
+
+    /// The websites the checker goes through, paused and deleted ones left out
+    async fn monitored_websites(&self) -> Result<Vec<Website>, sqlx::Error>;
+
+    /// Id and webhook URL of every notification channel
+    async fn notification_webhooks(&self) -> Result<Vec<(i32, String)>, sqlx::Error>;
+
+    /// Alias, channel id, kind and URL of every channel assigned to a website
+    async fn channel_routes(&self) -> Result<Vec<(String, i32, String, String)>, sqlx::Error>;
+
+    /// Manual statuses expired by `now`, returns how many were cleared
+    async fn clear_expired_status_overrides(&self, now: DateTime<Utc>) -> Result<u64, sqlx::Error>;
+
+    /// Status and time of the website's last check
+    async fn last_status(&self, alias: &str) -> Result<Option<(i16, DateTime<Utc>)>, sqlx::Error>;
+
+    /// Hash of the website's last check that tracked its content
+    async fn last_content_hash(&self, alias: &str) -> Result<Option<String>, sqlx::Error>;
+
+    async fn insert_event(&self, alias: &str, kind: &str, message: &str)
+    -> Result<(), sqlx::Error>;
+
+    /// Average response time per hour, oldest first
+    async fn hourly_latency_series(&self, alias: &str) -> Result<Vec<f64>, sqlx::Error>;
+
+    /// What every channel of the website was last told, by channel
+    async fn notification_states(
+        &self,
+        alias: &str,
+    ) -> Result<Vec<(String, NotifiedState)>, sqlx::Error>;
+
+    async fn upsert_notification_state(
+        &self,
+        alias: &str,
+        channel: &str,
+        state: &NotifiedState,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Remembers the certificate the website serves and the expiry warning it got last
+    async fn update_certificate(
+        &self,
+        alias: &str,
+        serial: &str,
+        expires_at: DateTime<Utc>,
+        notified_days: Option<i32>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Marks the incident as escalated for missing the acknowledgment SLA
+    async fn mark_ack_escalated(
+        &self,
+        incident_id: i32,
+        now: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn escalation_rules(&self) -> Result<Vec<EscalationRule>, sqlx::Error>;
+
+    /// How many steps of the rule already fired for the incident
+    async fn fired_escalation_steps(
+        &self,
+        incident_id: i32,
+        rule_id: i32,
+    ) -> Result<Option<i32>, sqlx::Error>;
+
+    async fn upsert_fired_escalation_steps(
+        &self,
+        incident_id: i32,
+        rule_id: i32,
+        steps: i32,
+        now: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// A trivial query, for the readiness probe
+    async fn ping(&self) -> Result<(), sqlx::Error>;
+
+    /// Every website's latest check
+    async fn website_metrics(&self) -> Result<Vec<WebsiteMetrics>, sqlx::Error>;
+
+    async fn website_summary(&self, alias: &str) -> Result<Option<WebsiteSummary>, sqlx::Error>;
+
+    /// Uptime and degraded percentage of the website's checks from `from` until `until`
+    async fn window_stats(
+        &self,
+        alias: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<(Option<f64>, Option<f64>), sqlx::Error>;
+
+    /// Everything but the website's history, returns how many websites went by `alias`
+    async fn update_website(&self, alias: &str, website: &Website) -> Result<u64, sqlx::Error>;
+
+    /// Returns how many websites went by `alias`
+    async fn rename_website(&self, alias: &str, new_alias: &str) -> Result<u64, sqlx::Error>;
+
+    /// Everything a backup is made of, status overrides that expired by `now` left out
+    async fn backup_tables(&self, now: DateTime<Utc>) -> Result<BackupTables, sqlx::Error>;
+
+    /// Every step in one transaction, each in a savepoint of its own so the report covers
+    /// all of them even after one failed. Nothing is kept on a dry run
+    async fn restore_backup(
+        &self,
+        steps: Vec<RestoreStep>,
+        dry_run: bool,
+    ) -> Result<RestoreReport, ApiError>;
+
+    /// Uptime per weekday and hour over the last `weeks`
+    async fn uptime_heatmap(
+        &self,
+        alias: &str,
+        weeks: i32,
+    ) -> Result<Vec<HeatmapSample>, sqlx::Error>;
+
+    /// Response time percentiles per bucket of the range from `first_bucket` on, buckets
+    /// without checks are left out
+    async fn latency(
+        &self,
+        alias: &str,
+        range: StatsRange,
+        first_bucket: DateTime<Utc>,
+    ) -> Result<Vec<LatencyPercentiles>, sqlx::Error>;
+
+    /// Checks per status since `from`
+    async fn status_code_counts(
+        &self,
+        alias: &str,
+        from: DateTime<Utc>,
+    ) -> Result<Vec<StatusCount>, sqlx::Error>;
+
+    /// Uptime per day from `from` until before `until`
+    async fn month_trends(
+        &self,
+        alias: &str,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<MonthSample>, sqlx::Error>;
+
+    async fn connection_reuse(&self, alias: &str) -> Result<ConnectionReuse, sqlx::Error>;
+
+    /// When the website was last checked, `None` before its first check
+    async fn last_check(&self, alias: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error>;
+
+    async fn events(&self, alias: &str) -> Result<Vec<Event>, sqlx::Error>;
+
+    async fn notification_log(&self, alias: &str)
+    -> Result<Vec<NotificationLogEntry>, sqlx::Error>;
+
+    /// The channels the website notifies
+    async fn website_channels(&self, alias: &str) -> Result<Vec<ChannelSummary>, sqlx::Error>;
+
+    /// Why the website's last failed check failed, if it was recorded
+    async fn last_failure(&self, alias: &str) -> Result<Option<String>, sqlx::Error>;
+
+    /// Start and acknowledgment of the website's incidents acknowledged since `since`
+    async fn acknowledged_incidents(
+        &self,
+        alias: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>, sqlx::Error>;
+
+    /// Every website's counts over the period, or only those of `alias`
+    async fn sla_counts(
+        &self,
+        alias: Option<&str>,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<SlaCounts>, sqlx::Error>;
+
+    /// Since when the website is up, or down
+    async fn status_since(
+        &self,
+        alias: &str,
+        up: bool,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error>;
+
+    /// Returns how many incidents of the website had the id
+    async fn set_incident_impact(
+        &self,
+        alias: &str,
+        id: i32,
+        impact: &str,
+    ) -> Result<u64, sqlx::Error>;
+
+    async fn incident(&self, id: i32) -> Result<Option<IncidentDetail>, sqlx::Error>;
+
+    /// When the website answered with a 200 again after the incident
+    async fn incident_resolution(&self, id: i32) -> Result<Option<DateTime<Utc>>, sqlx::Error>;
+
+    async fn incident_updates(&self, id: i32) -> Result<Vec<IncidentUpdate>, sqlx::Error>;
+
+    async fn insert_incident_update(&self, id: i32, message: &str) -> Result<(), sqlx::Error>;
+
+    /// Only the first acknowledgment of an incident is kept
+    async fn acknowledge_incident(
+        &self,
+        id: i32,
+        by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn notifications(&self) -> Result<Vec<NotificationTarget>, sqlx::Error>;
+
+    async fn insert_notification(&self, url: &str) -> Result<(), sqlx::Error>;
+
+    /// Returns how many notifications had the id
+    async fn delete_notification(&self, id: i32) -> Result<u64, sqlx::Error>;
+
+    async fn notification_url(&self, id: i32) -> Result<Option<String>, sqlx::Error>;
+
+    /// Sets the manual status, `None` clears it. Returns how many websites went by `alias`
+    async fn update_status_override(
+        &self,
+        alias: &str,
+        status: Option<&str>,
+        note: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, sqlx::Error>;
+
+    /// Coalescing of simultaneous incidents for every website of the host
+    async fn set_shared_fate(&self, host: &str, enabled: bool) -> Result<(), sqlx::Error>;
+
+    async fn notification_channels(&self) -> Result<Vec<ChannelSummary>, sqlx::Error>;
+
+    async fn insert_notification_channel(
+        &self,
+        name: &str,
+        kind: &str,
+        config: &str,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Kind and stored config of the channel
+    async fn notification_channel(&self, id: i32) -> Result<Option<(String, String)>, sqlx::Error>;
+
+    /// The channel with its assignments, escalation rules keep going without it. Returns
+    /// how many channels had the id
+    async fn delete_notification_channel(&self, id: i32) -> Result<u64, sqlx::Error>;
+
+    async fn assign_channel(&self, alias: &str, channel_id: i32) -> Result<(), sqlx::Error>;
+
+    /// Returns how many of the website's assignments were of the channel
+    async fn unassign_channel(&self, alias: &str, channel_id: i32) -> Result<u64, sqlx::Error>;
+
+    async fn website_escalation_rules(
+        &self,
+        alias: &str,
+    ) -> Result<Vec<EscalationRule>, sqlx::Error>;
+
+    /// Returns how many websites went by `alias`
+    async fn insert_escalation_rule(
+        &self,
+        alias: &str,
+        rule: &NewEscalationRule,
+    ) -> Result<u64, sqlx::Error>;
+
+    /// The rule with what it fired. Returns how many of the website's rules had the id
+    async fn delete_escalation_rule(&self, alias: &str, id: i32) -> Result<u64, sqlx::Error>;
+
+    /// Time, status and response time of the website's checks from `from` until before
+    /// `to`, oldest first, read while they are consumed
+    fn window_logs<'a>(
+        &'a self,
+        alias: &'a str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> BoxStream<'a, Result<CheckRow, sqlx::Error>>;
+}
+
+/// How a backend gets latency percentiles. Postgres computes them in SQL, the others hand
+/// over the samples for `latency::from_samples`
+pub(crate) enum LatencyQuery {
+    Percentiles {
+        by_hour: &'static str,
+        by_day: &'static str,
+    },
+    Samples(&'static str),
+}
+
+/// Binds the first list of values, for backends with numbered placeholders like `$1`
+/// that take each value once however often the query uses it. The backend's queries
+/// module re-exports it as `bind!`
+macro_rules! bind_numbered {
+    ($query:expr, [$($numbered:expr),* $(,)?], [$($positional:expr),* $(,)?] $(,)?) => {
+        $query$(.bind($numbered))*
+    };
+}
+
+/// Binds the second list of values, for MySQL whose `?` placeholders take a value each
+/// in the order they appear in the query. The backend's queries module re-exports it as
+/// `bind!`
+macro_rules! bind_positional {
+    ($query:expr, [$($numbered:expr),* $(,)?], [$($positional:expr),* $(,)?] $(,)?) => {
+        $query$(.bind($positional))*
+    };
+}
+
+pub(crate) use {bind_numbered, bind_positional};
+
+/// Decodes each row on its own, so a website whose config doesn't decode is skipped with
+/// a warning instead of failing the query for every other website
+pub(crate) fn decode_websites<R>(rows: &[R]) -> Vec<Website>
+where
+    R: Row,
+    for<'r> Website: FromRow<'r, R>,
+    for<'r> &'r str: ColumnIndex<R>,
+    for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+{
+    rows.iter()
+        .filter_map(|row| match Website::from_row(row) {
+            Ok(website) => Some(website),
+            Err(e) => {
+                let alias = row.try_get::<String, _>("alias").unwrap_or_default();
+                tracing::warn!("Skipping website {alias}, its row doesn't decode: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Implements `Store` for a backend's pool, whose connections are `$connection`. Expanded
+/// in the backend's queries module, so the queries, `LATENCY_QUERY` and `bind!` are the
+/// backend's own
+macro_rules! impl_store {
+    ($pool:ty, $connection:ty) => {
+        /// The website and its channel, left to the caller to commit or roll back
+        async fn write_website(
+            website: &crate::Website,
+            db: &mut $connection,
+        ) -> Result<crate::WebsiteSummary, crate::ApiError> {
+            if let Err(e) = sqlx::query(INSERT_INTO_WEBSITES_QUERY)
+                .bind(&website.url)
+                .bind(&website.alias)
+                .bind(&website.ip_version)
+                .bind(&website.impact_weight)
+                .bind(website.config.to_json())
+                .bind(&website.monitor_type)
+                .bind(&website.client_cert_path)
+                .bind(&website.client_key_path)
+                .bind(&website.client_cert_encrypted)
+                .bind(&website.client_key_encrypted)
+                .bind(&website.client_cert_fingerprint)
+                .bind(website.client_cert_expires_at)
+                .bind(&website.slack_webhook_url)
+                .bind(&website.discord_webhook_url)
+                .bind(website.invert)
+                .bind(&website.alert_email_to)
+                .bind(website.retention_days)
+                .bind(chrono::Utc::now())
+                .execute(&mut *db)
+                .await
+            {
+                return Err(crate::website_write_error(e, &website.alias));
+            };
+
+            if let Some(channel_id) = website.channel_id
+                && let Err(e) = bind!(
+                    sqlx::query(ASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY),
+                    [&website.alias, channel_id],
+                    [channel_id, &website.alias],
+                )
+                .execute(&mut *db)
+                .await
+            {
+                return Err(crate::website_write_error(e, &website.alias));
+            };
+
+            Ok(
+                sqlx::query_as::<_, crate::WebsiteSummary>(SELECT_WEBSITE_SUMMARY_BY_ALIAS_QUERY)
+                    .bind(&website.alias)
+                    .fetch_one(&mut *db)
+                    .await?,
+            )
+        }
+
+        /// Everything but the website's history, returns how many websites went by `alias`
+        async fn update_website(
+            alias: &str,
+            website: &crate::Website,
+            db: &mut $connection,
+        ) -> Result<u64, sqlx::Error> {
+            // MySQL repeats the new fingerprint for every certificate column it
+            // decides on and takes the current alias last
+            let updated = bind!(
+                sqlx::query(UPDATE_WEBSITE_BY_ALIAS_QUERY),
+                [
+                    alias,
+                    &website.url,
+                    &website.alias,
+                    &website.ip_version,
+                    &website.impact_weight,
+                    website.config.to_json(),
+                    &website.monitor_type,
+                    &website.client_cert_path,
+                    &website.client_key_path,
+                    &website.client_cert_encrypted,
+                    &website.client_key_encrypted,
+                    &website.client_cert_fingerprint,
+                    website.client_cert_expires_at,
+                    &website.slack_webhook_url,
+                    &website.discord_webhook_url,
+                    website.invert,
+                    &website.alert_email_to,
+                    website.retention_days,
+                ],
+                [
+                    &website.url,
+                    &website.alias,
+                    &website.ip_version,
+                    &website.impact_weight,
+                    website.config.to_json(),
+                    &website.monitor_type,
+                    &website.client_cert_fingerprint,
+                    &website.client_cert_path,
+                    &website.client_cert_fingerprint,
+                    &website.client_key_path,
+                    &website.client_cert_fingerprint,
+                    &website.client_cert_encrypted,
+                    &website.client_cert_fingerprint,
+                    &website.client_key_encrypted,
+                    &website.client_cert_fingerprint,
+                    &website.client_cert_fingerprint,
+                    website.client_cert_expires_at,
+                    &website.slack_webhook_url,
+                    &website.discord_webhook_url,
+                    website.invert,
+                    &website.alert_email_to,
+                    website.retention_days,
+                    alias,
+                ],
+            )
+            .execute(db)
+            .await?;
+            Ok(updated.rows_affected())
+        }
+
+        async fn restore_step(
+            step: crate::backup::RestoreStep,
+            db: &mut $connection,
+        ) -> Result<crate::backup::RestoreAction, crate::ApiError> {
+            use crate::backup::{RestoreAction, RestoreStep};
+
+            match step {
+                RestoreStep::Channel { name, settings } => {
+                    let existing = find_channel(&name, db).await?;
+                    match (settings, existing) {
+                        (None, Some(_)) => Ok(RestoreAction::Unchanged),
+                        (None, None) => Err(crate::ApiError::BadRequest(
+                            "The backup has no settings for the channel, back it up with \
+                                include_secrets=true"
+                                .to_owned(),
+                        )),
+                        (Some(Err(e)), _) => Err(crate::ApiError::BadRequest(e)),
+                        (Some(Ok((kind, config))), Some(id)) => {
+                            bind!(
+                                sqlx::query(UPDATE_NOTIFICATION_CHANNEL_BY_ID_QUERY),
+                                [id, kind, &config],
+                                [kind, &config, id],
+                            )
+                            .execute(&mut *db)
+                            .await?;
+                            Ok(RestoreAction::Updated)
+                        }
+                        (Some(Ok((kind, config))), None) => {
+                            sqlx::query(INSERT_NOTIFICATION_CHANNEL_QUERY)
+                                .bind(name)
+                                .bind(kind)
+                                .bind(config)
+                                .execute(&mut *db)
+                                .await?;
+                            Ok(RestoreAction::Created)
+                        }
+                    }
+                }
+                RestoreStep::Webhook(url) => {
+                    let existing =
+                        sqlx::query_scalar::<_, i64>(SELECT_NOTIFICATION_COUNT_BY_URL_QUERY)
+                            .bind(&url)
+                            .fetch_one(&mut *db)
+                            .await?;
+                    if existing > 0 {
+                        return Ok(RestoreAction::Unchanged);
+                    }
+                    sqlx::query(INSERT_NOTIFICATION_QUERY)
+                        .bind(url)
+                        .execute(&mut *db)
+                        .await?;
+                    Ok(RestoreAction::Created)
+                }
+                RestoreStep::Website(restored) => restore_backed_up_website(*restored, db).await,
+            }
+        }
+
+        /// Secrets the backup left out stay as they are, what goes along with the website
+        /// is replaced by the backup's
+        async fn restore_backed_up_website(
+            restored: crate::backup::RestoredWebsite,
+            db: &mut $connection,
+        ) -> Result<crate::backup::RestoreAction, crate::ApiError> {
+            let website = restored.website?;
+            let updated = update_website(&website.alias, &website, &mut *db)
+                .await
+                .map_err(|e| crate::website_write_error(e, &website.alias))?;
+            let action = if updated == 0 {
+                write_website(&website, db).await?;
+                crate::backup::RestoreAction::Created
+            } else {
+                crate::backup::RestoreAction::Updated
+            };
+
+            sqlx::query(DELETE_CHANNEL_ASSIGNMENTS_BY_WEBSITE_ALIAS_QUERY)
+                .bind(&website.alias)
+                .execute(&mut *db)
+                .await?;
+            for name in &restored.channels {
+                let channel_id = restored_channel_id(name, db).await?;
+                bind!(
+                    sqlx::query(ASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY),
+                    [&website.alias, channel_id],
+                    [channel_id, &website.alias],
+                )
+                .execute(&mut *db)
+                .await?;
+            }
+
+            sqlx::query(DELETE_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY)
+                .bind(&website.alias)
+                .execute(&mut *db)
+                .await?;
+            for rule in restored.escalation_rules {
+                let channel_id = match &rule.channel {
+                    Some(name) => Some(restored_channel_id(name, db).await?),
+                    None => None,
+                };
+                let rule =
+                    crate::escalation_rule(rule.after_minutes, rule.repeat_minutes, channel_id)?;
+                bind!(
+                    sqlx::query(INSERT_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY),
+                    [
+                        &website.alias,
+                        rule.after_minutes,
+                        rule.repeat_minutes,
+                        rule.channel_id
+                    ],
+                    [
+                        rule.after_minutes,
+                        rule.repeat_minutes,
+                        rule.channel_id,
+                        &website.alias
+                    ],
+                )
+                .execute(&mut *db)
+                .await?;
+            }
+
+            let (status, note, expires_at) =
+                match crate::restored_status_override(restored.status_override)? {
+                    Some((status, note, expires_at)) => (Some(status.as_str()), note, expires_at),
+                    None => (None, None, None),
+                };
+            sqlx::query(UPDATE_STATUS_OVERRIDE_BY_ALIAS_QUERY)
+                .bind(status)
+                .bind(note)
+                .bind(expires_at)
+                .bind(&website.alias)
+                .execute(&mut *db)
+                .await?;
+
+            Ok(action)
+        }
+
+        async fn find_channel(
+            name: &str,
+            db: &mut $connection,
+        ) -> Result<Option<i32>, sqlx::Error> {
+            sqlx::query_scalar::<_, i32>(SELECT_NOTIFICATION_CHANNEL_ID_BY_NAME_QUERY)
+                .bind(name)
+                .fetch_optional(&mut *db)
+                .await
+        }
+
+        async fn restored_channel_id(
+            name: &str,
+            db: &mut $connection,
+        ) -> Result<i32, crate::ApiError> {
+            find_channel(name, db).await?.ok_or_else(|| {
+                crate::ApiError::BadRequest(format!("No notification channel is named '{name}'"))
+            })
+        }
+
+        #[async_trait::async_trait]
+        impl crate::store::Store for $pool {
+            async fn insert_website(
+                &self,
+                website: &crate::Website,
+            ) -> Result<crate::WebsiteSummary, crate::ApiError> {
+                let mut tx = self.begin().await?;
+                match write_website(website, &mut tx).await {
+                    Ok(created) => {
+                        tx.commit().await?;
+                        Ok(created)
+                    }
+                    Err(e) => {
+                        tx.rollback().await?;
+                        Err(e)
+                    }
+                }
+            }
+
+            async fn import_websites(
+                &self,
+                rows: crate::store::ImportRows,
+                mode: crate::website_import::ImportMode,
+            ) -> Result<crate::website_import::ImportReport, crate::ApiError> {
+                use sqlx::Acquire;
+
+                let mut report = crate::website_import::ImportReport::new(mode);
+                let mut tx = self.begin().await?;
+                for (alias, website) in rows {
+                    let outcome = match website {
+                        Ok(website) => {
+                            let mut savepoint = tx.begin().await?;
+                            match write_website(&website, &mut savepoint).await {
+                                Ok(_) => savepoint.commit().await.map_err(crate::ApiError::SQL),
+                                Err(e) => {
+                                    savepoint.rollback().await?;
+                                    Err(e)
+                                }
+                            }
+                        }
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = report.record(alias, outcome) {
+                        tx.rollback().await?;
+                        return Err(e);
+                    }
+                }
+
+                let commit = report.should_commit();
+                if commit {
+                    tx.commit().await?;
+                } else {
+                    tx.rollback().await?;
+                }
+                Ok(report.finish(commit))
+            }
+
+            async fn export_websites(&self) -> Result<Vec<crate::Website>, sqlx::Error> {
+                sqlx::query_as::<_, crate::Website>(SELECT_EXPORTED_WEBSITES_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn soft_delete_website(
+                &self,
+                alias: &str,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(), crate::ApiError> {
+                let deleted = bind!(
+                    sqlx::query(SOFT_DELETE_WEBSITE_BY_ALIAS_QUERY),
+                    [alias, now],
+                    [now, alias],
+                )
+                .execute(self)
+                .await?;
+                if deleted.rows_affected() == 0 {
+                    return Err(crate::ApiError::NotFound);
+                }
+                Ok(())
+            }
+
+            async fn restore_website(
+                &self,
+                alias: &str,
+                deleted_since: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(), crate::ApiError> {
+                let restored = sqlx::query(RESTORE_DELETED_WEBSITE_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .bind(deleted_since)
+                    .execute(self)
+                    .await?;
+                if restored.rows_affected() == 0 {
+                    return Err(crate::ApiError::NotFound);
+                }
+                Ok(())
+            }
+
+            async fn deleted_websites(
+                &self,
+                deleted_since: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::DeletedWebsite>, sqlx::Error> {
+                sqlx::query_as::<_, crate::DeletedWebsite>(SELECT_DELETED_WEBSITES_QUERY)
+                    .bind(deleted_since)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn purgeable_websites(
+                &self,
+                deleted_before: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<String>, sqlx::Error> {
+                sqlx::query_scalar::<_, String>(SELECT_PURGEABLE_WEBSITES_QUERY)
+                    .bind(deleted_before)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn purge_website(&self, alias: &str) -> Result<(), crate::ApiError> {
+                let mut tx = self.begin().await?;
+                match sqlx::query(DELETE_WEBSITE_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .execute(&mut *tx)
+                    .await
+                {
+                    Ok(deleted) if deleted.rows_affected() == 0 => {
+                        tx.rollback().await?;
+                        return Err(crate::ApiError::NotFound);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tx.rollback().await?;
+                        return Err(crate::ApiError::SQL(e));
+                    }
+                }
+
+                tx.commit().await?;
+
+                Ok(())
+            }
+
+            async fn clear_history(
+                &self,
+                alias: &str,
+                before: Option<chrono::DateTime<chrono::Utc>>,
+            ) -> Result<crate::ClearedHistory, crate::ApiError> {
+                let mut tx = self.begin().await?;
+                match sqlx::query_scalar::<_, i32>(SELECT_WEBSITE_ID_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_optional(&mut *tx)
+                    .await
+                {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        tx.rollback().await?;
+                        return Err(crate::ApiError::NotFound);
+                    }
+                    Err(e) => {
+                        tx.rollback().await?;
+                        return Err(crate::ApiError::SQL(e));
+                    }
+                }
+
+                let mut cleared = crate::ClearedHistory::default();
+                for (query, deleted) in [
+                    (
+                        DELETE_INCIDENT_UPDATES_BEFORE,
+                        &mut cleared.incident_updates,
+                    ),
+                    (
+                        DELETE_INCIDENT_ESCALATIONS_BEFORE,
+                        &mut cleared.incident_escalations,
+                    ),
+                    (DELETE_LOGS_BEFORE, &mut cleared.logs),
+                ] {
+                    match bind!(sqlx::query(query), [alias, before], [alias, before, before])
+                        .execute(&mut *tx)
+                        .await
+                    {
+                        Ok(result) => *deleted = result.rows_affected(),
+                        Err(e) => {
+                            tx.rollback().await?;
+                            return Err(crate::ApiError::SQL(e));
+                        }
+                    }
+                }
+
+                // The hours the cleared checks were folded into go along with them
+                if let Err(e) = bind!(
+                    sqlx::query(DELETE_STATS_ROLLUP_BEFORE),
+                    [alias, before],
+                    [alias, before, before],
+                )
+                .execute(&mut *tx)
+                .await
+                {
+                    tx.rollback().await?;
+                    return Err(crate::ApiError::SQL(e));
+                }
+
+                tx.commit().await?;
+
+                Ok(cleared)
+            }
+
+            async fn content_version(&self) -> Result<crate::etag::ContentVersion, sqlx::Error> {
+                sqlx::query_as::<_, crate::etag::ContentVersion>(SELECT_CONTENT_VERSION_QUERY)
+                    .fetch_one(self)
+                    .await
+            }
+
+            async fn websites(&self) -> Result<Vec<crate::Website>, sqlx::Error> {
+                let rows = sqlx::query(SELECT_URL_ALIAS_WEBSITES_QUERY)
+                    .fetch_all(self)
+                    .await?;
+                Ok(crate::store::decode_websites(&rows))
+            }
+
+            async fn website_summaries(
+                &self,
+                limit: i64,
+                offset: i64,
+            ) -> Result<Vec<crate::WebsiteSummary>, sqlx::Error> {
+                sqlx::query_as::<_, crate::WebsiteSummary>(SELECT_WEBSITE_SUMMARIES_QUERY)
+                    .bind(limit)
+                    .bind(offset)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn website_count(&self) -> Result<i64, sqlx::Error> {
+                sqlx::query_scalar::<_, i64>(SELECT_WEBSITES_COUNT_QUERY)
+                    .fetch_one(self)
+                    .await
+            }
+
+            async fn website(&self, alias: &str) -> Result<Option<crate::Website>, sqlx::Error> {
+                sqlx::query_as::<_, crate::Website>(SELECT_WEBSITE_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn website_id(&self, alias: &str) -> Result<Option<i32>, sqlx::Error> {
+                sqlx::query_scalar::<_, i32>(SELECT_WEBSITE_ID_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn website_alias(&self, id: i32) -> Result<Option<String>, sqlx::Error> {
+                sqlx::query_scalar::<_, String>(SELECT_WEBSITE_ALIAS_BY_ID_QUERY)
+                    .bind(id)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn open_incidents(
+                &self,
+            ) -> Result<Vec<crate::acknowledgment::OpenIncident>, sqlx::Error> {
+                sqlx::query_as::<_, crate::acknowledgment::OpenIncident>(
+                    SELECT_OPEN_INCIDENTS_QUERY,
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn active_status_overrides(
+                &self,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::manual_status::StatusOverride>, sqlx::Error> {
+                sqlx::query_as::<_, crate::manual_status::StatusOverride>(
+                    SELECT_ACTIVE_STATUS_OVERRIDES_QUERY,
+                )
+                .bind(now)
+                .fetch_all(self)
+                .await
+            }
+
+            async fn shared_fate_hosts(&self) -> Result<Vec<String>, sqlx::Error> {
+                sqlx::query_scalar::<_, String>(SELECT_SHARED_FATE_HOSTS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn active_incident_impacts(&self) -> Result<Vec<(String, String)>, sqlx::Error> {
+                sqlx::query_as::<_, (String, String)>(SELECT_ACTIVE_INCIDENT_IMPACTS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn status_summaries(
+                &self,
+                day_ago: chrono::DateTime<chrono::Utc>,
+                month_ago: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::public_status::StatusSummary>, sqlx::Error> {
+                bind!(
+                    sqlx::query_as::<_, crate::public_status::StatusSummary>(
+                        SELECT_STATUS_SUMMARIES,
+                    ),
+                    [day_ago, month_ago],
+                    [day_ago, day_ago, month_ago],
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn stats(
+                &self,
+                website_id: i32,
+                split_by: crate::stats_range::SplitBy,
+                first_bucket: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::WebsiteStats>, sqlx::Error> {
+                let query = match split_by {
+                    crate::stats_range::SplitBy::Hour => SELECT_STATS_BY_HOUR,
+                    crate::stats_range::SplitBy::Day => SELECT_STATS_BY_DAY,
+                };
+                bind!(
+                    sqlx::query_as::<_, crate::WebsiteStats>(query),
+                    [website_id, first_bucket],
+                    [website_id, first_bucket, website_id, first_bucket],
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn incidents(
+                &self,
+                website_id: i32,
+                filter: crate::incident_filter::IncidentFilter,
+            ) -> Result<Vec<crate::Incident>, sqlx::Error> {
+                bind!(
+                    sqlx::query_as::<_, crate::Incident>(SELECT_FILTERED_INCIDENTS),
+                    [
+                        website_id,
+                        filter.from,
+                        filter.to,
+                        filter.status,
+                        filter.limit(),
+                    ],
+                    [
+                        website_id,
+                        filter.from,
+                        filter.from,
+                        filter.to,
+                        filter.to,
+                        filter.status,
+                        filter.status,
+                        filter.limit(),
+                    ],
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn write_logs(
+                &self,
+                logs: &[crate::checker::PendingLog],
+            ) -> Result<usize, sqlx::Error> {
+                use sqlx::Acquire;
+
+                let website_ids: std::collections::HashMap<String, i32> =
+                    sqlx::query_as::<_, (String, i32)>(SELECT_WEBSITE_IDS_QUERY)
+                        .fetch_all(self)
+                        .await?
+                        .into_iter()
+                        .collect();
+                let mut tx = self.begin().await?;
+                let mut written = 0;
+                for log in logs {
+                    let Some(website_id) = website_ids.get(&log.alias) else {
+                        tracing::warn!("{} was deleted before its check was written", log.alias);
+                        continue;
+                    };
+                    let mut savepoint = tx.begin().await?;
+                    match sqlx::query(INSERT_INTO_LOGS_QUERY)
+                        .bind(website_id)
+                        .bind(log.status)
+                        .bind(log.ip_family)
+                        .bind(log.response_time_ms)
+                        .bind(log.connection_reused)
+                        .bind(log.degraded)
+                        .bind(&log.error)
+                        .bind(&log.reason_phrase)
+                        .bind(&log.server_header)
+                        .bind(&log.response_headers)
+                        .bind(&log.content_hash)
+                        .bind(log.checked_at)
+                        .execute(&mut *savepoint)
+                        .await
+                    {
+                        Ok(_) => {
+                            savepoint.commit().await?;
+                            written += 1;
+                        }
+                        Err(e) => {
+                            savepoint.rollback().await?;
+                            tracing::warn!("Writing the check of {} failed: {e}", log.alias);
+                        }
+                    }
+                }
+                tx.commit().await?;
+                Ok(written)
+            }
+
+            async fn monitored_websites(&self) -> Result<Vec<crate::Website>, sqlx::Error> {
+                let rows = sqlx::query(SELECT_MONITORED_WEBSITES_QUERY)
+                    .fetch_all(self)
+                    .await?;
+                Ok(crate::store::decode_websites(&rows))
+            }
+
+            async fn notification_webhooks(&self) -> Result<Vec<(i32, String)>, sqlx::Error> {
+                sqlx::query_as::<_, (i32, String)>(SELECT_NOTIFICATIONS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn channel_routes(
+                &self,
+            ) -> Result<Vec<(String, i32, String, String)>, sqlx::Error> {
+                sqlx::query_as::<_, (String, i32, String, String)>(SELECT_CHANNEL_ROUTES_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn clear_expired_status_overrides(
+                &self,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<u64, sqlx::Error> {
+                let cleared = sqlx::query(CLEAR_EXPIRED_STATUS_OVERRIDES_QUERY)
+                    .bind(now)
+                    .execute(self)
+                    .await?;
+                Ok(cleared.rows_affected())
+            }
+
+            async fn last_status(
+                &self,
+                alias: &str,
+            ) -> Result<Option<(i16, chrono::DateTime<chrono::Utc>)>, sqlx::Error> {
+                sqlx::query_as::<_, (i16, chrono::DateTime<chrono::Utc>)>(
+                    SELECT_LAST_STATUS_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_optional(self)
+                .await
+            }
+
+            async fn last_content_hash(&self, alias: &str) -> Result<Option<String>, sqlx::Error> {
+                sqlx::query_scalar::<_, String>(SELECT_LAST_CONTENT_HASH_BY_WEBSITE_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn insert_event(
+                &self,
+                alias: &str,
+                kind: &str,
+                message: &str,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(INSERT_EVENT_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .bind(kind)
+                    .bind(message)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn hourly_latency_series(&self, alias: &str) -> Result<Vec<f64>, sqlx::Error> {
+                sqlx::query_scalar::<_, f64>(SELECT_HOURLY_LATENCY_SERIES)
+                    .bind(alias)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn notification_states(
+                &self,
+                alias: &str,
+            ) -> Result<Vec<(String, crate::notifications::throttle::NotifiedState)>, sqlx::Error>
+            {
+                let states = sqlx::query_as::<
+                    _,
+                    (
+                        String,
+                        i16,
+                        chrono::DateTime<chrono::Utc>,
+                        chrono::DateTime<chrono::Utc>,
+                    ),
+                >(SELECT_NOTIFICATION_STATES_BY_WEBSITE_ALIAS_QUERY)
+                .bind(alias)
+                .fetch_all(self)
+                .await?;
+                Ok(states
+                    .into_iter()
+                    .map(|(channel, status, since, notified_at)| {
+                        (
+                            channel,
+                            crate::notifications::throttle::NotifiedState {
+                                status,
+                                since,
+                                notified_at,
+                            },
+                        )
+                    })
+                    .collect())
+            }
+
+            async fn upsert_notification_state(
+                &self,
+                alias: &str,
+                channel: &str,
+                state: &crate::notifications::throttle::NotifiedState,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(UPSERT_NOTIFICATION_STATE_QUERY)
+                    .bind(alias)
+                    .bind(channel)
+                    .bind(state.status)
+                    .bind(state.since)
+                    .bind(state.notified_at)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn update_certificate(
+                &self,
+                alias: &str,
+                serial: &str,
+                expires_at: chrono::DateTime<chrono::Utc>,
+                notified_days: Option<i32>,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(UPDATE_CERTIFICATE_BY_ALIAS_QUERY)
+                    .bind(serial)
+                    .bind(expires_at)
+                    .bind(notified_days)
+                    .bind(alias)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn mark_ack_escalated(
+                &self,
+                incident_id: i32,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(MARK_ACK_ESCALATED_QUERY)
+                    .bind(now)
+                    .bind(incident_id)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn escalation_rules(
+                &self,
+            ) -> Result<Vec<crate::escalation::EscalationRule>, sqlx::Error> {
+                sqlx::query_as::<_, crate::escalation::EscalationRule>(
+                    SELECT_ESCALATION_RULES_QUERY,
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn fired_escalation_steps(
+                &self,
+                incident_id: i32,
+                rule_id: i32,
+            ) -> Result<Option<i32>, sqlx::Error> {
+                sqlx::query_scalar::<_, i32>(SELECT_FIRED_ESCALATION_STEPS_QUERY)
+                    .bind(incident_id)
+                    .bind(rule_id)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn upsert_fired_escalation_steps(
+                &self,
+                incident_id: i32,
+                rule_id: i32,
+                steps: i32,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(UPSERT_FIRED_ESCALATION_STEPS_QUERY)
+                    .bind(incident_id)
+                    .bind(rule_id)
+                    .bind(steps)
+                    .bind(now)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn ping(&self) -> Result<(), sqlx::Error> {
+                sqlx::query(SELECT_ONE_QUERY).execute(self).await?;
+                Ok(())
+            }
+
+            async fn website_metrics(
+                &self,
+            ) -> Result<Vec<crate::metrics::WebsiteMetrics>, sqlx::Error> {
+                sqlx::query_as::<_, crate::metrics::WebsiteMetrics>(SELECT_WEBSITE_METRICS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn website_summary(
+                &self,
+                alias: &str,
+            ) -> Result<Option<crate::WebsiteSummary>, sqlx::Error> {
+                sqlx::query_as::<_, crate::WebsiteSummary>(SELECT_WEBSITE_SUMMARY_BY_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn window_stats(
+                &self,
+                alias: &str,
+                from: chrono::DateTime<chrono::Utc>,
+                until: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(Option<f64>, Option<f64>), sqlx::Error> {
+                sqlx::query_as::<_, (Option<f64>, Option<f64>)>(SELECT_WINDOW_STATS)
+                    .bind(alias)
+                    .bind(from)
+                    .bind(until)
+                    .fetch_one(self)
+                    .await
+            }
+
+            async fn update_website(
+                &self,
+                alias: &str,
+                website: &crate::Website,
+            ) -> Result<u64, sqlx::Error> {
+                update_website(alias, website, &mut *self.acquire().await?).await
+            }
+
+            async fn rename_website(
+                &self,
+                alias: &str,
+                new_alias: &str,
+            ) -> Result<u64, sqlx::Error> {
+                let renamed = bind!(
+                    sqlx::query(RENAME_WEBSITE_BY_ALIAS_QUERY),
+                    [alias, new_alias],
+                    [new_alias, alias],
+                )
+                .execute(self)
+                .await?;
+                Ok(renamed.rows_affected())
+            }
+
+            async fn backup_tables(
+                &self,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<crate::backup::BackupTables, sqlx::Error> {
+                Ok(crate::backup::BackupTables {
+                    websites: sqlx::query_as::<_, crate::Website>(SELECT_BACKUP_WEBSITES_QUERY)
+                        .fetch_all(self)
+                        .await?,
+                    status_overrides: sqlx::query_as::<_, crate::manual_status::StatusOverride>(
+                        SELECT_ACTIVE_STATUS_OVERRIDES_QUERY,
+                    )
+                    .bind(now)
+                    .fetch_all(self)
+                    .await?,
+                    channels: sqlx::query_as::<_, (String, String, String)>(
+                        SELECT_NOTIFICATION_CHANNEL_SETTINGS_QUERY,
+                    )
+                    .fetch_all(self)
+                    .await?,
+                    assignments: sqlx::query_as::<_, (String, String)>(
+                        SELECT_CHANNEL_ASSIGNMENT_NAMES_QUERY,
+                    )
+                    .fetch_all(self)
+                    .await?,
+                    escalation_rules:
+                        sqlx::query_as::<_, (String, i32, Option<i32>, Option<String>)>(
+                            SELECT_BACKUP_ESCALATION_RULES_QUERY,
+                        )
+                        .fetch_all(self)
+                        .await?,
+                    webhooks: sqlx::query_scalar::<_, String>(SELECT_NOTIFICATION_URLS_QUERY)
+                        .fetch_all(self)
+                        .await?,
+                })
+            }
+
+            async fn restore_backup(
+                &self,
+                steps: Vec<crate::backup::RestoreStep>,
+                dry_run: bool,
+            ) -> Result<crate::backup::RestoreReport, crate::ApiError> {
+                use sqlx::Acquire;
+
+                let mut report = crate::backup::RestoreReport::new(dry_run);
+                let mut tx = self.begin().await?;
+                for step in steps {
+                    let (kind, name) = (step.kind(), step.name());
+                    let mut savepoint = tx.begin().await?;
+                    let outcome = restore_step(step, &mut savepoint).await;
+                    if outcome.is_ok() {
+                        savepoint.commit().await?;
+                    } else {
+                        savepoint.rollback().await?;
+                    }
+                    if let Err(e) = report.record(kind, name, outcome) {
+                        tx.rollback().await?;
+                        return Err(e);
+                    }
+                }
+
+                let commit = report.should_commit();
+                if commit {
+                    tx.commit().await?;
+                } else {
+                    tx.rollback().await?;
+                }
+                Ok(report.finish(commit))
+            }
+
+            async fn uptime_heatmap(
+                &self,
+                alias: &str,
+                weeks: i32,
+            ) -> Result<Vec<crate::heatmap::HeatmapSample>, sqlx::Error> {
+                sqlx::query_as::<_, crate::heatmap::HeatmapSample>(SELECT_UPTIME_HEATMAP)
+                    .bind(alias)
+                    .bind(weeks)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn latency(
+                &self,
+                alias: &str,
+                range: crate::stats_range::StatsRange,
+                first_bucket: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::latency::LatencyPercentiles>, sqlx::Error> {
+                match LATENCY_QUERY {
+                    crate::store::LatencyQuery::Percentiles { by_hour, by_day } => {
+                        let query = match range.split_by() {
+                            crate::stats_range::SplitBy::Hour => by_hour,
+                            crate::stats_range::SplitBy::Day => by_day,
+                        };
+                        sqlx::query_as::<_, crate::latency::LatencyPercentiles>(query)
+                            .bind(alias)
+                            .bind(first_bucket)
+                            .fetch_all(self)
+                            .await
+                    }
+                    crate::store::LatencyQuery::Samples(query) => {
+                        sqlx::query_as::<_, crate::latency::LatencySample>(query)
+                            .bind(alias)
+                            .bind(first_bucket)
+                            .fetch_all(self)
+                            .await
+                            .map(|samples| crate::latency::from_samples(samples, range))
+                    }
+                }
+            }
+
+            async fn status_code_counts(
+                &self,
+                alias: &str,
+                from: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::status_codes::StatusCount>, sqlx::Error> {
+                sqlx::query_as::<_, crate::status_codes::StatusCount>(SELECT_STATUS_CODE_COUNTS)
+                    .bind(alias)
+                    .bind(from)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn month_trends(
+                &self,
+                alias: &str,
+                from: chrono::DateTime<chrono::Utc>,
+                until: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::trends::MonthSample>, sqlx::Error> {
+                sqlx::query_as::<_, crate::trends::MonthSample>(SELECT_MONTH_TRENDS)
+                    .bind(alias)
+                    .bind(from)
+                    .bind(until)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn connection_reuse(
+                &self,
+                alias: &str,
+            ) -> Result<crate::ConnectionReuse, sqlx::Error> {
+                sqlx::query_as::<_, crate::ConnectionReuse>(
+                    SELECT_CONNECTION_REUSE_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn last_check(
+                &self,
+                alias: &str,
+            ) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+                sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+                    SELECT_LAST_CHECK_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn events(&self, alias: &str) -> Result<Vec<crate::Event>, sqlx::Error> {
+                sqlx::query_as::<_, crate::Event>(SELECT_EVENTS_BY_WEBSITE_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn notification_log(
+                &self,
+                alias: &str,
+            ) -> Result<Vec<crate::NotificationLogEntry>, sqlx::Error> {
+                sqlx::query_as::<_, crate::NotificationLogEntry>(
+                    SELECT_NOTIFICATION_LOG_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_all(self)
+                .await
+            }
+
+            async fn website_channels(
+                &self,
+                alias: &str,
+            ) -> Result<Vec<crate::ChannelSummary>, sqlx::Error> {
+                sqlx::query_as::<_, crate::ChannelSummary>(SELECT_CHANNELS_BY_WEBSITE_ALIAS_QUERY)
+                    .bind(alias)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn last_failure(&self, alias: &str) -> Result<Option<String>, sqlx::Error> {
+                let failure = sqlx::query_scalar::<_, Option<String>>(
+                    SELECT_LAST_FAILURE_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_optional(self)
+                .await?;
+                Ok(failure.flatten())
+            }
+
+            async fn acknowledged_incidents(
+                &self,
+                alias: &str,
+                since: chrono::DateTime<chrono::Utc>,
+            ) -> Result<
+                Vec<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+                sqlx::Error,
+            > {
+                sqlx::query_as::<_, (chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>(
+                    SELECT_ACKNOWLEDGED_INCIDENTS_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .bind(since)
+                .fetch_all(self)
+                .await
+            }
+
+            async fn sla_counts(
+                &self,
+                alias: Option<&str>,
+                from: chrono::DateTime<chrono::Utc>,
+                until: chrono::DateTime<chrono::Utc>,
+            ) -> Result<Vec<crate::sla::SlaCounts>, sqlx::Error> {
+                bind!(
+                    sqlx::query_as::<_, crate::sla::SlaCounts>(SELECT_SLA_COUNTS),
+                    [alias, from, until],
+                    [alias, alias, from, until, until, alias, alias],
+                )
+                .fetch_all(self)
+                .await
+            }
+
+            async fn status_since(
+                &self,
+                alias: &str,
+                up: bool,
+            ) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+                bind!(
+                    sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+                        SELECT_STATUS_SINCE_BY_WEBSITE_ALIAS_QUERY,
+                    ),
+                    [alias, up],
+                    [up, alias],
+                )
+                .fetch_one(self)
+                .await
+            }
+
+            async fn set_incident_impact(
+                &self,
+                alias: &str,
+                id: i32,
+                impact: &str,
+            ) -> Result<u64, sqlx::Error> {
+                let updated = sqlx::query(UPDATE_INCIDENT_IMPACT_QUERY)
+                    .bind(impact)
+                    .bind(id)
+                    .bind(alias)
+                    .execute(self)
+                    .await?;
+                Ok(updated.rows_affected())
+            }
+
+            async fn incident(
+                &self,
+                id: i32,
+            ) -> Result<Option<crate::IncidentDetail>, sqlx::Error> {
+                sqlx::query_as::<_, crate::IncidentDetail>(SELECT_INCIDENT_BY_ID_QUERY)
+                    .bind(id)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn incident_resolution(
+                &self,
+                id: i32,
+            ) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+                sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+                    SELECT_INCIDENT_RESOLUTION_QUERY,
+                )
+                .bind(id)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn incident_updates(
+                &self,
+                id: i32,
+            ) -> Result<Vec<crate::IncidentUpdate>, sqlx::Error> {
+                sqlx::query_as::<_, crate::IncidentUpdate>(SELECT_INCIDENT_UPDATES_QUERY)
+                    .bind(id)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn insert_incident_update(
+                &self,
+                id: i32,
+                message: &str,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(INSERT_INCIDENT_UPDATE_QUERY)
+                    .bind(id)
+                    .bind(message)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn acknowledge_incident(
+                &self,
+                id: i32,
+                by: &str,
+                now: chrono::DateTime<chrono::Utc>,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(ACKNOWLEDGE_INCIDENT_QUERY)
+                    .bind(by)
+                    .bind(now)
+                    .bind(id)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn notifications(&self) -> Result<Vec<crate::NotificationTarget>, sqlx::Error> {
+                sqlx::query_as::<_, crate::NotificationTarget>(SELECT_NOTIFICATIONS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn insert_notification(&self, url: &str) -> Result<(), sqlx::Error> {
+                sqlx::query(INSERT_NOTIFICATION_QUERY)
+                    .bind(url)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn delete_notification(&self, id: i32) -> Result<u64, sqlx::Error> {
+                let deleted = sqlx::query(DELETE_NOTIFICATION_BY_ID_QUERY)
+                    .bind(id)
+                    .execute(self)
+                    .await?;
+                Ok(deleted.rows_affected())
+            }
+
+            async fn notification_url(&self, id: i32) -> Result<Option<String>, sqlx::Error> {
+                sqlx::query_scalar::<_, String>(SELECT_NOTIFICATION_URL_BY_ID_QUERY)
+                    .bind(id)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn update_status_override(
+                &self,
+                alias: &str,
+                status: Option<&str>,
+                note: Option<String>,
+                expires_at: Option<chrono::DateTime<chrono::Utc>>,
+            ) -> Result<u64, sqlx::Error> {
+                let updated = sqlx::query(UPDATE_STATUS_OVERRIDE_BY_ALIAS_QUERY)
+                    .bind(status)
+                    .bind(note)
+                    .bind(expires_at)
+                    .bind(alias)
+                    .execute(self)
+                    .await?;
+                Ok(updated.rows_affected())
+            }
+
+            async fn set_shared_fate(&self, host: &str, enabled: bool) -> Result<(), sqlx::Error> {
+                let query = if enabled {
+                    ENABLE_SHARED_FATE_QUERY
+                } else {
+                    DISABLE_SHARED_FATE_QUERY
+                };
+                sqlx::query(query).bind(host).execute(self).await?;
+                Ok(())
+            }
+
+            async fn notification_channels(
+                &self,
+            ) -> Result<Vec<crate::ChannelSummary>, sqlx::Error> {
+                sqlx::query_as::<_, crate::ChannelSummary>(SELECT_NOTIFICATION_CHANNELS_QUERY)
+                    .fetch_all(self)
+                    .await
+            }
+
+            async fn insert_notification_channel(
+                &self,
+                name: &str,
+                kind: &str,
+                config: &str,
+            ) -> Result<(), sqlx::Error> {
+                sqlx::query(INSERT_NOTIFICATION_CHANNEL_QUERY)
+                    .bind(name)
+                    .bind(kind)
+                    .bind(config)
+                    .execute(self)
+                    .await?;
+                Ok(())
+            }
+
+            async fn notification_channel(
+                &self,
+                id: i32,
+            ) -> Result<Option<(String, String)>, sqlx::Error> {
+                sqlx::query_as::<_, (String, String)>(SELECT_NOTIFICATION_CHANNEL_BY_ID_QUERY)
+                    .bind(id)
+                    .fetch_optional(self)
+                    .await
+            }
+
+            async fn delete_notification_channel(&self, id: i32) -> Result<u64, sqlx::Error> {
+                let mut tx = self.begin().await?;
+                sqlx::query(DELETE_CHANNEL_ASSIGNMENTS_BY_CHANNEL_ID_QUERY)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(DETACH_ESCALATION_RULES_BY_CHANNEL_ID_QUERY)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                let deleted = sqlx::query(DELETE_NOTIFICATION_CHANNEL_BY_ID_QUERY)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+                tx.commit().await?;
+                Ok(deleted)
+            }
+
+            async fn assign_channel(
+                &self,
+                alias: &str,
+                channel_id: i32,
+            ) -> Result<(), sqlx::Error> {
+                bind!(
+                    sqlx::query(ASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY),
+                    [alias, channel_id],
+                    [channel_id, alias],
+                )
+                .execute(self)
+                .await?;
+                Ok(())
+            }
+
+            async fn unassign_channel(
+                &self,
+                alias: &str,
+                channel_id: i32,
+            ) -> Result<u64, sqlx::Error> {
+                let unassigned = sqlx::query(UNASSIGN_CHANNEL_BY_WEBSITE_ALIAS_QUERY)
+                    .bind(alias)
+                    .bind(channel_id)
+                    .execute(self)
+                    .await?;
+                Ok(unassigned.rows_affected())
+            }
+
+            async fn website_escalation_rules(
+                &self,
+                alias: &str,
+            ) -> Result<Vec<crate::escalation::EscalationRule>, sqlx::Error> {
+                sqlx::query_as::<_, crate::escalation::EscalationRule>(
+                    SELECT_ESCALATION_RULES_BY_WEBSITE_ALIAS_QUERY,
+                )
+                .bind(alias)
+                .fetch_all(self)
+                .await
+            }
+
+            async fn insert_escalation_rule(
+                &self,
+                alias: &str,
+                rule: &crate::escalation::NewEscalationRule,
+            ) -> Result<u64, sqlx::Error> {
+                let inserted = bind!(
+                    sqlx::query(INSERT_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY),
+                    [
+                        alias,
+                        rule.after_minutes,
+                        rule.repeat_minutes,
+                        rule.channel_id
+                    ],
+                    [
+                        rule.after_minutes,
+                        rule.repeat_minutes,
+                        rule.channel_id,
+                        alias
+                    ],
+                )
+                .execute(self)
+                .await?;
+                Ok(inserted.rows_affected())
+            }
+
+            async fn delete_escalation_rule(
+                &self,
+                alias: &str,
+                id: i32,
+            ) -> Result<u64, sqlx::Error> {
+                let mut tx = self.begin().await?;
+                sqlx::query(DELETE_INCIDENT_ESCALATIONS_BY_RULE_QUERY)
+                    .bind(alias)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                let deleted = sqlx::query(DELETE_ESCALATION_RULE_BY_WEBSITE_ALIAS_QUERY)
+                    .bind(alias)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+                tx.commit().await?;
+                Ok(deleted)
+            }
+
+            fn window_logs<'a>(
+                &'a self,
+                alias: &'a str,
+                from: chrono::DateTime<chrono::Utc>,
+                to: chrono::DateTime<chrono::Utc>,
+            ) -> futures_util::stream::BoxStream<'a, Result<crate::store::CheckRow, sqlx::Error>>
+            {
+                sqlx::query_as(SELECT_WINDOW_LOGS)
+                    .bind(alias)
+                    .bind(from)
+                    .bind(to)
+                    .fetch(self)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_store;
@@ -0,0 +1,343 @@
+use chrono::{DateTime, Datelike, Months, TimeDelta, TimeZone, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A month whose first check came later than this after it began is missing its start,
+/// like when the website was added mid-month
+const START_TOLERANCE: TimeDelta = TimeDelta::hours(1);
+
+/// One calendar month as aggregated by the backend queries, `month` is "YYYY-MM"
+#[derive(sqlx::FromRow)]
+pub struct MonthSample {
+    month: String,
+    uptime_pct: Option<f64>,
+    latency_p95_ms: Option<f64>,
+    incidents: i64,
+    first_check_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct MonthSummary {
+    /// "YYYY-MM"
+    pub month: String,
+    /// Still going on or without data for its beginning, so not comparable as is
+    pub partial: bool,
+    /// `None` when there wasn't a single check in the month
+    pub uptime_pct: Option<f64>,
+    pub latency_p95_ms: Option<f64>,
+    pub incidents: i64,
+}
+
+/// This month against the last one
+#[derive(Clone, Serialize, ToSchema)]
+pub struct Trends {
+    pub this_month: MonthSummary,
+    pub last_month: MonthSummary,
+    /// `None` unless both months have the value
+    pub uptime: Option<Delta>,
+    pub latency_p95: Option<Delta>,
+    pub incidents: Option<Delta>,
+}
+
+#[derive(Clone, Copy, Serialize, ToSchema)]
+pub struct Delta {
+    /// This month's value minus last month's
+    pub change: f64,
+    /// Whether the value going up is an improvement, like uptime and unlike latency
+    pub higher_is_better: bool,
+}
+
+/// Start of the month `now` is in and of the month before it, the current month ends
+/// at the start of the next one
+pub fn month_windows(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>, DateTime<Utc>) {
+    let this_month = Utc
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .expect("the first of a month at midnight exists in UTC");
+    let last_month = this_month - Months::new(1);
+    let next_month = this_month + Months::new(1);
+
+    (last_month, this_month, next_month)
+}
+
+impl Trends {
+    /// `samples` may hold either month or none of them
+    pub fn from_samples(samples: Vec<MonthSample>, now: DateTime<Utc>) -> Self {
+        let (last_month_start, this_month_start, next_month_start) = month_windows(now);
+        let summary = |start: DateTime<Utc>, end: DateTime<Utc>| {
+            let month = start.format("%Y-%m").to_string();
+            match samples.iter().find(|sample| sample.month == month) {
+                Some(sample) => MonthSummary {
+                    partial: end > now || sample.first_check_at - start > START_TOLERANCE,
+                    month,
+                    uptime_pct: sample.uptime_pct,
+                    latency_p95_ms: sample.latency_p95_ms,
+                    incidents: sample.incidents,
+                },
+                None => MonthSummary {
+                    month,
+                    partial: true,
+                    uptime_pct: None,
+                    latency_p95_ms: None,
+                    incidents: 0,
+                },
+            }
+        };
+        let this_month = summary(this_month_start, next_month_start);
+        let last_month = summary(last_month_start, this_month_start);
+
+        let incidents =
+            |summary: &MonthSummary| summary.uptime_pct.map(|_| summary.incidents as f64);
+        Self {
+            uptime: Delta::between(this_month.uptime_pct, last_month.uptime_pct, true),
+            latency_p95: Delta::between(
+                this_month.latency_p95_ms,
+                last_month.latency_p95_ms,
+                false,
+            ),
+            incidents: Delta::between(incidents(&this_month), incidents(&last_month), false),
+            this_month,
+            last_month,
+        }
+    }
+
+    /// Either month is partial, so the deltas compare unequal stretches of time
+    pub fn is_partial(&self) -> bool {
+        self.this_month.partial || self.last_month.partial
+    }
+}
+
+impl Delta {
+    fn between(
+        this_month: Option<f64>,
+        last_month: Option<f64>,
+        higher_is_better: bool,
+    ) -> Option<Self> {
+        Some(Self {
+            change: this_month? - last_month?,
+            higher_is_better,
+        })
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self.change {
+            change if change > 0.0 => "▲",
+            change if change < 0.0 => "▼",
+            _ => "▶",
+        }
+    }
+
+    /// Green for improvements, red for regressions
+    pub fn css_class(&self) -> &'static str {
+        match self.change {
+            change if change > 0.0 && self.higher_is_better => "trend-better",
+            change if change < 0.0 && !self.higher_is_better => "trend-better",
+            change if change != 0.0 => "trend-worse",
+            _ => "trend-flat",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppState;
+    use crate::checker::{CONNECTION_FAILED_STATUS, PendingLog};
+    use crate::tests::{check, on_every_backend, website};
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn sample(month: &str, uptime_pct: f64, latency_p95_ms: f64, incidents: i64) -> MonthSample {
+        MonthSample {
+            month: month.to_owned(),
+            uptime_pct: Some(uptime_pct),
+            latency_p95_ms: Some(latency_p95_ms),
+            incidents,
+            first_check_at: at(&format!("{month}-01T00:00:00Z")),
+        }
+    }
+
+    #[test]
+    fn deltas_are_this_month_minus_the_last() {
+        let trends = Trends::from_samples(
+            vec![
+                sample("2026-09", 99.5, 300.0, 4),
+                sample("2026-10", 99.9, 250.0, 1),
+            ],
+            at("2026-10-16T08:00:00Z"),
+        );
+
+        let uptime = trends.uptime.unwrap();
+        let latency = trends.latency_p95.unwrap();
+        let incidents = trends.incidents.unwrap();
+        assert!((uptime.change - 0.4).abs() < 1e-9);
+        assert_eq!((uptime.arrow(), uptime.css_class()), ("▲", "trend-better"));
+        assert_eq!(latency.change, -50.0);
+        assert_eq!(
+            (latency.arrow(), latency.css_class()),
+            ("▼", "trend-better")
+        );
+        assert_eq!(incidents.change, -3.0);
+        assert_eq!(incidents.css_class(), "trend-better");
+    }
+
+    #[test]
+    fn regressions_and_standstills_are_told_apart() {
+        let trends = Trends::from_samples(
+            vec![
+                sample("2026-09", 99.9, 250.0, 1),
+                sample("2026-10", 99.0, 250.0, 3),
+            ],
+            at("2026-10-16T08:00:00Z"),
+        );
+
+        assert_eq!(trends.uptime.unwrap().css_class(), "trend-worse");
+        assert_eq!(trends.incidents.unwrap().css_class(), "trend-worse");
+        let latency = trends.latency_p95.unwrap();
+        assert_eq!((latency.arrow(), latency.css_class()), ("▶", "trend-flat"));
+    }
+
+    #[test]
+    fn the_current_month_is_partial_and_the_last_complete_one_is_not() {
+        let trends = Trends::from_samples(
+            vec![
+                sample("2026-09", 99.5, 300.0, 4),
+                sample("2026-10", 99.9, 250.0, 1),
+            ],
+            at("2026-10-16T08:00:00Z"),
+        );
+
+        assert_eq!(trends.last_month.month, "2026-09");
+        assert!(!trends.last_month.partial);
+        assert_eq!(trends.this_month.month, "2026-10");
+        assert!(trends.this_month.partial);
+        assert!(trends.is_partial());
+    }
+
+    #[test]
+    fn a_website_added_mid_month_has_a_partial_month() {
+        let mut added = sample("2026-09", 100.0, 200.0, 0);
+        added.first_check_at = at("2026-09-12T14:03:00Z");
+        let mut late_by_minutes = sample("2026-08", 100.0, 200.0, 0);
+        late_by_minutes.first_check_at = at("2026-08-01T00:42:00Z");
+
+        let september = Trends::from_samples(vec![added], at("2026-10-02T00:00:00Z"));
+        let august = Trends::from_samples(vec![late_by_minutes], at("2026-09-02T00:00:00Z"));
+
+        assert!(september.last_month.partial);
+        assert!(!august.last_month.partial);
+    }
+
+    #[test]
+    fn a_month_without_checks_is_partial_and_not_compared() {
+        let trends = Trends::from_samples(
+            vec![sample("2026-10", 99.9, 250.0, 1)],
+            at("2026-10-16T08:00:00Z"),
+        );
+
+        assert!(trends.last_month.partial);
+        assert_eq!(trends.last_month.uptime_pct, None);
+        assert!(trends.uptime.is_none());
+        assert!(trends.latency_p95.is_none());
+        assert!(trends.incidents.is_none());
+    }
+
+    #[test]
+    fn checks_without_latency_leave_only_the_latency_uncompared() {
+        let mut pings_failed = sample("2026-10", 0.0, 0.0, 1);
+        pings_failed.latency_p95_ms = None;
+
+        let trends = Trends::from_samples(
+            vec![sample("2026-09", 100.0, 20.0, 0), pings_failed],
+            at("2026-10-16T08:00:00Z"),
+        );
+
+        assert!(trends.uptime.is_some());
+        assert!(trends.incidents.is_some());
+        assert!(trends.latency_p95.is_none());
+    }
+
+    #[test]
+    fn january_is_compared_with_the_december_before() {
+        let (last_month, this_month, next_month) = month_windows(at("2027-01-09T10:00:00Z"));
+
+        assert_eq!(last_month, at("2026-12-01T00:00:00Z"));
+        assert_eq!(this_month, at("2027-01-01T00:00:00Z"));
+        assert_eq!(next_month, at("2027-02-01T00:00:00Z"));
+    }
+
+    /// Hourly checks from `from` until before `until`, failing for the hours in `down`
+    fn hourly_checks(
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        down: &[DateTime<Utc>],
+    ) -> Vec<PendingLog> {
+        std::iter::successors(Some(from), |hour| Some(*hour + TimeDelta::hours(1)))
+            .take_while(|hour| *hour < until)
+            .map(|hour| {
+                if down.contains(&hour) {
+                    return check("trending", CONNECTION_FAILED_STATUS, hour);
+                }
+                PendingLog {
+                    response_time_ms: Some(100),
+                    ..check("trending", 200, hour)
+                }
+            })
+            .collect()
+    }
+
+    async fn trends_are_computed_from_a_synthetic_history(db: AppState) {
+        let store = db.store();
+        store
+            .insert_website(&website("trending", serde_json::json!({})))
+            .await
+            .unwrap();
+        let outages = [
+            at("2026-09-03T10:00:00Z"),
+            at("2026-09-03T11:00:00Z"),
+            at("2026-09-20T02:00:00Z"),
+        ];
+        store
+            .write_logs(&hourly_checks(
+                at("2026-09-01T00:00:00Z"),
+                at("2026-10-01T00:00:00Z"),
+                &outages,
+            ))
+            .await
+            .unwrap();
+        store
+            .write_logs(&hourly_checks(
+                at("2026-10-01T00:00:00Z"),
+                at("2026-10-16T00:00:00Z"),
+                &[],
+            ))
+            .await
+            .unwrap();
+        let now = at("2026-10-16T08:00:00Z");
+        let (last_month, _, next_month) = month_windows(now);
+
+        let samples = store
+            .month_trends("trending", last_month, next_month)
+            .await
+            .unwrap();
+        let trends = Trends::from_samples(samples, now);
+
+        assert!(!trends.last_month.partial);
+        assert!(trends.this_month.partial);
+        assert_eq!(trends.last_month.incidents, 2);
+        assert_eq!(trends.this_month.incidents, 0);
+        assert_eq!(trends.this_month.uptime_pct, Some(100.0));
+        let uptime = trends.uptime.unwrap();
+        assert!(
+            (uptime.change - 3.0 * 100.0 / 720.0).abs() < 1e-4,
+            "{}",
+            uptime.change
+        );
+        assert_eq!(trends.latency_p95.unwrap().change, 0.0);
+        assert_eq!(trends.incidents.unwrap().change, -2.0);
+    }
+
+    on_every_backend!(trends_are_computed_from_a_synthetic_history, "trends");
+}
@@ -0,0 +1,52 @@
+use crate::{WebsiteStats, latency::LatencyPercentiles};
+
+/// One bucket of an uptime chart as the bar template draws it
+pub struct UptimeBar {
+    /// RFC 3339, for the bar's data attribute
+    pub time: String,
+    pub uptime_pct: Option<i16>,
+    pub css_class: &'static str,
+    /// What hovering the bar shows
+    pub label: String,
+}
+
+/// The bars of `data`, oldest first. `latency` holds the response times of the same
+/// buckets, charts without them pass none
+pub fn chart(data: &[WebsiteStats], latency: &[LatencyPercentiles]) -> Vec<UptimeBar> {
+    data.iter()
+        .enumerate()
+        .map(|(bucket, stats)| {
+            let p95_ms = latency.get(bucket).and_then(|latency| latency.p95_ms);
+            bar(stats, p95_ms)
+        })
+        .collect()
+}
+
+fn bar(stats: &WebsiteStats, p95_ms: Option<f64>) -> UptimeBar {
+    let time = stats.time.format("%Y-%m-%d %H:%M UTC");
+    let degraded_pct = stats.degraded_pct.unwrap_or(0);
+    let (css_class, mut label) = match stats.uptime_pct {
+        None => ("bar-none", format!("{time} No data")),
+        Some(uptime_pct) => {
+            let css_class = match uptime_pct {
+                100 if degraded_pct == 0 => "bar-up",
+                0 => "bar-down",
+                _ => "bar-partial",
+            };
+            (css_class, format!("{time} Uptime: {uptime_pct}%"))
+        }
+    };
+    if degraded_pct > 0 {
+        label.push_str(&format!(" ({degraded_pct}% slow)"));
+    }
+    if let Some(p95_ms) = p95_ms {
+        label.push_str(&format!(", p95 {p95_ms:.0} ms"));
+    }
+
+    UptimeBar {
+        time: stats.time.to_rfc3339(),
+        uptime_pct: stats.uptime_pct,
+        css_class,
+        label,
+    }
+}
@@ -0,0 +1,476 @@
+use crate::{ApiError, Website, field_errors::FieldError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+/// Columns of the CSV export, the import reads these and any other input of the form
+const EXPORT_COLUMNS: [&str; 17] = [
+    "url",
+    "alias",
+    "monitor_type",
+    "ip_version",
+    "impact_weight",
+    "invert",
+    "alert_email_to",
+    "retention_days",
+    "fresh_connection",
+    "cron_schedule",
+    "degraded_threshold_ms",
+    "strict",
+    "expected_keyword",
+    "forbidden_keyword",
+    "track_content",
+    "renotify_minutes",
+    "exec_args",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Good rows are kept whatever happens to the others
+    Partial,
+    /// Any skipped or invalid row rolls back the whole import
+    AllOrNothing,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportParams {
+    /// partial or all_or_nothing, partial when left out
+    mode: Option<String>,
+}
+
+impl ImportParams {
+    pub fn mode(&self) -> Result<ImportMode, String> {
+        match self.mode.as_deref() {
+            None | Some("partial") => Ok(ImportMode::Partial),
+            Some("all_or_nothing") => Ok(ImportMode::AllOrNothing),
+            Some(other) => Err(format!(
+                "Unsupported mode '{other}', use partial or all_or_nothing"
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// json or csv, json when left out
+    format: Option<String>,
+}
+
+impl ExportParams {
+    pub fn format(&self) -> Result<ExportFormat, String> {
+        match self.format.as_deref() {
+            None | Some("json") => Ok(ExportFormat::Json),
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some(other) => Err(format!("Unsupported format '{other}', use json or csv")),
+        }
+    }
+}
+
+/// One website of the import as read, before anything is checked
+pub struct ImportRow {
+    pub alias: Option<String>,
+    /// Why the row couldn't even be read as a website
+    pub website: Result<Website, String>,
+}
+
+/// CSV when declared as such, JSON when declared as such, otherwise whatever the body
+/// looks like
+pub fn parse_rows(content_type: Option<&str>, body: &str) -> Result<Vec<ImportRow>, String> {
+    let is_csv = match content_type {
+        Some(content_type) if content_type.contains("csv") => true,
+        Some(content_type) if content_type.contains("json") => false,
+        _ => !body.trim_start().starts_with('['),
+    };
+    let rows = if is_csv {
+        csv_rows(body)?
+    } else {
+        json_rows(body)?
+    };
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ImportRow {
+            alias: row.get("alias").and_then(Value::as_str).map(str::to_owned),
+            website: serde_json::from_value(Value::Object(row)).map_err(|e| e.to_string()),
+        })
+        .collect())
+}
+
+/// Every value as the text the website form would send for it, so rows go through the
+/// same deserialization as a submitted form
+fn json_rows(body: &str) -> Result<Vec<Map<String, Value>>, String> {
+    let rows: Vec<Value> =
+        serde_json::from_str(body).map_err(|e| format!("Not a JSON array of websites: {e}"))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| match row {
+            Value::Object(fields) => form_fields(fields),
+            _ => Map::new(),
+        })
+        .collect())
+}
+
+/// A website from JSON fields, read like a website of the JSON import
+pub fn website_from_json(fields: Map<String, Value>) -> Result<Website, String> {
+    serde_json::from_value(Value::Object(form_fields(fields))).map_err(|e| e.to_string())
+}
+
+fn form_fields(fields: Map<String, Value>) -> Map<String, Value> {
+    fields
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, Value::String(form_value(value)?))))
+        .collect()
+}
+
+/// A header row naming the inputs, then one website per row. Empty cells count as left out
+fn csv_rows(body: &str) -> Result<Vec<Map<String, Value>>, String> {
+    let mut records = csv_records(body)?.into_iter();
+    let Some(header) = records.next() else {
+        return Ok(Vec::new());
+    };
+
+    Ok(records
+        .map(|record| {
+            header
+                .iter()
+                .zip(record)
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(field, value)| (field.trim().to_owned(), Value::String(value)))
+                .collect()
+        })
+        .collect())
+}
+
+/// RFC 4180: commas between fields, quotes around fields holding commas, quotes or line
+/// breaks and doubled quotes inside those. Blank lines are skipped
+fn csv_records(body: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|field| !field.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("The CSV ends inside a quoted field".to_owned());
+    }
+    record.push(field);
+    if record.iter().any(|field| !field.is_empty()) {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Nulls are left out, lists become one entry per line like the exec arguments input
+fn form_value(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text),
+        Value::Bool(flag) => Some(flag.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(values) => Some(
+            values
+                .into_iter()
+                .filter_map(form_value)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Value::Object(_) => Some(value.to_string()),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    /// The alias is taken, by an existing website or an earlier row
+    Skipped,
+    Invalid,
+    /// Would have been created, but the all or nothing import was rolled back
+    RolledBack,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RowReport {
+    /// Position in the import counting from 1, the CSV header doesn't count
+    pub row: usize,
+    pub alias: Option<String>,
+    pub status: RowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// What happened to every row, in the order they came in
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    pub committed: bool,
+    pub created: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    pub rows: Vec<RowReport>,
+    #[serde(skip)]
+    mode: ImportMode,
+}
+
+impl ImportReport {
+    pub fn new(mode: ImportMode) -> Self {
+        Self {
+            committed: false,
+            created: 0,
+            skipped: 0,
+            invalid: 0,
+            rows: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Rows the caller got wrong are reported, anything else fails the whole import
+    pub fn record(
+        &mut self,
+        alias: Option<String>,
+        outcome: Result<(), ApiError>,
+    ) -> Result<(), ApiError> {
+        let (status, reason, fields) = match outcome {
+            Ok(()) => (RowStatus::Created, None, Vec::new()),
+            Err(ApiError::Conflict(reason)) => (RowStatus::Skipped, Some(reason), Vec::new()),
+            Err(ApiError::Invalid(fields)) => (RowStatus::Invalid, None, fields),
+            Err(ApiError::BadRequest(reason)) => (RowStatus::Invalid, Some(reason), Vec::new()),
+            Err(e) => return Err(e),
+        };
+        match status {
+            RowStatus::Created => self.created += 1,
+            RowStatus::Skipped => self.skipped += 1,
+            _ => self.invalid += 1,
+        }
+        self.rows.push(RowReport {
+            row: self.rows.len() + 1,
+            alias,
+            status,
+            reason,
+            fields,
+        });
+
+        Ok(())
+    }
+
+    /// Whether the created rows are kept
+    pub fn should_commit(&self) -> bool {
+        self.mode == ImportMode::Partial || self.skipped + self.invalid == 0
+    }
+
+    pub fn finish(mut self, committed: bool) -> Self {
+        self.committed = committed;
+        if !committed {
+            for row in &mut self.rows {
+                if row.status == RowStatus::Created {
+                    row.status = RowStatus::RolledBack;
+                }
+            }
+            self.created = 0;
+        }
+        self
+    }
+}
+
+/// A website as the import takes it back, secrets like webhooks and client
+/// certificates stay out of the export
+#[derive(Serialize, ToSchema)]
+pub struct ExportedWebsite {
+    url: String,
+    alias: String,
+    monitor_type: String,
+    ip_version: String,
+    impact_weight: String,
+    invert: bool,
+    alert_email_to: Option<String>,
+    retention_days: Option<i32>,
+    #[schema(value_type = Option<String>)]
+    fresh_connection: Option<&'static str>,
+    cron_schedule: Option<String>,
+    degraded_threshold_ms: Option<i32>,
+    strict: bool,
+    expected_keyword: Option<String>,
+    forbidden_keyword: Option<String>,
+    track_content: bool,
+    renotify_minutes: Option<i32>,
+    exec_args: Vec<String>,
+}
+
+impl From<Website> for ExportedWebsite {
+    fn from(website: Website) -> Self {
+        let config = website.config;
+        Self {
+            url: website.url,
+            alias: website.alias,
+            monitor_type: website.monitor_type,
+            ip_version: website.ip_version,
+            impact_weight: website.impact_weight,
+            invert: website.invert,
+            alert_email_to: website.alert_email_to,
+            retention_days: website.retention_days,
+            fresh_connection: config
+                .fresh_connection
+                .map(|fresh| if fresh { "fresh" } else { "reuse" }),
+            cron_schedule: config.cron_schedule,
+            degraded_threshold_ms: config.degraded_threshold_ms,
+            strict: config.strict,
+            expected_keyword: config.expected_keyword,
+            forbidden_keyword: config.forbidden_keyword,
+            track_content: config.track_content,
+            renotify_minutes: config.renotify_minutes,
+            exec_args: config.exec_args,
+        }
+    }
+}
+
+/// The export as CSV, each value written the way the import reads it back
+pub fn websites_csv(websites: &[ExportedWebsite]) -> String {
+    let mut csv = EXPORT_COLUMNS.join(",");
+    csv.push('\n');
+    for website in websites {
+        let Ok(Value::Object(mut fields)) = serde_json::to_value(website) else {
+            continue;
+        };
+        let record = EXPORT_COLUMNS
+            .iter()
+            .map(|column| {
+                let value = fields
+                    .remove(*column)
+                    .and_then(form_value)
+                    .unwrap_or_default();
+                csv_field(&value)
+            })
+            .collect::<Vec<_>>();
+        csv.push_str(&record.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::website;
+
+    fn records(body: &str) -> Vec<Vec<String>> {
+        csv_records(body).unwrap()
+    }
+
+    #[test]
+    fn quoted_fields_keep_their_commas_quotes_and_line_breaks() {
+        assert_eq!(
+            records("alias,expected_keyword\nshop,\"a, \"\"b\"\"\nc\"\n"),
+            [
+                vec!["alias", "expected_keyword"],
+                vec!["shop", "a, \"b\"\nc"]
+            ]
+        );
+        assert_eq!(
+            csv_records("alias\n\"shop").unwrap_err(),
+            "The CSV ends inside a quoted field"
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings_and_blank_lines_are_no_records() {
+        assert_eq!(
+            records("url,alias\r\n\r\nhttps://a.example,a\r\n,\r\nhttps://b.example,b"),
+            [
+                vec!["url", "alias"],
+                vec!["https://a.example", "a"],
+                vec!["https://b.example", "b"]
+            ]
+        );
+        assert_eq!(records("\r\n\n"), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn unknown_columns_are_ignored_and_missing_ones_left_out() {
+        let rows = parse_rows(
+            Some("text/csv"),
+            "url,alias,colour\nhttps://shop.example,shop,blue\n",
+        )
+        .unwrap();
+        let website = rows[0].website.as_ref().unwrap();
+        assert_eq!(
+            (website.url.as_str(), website.alias.as_str()),
+            ("https://shop.example", "shop")
+        );
+
+        let rows = parse_rows(Some("text/csv"), "alias\nshop\n").unwrap();
+        assert_eq!(rows[0].alias.as_deref(), Some("shop"));
+        assert!(rows[0].website.as_ref().err().unwrap().contains("url"));
+    }
+
+    #[test]
+    fn the_csv_export_imports_as_it_was() {
+        let exported: Vec<ExportedWebsite> = [
+            website("plain", serde_json::json!({})),
+            website(
+                "tricky",
+                serde_json::json!({
+                    "expected_keyword": "Welcome, \"friend\"",
+                    "strict": "on",
+                    "degraded_threshold_ms": "500",
+                    "exec_args": "--name\nsecond, \"quoted\"",
+                }),
+            ),
+        ]
+        .into_iter()
+        .map(ExportedWebsite::from)
+        .collect();
+        assert_eq!(exported[1].exec_args, ["--name", "second, \"quoted\""]);
+
+        let reimported: Vec<ExportedWebsite> = parse_rows(None, &websites_csv(&exported))
+            .unwrap()
+            .into_iter()
+            .map(|row| ExportedWebsite::from(row.website.unwrap()))
+            .collect();
+
+        assert_eq!(
+            serde_json::to_value(reimported).unwrap(),
+            serde_json::to_value(exported).unwrap()
+        );
+    }
+}